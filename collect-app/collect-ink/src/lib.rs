@@ -2,10 +2,116 @@
 
 use ink_core::{
     env::DefaultSrmlTypes,
-    memory::format,
+    memory::{format, vec::Vec},
     storage,
 };
 use ink_lang::contract;
+use parity_codec::{Encode, Decode};
+
+mod blake2;
+
+use blake2::blake2_256;
+
+/// Why a transfer failed
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Error {
+    /// The sender (or the owner, for `transfer_from`) does not have enough tokens
+    InsufficientBalance,
+    /// The caller is not allowed to spend this much on behalf of the owner
+    InsufficientAllowance,
+    /// The owner paused transfers
+    Paused,
+    /// The sender or the recipient is frozen by the owner
+    AccountFrozen,
+    /// The faucet holds less than one drip
+    FaucetEmpty,
+    /// The caller got a drip less than `faucet_cooldown` blocks ago
+    CooldownActive,
+    /// The caller has no vesting grant or lock, or none of it vested or matured since the last release
+    NothingToRelease,
+    /// The caller has no staking rewards to claim
+    NoRewards,
+    /// No proposal with this id exists
+    ProposalNotFound,
+    /// The voting period of the proposal is over
+    VotingClosed,
+    /// The caller already voted on the proposal
+    AlreadyVoted,
+    /// The caller held no tokens when the proposal was created
+    NoVotingWeight,
+    /// The recipient already holds `MAX_LOCKS` locks
+    TooManyLocks,
+    /// The caller has no dividends to claim
+    NoDividends,
+    /// No airdrop was started, or the deadline of the latest one passed
+    AirdropClosed,
+    /// The claim with this index was already paid
+    AlreadyClaimed,
+    /// The proof does not lead from the claim to the root of the airdrop
+    InvalidProof,
+    /// A lock of zero tokens, or one that unlocks more than `MAX_LOCKUP` blocks from now
+    InvalidLock,
+}
+
+/// Tokens the owner set aside for a beneficiary. Nothing vests before `cliff` blocks have passed since
+/// `start`, then the grant vests linearly until all of it has vested `duration` blocks after `start`.
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Grant<Balance, BlockNumber> {
+    pub total: Balance,
+    pub released: Balance,
+    pub start: BlockNumber,
+    pub cliff: BlockNumber,
+    pub duration: BlockNumber,
+    /// The owner can take back the tokens that did not vest yet
+    pub revocable: bool,
+}
+
+/// The most recipients in one `transfer_batch` call
+const MAX_BATCH_TRANSFERS: usize = 100;
+
+/// A proposal voted on by the token holders, weighted by their balances at the snapshot taken when it
+/// was created. What the proposal is about is only known by the hash of its off-chain payload.
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Proposal<AccountId, Balance, BlockNumber> {
+    pub proposer: AccountId,
+    pub payload: [u8; 32],
+    pub snapshot: u32,
+    /// Votes are accepted up to and including this block
+    pub end: BlockNumber,
+    pub yes: Balance,
+    pub no: Balance,
+    /// Set by `execute` once the proposal passed. Acting on it is left to the holders.
+    pub executed: bool,
+}
+
+/// The most locks an account can hold at once. `unlock` frees the slots of matured locks.
+const MAX_LOCKS: usize = 16;
+
+/// The most blocks a lock can run for, about a year of 6 second blocks. Together with `MAX_LOCKS` it bounds
+/// how long a sender can take up the lock slots of another account.
+const MAX_LOCKUP: u32 = 5_256_000;
+
+/// Transfer fees are in basis points, hundredths of a percent
+const FEE_DENOMINATOR: u32 = 10_000;
+
+/// `acc_reward_per_share` and `dividend_per_token` are scaled by this, so that rewards and dividends
+/// smaller than the tokens they are shared by still count
+const REWARD_PRECISION: u128 = 1_000_000_000_000;
+
+/// The most hashes in the proof of an airdrop claim, enough for a tree of 2^32 claims
+const MAX_PROOF_LENGTH: usize = 32;
+
+/// The root that a Merkle proof leads to from `leaf`. Each step hashes the node and the next hash of the proof,
+/// the smaller one first, so a proof does not have to say on which side each hash is.
+pub fn merkle_root(leaf: [u8; 32], proof: &[[u8; 32]]) -> [u8; 32] {
+    proof.iter().fold(leaf, |node, sibling| {
+        let (first, second) = if node <= *sibling { (node, *sibling) } else { (*sibling, node) };
+        let mut pair = [0u8; 64];
+        pair[..32].copy_from_slice(&first);
+        pair[32..].copy_from_slice(&second);
+        blake2_256(&pair)
+    })
+}
 
 contract! {
     #![env = DefaultSrmlTypes]
@@ -16,13 +122,163 @@ contract! {
         value: Balance,
     }
 
-    // Event emitted when an approval occurs
+    // Event emitted whenever an allowance changes, by `approve` or by spending it with `transfer_from`
     event Approval {
         owner: AccountId,
         spender: AccountId,
         value: Balance,
     }
 
+    // Event emitted when the owner pauses transfers
+    event Paused {
+        by: AccountId,
+    }
+
+    // Event emitted when the owner resumes transfers
+    event Unpaused {
+        by: AccountId,
+    }
+
+    // Event emitted when the new owner accepted the ownership of the contract
+    event OwnershipTransferred {
+        from: AccountId,
+        to: AccountId,
+    }
+
+    // Event emitted when the owner freezes an account
+    event AccountFrozen {
+        account: AccountId,
+    }
+
+    // Event emitted when the owner unfreezes an account
+    event AccountUnfrozen {
+        account: AccountId,
+    }
+
+    // Event emitted when the owner takes a balance snapshot
+    event Snapshot {
+        id: u32,
+    }
+
+    // Event emitted when the owner moves tokens into the faucet reservoir
+    event FaucetRefilled {
+        value: Balance,
+    }
+
+    // Event emitted when the faucet pays out a drip
+    event Drip {
+        to: AccountId,
+        value: Balance,
+    }
+
+    // Event emitted when the owner sets aside tokens for a beneficiary
+    event GrantCreated {
+        beneficiary: AccountId,
+        value: Balance,
+    }
+
+    // Event emitted when a beneficiary releases vested tokens
+    event TokensReleased {
+        beneficiary: AccountId,
+        value: Balance,
+    }
+
+    // Event emitted when the owner revokes a grant. `refund` is the unvested part returned to the owner.
+    event GrantRevoked {
+        beneficiary: AccountId,
+        refund: Balance,
+    }
+
+    // Event emitted when an account stakes tokens
+    event Staked {
+        who: AccountId,
+        value: Balance,
+    }
+
+    // Event emitted when an account takes staked tokens back
+    event Unstaked {
+        who: AccountId,
+        value: Balance,
+    }
+
+    // Event emitted when staking rewards are paid to an account
+    event RewardPaid {
+        who: AccountId,
+        value: Balance,
+    }
+
+    // Event emitted when a holder creates a proposal
+    event ProposalCreated {
+        id: u32,
+        proposer: AccountId,
+    }
+
+    // Event emitted when a holder votes on a proposal
+    event Voted {
+        id: u32,
+        voter: AccountId,
+        approve: bool,
+        weight: Balance,
+    }
+
+    // Event emitted when a proposal that passed is executed
+    event ProposalExecuted {
+        id: u32,
+    }
+
+    // Event emitted when tokens are sent with a lockup
+    event TransferLocked {
+        from: AccountId,
+        to: AccountId,
+        value: Balance,
+        unlock_block: BlockNumber,
+    }
+
+    // Event emitted when matured locks are moved into the spendable balance
+    event Unlocked {
+        who: AccountId,
+        value: Balance,
+    }
+
+    // Event emitted with the Transfer events of a transfer that paid a fee. `net` went to `to` and `fee`
+    // to the fee collector.
+    event TransferFee {
+        from: AccountId,
+        to: AccountId,
+        net: Balance,
+        fee: Balance,
+    }
+
+    // Event emitted when the owner distributes dividends to the holders
+    event DividendDistributed {
+        value: Balance,
+    }
+
+    // Event emitted when a holder claims its dividends
+    event DividendClaimed {
+        who: AccountId,
+        value: Balance,
+    }
+
+    // Event emitted when the owner sets aside tokens for an airdrop
+    event AirdropStarted {
+        id: u32,
+        value: Balance,
+        deadline: BlockNumber,
+    }
+
+    // Event emitted when a claim of the airdrop is paid
+    event AirdropClaimed {
+        index: u32,
+        who: AccountId,
+        value: Balance,
+    }
+
+    // Event emitted when the owner takes back the unclaimed tokens of an airdrop
+    event AirdropSwept {
+        value: Balance,
+    }
+
     struct Erc20 {
         /// The total supply.
         total_supply: storage::Value<Balance>,
@@ -30,22 +286,153 @@ contract! {
         balances: storage::HashMap<AccountId, Balance>,
         /// Balances that are spendable by non-owners: (owner, spender) -> allowed
         allowances: storage::HashMap<(AccountId, AccountId), Balance>,
+        /// The account that deployed the contract. Only it can mint and burn.
+        owner: storage::Value<AccountId>,
+        /// While true, `transfer` and `transfer_from` fail. Minting and burning still work.
+        paused: storage::Value<bool>,
+        /// The account that the owner offered the ownership to. It becomes the owner once it accepts.
+        pending_owner: storage::Value<Option<AccountId>>,
+        /// The most tokens that can ever exist. `mint` fails beyond it.
+        cap: storage::Value<Balance>,
+        /// Accounts that can neither send nor receive tokens
+        frozen: storage::HashMap<AccountId, bool>,
+        /// The id of the latest snapshot. Ids start at 1, 0 means no snapshot was taken yet.
+        snapshot_id: storage::Value<u32>,
+        /// The total supply when each snapshot was taken
+        supply_snapshots: storage::HashMap<u32, Balance>,
+        /// The sum of all balances when each snapshot was taken, which is the weight that can vote on the
+        /// proposals of the snapshot
+        balances_total_snapshots: storage::HashMap<u32, Balance>,
+        /// Balances are saved copy-on-write: the first change to a balance after a snapshot saves the
+        /// old balance under the latest snapshot id, which is also recorded in `balance_saved_at`.
+        balance_snapshots: storage::HashMap<(AccountId, u32), Balance>,
+        balance_saved_at: storage::HashMap<AccountId, u32>,
+        /// Tokens held by the faucet. They are taken out of the owner's balance, so the total supply
+        /// does not change.
+        faucet_reservoir: storage::Value<Balance>,
+        /// The amount of each drip, and the number of blocks an account waits between drips
+        faucet_amount: storage::Value<Balance>,
+        faucet_cooldown: storage::Value<BlockNumber>,
+        /// The block of the last drip of each account
+        last_drip: storage::HashMap<AccountId, BlockNumber>,
+        /// Vesting grants. Like the faucet, the tokens of a grant are taken out of the owner's balance
+        /// until they are released.
+        grants: storage::HashMap<AccountId, Grant<Balance, BlockNumber>>,
+        /// The tokens staked by all accounts
+        total_staked: storage::Value<Balance>,
+        /// The tokens staked by each account, and its reward debt: the rewards per share it was already
+        /// paid for, times its stake. Its pending rewards are `stake * acc_reward_per_share - debt`.
+        stakes: storage::HashMap<AccountId, (Balance, Balance)>,
+        /// The rewards shared by the stakers every block
+        reward_rate: storage::Value<Balance>,
+        /// Rewards funded by the owner that were not shared out yet. Rewards stop when it runs out.
+        reward_pool: storage::Value<Balance>,
+        /// The rewards earned by one staked token since deployment, times `REWARD_PRECISION`
+        acc_reward_per_share: storage::Value<Balance>,
+        /// The block up to which `acc_reward_per_share` is up to date
+        last_reward_block: storage::Value<BlockNumber>,
+        /// The number of blocks a proposal is open for votes. Proposals can not be created while it is 0.
+        voting_period: storage::Value<BlockNumber>,
+        /// The percentage of the balances at the snapshot that has to vote for a proposal to pass. Tokens held
+        /// by the faucet, grants, stakes, locks, the dividend pool and the airdrop can not vote, so they do not count.
+        quorum_percent: storage::Value<u32>,
+        /// The id of the latest proposal. Ids start at 1.
+        proposal_count: storage::Value<u32>,
+        proposals: storage::HashMap<u32, Proposal<AccountId, Balance, BlockNumber>>,
+        /// Whether an account voted on a proposal
+        votes: storage::HashMap<(u32, AccountId), bool>,
+        /// Tokens sent with `transfer_locked`, as (value, unlock block). They are not part of the balance
+        /// until `unlock` moves them there.
+        locks: storage::HashMap<AccountId, Vec<(Balance, BlockNumber)>>,
+        /// While enabled, transfers pay `transfer_fee_bps` of the value to `fee_collector`
+        fee_enabled: storage::Value<bool>,
+        transfer_fee_bps: storage::Value<u32>,
+        fee_collector: storage::Value<AccountId>,
+        /// The sum of all balances. It is less than the total supply by the tokens held by the faucet,
+        /// grants, stakes, locks, the dividend pool and the airdrop.
+        balances_total: storage::Value<Balance>,
+        /// The dividends distributed per token of balance since deployment, times `REWARD_PRECISION`
+        dividend_per_token: storage::Value<Balance>,
+        /// Dividends that were distributed and not claimed yet
+        dividend_pool: storage::Value<Balance>,
+        /// Dividends are settled whenever a balance changes: the dividends earned by the old balance since
+        /// `dividend_paid_at` are added to `dividends_owed`, and `dividend_paid_at` moves up to the current
+        /// `dividend_per_token`.
+        dividends_owed: storage::HashMap<AccountId, Balance>,
+        dividend_paid_at: storage::HashMap<AccountId, Balance>,
+        /// The id of the latest airdrop. Ids start at 1, 0 means no airdrop was started yet.
+        airdrop_id: storage::Value<u32>,
+        /// The Merkle root of the claims of the latest airdrop. A claim is the leaf
+        /// `blake2_256((index, account, value).encode())`.
+        airdrop_root: storage::Value<[u8; 32]>,
+        /// Tokens of the latest airdrop that were not claimed yet. Like the faucet, they are taken out of the
+        /// owner's balance.
+        airdrop_pool: storage::Value<Balance>,
+        /// Claims are paid up to and including this block, then the owner can sweep what is left
+        airdrop_deadline: storage::Value<BlockNumber>,
+        /// Which claims of each airdrop were paid, 32 claim indexes per word: (airdrop id, index / 32) -> bits
+        airdrop_claimed: storage::HashMap<(u32, u32), u32>,
     }
 
     impl Deploy for Erc20 {
-        fn deploy(&mut self, init_value: Balance) {
+        /// `allocations` are paid out of `init_value` and the caller gets the rest. If they add up to more
+        /// than `init_value`, the initial supply is their sum. A `cap` below the initial supply is raised to
+        /// it, so the initial supply always fits.
+        fn deploy(&mut self, init_value: Balance, cap: Balance, allocations: Vec<(AccountId, Balance)>) {
             // ACTION: `set` the total supply to `init_value`
             // ACTION: `insert` the `init_value` as the `env.caller()` balance
+            let mut allocated: Balance = 0;
+            for (to, value) in allocations.iter() {
+                allocated = allocated.checked_add(*value).expect("allocations overflow the balance type");
+                let balance = *self.balances.get(to).unwrap_or(&0);
+                self.balances.insert(*to, balance + *value);
+            }
+            let init_value = if allocated > init_value { allocated } else { init_value };
+            let remainder = init_value - allocated;
+            let balance = *self.balances.get(&env.caller()).unwrap_or(&0);
+            self.balances.insert(env.caller(), balance + remainder);
             self.total_supply.set(init_value);
-            self.balances.insert(env.caller(), init_value);
+            self.cap.set(if cap < init_value { init_value } else { cap });
+            self.owner.set(env.caller());
+            self.paused.set(false);
+            self.pending_owner.set(None);
+            self.snapshot_id.set(0);
+            self.faucet_reservoir.set(0);
+            self.faucet_amount.set(0);
+            self.faucet_cooldown.set(0);
+            self.total_staked.set(0);
+            self.reward_rate.set(0);
+            self.reward_pool.set(0);
+            self.acc_reward_per_share.set(0);
+            self.last_reward_block.set(env.block_number());
+            self.voting_period.set(0);
+            self.quorum_percent.set(0);
+            self.proposal_count.set(0);
+            self.fee_enabled.set(false);
+            self.transfer_fee_bps.set(0);
+            self.fee_collector.set(env.caller());
+            self.balances_total.set(init_value);
+            self.dividend_per_token.set(0);
+            self.dividend_pool.set(0);
+            self.airdrop_id.set(0);
+            self.airdrop_root.set([0; 32]);
+            self.airdrop_pool.set(0);
+            self.airdrop_deadline.set(0);
             // ACTION: Call `env.emit` with the `Transfer` event
             //   HINT: According to the ERC20 specification, we should set from to `None`
             //   HINT: Since we use `Option<AccountId>`, you need to wrap accounts in `Some()`
             env.emit(Transfer {
                 from: None,
                 to: Some(env.caller()),
-                value: init_value
+                value: remainder
             });
+            for (to, value) in allocations {
+                env.emit(Transfer {
+                    from: None,
+                    to: Some(to),
+                    value
+                });
+            }
         }
     }
 
@@ -69,12 +456,163 @@ contract! {
             balance
         }
 
+        /// Returns the most tokens that can ever exist.
+        pub(external) fn cap(&self) -> Balance {
+            let cap = *self.cap;
+            env.println(&format!("Erc20::cap = {:?}", cap));
+            cap
+        }
+
+        /// Returns the balance of `owner` when snapshot `id` was taken, or None if there is no such snapshot.
+        pub(external) fn balance_of_at(&self, owner: AccountId, id: u32) -> Option<Balance> {
+            let balance = self.balance_at_impl(&owner, id);
+            env.println(&format!("Erc20::balance_of_at(owner = {:?}, id = {:?}) = {:?}", owner, id, balance));
+            balance
+        }
+
+        /// Returns the total supply when snapshot `id` was taken, or None if there is no such snapshot.
+        pub(external) fn total_supply_at(&self, id: u32) -> Option<Balance> {
+            let total_supply = self.supply_snapshots.get(&id).cloned();
+            env.println(&format!("Erc20::total_supply_at(id = {:?}) = {:?}", id, total_supply));
+            total_supply
+        }
+
+        /// Returns the tokens left in the faucet.
+        pub(external) fn faucet_reservoir(&self) -> Balance {
+            let reservoir = *self.faucet_reservoir;
+            env.println(&format!("Erc20::faucet_reservoir = {:?}", reservoir));
+            reservoir
+        }
+
+        /// Returns the tokens of the latest airdrop that were not claimed yet.
+        pub(external) fn airdrop_pool(&self) -> Balance {
+            let pool = *self.airdrop_pool;
+            env.println(&format!("Erc20::airdrop_pool = {:?}", pool));
+            pool
+        }
+
+        /// Returns true if the claim with this index of the latest airdrop was paid.
+        pub(external) fn is_airdrop_claimed(&self, index: u32) -> bool {
+            let claimed = self.airdrop_claimed_impl(index);
+            env.println(&format!("Erc20::is_airdrop_claimed(index = {:?}) = {:?}", index, claimed));
+            claimed
+        }
+
+        /// Returns the vesting grant of the beneficiary, if any.
+        pub(external) fn grant_of(&self, beneficiary: AccountId) -> Option<Grant<Balance, BlockNumber>> {
+            let grant = self.grants.get(&beneficiary).cloned();
+            env.println(&format!("Erc20::grant_of(beneficiary = {:?}) = {:?}", beneficiary, grant));
+            grant
+        }
+
+        /// Returns the tokens of the beneficiary's grant that vested and were not released yet.
+        pub(external) fn releasable(&self, beneficiary: AccountId) -> Balance {
+            let releasable = match self.grants.get(&beneficiary) {
+                Some(grant) => self.vested(grant, env.block_number()) - grant.released,
+                None => 0,
+            };
+            env.println(&format!("Erc20::releasable(beneficiary = {:?}) = {:?}", beneficiary, releasable));
+            releasable
+        }
+
+        /// Returns the tokens staked by all accounts.
+        pub(external) fn total_staked(&self) -> Balance {
+            let total_staked = *self.total_staked;
+            env.println(&format!("Erc20::total_staked = {:?}", total_staked));
+            total_staked
+        }
+
+        /// Returns the tokens staked by the account.
+        pub(external) fn staked_of(&self, who: AccountId) -> Balance {
+            let staked = self.stakes.get(&who).map(|(staked, _)| *staked).unwrap_or(0);
+            env.println(&format!("Erc20::staked_of(who = {:?}) = {:?}", who, staked));
+            staked
+        }
+
+        /// Returns the rewards the account can claim as of the current block.
+        pub(external) fn pending_rewards(&self, who: AccountId) -> Balance {
+            let (acc, _) = self.accrued_rewards(env.block_number());
+            let pending = self.pending_impl(&who, acc);
+            env.println(&format!("Erc20::pending_rewards(who = {:?}) = {:?}", who, pending));
+            pending
+        }
+
+        /// Returns the proposal with the given id, if any.
+        pub(external) fn proposal(&self, id: u32) -> Option<Proposal<AccountId, Balance, BlockNumber>> {
+            let proposal = self.proposals.get(&id).cloned();
+            env.println(&format!("Erc20::proposal(id = {:?}) = {:?}", id, proposal));
+            proposal
+        }
+
+        /// Returns the tokens of the account that are still locked or matured but not unlocked yet.
+        pub(external) fn locked_balance_of(&self, who: AccountId) -> Balance {
+            let locked = self.locks.get(&who).map(|locks| locks.iter().map(|(value, _)| *value).sum()).unwrap_or(0);
+            env.println(&format!("Erc20::locked_balance_of(who = {:?}) = {:?}", who, locked));
+            locked
+        }
+
+        /// Returns the locked tokens of the account that `unlock` would move into its balance now.
+        pub(external) fn unlockable_balance_of(&self, who: AccountId) -> Balance {
+            let now = env.block_number();
+            let unlockable = self.locks.get(&who)
+                .map(|locks| locks.iter().filter(|(_, at)| *at <= now).map(|(value, _)| *value).sum())
+                .unwrap_or(0);
+            env.println(&format!("Erc20::unlockable_balance_of(who = {:?}) = {:?}", who, unlockable));
+            unlockable
+        }
+
+        /// Returns the transfer fee in basis points and the fee collector, or None while fees are disabled.
+        pub(external) fn transfer_fee(&self) -> Option<(u32, AccountId)> {
+            let fee = if *self.fee_enabled {
+                Some((*self.transfer_fee_bps, *self.fee_collector))
+            } else {
+                None
+            };
+            env.println(&format!("Erc20::transfer_fee = {:?}", fee));
+            fee
+        }
+
+        /// Returns the dividends the account can claim.
+        pub(external) fn dividend_of(&self, who: AccountId) -> Balance {
+            let dividend = self.dividend_impl(&who);
+            env.println(&format!("Erc20::dividend_of(who = {:?}) = {:?}", who, dividend));
+            dividend
+        }
+
+        /// Returns the owner of the contract.
+        pub(external) fn owner(&self) -> AccountId {
+            let owner = *self.owner;
+            env.println(&format!("Erc20::owner = {:?}", owner));
+            owner
+        }
+
+        /// Returns the account that can accept the ownership, if any.
+        pub(external) fn pending_owner(&self) -> Option<AccountId> {
+            let pending_owner = *self.pending_owner;
+            env.println(&format!("Erc20::pending_owner = {:?}", pending_owner));
+            pending_owner
+        }
+
+        /// Returns true if the account is frozen.
+        pub(external) fn is_frozen(&self, account: AccountId) -> bool {
+            let frozen = self.is_frozen_impl(&account);
+            env.println(&format!("Erc20::is_frozen(account = {:?}) = {:?}", account, frozen));
+            frozen
+        }
+
+        /// Returns true while transfers are paused.
+        pub(external) fn paused(&self) -> bool {
+            let paused = *self.paused;
+            env.println(&format!("Erc20::paused = {:?}", paused));
+            paused
+        }
+
         /// Returns the amount of tokens that an owner allowed to a spender.
         pub(external) fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance {
             // ACTION: Create a getter for the `allowances` HashMap
             //   HINT: Take a look at the getters above if you forget the details
             // ACTION: Return the `allowance` at the end
-           let allowance = self.allowance_or_zero(&owner, &spender);
+            let allowance = self.allowance_or_zero(&owner, &spender);
             env.println(&format!(
                 "Erc20::allowance(owner = {:?}, spender = {:?}) = {:?}",
                 owner, spender, allowance
@@ -83,11 +621,104 @@ contract! {
         }
 
         /// Transfers token from the sender to the `to` AccountId.
-        pub(external) fn transfer(&mut self, to: AccountId, value: Balance) -> bool {
+        pub(external) fn transfer(&mut self, to: AccountId, value: Balance) -> Result<(), Error> {
             // ACTION: Call the `transfer_impl` with `from` as `env.caller()`
             self.transfer_impl(env, env.caller(), to, value)
         }
 
+        /// Transfers tokens from the sender to a lock of the `to` AccountId, which it can not spend before
+        /// `unlock_block`. The value can not be zero and `unlock_block` at most `MAX_LOCKUP` blocks away, so
+        /// the lock slots of an account can not be filled for free or for good.
+        pub(external) fn transfer_locked(&mut self, to: AccountId, value: Balance, unlock_block: BlockNumber) -> Result<(), Error> {
+            let from = env.caller();
+            if *self.paused {
+                return Err(Error::Paused)
+            }
+            if self.is_frozen_impl(&from) || self.is_frozen_impl(&to) {
+                return Err(Error::AccountFrozen)
+            }
+            if value == 0 || unlock_block > env.block_number() + BlockNumber::from(MAX_LOCKUP) {
+                return Err(Error::InvalidLock)
+            }
+            let balance_from = self.balance_of_or_zero(&from);
+            if balance_from < value {
+                return Err(Error::InsufficientBalance)
+            }
+            let mut locks = self.locks.get(&to).cloned().unwrap_or_default();
+            if locks.len() >= MAX_LOCKS {
+                return Err(Error::TooManyLocks)
+            }
+            locks.push((value, unlock_block));
+            self.locks.insert(to, locks);
+            self.set_balance(from, balance_from - value);
+            env.emit(TransferLocked {
+                from,
+                to,
+                value,
+                unlock_block
+            });
+            Ok(())
+        }
+
+        /// Move the caller's matured locks into its spendable balance. Matured locks are removed even when
+        /// they hold no tokens, such as zero value locks made before those were refused, so they free their slots.
+        pub(external) fn unlock(&mut self) -> Result<(), Error> {
+            let who = env.caller();
+            if self.is_frozen_impl(&who) {
+                return Err(Error::AccountFrozen)
+            }
+            let now = env.block_number();
+            let (matured, locked): (Vec<_>, Vec<_>) = self.locks.get(&who).cloned().unwrap_or_default()
+                .into_iter()
+                .partition(|(_, at)| *at <= now);
+            if matured.is_empty() {
+                return Err(Error::NothingToRelease)
+            }
+            if locked.is_empty() {
+                self.locks.remove(&who);
+            } else {
+                self.locks.insert(who, locked);
+            }
+            let value: Balance = matured.iter().map(|(value, _)| *value).sum();
+            if value == 0 {
+                return Ok(())
+            }
+            let balance = self.balance_of_or_zero(&who);
+            self.set_balance(who, balance + value);
+            env.emit(Unlocked {
+                who,
+                value
+            });
+            Ok(())
+        }
+
+        /// Transfers tokens from the sender to several AccountIds, for example for an airdrop. The total is
+        /// checked against the sender's balance first, so either every transfer is made or none is.
+        pub(external) fn transfer_batch(&mut self, transfers: Vec<(AccountId, Balance)>) -> bool {
+            if *self.paused || transfers.len() > MAX_BATCH_TRANSFERS {
+                return false
+            }
+            let from = env.caller();
+            if self.is_frozen_impl(&from) || transfers.iter().any(|(to, _)| self.is_frozen_impl(to)) {
+                return false
+            }
+            let mut total: Balance = 0;
+            for (_, value) in transfers.iter() {
+                total = match total.checked_add(*value) {
+                    Some(total) => total,
+                    None => return false,
+                };
+            }
+            if self.balance_of_or_zero(&from) < total {
+                return false
+            }
+            for (to, value) in transfers {
+                // Can not fail, the total was checked above
+                let _ = self.transfer_impl(env, from, to, value);
+            }
+            true
+        }
+
         /// Approve the passed AccountId to spend the specified amount of tokens
         /// on the behalf of the message's sender.
         pub(external) fn approve(&mut self, spender: AccountId, value: Balance) -> bool {
@@ -106,131 +737,1816 @@ contract! {
             true
         }
 
+        /// Raise the allowance of `spender` by `delta`. Unlike `approve`, this can not be front-run
+        /// by the spender to use both the old and the new allowance. Fails on overflow.
+        pub(external) fn increase_allowance(&mut self, spender: AccountId, delta: Balance) -> bool {
+            let owner = env.caller();
+            let allowance = match self.allowance_or_zero(&owner, &spender).checked_add(delta) {
+                Some(allowance) => allowance,
+                None => return false,
+            };
+            self.allowances.insert((owner, spender), allowance);
+            env.emit(Approval {
+                owner: owner,
+                spender: spender,
+                value: allowance
+            });
+            true
+        }
+
+        /// Lower the allowance of `spender` by `delta`. Fails if the allowance is smaller than `delta`,
+        /// which means the spender already used part of it.
+        pub(external) fn decrease_allowance(&mut self, spender: AccountId, delta: Balance) -> bool {
+            let owner = env.caller();
+            let allowance = match self.allowance_or_zero(&owner, &spender).checked_sub(delta) {
+                Some(allowance) => allowance,
+                None => return false,
+            };
+            self.allowances.insert((owner, spender), allowance);
+            env.emit(Approval {
+                owner: owner,
+                spender: spender,
+                value: allowance
+            });
+            true
+        }
+
         /// Transfer tokens from one AccountId to another.
-        pub(external) fn transfer_from(&mut self, from: AccountId, to: AccountId, value: Balance) -> bool {
+        pub(external) fn transfer_from(&mut self, from: AccountId, to: AccountId, value: Balance) -> Result<(), Error> {
             // ACTION: Get the allowance for `(from, env.caller())` using `allowance_or_zero`
-            // ACTION: `if` the `allowance` is less than the `value`, exit early and return `false`
+            // ACTION: `if` the `allowance` is less than the `value`, exit early with `InsufficientAllowance`
             // ACTION: `insert` the new allowance into the map for `(from, env.caller())`
             // ACTION: Finally, call the `transfer_impl` for `from` and `to`
+            //   NOTE: Storage writes are not reverted when a message returns an error, so the allowance
+            //         is only spent once the transfer went through
             let allowance = self.allowance_or_zero(&from, &env.caller());
             if allowance < value {
-                return false
+                return Err(Error::InsufficientAllowance)
             }
+            self.transfer_impl(env, from, to, value)?;
             self.allowances.insert((from, env.caller()), allowance - value);
-            self.transfer_impl(env, from, to, value)
+            env.emit(Approval {
+                owner: from,
+                spender: env.caller(),
+                value: allowance - value
+            });
+            Ok(())
         }
-    }
 
-    impl Erc20 {
-        /// Returns the balance of the AccountId or 0 if there is no balance.
-        fn balance_of_or_zero(&self, of: &AccountId) -> Balance {
-            // ACTION: `get` the balance of `of`, then `unwrap_or` fallback to 0
-            // ACTION: Return the balance
-            *self.balances.get(of).unwrap_or(&0)
+        /// Create new tokens for the `to` AccountId. Only the owner can mint.
+        pub(external) fn mint(&mut self, to: AccountId, value: Balance) -> bool {
+            if !self.is_owner(&env.caller()) {
+                return false
+            }
+            let total_supply = match self.total_supply.checked_add(value) {
+                Some(total_supply) if total_supply <= *self.cap => total_supply,
+                _ => return false,
+            };
+            let balance_to = self.balance_of_or_zero(&to);
+            self.total_supply.set(total_supply);
+            self.set_balance(to, balance_to + value);
+            env.emit(Transfer {
+                from: None,
+                to: Some(to),
+                value
+            });
+            true
         }
 
-        /// Returns the allowance or 0 of there is no allowance.
-        fn allowance_or_zero(&self, owner: &AccountId, spender: &AccountId) -> Balance {
-            // ACTION: Return the allowance between `(owner, spender)`, `unwrap_or` return 0
-            *self.allowances.get(&(*owner, *spender)).unwrap_or(&0)
+        /// Offer the ownership to `new_owner`. Nothing changes until it calls `accept_ownership`, so a
+        /// mistyped account can not lock the owner functions. A later offer replaces this one.
+        pub(external) fn transfer_ownership(&mut self, new_owner: AccountId) -> bool {
+            if !self.is_owner(&env.caller()) {
+                return false
+            }
+            self.pending_owner.set(Some(new_owner));
+            true
         }
 
-        /// Transfers token from a specified AccountId to another AccountId.
-        fn transfer_impl(&mut self, env: &mut ink_model::EnvHandler<ink_core::env::ContractEnv<DefaultSrmlTypes>>, from: AccountId, to: AccountId, value: Balance) -> bool {
-            // ACTION: Get the balance for `from` and `to`
-            //   HINT: Use the `balance_of_or_zero` function to do this
-            // ACTION: If `balance` from is less than `value`, return `false`
-            // ACTION: Insert new values for `from` and `to`
-            //         * balance_from - value
-            //         * balance_to + value
-            let balance_from = self.balance_of_or_zero(&from);
-            let balance_to = self.balance_of_or_zero(&to);
-            if balance_from < value {
+        /// Take over the ownership that was offered to the caller.
+        pub(external) fn accept_ownership(&mut self) -> bool {
+            let caller = env.caller();
+            if *self.pending_owner != Some(caller) {
                 return false
             }
-            self.balances.insert(from, balance_from - value);
-            self.balances.insert(to, balance_to + value);
-            // ACTION: Call `env.emit` with the `Transfer` event
-            //   HINT: Since we use `Option<AccountId>`, you need to wrap accounts in `Some()`
-            env.emit( Transfer {
-                from: Some(from),
-                to: Some(to),
-                value
+            let previous = *self.owner;
+            self.owner.set(caller);
+            self.pending_owner.set(None);
+            env.emit(OwnershipTransferred {
+                from: previous,
+                to: caller
             });
             true
         }
-    }
-}
 
+        /// Stop the account from sending or receiving tokens. Only the owner can freeze accounts.
+        pub(external) fn freeze_account(&mut self, account: AccountId) -> bool {
+            if !self.is_owner(&env.caller()) || self.is_frozen_impl(&account) {
+                return false
+            }
+            self.frozen.insert(account, true);
+            env.emit(AccountFrozen {
+                account
+            });
+            true
+        }
 
-#[cfg(all(test, feature = "test-env"))]
-mod tests {
-    use super::*;
-    use ink_core::env;
-    type Types = ink_core::env::DefaultSrmlTypes;
+        /// Let a frozen account send and receive tokens again. Only the owner can unfreeze accounts.
+        pub(external) fn unfreeze_account(&mut self, account: AccountId) -> bool {
+            if !self.is_owner(&env.caller()) || !self.is_frozen_impl(&account) {
+                return false
+            }
+            self.frozen.remove(&account);
+            env.emit(AccountUnfrozen {
+                account
+            });
+            true
+        }
 
-    #[test]
-    fn deployment_works() {
-        let alice = AccountId::from([0x0; 32]);
-        env::test::set_caller::<Types>(alice);
+        /// Destroy tokens of the `from` AccountId using the caller's allowance, for example by an escrow
+        /// contract that was approved to manage them.
+        pub(external) fn burn_from(&mut self, from: AccountId, value: Balance) -> Result<(), Error> {
+            let spender = env.caller();
+            let allowance = self.allowance_or_zero(&from, &spender);
+            if allowance < value {
+                return Err(Error::InsufficientAllowance)
+            }
+            if self.is_frozen_impl(&from) {
+                return Err(Error::AccountFrozen)
+            }
+            let balance_from = self.balance_of_or_zero(&from);
+            if balance_from < value {
+                return Err(Error::InsufficientBalance)
+            }
+            self.set_balance(from, balance_from - value);
+            self.total_supply.set(*self.total_supply - value);
+            self.allowances.insert((from, spender), allowance - value);
+            env.emit(Transfer {
+                from: Some(from),
+                to: None,
+                value
+            });
+            env.emit(Approval {
+                owner: from,
+                spender: spender,
+                value: allowance - value
+            });
+            Ok(())
+        }
 
-        // Deploy the contract with some `init_value`
-        let erc20 = Erc20::deploy_mock(1234);
-        // Check that the `total_supply` is `init_value`
-        assert_eq!(erc20.total_supply(), 1234);
-        // Check that `balance_of` Alice is `init_value`
-        assert_eq!(erc20.balance_of(alice), 1234);
-    }
+        /// Set the amount of each faucet drip and the cooldown in blocks. Only the owner can do this.
+        pub(external) fn set_faucet(&mut self, amount: Balance, cooldown: BlockNumber) -> bool {
+            if !self.is_owner(&env.caller()) {
+                return false
+            }
+            self.faucet_amount.set(amount);
+            self.faucet_cooldown.set(cooldown);
+            true
+        }
 
-    #[test]
-    fn transfer_works() {
+        /// Move tokens from the owner's balance into the faucet reservoir. Only the owner can refill.
+        pub(external) fn refill_faucet(&mut self, value: Balance) -> bool {
+            let owner = env.caller();
+            if !self.is_owner(&owner) {
+                return false
+            }
+            let balance = self.balance_of_or_zero(&owner);
+            if balance < value {
+                return false
+            }
+            self.set_balance(owner, balance - value);
+            self.faucet_reservoir.set(*self.faucet_reservoir + value);
+            env.emit(FaucetRefilled {
+                value
+            });
+            true
+        }
+
+        /// Pay one drip from the faucet to the caller, at most once every `faucet_cooldown` blocks.
+        pub(external) fn drip(&mut self) -> Result<(), Error> {
+            let caller = env.caller();
+            if *self.paused {
+                return Err(Error::Paused)
+            }
+            if self.is_frozen_impl(&caller) {
+                return Err(Error::AccountFrozen)
+            }
+            let amount = *self.faucet_amount;
+            if amount == 0 || *self.faucet_reservoir < amount {
+                return Err(Error::FaucetEmpty)
+            }
+            let now = env.block_number();
+            if let Some(last) = self.last_drip.get(&caller) {
+                if now < *last + *self.faucet_cooldown {
+                    return Err(Error::CooldownActive)
+                }
+            }
+            self.faucet_reservoir.set(*self.faucet_reservoir - amount);
+            self.last_drip.insert(caller, now);
+            let balance = self.balance_of_or_zero(&caller);
+            self.set_balance(caller, balance + amount);
+            env.emit(Drip {
+                to: caller,
+                value: amount
+            });
+            Ok(())
+        }
+
+        /// Set aside `value` of the owner's tokens for the beneficiary, vesting from the current block.
+        /// A beneficiary has at most one grant at a time. Only the owner can grant.
+        pub(external) fn grant_vesting(
+            &mut self,
+            beneficiary: AccountId,
+            value: Balance,
+            cliff: BlockNumber,
+            duration: BlockNumber,
+            revocable: bool
+        ) -> bool {
+            let owner = env.caller();
+            if !self.is_owner(&owner) || value == 0 || duration == 0 || cliff > duration {
+                return false
+            }
+            if self.grants.get(&beneficiary).is_some() {
+                return false
+            }
+            let balance = self.balance_of_or_zero(&owner);
+            if balance < value {
+                return false
+            }
+            self.set_balance(owner, balance - value);
+            self.grants.insert(beneficiary, Grant {
+                total: value,
+                released: 0,
+                start: env.block_number(),
+                cliff,
+                duration,
+                revocable,
+            });
+            env.emit(GrantCreated {
+                beneficiary,
+                value
+            });
+            true
+        }
+
+        /// Pay the caller the tokens of its grant that vested since the last release. The grant is removed
+        /// once all of it is released.
+        pub(external) fn release(&mut self) -> Result<(), Error> {
+            let beneficiary = env.caller();
+            if *self.paused {
+                return Err(Error::Paused)
+            }
+            if self.is_frozen_impl(&beneficiary) {
+                return Err(Error::AccountFrozen)
+            }
+            let mut grant = match self.grants.get(&beneficiary) {
+                Some(grant) => *grant,
+                None => return Err(Error::NothingToRelease),
+            };
+            let value = self.vested(&grant, env.block_number()) - grant.released;
+            if value == 0 {
+                return Err(Error::NothingToRelease)
+            }
+            grant.released += value;
+            if grant.released == grant.total {
+                self.grants.remove(&beneficiary);
+            } else {
+                self.grants.insert(beneficiary, grant);
+            }
+            let balance = self.balance_of_or_zero(&beneficiary);
+            self.set_balance(beneficiary, balance + value);
+            env.emit(TokensReleased {
+                beneficiary,
+                value
+            });
+            Ok(())
+        }
+
+        /// End a revocable grant. What vested so far is paid to the beneficiary and the rest goes back to
+        /// the owner. Only the owner can revoke.
+        pub(external) fn revoke_vesting(&mut self, beneficiary: AccountId) -> bool {
+            let owner = env.caller();
+            if !self.is_owner(&owner) {
+                return false
+            }
+            let grant = match self.grants.get(&beneficiary) {
+                Some(grant) if grant.revocable => *grant,
+                _ => return false,
+            };
+            let vested = self.vested(&grant, env.block_number());
+            self.grants.remove(&beneficiary);
+            let unreleased = vested - grant.released;
+            if unreleased > 0 {
+                let balance = self.balance_of_or_zero(&beneficiary);
+                self.set_balance(beneficiary, balance + unreleased);
+            }
+            let refund = grant.total - vested;
+            let balance = self.balance_of_or_zero(&owner);
+            self.set_balance(owner, balance + refund);
+            env.emit(GrantRevoked {
+                beneficiary,
+                refund
+            });
+            true
+        }
+
+        /// Set the rewards shared by the stakers every block. Only the owner can do this.
+        pub(external) fn set_reward_rate(&mut self, rate: Balance) -> bool {
+            if !self.is_owner(&env.caller()) {
+                return false
+            }
+            // Rewards up to now are shared at the old rate
+            self.update_rewards(env.block_number());
+            self.reward_rate.set(rate);
+            true
+        }
+
+        /// Move tokens from the owner's balance into the reward pool. Only the owner can fund rewards.
+        pub(external) fn fund_rewards(&mut self, value: Balance) -> bool {
+            let owner = env.caller();
+            if !self.is_owner(&owner) {
+                return false
+            }
+            let balance = self.balance_of_or_zero(&owner);
+            if balance < value {
+                return false
+            }
+            self.update_rewards(env.block_number());
+            self.set_balance(owner, balance - value);
+            self.reward_pool.set(*self.reward_pool + value);
+            true
+        }
+
+        /// Stake tokens from the caller's balance. Pending rewards are paid out first.
+        pub(external) fn stake(&mut self, value: Balance) -> Result<(), Error> {
+            let who = env.caller();
+            if *self.paused {
+                return Err(Error::Paused)
+            }
+            if self.is_frozen_impl(&who) {
+                return Err(Error::AccountFrozen)
+            }
+            let balance = self.balance_of_or_zero(&who);
+            if balance < value {
+                return Err(Error::InsufficientBalance)
+            }
+            let acc = self.update_rewards(env.block_number());
+            self.pay_rewards(env, who, acc);
+            let balance = self.balance_of_or_zero(&who);
+            self.set_balance(who, balance - value);
+            let staked = self.stakes.get(&who).map(|(staked, _)| *staked).unwrap_or(0) + value;
+            self.stakes.insert(who, (staked, staked * acc / REWARD_PRECISION));
+            self.total_staked.set(*self.total_staked + value);
+            env.emit(Staked {
+                who,
+                value
+            });
+            Ok(())
+        }
+
+        /// Take staked tokens back into the caller's balance. Pending rewards are paid out first.
+        pub(external) fn unstake(&mut self, value: Balance) -> Result<(), Error> {
+            let who = env.caller();
+            if *self.paused {
+                return Err(Error::Paused)
+            }
+            if self.is_frozen_impl(&who) {
+                return Err(Error::AccountFrozen)
+            }
+            let staked = self.stakes.get(&who).map(|(staked, _)| *staked).unwrap_or(0);
+            if staked < value {
+                return Err(Error::InsufficientBalance)
+            }
+            let acc = self.update_rewards(env.block_number());
+            self.pay_rewards(env, who, acc);
+            let staked = staked - value;
+            if staked == 0 {
+                self.stakes.remove(&who);
+            } else {
+                self.stakes.insert(who, (staked, staked * acc / REWARD_PRECISION));
+            }
+            self.total_staked.set(*self.total_staked - value);
+            let balance = self.balance_of_or_zero(&who);
+            self.set_balance(who, balance + value);
+            env.emit(Unstaked {
+                who,
+                value
+            });
+            Ok(())
+        }
+
+        /// Pay the caller its pending staking rewards.
+        pub(external) fn claim(&mut self) -> Result<(), Error> {
+            let who = env.caller();
+            if *self.paused {
+                return Err(Error::Paused)
+            }
+            if self.is_frozen_impl(&who) {
+                return Err(Error::AccountFrozen)
+            }
+            let acc = self.update_rewards(env.block_number());
+            if self.pay_rewards(env, who, acc) == 0 {
+                return Err(Error::NoRewards)
+            }
+            Ok(())
+        }
+
+        /// Set the transfer fee in basis points and the account it is paid to. Only the owner can do this.
+        pub(external) fn set_transfer_fee(&mut self, bps: u32, collector: AccountId) -> bool {
+            if !self.is_owner(&env.caller()) || bps > FEE_DENOMINATOR {
+                return false
+            }
+            self.transfer_fee_bps.set(bps);
+            self.fee_collector.set(collector);
+            true
+        }
+
+        /// Turn the transfer fee on or off. Only the owner can do this.
+        pub(external) fn set_fee_enabled(&mut self, enabled: bool) -> bool {
+            if !self.is_owner(&env.caller()) {
+                return false
+            }
+            self.fee_enabled.set(enabled);
+            true
+        }
+
+        /// Move tokens from the owner's balance into the dividend pool, shared by all holders in proportion
+        /// to their balances after the move. Only the owner can distribute.
+        pub(external) fn distribute(&mut self, value: Balance) -> bool {
+            let owner = env.caller();
+            if !self.is_owner(&owner) || value == 0 {
+                return false
+            }
+            let balance = self.balance_of_or_zero(&owner);
+            if balance < value || *self.balances_total == value {
+                return false
+            }
+            // The holders share the balances that are left once the owner paid the value
+            let scaled = match value.checked_mul(REWARD_PRECISION) {
+                Some(scaled) => scaled,
+                None => return false,
+            };
+            let per_token = match self.dividend_per_token.checked_add(scaled / (*self.balances_total - value)) {
+                Some(per_token) => per_token,
+                None => return false,
+            };
+            self.set_balance(owner, balance - value);
+            self.dividend_per_token.set(per_token);
+            self.dividend_pool.set(*self.dividend_pool + value);
+            env.emit(DividendDistributed {
+                value
+            });
+            true
+        }
+
+        /// Pay the caller its dividends.
+        pub(external) fn claim_dividend(&mut self) -> Result<(), Error> {
+            let who = env.caller();
+            if *self.paused {
+                return Err(Error::Paused)
+            }
+            if self.is_frozen_impl(&who) {
+                return Err(Error::AccountFrozen)
+            }
+            let value = self.dividend_impl(&who);
+            if value == 0 {
+                return Err(Error::NoDividends)
+            }
+            // Settling at the current balance moves everything the caller earned into `dividends_owed`
+            let balance = self.balance_of_or_zero(&who);
+            self.settle_dividends(&who, balance);
+            self.dividends_owed.insert(who, 0);
+            self.dividend_pool.set(*self.dividend_pool - value);
+            self.set_balance(who, balance + value);
+            env.emit(DividendClaimed {
+                who,
+                value
+            });
+            Ok(())
+        }
+
+        /// Set aside `value` of the owner's tokens for the claims under the Merkle `root`, paid up to and
+        /// including block `deadline`. One airdrop runs at a time, so a new one starts once the tokens of the
+        /// last one were claimed or swept. Only the owner can start an airdrop.
+        pub(external) fn start_airdrop(&mut self, root: [u8; 32], value: Balance, deadline: BlockNumber) -> bool {
+            let owner = env.caller();
+            if !self.is_owner(&owner) || value == 0 || *self.airdrop_pool > 0 || deadline < env.block_number() {
+                return false
+            }
+            let balance = self.balance_of_or_zero(&owner);
+            if balance < value {
+                return false
+            }
+            self.set_balance(owner, balance - value);
+            let id = *self.airdrop_id + 1;
+            self.airdrop_id.set(id);
+            self.airdrop_root.set(root);
+            self.airdrop_pool.set(value);
+            self.airdrop_deadline.set(deadline);
+            env.emit(AirdropStarted {
+                id,
+                value,
+                deadline
+            });
+            true
+        }
+
+        /// Pay the caller the `value` of claim `index` of the latest airdrop. `proof` holds the hashes that lead
+        /// from the leaf of the claim to the root, as checked by `merkle_root`. Each claim is paid once.
+        pub(external) fn claim_airdrop(
+            &mut self,
+            index: u32,
+            value: Balance,
+            proof: Vec<[u8; 32]>
+        ) -> Result<(), Error> {
+            let who = env.caller();
+            if *self.paused {
+                return Err(Error::Paused)
+            }
+            if self.is_frozen_impl(&who) {
+                return Err(Error::AccountFrozen)
+            }
+            if *self.airdrop_id == 0 || env.block_number() > *self.airdrop_deadline {
+                return Err(Error::AirdropClosed)
+            }
+            if self.airdrop_claimed_impl(index) {
+                return Err(Error::AlreadyClaimed)
+            }
+            let leaf = blake2_256(&(index, who, value).encode());
+            if proof.len() > MAX_PROOF_LENGTH || merkle_root(leaf, &proof) != *self.airdrop_root {
+                return Err(Error::InvalidProof)
+            }
+            // Only a root that promises more than the owner set aside runs out
+            if *self.airdrop_pool < value {
+                return Err(Error::InsufficientBalance)
+            }
+            let key = (*self.airdrop_id, index / 32);
+            let bits = *self.airdrop_claimed.get(&key).unwrap_or(&0);
+            self.airdrop_claimed.insert(key, bits | (1 << (index % 32)));
+            self.airdrop_pool.set(*self.airdrop_pool - value);
+            let balance = self.balance_of_or_zero(&who);
+            self.set_balance(who, balance + value);
+            env.emit(AirdropClaimed {
+                index,
+                who,
+                value
+            });
+            Ok(())
+        }
+
+        /// Return the unclaimed tokens of the latest airdrop to the owner once its deadline passed. Only the
+        /// owner can sweep.
+        pub(external) fn sweep_airdrop(&mut self) -> bool {
+            let owner = env.caller();
+            let value = *self.airdrop_pool;
+            if !self.is_owner(&owner) || value == 0 || env.block_number() <= *self.airdrop_deadline {
+                return false
+            }
+            self.airdrop_pool.set(0);
+            let balance = self.balance_of_or_zero(&owner);
+            self.set_balance(owner, balance + value);
+            env.emit(AirdropSwept {
+                value
+            });
+            true
+        }
+
+        /// Record the current balances under a new snapshot id, which is returned. Only the owner can
+        /// take snapshots, here or with `propose`, so that nobody else can make every transfer pay for an
+        /// extra storage write.
+        pub(external) fn snapshot(&mut self) -> Option<u32> {
+            if !self.is_owner(&env.caller()) {
+                return None
+            }
+            Some(self.snapshot_impl(env))
+        }
+
+        /// Set how many blocks proposals are open for votes and the quorum. Only the owner can do this.
+        pub(external) fn set_voting(&mut self, period: BlockNumber, quorum_percent: u32) -> bool {
+            if !self.is_owner(&env.caller()) || quorum_percent > 100 {
+                return false
+            }
+            self.voting_period.set(period);
+            self.quorum_percent.set(quorum_percent);
+            true
+        }
+
+        /// Create a proposal for the hash of an off-chain payload. It takes a new snapshot, so the voting
+        /// weights are the balances as of now. Only the owner can propose, as only it can take snapshots.
+        pub(external) fn propose(&mut self, payload: [u8; 32]) -> Option<u32> {
+            let proposer = env.caller();
+            if *self.voting_period == 0 || !self.is_owner(&proposer) {
+                return None
+            }
+            let snapshot = self.snapshot_impl(env);
+            let id = *self.proposal_count + 1;
+            self.proposal_count.set(id);
+            self.proposals.insert(id, Proposal {
+                proposer,
+                payload,
+                snapshot,
+                end: env.block_number() + *self.voting_period,
+                yes: 0,
+                no: 0,
+                executed: false,
+            });
+            env.emit(ProposalCreated {
+                id,
+                proposer
+            });
+            Some(id)
+        }
+
+        /// Vote on an open proposal with the caller's balance at the proposal's snapshot. Tokens moved after
+        /// the proposal was created do not change the weight. Staked tokens and tokens held by the faucet
+        /// or in vesting grants are not part of the balance and do not count.
+        pub(external) fn vote(&mut self, id: u32, approve: bool) -> Result<(), Error> {
+            let voter = env.caller();
+            let mut proposal = match self.proposals.get(&id) {
+                Some(proposal) => *proposal,
+                None => return Err(Error::ProposalNotFound),
+            };
+            if env.block_number() > proposal.end {
+                return Err(Error::VotingClosed)
+            }
+            if self.votes.get(&(id, voter)).is_some() {
+                return Err(Error::AlreadyVoted)
+            }
+            let weight = self.balance_at_impl(&voter, proposal.snapshot).unwrap_or(0);
+            if weight == 0 {
+                return Err(Error::NoVotingWeight)
+            }
+            if approve {
+                proposal.yes += weight;
+            } else {
+                proposal.no += weight;
+            }
+            self.proposals.insert(id, proposal);
+            self.votes.insert((id, voter), approve);
+            env.emit(Voted {
+                id,
+                voter,
+                approve,
+                weight
+            });
+            Ok(())
+        }
+
+        /// Mark a proposal as executed once its voting period is over, the quorum voted and more weight
+        /// approved it than rejected it. Anyone can execute.
+        pub(external) fn execute(&mut self, id: u32) -> bool {
+            let mut proposal = match self.proposals.get(&id) {
+                Some(proposal) => *proposal,
+                None => return false,
+            };
+            if proposal.executed || env.block_number() <= proposal.end || proposal.yes <= proposal.no {
+                return false
+            }
+            let voteable = *self.balances_total_snapshots.get(&proposal.snapshot).unwrap_or(&0);
+            let quorum = voteable / 100 * Balance::from(*self.quorum_percent);
+            if proposal.yes + proposal.no < quorum {
+                return false
+            }
+            proposal.executed = true;
+            self.proposals.insert(id, proposal);
+            env.emit(ProposalExecuted {
+                id
+            });
+            true
+        }
+
+        /// Stop all transfers, as a circuit breaker. Only the owner can pause.
+        pub(external) fn pause(&mut self) -> bool {
+            if !self.is_owner(&env.caller()) || *self.paused {
+                return false
+            }
+            self.paused.set(true);
+            env.emit(Paused {
+                by: env.caller()
+            });
+            true
+        }
+
+        /// Allow transfers again. Only the owner can unpause.
+        pub(external) fn unpause(&mut self) -> bool {
+            if !self.is_owner(&env.caller()) || !*self.paused {
+                return false
+            }
+            self.paused.set(false);
+            env.emit(Unpaused {
+                by: env.caller()
+            });
+            true
+        }
+
+        /// Destroy tokens of the `from` AccountId. Only the owner can burn.
+        pub(external) fn burn(&mut self, from: AccountId, value: Balance) -> bool {
+            if !self.is_owner(&env.caller()) {
+                return false
+            }
+            let balance_from = self.balance_of_or_zero(&from);
+            if balance_from < value {
+                return false
+            }
+            self.set_balance(from, balance_from - value);
+            self.total_supply.set(*self.total_supply - value);
+            env.emit(Transfer {
+                from: Some(from),
+                to: None,
+                value
+            });
+            true
+        }
+    }
+
+    impl Erc20 {
+        /// Returns true if the caller is the owner of the contract.
+        fn is_owner(&self, caller: &AccountId) -> bool {
+            *self.owner == *caller
+        }
+
+        /// Returns true if the account is in the frozen list.
+        fn is_frozen_impl(&self, account: &AccountId) -> bool {
+            *self.frozen.get(account).unwrap_or(&false)
+        }
+
+        /// Changes a balance, first saving the old balance if a snapshot was taken since it last changed.
+        fn set_balance(&mut self, account: AccountId, value: Balance) {
+            let id = *self.snapshot_id;
+            let saved_at = *self.balance_saved_at.get(&account).unwrap_or(&0);
+            if id > saved_at {
+                let old = self.balance_of_or_zero(&account);
+                self.balance_snapshots.insert((account, id), old);
+                self.balance_saved_at.insert(account, id);
+            }
+            let old = self.balance_of_or_zero(&account);
+            self.settle_dividends(&account, old);
+            self.balances_total.set(*self.balances_total - old + value);
+            self.balances.insert(account, value);
+        }
+
+        /// Adds the dividends earned by `balance` since the account was last settled to what it is owed.
+        fn settle_dividends(&mut self, account: &AccountId, balance: Balance) {
+            let per_token = *self.dividend_per_token;
+            let paid_at = *self.dividend_paid_at.get(account).unwrap_or(&0);
+            if per_token == paid_at {
+                return
+            }
+            let earned = balance * (per_token - paid_at) / REWARD_PRECISION;
+            if earned > 0 {
+                let owed = *self.dividends_owed.get(account).unwrap_or(&0);
+                self.dividends_owed.insert(*account, owed + earned);
+            }
+            self.dividend_paid_at.insert(*account, per_token);
+        }
+
+        /// The dividends owed to the account plus those its current balance earned since it was settled.
+        fn dividend_impl(&self, who: &AccountId) -> Balance {
+            let owed = *self.dividends_owed.get(who).unwrap_or(&0);
+            let paid_at = *self.dividend_paid_at.get(who).unwrap_or(&0);
+            owed + self.balance_of_or_zero(who) * (*self.dividend_per_token - paid_at) / REWARD_PRECISION
+        }
+
+        /// The balance as of snapshot `id` is the first one saved at `id` or later. If the balance did not
+        /// change since `id`, it is the current balance.
+        fn balance_at_impl(&self, owner: &AccountId, id: u32) -> Option<Balance> {
+            let latest = *self.snapshot_id;
+            if id == 0 || id > latest {
+                return None
+            }
+            for saved in id..=latest {
+                if let Some(balance) = self.balance_snapshots.get(&(*owner, saved)) {
+                    return Some(*balance)
+                }
+            }
+            Some(self.balance_of_or_zero(owner))
+        }
+
+        /// The part of the grant that vested by block `now`. The division is split so that large grants do
+        /// not overflow.
+        fn vested(&self, grant: &Grant<Balance, BlockNumber>, now: BlockNumber) -> Balance {
+            if now < grant.start + grant.cliff {
+                return 0
+            }
+            let elapsed = now - grant.start;
+            if elapsed >= grant.duration {
+                return grant.total
+            }
+            let elapsed = Balance::from(elapsed);
+            let duration = Balance::from(grant.duration);
+            grant.total / duration * elapsed + grant.total % duration * elapsed / duration
+        }
+
+        /// Returns `acc_reward_per_share` and the reward pool after sharing the rewards of the blocks up to
+        /// `now`, without storing them.
+        fn accrued_rewards(&self, now: BlockNumber) -> (Balance, Balance) {
+            let acc = *self.acc_reward_per_share;
+            let pool = *self.reward_pool;
+            let last = *self.last_reward_block;
+            let total_staked = *self.total_staked;
+            if now <= last || total_staked == 0 {
+                return (acc, pool)
+            }
+            let blocks = Balance::from(now - last);
+            // The reward is capped so it can be scaled by REWARD_PRECISION, the rest stays in the pool
+            let reward = self.reward_rate.saturating_mul(blocks).min(pool).min(Balance::max_value() / REWARD_PRECISION);
+            let scaled = reward.checked_mul(REWARD_PRECISION).unwrap_or(Balance::max_value());
+            (acc.saturating_add(scaled / total_staked), pool - reward)
+        }
+
+        /// Stores the rewards shared up to `now` and returns the new `acc_reward_per_share`. Blocks without
+        /// stakers are skipped, their rewards stay in the pool.
+        fn update_rewards(&mut self, now: BlockNumber) -> Balance {
+            let (acc, pool) = self.accrued_rewards(now);
+            self.acc_reward_per_share.set(acc);
+            self.reward_pool.set(pool);
+            if now > *self.last_reward_block {
+                self.last_reward_block.set(now);
+            }
+            acc
+        }
+
+        /// The rewards the account earned at `acc` rewards per share that it was not paid yet.
+        fn pending_impl(&self, who: &AccountId, acc: Balance) -> Balance {
+            match self.stakes.get(who) {
+                Some((staked, debt)) => staked * acc / REWARD_PRECISION - debt,
+                None => 0,
+            }
+        }
+
+        /// Pays the pending rewards of the account and resets its reward debt. Returns the rewards paid.
+        fn pay_rewards(&mut self, env: &mut ink_model::EnvHandler<ink_core::env::ContractEnv<DefaultSrmlTypes>>, who: AccountId, acc: Balance) -> Balance {
+            let pending = self.pending_impl(&who, acc);
+            if pending == 0 {
+                return 0
+            }
+            let staked = self.stakes.get(&who).map(|(staked, _)| *staked).unwrap_or(0);
+            self.stakes.insert(who, (staked, staked * acc / REWARD_PRECISION));
+            let balance = self.balance_of_or_zero(&who);
+            self.set_balance(who, balance + pending);
+            env.emit(RewardPaid {
+                who,
+                value: pending
+            });
+            pending
+        }
+
+        /// Records the total supply under a new snapshot id and returns the id.
+        fn snapshot_impl(&mut self, env: &mut ink_model::EnvHandler<ink_core::env::ContractEnv<DefaultSrmlTypes>>) -> u32 {
+            let id = *self.snapshot_id + 1;
+            self.snapshot_id.set(id);
+            self.supply_snapshots.insert(id, *self.total_supply);
+            self.balances_total_snapshots.insert(id, *self.balances_total);
+            env.emit(Snapshot {
+                id
+            });
+            id
+        }
+
+        /// Returns true if the claim with this index of the latest airdrop was paid.
+        fn airdrop_claimed_impl(&self, index: u32) -> bool {
+            let bits = *self.airdrop_claimed.get(&(*self.airdrop_id, index / 32)).unwrap_or(&0);
+            bits & (1 << (index % 32)) != 0
+        }
+
+        /// Returns the balance of the AccountId or 0 if there is no balance.
+        fn balance_of_or_zero(&self, of: &AccountId) -> Balance {
+            // ACTION: `get` the balance of `of`, then `unwrap_or` fallback to 0
+            // ACTION: Return the balance
+            *self.balances.get(of).unwrap_or(&0)
+        }
+
+        /// Returns the allowance or 0 of there is no allowance.
+        fn allowance_or_zero(&self, owner: &AccountId, spender: &AccountId) -> Balance {
+            // ACTION: Return the allowance between `(owner, spender)`, `unwrap_or` return 0
+            *self.allowances.get(&(*owner, *spender)).unwrap_or(&0)
+        }
+
+        /// The fee on a transfer of `value`, rounded down. The sender pays `value` and the recipient gets
+        /// `value` minus the fee.
+        fn fee_for(&self, value: Balance) -> Balance {
+            if !*self.fee_enabled {
+                return 0
+            }
+            let bps = Balance::from(*self.transfer_fee_bps);
+            let denominator = Balance::from(FEE_DENOMINATOR);
+            value / denominator * bps + value % denominator * bps / denominator
+        }
+
+        /// Transfers token from a specified AccountId to another AccountId, less the transfer fee.
+        fn transfer_impl(&mut self, env: &mut ink_model::EnvHandler<ink_core::env::ContractEnv<DefaultSrmlTypes>>, from: AccountId, to: AccountId, value: Balance) -> Result<(), Error> {
+            // ACTION: Get the balance for `from` and `to`
+            //   HINT: Use the `balance_of_or_zero` function to do this
+            // ACTION: If `balance` from is less than `value`, return `InsufficientBalance`
+            // ACTION: Insert new values for `from` and `to`
+            //         * balance_from - value
+            //         * balance_to + value
+            if *self.paused {
+                return Err(Error::Paused)
+            }
+            if self.is_frozen_impl(&from) || self.is_frozen_impl(&to) {
+                return Err(Error::AccountFrozen)
+            }
+            let balance_from = self.balance_of_or_zero(&from);
+            if balance_from < value {
+                return Err(Error::InsufficientBalance)
+            }
+            let fee = self.fee_for(value);
+            let net = value - fee;
+            // Balances are read right before they are written, in case `to` or the collector is `from`
+            self.set_balance(from, balance_from - value);
+            let balance_to = self.balance_of_or_zero(&to);
+            self.set_balance(to, balance_to + net);
+            // ACTION: Call `env.emit` with the `Transfer` event
+            //   HINT: Since we use `Option<AccountId>`, you need to wrap accounts in `Some()`
+            env.emit( Transfer {
+                from: Some(from),
+                to: Some(to),
+                value: net
+            });
+            if fee > 0 {
+                let collector = *self.fee_collector;
+                let balance_collector = self.balance_of_or_zero(&collector);
+                self.set_balance(collector, balance_collector + fee);
+                env.emit(Transfer {
+                    from: Some(from),
+                    to: Some(collector),
+                    value: fee
+                });
+                env.emit(TransferFee {
+                    from,
+                    to,
+                    net,
+                    fee
+                });
+            }
+            Ok(())
+        }
+    }
+}
+
+
+#[cfg(all(test, feature = "test-env"))]
+mod tests {
+    use super::*;
+    use ink_core::env;
+    type Types = ink_core::env::DefaultSrmlTypes;
+
+    #[test]
+    fn deployment_works() {
+        let alice = AccountId::from([0x0; 32]);
+        env::test::set_caller::<Types>(alice);
+
+        // Deploy the contract with some `init_value`
+        let erc20 = Erc20::deploy_mock(1234, 1_000_000, Vec::new());
+        // Check that the `total_supply` is `init_value`
+        assert_eq!(erc20.total_supply(), 1234);
+        // Check that `balance_of` Alice is `init_value`
+        assert_eq!(erc20.balance_of(alice), 1234);
+    }
+
+    #[test]
+    fn transfer_works() {
+        let alice = AccountId::from([0x0; 32]);
+        let bob = AccountId::from([0x1; 32]);
+
+        env::test::set_caller::<Types>(alice);
+        // Deploy the contract with some `init_value`
+        let mut erc20 = Erc20::deploy_mock(1234, 1_000_000, Vec::new());
+        // Alice does not have enough funds for this
+        assert_eq!(erc20.transfer(bob, 4321), Err(Error::InsufficientBalance));
+        // Alice can do this though
+        assert_eq!(erc20.transfer(bob, 234), Ok(()));
+        // Check Alice and Bob have the expected balance
+        assert_eq!(erc20.balance_of(alice), 1000);
+        assert_eq!(erc20.balance_of(bob), 234);
+    }
+
+    #[test]
+    fn transfer_batch_works() {
+        let alice = AccountId::from([0x0; 32]);
+        let bob = AccountId::from([0x1; 32]);
+        let charlie = AccountId::from([0x2; 32]);
+
+        env::test::set_caller::<Types>(alice);
+        let mut erc20 = Erc20::deploy_mock(100, 1_000_000, Vec::new());
+        // The total is more than Alice has, so nothing is transferred
+        assert_eq!(erc20.transfer_batch(vec![(bob, 60), (charlie, 50)]), false);
+        assert_eq!(erc20.balance_of(bob), 0);
+        assert_eq!(erc20.transfer_batch(vec![(bob, 1), (charlie, Balance::max_value())]), false);
+
+        assert_eq!(erc20.transfer_batch(vec![(bob, 60), (charlie, 30), (bob, 5)]), true);
+        assert_eq!(erc20.balance_of(alice), 5);
+        assert_eq!(erc20.balance_of(bob), 65);
+        assert_eq!(erc20.balance_of(charlie), 30);
+        // One Transfer per recipient, after the one from deploy
+        assert_eq!(env::test::emitted_events::<Types>().count(), 4);
+
+        let too_many = vec![(bob, 0); MAX_BATCH_TRANSFERS + 1];
+        assert_eq!(erc20.transfer_batch(too_many), false);
+    }
+
+    #[test]
+    fn allowance_works() {
+        let alice = AccountId::from([0x0; 32]);
+        let bob = AccountId::from([0x1; 32]);
+        let charlie = AccountId::from([0x2; 32]);
+
+        env::test::set_caller::<Types>(alice);
+        // Deploy the contract with some `init_value`
+        let mut erc20 = Erc20::deploy_mock(1234, 1_000_000, Vec::new());
+        // Bob does not have an allowance from Alice's balance
+        assert_eq!(erc20.allowance(alice, bob), 0);
+        // Thus, Bob cannot transfer out of Alice's account
+        env::test::set_caller::<Types>(bob);
+        assert_eq!(erc20.transfer_from(alice, bob, 1), Err(Error::InsufficientAllowance));
+        // Alice can approve bob for some of her funds
+        env::test::set_caller::<Types>(alice);
+        assert_eq!(erc20.approve(bob, 20), true);
+        // And the allowance reflects that correctly
+        assert_eq!(erc20.allowance(alice, bob), 20);
+
+        // Charlie cannot send on behalf of Bob
+        env::test::set_caller::<Types>(charlie);
+        assert_eq!(erc20.transfer_from(alice, bob, 10), Err(Error::InsufficientAllowance));
+        // Bob cannot transfer more than he is allowed
+        env::test::set_caller::<Types>(bob);
+        assert_eq!(erc20.transfer_from(alice, charlie, 25), Err(Error::InsufficientAllowance));
+        // A smaller amount should work though
+        assert_eq!(erc20.transfer_from(alice, charlie, 10), Ok(()));
+        // Check that the allowance is updated
+        assert_eq!(erc20.allowance(alice, bob), 10);
+        // and the balance transferred to the right person
+        assert_eq!(erc20.balance_of(charlie), 10);
+    }
+
+    #[test]
+    fn allowance_exhausts() {
+        let alice = AccountId::from([0x0; 32]);
+        let bob = AccountId::from([0x1; 32]);
+        let charlie = AccountId::from([0x2; 32]);
+
+        env::test::set_caller::<Types>(alice);
+        let mut erc20 = Erc20::deploy_mock(100, 1_000_000, Vec::new());
+        assert_eq!(erc20.approve(bob, 150), true);
+        assert_eq!(erc20.transfer(charlie, 60), Ok(()));
+
+        // Alice only has 40 left, so the transfer fails and the allowance is not spent
+        env::test::set_caller::<Types>(bob);
+        assert_eq!(erc20.transfer_from(alice, bob, 50), Err(Error::InsufficientBalance));
+        assert_eq!(erc20.allowance(alice, bob), 150);
+
+        // Bob spends the allowance in two steps until it runs out
+        env::test::set_caller::<Types>(charlie);
+        assert_eq!(erc20.transfer(alice, 60), Ok(()));
+        env::test::set_caller::<Types>(bob);
+        assert_eq!(erc20.transfer_from(alice, bob, 100), Ok(()));
+        assert_eq!(erc20.transfer_from(alice, bob, 50), Err(Error::InsufficientBalance));
+        assert_eq!(erc20.allowance(alice, bob), 50);
+        assert_eq!(erc20.transfer(alice, 50), Ok(()));
+        assert_eq!(erc20.transfer_from(alice, charlie, 50), Ok(()));
+        assert_eq!(erc20.allowance(alice, bob), 0);
+        assert_eq!(erc20.transfer_from(alice, charlie, 1), Err(Error::InsufficientAllowance));
+        assert_eq!(erc20.balance_of(charlie), 50);
+    }
+
+    #[test]
+    fn burn_from_works() {
+        let alice = AccountId::from([0x0; 32]);
+        let bob = AccountId::from([0x1; 32]);
+
+        env::test::set_caller::<Types>(alice);
+        let mut erc20 = Erc20::deploy_mock(100, 1_000_000, Vec::new());
+        assert_eq!(erc20.approve(bob, 150), true);
+        assert_eq!(erc20.transfer(bob, 60), Ok(()));
+
+        env::test::set_caller::<Types>(bob);
+        assert_eq!(erc20.burn_from(alice, 41), Err(Error::InsufficientBalance));
+        assert_eq!(erc20.burn_from(alice, 30), Ok(()));
+        assert_eq!(erc20.balance_of(alice), 10);
+        assert_eq!(erc20.total_supply(), 70);
+        assert_eq!(erc20.allowance(alice, bob), 120);
+        // Bob has no allowance from himself
+        assert_eq!(erc20.burn_from(bob, 1), Err(Error::InsufficientAllowance));
+        assert_eq!(
+            env::test::emitted_events::<Types>().nth(3).unwrap(),
+            [vec![0u8], (Some(alice), None::<AccountId>, 30u128).encode()].concat()
+        );
+    }
+
+    #[test]
+    fn increase_and_decrease_allowance_work() {
         let alice = AccountId::from([0x0; 32]);
         let bob = AccountId::from([0x1; 32]);
 
         env::test::set_caller::<Types>(alice);
-        // Deploy the contract with some `init_value`
-        let mut erc20 = Erc20::deploy_mock(1234);
-        // Alice does not have enough funds for this
-        assert_eq!(erc20.transfer(bob, 4321), false);
-        // Alice can do this though
-        assert_eq!(erc20.transfer(bob, 234), true);
-        // Check Alice and Bob have the expected balance
-        assert_eq!(erc20.balance_of(alice), 1000);
-        assert_eq!(erc20.balance_of(bob), 234);
+        let mut erc20 = Erc20::deploy_mock(100, 1_000_000, Vec::new());
+        assert_eq!(erc20.increase_allowance(bob, 30), true);
+        assert_eq!(erc20.increase_allowance(bob, 20), true);
+        assert_eq!(erc20.allowance(alice, bob), 50);
+        assert_eq!(erc20.increase_allowance(bob, Balance::max_value()), false);
+
+        // Bob spends part of it, so Alice can no longer take back the full amount
+        env::test::set_caller::<Types>(bob);
+        assert_eq!(erc20.transfer_from(alice, bob, 40), Ok(()));
+        env::test::set_caller::<Types>(alice);
+        assert_eq!(erc20.decrease_allowance(bob, 50), false);
+        assert_eq!(erc20.decrease_allowance(bob, 10), true);
+        assert_eq!(erc20.allowance(alice, bob), 0);
+        assert_eq!(
+            env::test::emitted_events::<Types>().last().unwrap(),
+            [vec![1u8], (alice, bob, 0u128).encode()].concat()
+        );
     }
 
     #[test]
-    fn allowance_works() {
+    fn mint_and_burn_work() {
+        let alice = AccountId::from([0x0; 32]);
+        let bob = AccountId::from([0x1; 32]);
+
+        env::test::set_caller::<Types>(alice);
+        let mut erc20 = Erc20::deploy_mock(100, 1_000_000, Vec::new());
+        assert_eq!(erc20.owner(), alice);
+        // Only the owner can mint and burn
+        env::test::set_caller::<Types>(bob);
+        assert_eq!(erc20.mint(bob, 50), false);
+        assert_eq!(erc20.burn(alice, 50), false);
+        assert_eq!(erc20.total_supply(), 100);
+
+        env::test::set_caller::<Types>(alice);
+        assert_eq!(erc20.mint(bob, 50), true);
+        assert_eq!(erc20.balance_of(bob), 50);
+        assert_eq!(erc20.total_supply(), 150);
+        // Burning more than the balance fails
+        assert_eq!(erc20.burn(bob, 51), false);
+        assert_eq!(erc20.burn(bob, 20), true);
+        assert_eq!(erc20.balance_of(bob), 30);
+        assert_eq!(erc20.total_supply(), 130);
+        // Deploy, mint and burn each emit a Transfer
+        assert_eq!(env::test::emitted_events::<Types>().count(), 3);
+    }
+
+    #[test]
+    fn pause_works() {
+        let alice = AccountId::from([0x0; 32]);
+        let bob = AccountId::from([0x1; 32]);
+
+        env::test::set_caller::<Types>(alice);
+        let mut erc20 = Erc20::deploy_mock(100, 1_000_000, Vec::new());
+        assert_eq!(erc20.approve(bob, 50), true);
+        // Only the owner can pause
+        env::test::set_caller::<Types>(bob);
+        assert_eq!(erc20.pause(), false);
+        env::test::set_caller::<Types>(alice);
+        assert_eq!(erc20.pause(), true);
+        assert_eq!(erc20.paused(), true);
+        assert_eq!(erc20.pause(), false);
+
+        // Transfers fail while paused, and the allowance is not spent
+        assert_eq!(erc20.transfer(bob, 10), Err(Error::Paused));
+        env::test::set_caller::<Types>(bob);
+        assert_eq!(erc20.transfer_from(alice, bob, 10), Err(Error::Paused));
+        assert_eq!(erc20.allowance(alice, bob), 50);
+        assert_eq!(erc20.unpause(), false);
+
+        env::test::set_caller::<Types>(alice);
+        assert_eq!(erc20.unpause(), true);
+        assert_eq!(erc20.transfer(bob, 10), Ok(()));
+        assert_eq!(erc20.balance_of(bob), 10);
+        // Deploy, approve, pause, unpause and the transfer
+        assert_eq!(env::test::emitted_events::<Types>().count(), 5);
+    }
+
+    #[test]
+    fn freeze_account_works() {
         let alice = AccountId::from([0x0; 32]);
         let bob = AccountId::from([0x1; 32]);
         let charlie = AccountId::from([0x2; 32]);
 
         env::test::set_caller::<Types>(alice);
-        // Deploy the contract with some `init_value`
-        let mut erc20 = Erc20::deploy_mock(1234);
-        // Bob does not have an allowance from Alice's balance
-        assert_eq!(erc20.allowance(alice, bob), 0);
-        // Thus, Bob cannot transfer out of Alice's account
+        let mut erc20 = Erc20::deploy_mock(100, 1_000_000, Vec::new());
+        assert_eq!(erc20.transfer(bob, 50), Ok(()));
+        // Only the owner can freeze
         env::test::set_caller::<Types>(bob);
-        assert_eq!(erc20.transfer_from(alice, bob, 1), false);
-        // Alice can approve bob for some of her funds
+        assert_eq!(erc20.freeze_account(charlie), false);
+        assert_eq!(erc20.approve(charlie, 20), true);
         env::test::set_caller::<Types>(alice);
-        assert_eq!(erc20.approve(bob, 20), true);
-        // And the allowance reflects that correctly
-        assert_eq!(erc20.allowance(alice, bob), 20);
+        assert_eq!(erc20.freeze_account(bob), true);
+        assert_eq!(erc20.is_frozen(bob), true);
+        assert_eq!(erc20.freeze_account(bob), false);
 
-        // Charlie cannot send on behalf of Bob
+        // A frozen account can not send or receive, directly or through an allowance
+        assert_eq!(erc20.transfer(bob, 10), Err(Error::AccountFrozen));
+        assert_eq!(erc20.transfer_batch(vec![(charlie, 10), (bob, 10)]), false);
+        assert_eq!(erc20.balance_of(charlie), 0);
+        env::test::set_caller::<Types>(bob);
+        assert_eq!(erc20.transfer(charlie, 10), Err(Error::AccountFrozen));
         env::test::set_caller::<Types>(charlie);
-        assert_eq!(erc20.transfer_from(alice, bob, 10), false);
-        // Bob cannot transfer more than he is allowed
+        assert_eq!(erc20.transfer_from(bob, charlie, 10), Err(Error::AccountFrozen));
+        assert_eq!(erc20.allowance(bob, charlie), 20);
+
+        env::test::set_caller::<Types>(alice);
+        assert_eq!(erc20.unfreeze_account(bob), true);
+        assert_eq!(erc20.unfreeze_account(bob), false);
+        assert_eq!(erc20.transfer(bob, 10), Ok(()));
+        assert_eq!(erc20.balance_of(bob), 60);
+    }
+
+    #[test]
+    fn snapshots_work() {
+        let alice = AccountId::from([0x0; 32]);
+        let bob = AccountId::from([0x1; 32]);
+
+        env::test::set_caller::<Types>(alice);
+        let mut erc20 = Erc20::deploy_mock(100, 1_000_000, Vec::new());
+        assert_eq!(erc20.balance_of_at(alice, 1), None);
         env::test::set_caller::<Types>(bob);
-        assert_eq!(erc20.transfer_from(alice, charlie, 25), false);
-        // A smaller amount should work though
-        assert_eq!(erc20.transfer_from(alice, charlie, 10), true);
-        // Check that the allowance is updated
-        assert_eq!(erc20.allowance(alice, bob), 10);
-        // and the balance transferred to the right person
-        assert_eq!(erc20.balance_of(charlie), 10);
+        assert_eq!(erc20.snapshot(), None);
+
+        env::test::set_caller::<Types>(alice);
+        assert_eq!(erc20.snapshot(), Some(1));
+        assert_eq!(erc20.transfer(bob, 30), Ok(()));
+        assert_eq!(erc20.transfer(bob, 20), Ok(()));
+        assert_eq!(erc20.snapshot(), Some(2));
+        assert_eq!(erc20.snapshot(), Some(3));
+        assert_eq!(erc20.mint(bob, 50), true);
+
+        assert_eq!(erc20.balance_of_at(alice, 1), Some(100));
+        assert_eq!(erc20.balance_of_at(bob, 1), Some(0));
+        assert_eq!(erc20.balance_of_at(alice, 2), Some(50));
+        assert_eq!(erc20.balance_of_at(bob, 2), Some(50));
+        assert_eq!(erc20.balance_of_at(bob, 3), Some(50));
+        assert_eq!(erc20.balance_of(bob), 100);
+        assert_eq!(erc20.total_supply_at(1), Some(100));
+        assert_eq!(erc20.total_supply_at(3), Some(100));
+        assert_eq!(erc20.total_supply_at(4), None);
+    }
+
+    #[test]
+    fn faucet_works() {
+        let alice = AccountId::from([0x0; 32]);
+        let bob = AccountId::from([0x1; 32]);
+        let charlie = AccountId::from([0x2; 32]);
+
+        env::test::set_caller::<Types>(alice);
+        let mut erc20 = Erc20::deploy_mock(100, 1_000_000, Vec::new());
+        assert_eq!(erc20.set_faucet(10, 5), true);
+        assert_eq!(erc20.refill_faucet(101), false);
+        assert_eq!(erc20.refill_faucet(25), true);
+        assert_eq!(erc20.balance_of(alice), 75);
+        assert_eq!(erc20.faucet_reservoir(), 25);
+        // Only the owner can configure and refill the faucet
+        env::test::set_caller::<Types>(bob);
+        assert_eq!(erc20.set_faucet(100, 0), false);
+        assert_eq!(erc20.refill_faucet(0), false);
+
+        env::test::set_block_number::<Types>(1);
+        assert_eq!(erc20.drip(), Ok(()));
+        assert_eq!(erc20.balance_of(bob), 10);
+        env::test::set_block_number::<Types>(5);
+        assert_eq!(erc20.drip(), Err(Error::CooldownActive));
+        env::test::set_block_number::<Types>(6);
+        assert_eq!(erc20.drip(), Ok(()));
+        assert_eq!(erc20.faucet_reservoir(), 5);
+
+        // Less than one drip is left
+        env::test::set_caller::<Types>(charlie);
+        assert_eq!(erc20.drip(), Err(Error::FaucetEmpty));
+        assert_eq!(erc20.total_supply(), 100);
+    }
+
+    #[test]
+    fn vesting_works() {
+        let alice = AccountId::from([0x0; 32]);
+        let bob = AccountId::from([0x1; 32]);
+        let charlie = AccountId::from([0x2; 32]);
+
+        env::test::set_caller::<Types>(alice);
+        env::test::set_block_number::<Types>(10);
+        let mut erc20 = Erc20::deploy_mock(1000, 1_000_000, Vec::new());
+        // The cliff can not be longer than the grant
+        assert_eq!(erc20.grant_vesting(bob, 100, 20, 10, false), false);
+        assert_eq!(erc20.grant_vesting(bob, 100, 2, 10, false), true);
+        assert_eq!(erc20.grant_vesting(bob, 100, 2, 10, false), false);
+        assert_eq!(erc20.grant_vesting(charlie, 400, 0, 4, true), true);
+        assert_eq!(erc20.balance_of(alice), 500);
+
+        env::test::set_caller::<Types>(bob);
+        env::test::set_block_number::<Types>(11);
+        assert_eq!(erc20.release(), Err(Error::NothingToRelease));
+        env::test::set_block_number::<Types>(13);
+        assert_eq!(erc20.releasable(bob), 30);
+        assert_eq!(erc20.release(), Ok(()));
+        assert_eq!(erc20.balance_of(bob), 30);
+        assert_eq!(erc20.release(), Err(Error::NothingToRelease));
+        // Bob's grant is not revocable
+        env::test::set_caller::<Types>(alice);
+        assert_eq!(erc20.revoke_vesting(bob), false);
+        // Half of Charlie's grant vested, Charlie gets it and Alice gets the rest back
+        assert_eq!(erc20.revoke_vesting(charlie), true);
+        assert_eq!(erc20.balance_of(charlie), 200);
+        assert_eq!(erc20.balance_of(alice), 700);
+        assert_eq!(erc20.grant_of(charlie), None);
+
+        // The grant is removed once fully released
+        env::test::set_caller::<Types>(bob);
+        env::test::set_block_number::<Types>(25);
+        assert_eq!(erc20.release(), Ok(()));
+        assert_eq!(erc20.balance_of(bob), 100);
+        assert_eq!(erc20.grant_of(bob), None);
+        assert_eq!(erc20.total_supply(), 1000);
+    }
+
+    #[test]
+    fn staking_rewards_work() {
+        let alice = AccountId::from([0x0; 32]);
+        let bob = AccountId::from([0x1; 32]);
+        let charlie = AccountId::from([0x2; 32]);
+
+        env::test::set_caller::<Types>(alice);
+        env::test::set_block_number::<Types>(0);
+        let mut erc20 = Erc20::deploy_mock(12000, 1_000_000, Vec::new());
+        assert!(erc20.transfer(bob, 1000).is_ok());
+        assert!(erc20.transfer(charlie, 1000).is_ok());
+        assert_eq!(erc20.set_reward_rate(100), true);
+        assert_eq!(erc20.fund_rewards(1000), true);
+
+        // Bob stakes alone for two blocks and earns all 200
+        env::test::set_block_number::<Types>(1);
+        env::test::set_caller::<Types>(bob);
+        assert_eq!(erc20.stake(100), Ok(()));
+        assert_eq!(erc20.stake(1000), Err(Error::InsufficientBalance));
+        env::test::set_block_number::<Types>(3);
+        assert_eq!(erc20.pending_rewards(bob), 200);
+        // Then Charlie stakes three times as much and earns three quarters of the next 200
+        env::test::set_caller::<Types>(charlie);
+        assert_eq!(erc20.stake(300), Ok(()));
+        assert_eq!(erc20.total_staked(), 400);
+        env::test::set_block_number::<Types>(5);
+        assert_eq!(erc20.pending_rewards(bob), 250);
+        assert_eq!(erc20.pending_rewards(charlie), 150);
+
+        env::test::set_caller::<Types>(bob);
+        assert_eq!(erc20.claim(), Ok(()));
+        assert_eq!(erc20.claim(), Err(Error::NoRewards));
+        assert_eq!(erc20.balance_of(bob), 900 + 250);
+        // Unstaking pays the pending rewards too
+        env::test::set_caller::<Types>(charlie);
+        assert_eq!(erc20.unstake(300), Ok(()));
+        assert_eq!(erc20.staked_of(charlie), 0);
+        assert_eq!(erc20.balance_of(charlie), 1000 + 150);
+
+        // Rewards stop once the pool runs out
+        env::test::set_block_number::<Types>(100);
+        assert_eq!(erc20.pending_rewards(bob), 600);
+        assert_eq!(erc20.total_supply(), 12000);
+    }
+
+    #[test]
+    fn token_voting_works() {
+        let alice = AccountId::from([0x0; 32]);
+        let bob = AccountId::from([0x1; 32]);
+        let charlie = AccountId::from([0x2; 32]);
+        let payload = [0x7; 32];
+
+        env::test::set_caller::<Types>(alice);
+        env::test::set_block_number::<Types>(1);
+        let mut erc20 = Erc20::deploy_mock(1000, 1_000_000, Vec::new());
+        assert!(erc20.transfer(bob, 300).is_ok());
+        // Voting has to be configured first
+        assert_eq!(erc20.propose(payload), None);
+        assert_eq!(erc20.set_voting(10, 50), true);
+        // Only the owner proposes, since a proposal takes a snapshot
+        env::test::set_caller::<Types>(bob);
+        assert_eq!(erc20.propose(payload), None);
+        env::test::set_caller::<Types>(alice);
+        assert_eq!(erc20.propose(payload), Some(1));
+
+        env::test::set_caller::<Types>(bob);
+        assert_eq!(erc20.vote(2, true), Err(Error::ProposalNotFound));
+        // Bob votes with 300, then moves 200 to Charlie. Charlie had nothing at the snapshot.
+        assert_eq!(erc20.vote(1, true), Ok(()));
+        assert_eq!(erc20.vote(1, false), Err(Error::AlreadyVoted));
+        assert!(erc20.transfer(charlie, 200).is_ok());
+        env::test::set_caller::<Types>(charlie);
+        assert_eq!(erc20.vote(1, true), Err(Error::NoVotingWeight));
+        // Alice votes with her 700 at the snapshot
+        env::test::set_caller::<Types>(alice);
+        assert!(erc20.transfer(charlie, 500).is_ok());
+        assert_eq!(erc20.vote(1, false), Ok(()));
+        let proposal = erc20.proposal(1).unwrap();
+        assert_eq!((proposal.yes, proposal.no), (300, 700));
+
+        // The quorum counts the balances at the snapshot only. With 200 of the 1000 tokens in the faucet,
+        // Charlie's 700 meet a quorum of 80% on their own.
+        assert_eq!(erc20.refill_faucet(200), true);
+        assert_eq!(erc20.set_voting(10, 80), true);
+        assert_eq!(erc20.propose(payload), Some(2));
+        env::test::set_caller::<Types>(charlie);
+        assert_eq!(erc20.vote(2, true), Ok(()));
+        assert_eq!(erc20.proposal(2).unwrap().yes, 700);
+        env::test::set_block_number::<Types>(11);
+        assert_eq!(erc20.execute(2), false);
+        env::test::set_block_number::<Types>(12);
+        assert_eq!(erc20.vote(2, true), Err(Error::VotingClosed));
+        assert_eq!(erc20.execute(1), false);
+        assert_eq!(erc20.execute(2), true);
+        assert_eq!(erc20.execute(2), false);
+        assert_eq!(erc20.proposal(2).unwrap().executed, true);
+    }
+
+    #[test]
+    fn transfer_locked_works() {
+        let alice = AccountId::from([0x0; 32]);
+        let bob = AccountId::from([0x1; 32]);
+
+        env::test::set_caller::<Types>(alice);
+        env::test::set_block_number::<Types>(1);
+        let mut erc20 = Erc20::deploy_mock(100, 1_000_000, Vec::new());
+        assert_eq!(erc20.transfer_locked(bob, 101, 5), Err(Error::InsufficientBalance));
+        assert_eq!(erc20.transfer_locked(bob, 30, 5), Ok(()));
+        assert_eq!(erc20.transfer_locked(bob, 20, 10), Ok(()));
+        assert_eq!(erc20.balance_of(alice), 50);
+        assert_eq!(erc20.balance_of(bob), 0);
+        assert_eq!(erc20.locked_balance_of(bob), 50);
+
+        // Locked tokens can not be spent
+        env::test::set_caller::<Types>(bob);
+        assert_eq!(erc20.transfer(alice, 1), Err(Error::InsufficientBalance));
+        env::test::set_block_number::<Types>(4);
+        assert_eq!(erc20.unlockable_balance_of(bob), 0);
+        assert_eq!(erc20.unlock(), Err(Error::NothingToRelease));
+        // The first lock matures at its unlock block
+        env::test::set_block_number::<Types>(5);
+        assert_eq!(erc20.unlockable_balance_of(bob), 30);
+        assert_eq!(erc20.unlock(), Ok(()));
+        assert_eq!(erc20.balance_of(bob), 30);
+        assert_eq!(erc20.locked_balance_of(bob), 20);
+        env::test::set_block_number::<Types>(10);
+        assert_eq!(erc20.unlock(), Ok(()));
+        assert_eq!(erc20.balance_of(bob), 50);
+        assert_eq!(erc20.locked_balance_of(bob), 0);
+        assert_eq!(erc20.total_supply(), 100);
+    }
+
+    #[test]
+    fn transfer_locked_limits_work() {
+        let alice = AccountId::from([0x0; 32]);
+        let bob = AccountId::from([0x1; 32]);
+
+        env::test::set_caller::<Types>(alice);
+        env::test::set_block_number::<Types>(1);
+        let mut erc20 = Erc20::deploy_mock(100, 1_000_000, Vec::new());
+        // Zero value locks and locks beyond the longest lockup are refused
+        assert_eq!(erc20.transfer_locked(bob, 0, 5), Err(Error::InvalidLock));
+        assert_eq!(erc20.transfer_locked(bob, 1, 1 + BlockNumber::from(MAX_LOCKUP) + 1), Err(Error::InvalidLock));
+        assert_eq!(erc20.transfer_locked(bob, 1, 1 + BlockNumber::from(MAX_LOCKUP)), Ok(()));
+
+        // Unlocking frees the slots of matured locks
+        for _ in 1..MAX_LOCKS {
+            assert_eq!(erc20.transfer_locked(bob, 1, 5), Ok(()));
+        }
+        assert_eq!(erc20.transfer_locked(bob, 1, 5), Err(Error::TooManyLocks));
+        env::test::set_caller::<Types>(bob);
+        env::test::set_block_number::<Types>(5);
+        assert_eq!(erc20.unlock(), Ok(()));
+        assert_eq!(erc20.balance_of(bob), 15);
+        assert_eq!(erc20.locked_balance_of(bob), 1);
+        assert_eq!(erc20.unlock(), Err(Error::NothingToRelease));
+        env::test::set_caller::<Types>(alice);
+        assert_eq!(erc20.transfer_locked(bob, 1, 6), Ok(()));
+        assert_eq!(erc20.locked_balance_of(bob), 2);
+    }
+
+    #[test]
+    fn transfer_fee_works() {
+        let alice = AccountId::from([0x0; 32]);
+        let bob = AccountId::from([0x1; 32]);
+        let charlie = AccountId::from([0x2; 32]);
+        let collector = AccountId::from([0x3; 32]);
+
+        env::test::set_caller::<Types>(alice);
+        let mut erc20 = Erc20::deploy_mock(100_000, 1_000_000, Vec::new());
+        assert_eq!(erc20.set_transfer_fee(10_001, collector), false);
+        // 2.5%, not charged until enabled
+        assert_eq!(erc20.set_transfer_fee(250, collector), true);
+        assert_eq!(erc20.transfer_fee(), None);
+        assert!(erc20.transfer(bob, 10_000).is_ok());
+        assert_eq!(erc20.balance_of(bob), 10_000);
+
+        assert_eq!(erc20.set_fee_enabled(true), true);
+        assert_eq!(erc20.transfer_fee(), Some((250, collector)));
+        assert!(erc20.transfer(bob, 10_000).is_ok());
+        assert_eq!(erc20.balance_of(bob), 10_000 + 9_750);
+        assert_eq!(erc20.balance_of(collector), 250);
+        // The fee is rounded down, so 39 * 2.5% = 0.975 is free
+        assert!(erc20.transfer(bob, 39).is_ok());
+        assert_eq!(erc20.balance_of(collector), 250);
+        assert!(erc20.transfer(bob, 41).is_ok());
+        assert_eq!(erc20.balance_of(collector), 251);
+
+        // transfer_from pays the fee too, and spends the full value of the allowance
+        env::test::set_caller::<Types>(bob);
+        assert_eq!(erc20.approve(charlie, 1000), true);
+        env::test::set_caller::<Types>(charlie);
+        assert!(erc20.transfer_from(bob, charlie, 1000).is_ok());
+        assert_eq!(erc20.balance_of(charlie), 975);
+        assert_eq!(erc20.allowance(bob, charlie), 0);
+        assert_eq!(erc20.balance_of(collector), 276);
+
+        env::test::set_caller::<Types>(bob);
+        assert_eq!(erc20.set_fee_enabled(false), false);
+        env::test::set_caller::<Types>(alice);
+        assert_eq!(erc20.set_fee_enabled(false), true);
+        assert!(erc20.transfer(bob, 1000).is_ok());
+        assert_eq!(erc20.balance_of(collector), 276);
+        assert_eq!(erc20.total_supply(), 100_000);
+    }
+
+    #[test]
+    fn dividends_work() {
+        let alice = AccountId::from([0x0; 32]);
+        let bob = AccountId::from([0x1; 32]);
+        let charlie = AccountId::from([0x2; 32]);
+
+        env::test::set_caller::<Types>(alice);
+        let mut erc20 = Erc20::deploy_mock(1200, 1_000_000, Vec::new());
+        assert!(erc20.transfer(bob, 300).is_ok());
+        assert!(erc20.transfer(charlie, 100).is_ok());
+        // Alice keeps 600 after moving 200 into the pool, so the holders have 600, 300 and 100
+        assert_eq!(erc20.distribute(200), true);
+        assert_eq!(erc20.dividend_of(alice), 120);
+        assert_eq!(erc20.dividend_of(bob), 60);
+        assert_eq!(erc20.dividend_of(charlie), 20);
+
+        // Tokens moved after a distribution do not take its dividends with them
+        env::test::set_caller::<Types>(bob);
+        assert!(erc20.transfer(charlie, 300).is_ok());
+        assert_eq!(erc20.dividend_of(bob), 60);
+        assert_eq!(erc20.dividend_of(charlie), 20);
+        assert_eq!(erc20.claim_dividend(), Ok(()));
+        assert_eq!(erc20.claim_dividend(), Err(Error::NoDividends));
+        assert_eq!(erc20.balance_of(bob), 60);
+
+        // Bob's claimed 60 now earns dividends too: the holders have 388, 60 and 400
+        env::test::set_caller::<Types>(alice);
+        assert_eq!(erc20.distribute(212), true);
+        assert_eq!(erc20.dividend_of(alice), 120 + 97);
+        assert_eq!(erc20.dividend_of(bob), 15);
+        assert_eq!(erc20.dividend_of(charlie), 20 + 100);
+        env::test::set_caller::<Types>(charlie);
+        assert_eq!(erc20.claim_dividend(), Ok(()));
+        assert_eq!(erc20.balance_of(charlie), 520);
+        assert_eq!(erc20.total_supply(), 1200);
+    }
+
+    #[test]
+    fn blake2_256_works() {
+        let hex = |digest: [u8; 32]| digest.iter().map(|byte| format!("{:02x}", byte)).collect::<String>();
+        assert_eq!(hex(blake2_256(b"")), "0e5751c026e543b2e8ab2eb06099daa1d1e5df47778f7787faab45cdf12fe3a8");
+        assert_eq!(hex(blake2_256(b"abc")), "bddd813c634239723171ef3fee98579b94964e3bb1cb3e427262c8c068d52319");
+        // Longer than one block of 128 bytes
+        assert_eq!(hex(blake2_256(&[0xab; 200])), "49ede9457e47dd751fb8acbc86cee75c48c217388788abffe19b51b1aabdcd52");
+    }
+
+    #[test]
+    fn airdrop_works() {
+        let alice = AccountId::from([0x0; 32]);
+        let bob = AccountId::from([0x1; 32]);
+        let charlie = AccountId::from([0x2; 32]);
+        let dave = AccountId::from([0x3; 32]);
+        let leaf = |index: u32, who: AccountId, value: Balance| blake2_256(&(index, who, value).encode());
+
+        // A tree of three claims: the first two leaves are paired, the third is paired with their node
+        let (bob_leaf, charlie_leaf, dave_leaf) = (leaf(0, bob, 10), leaf(1, charlie, 20), leaf(2, dave, 30));
+        let node = merkle_root(bob_leaf, &[charlie_leaf]);
+        let root = merkle_root(node, &[dave_leaf]);
+        assert_eq!(merkle_root(charlie_leaf, &[bob_leaf, dave_leaf]), root);
+
+        env::test::set_caller::<Types>(alice);
+        let mut erc20 = Erc20::deploy_mock(100, 1_000_000, Vec::new());
+        assert_eq!(erc20.claim_airdrop(0, 10, vec![charlie_leaf, dave_leaf]), Err(Error::AirdropClosed));
+        assert_eq!(erc20.start_airdrop(root, 101, 10), false);
+        assert_eq!(erc20.start_airdrop(root, 60, 10), true);
+        assert_eq!(erc20.balance_of(alice), 40);
+        assert_eq!(erc20.airdrop_pool(), 60);
+        // One airdrop runs at a time
+        assert_eq!(erc20.start_airdrop(root, 10, 10), false);
+
+        env::test::set_caller::<Types>(bob);
+        env::test::set_block_number::<Types>(1);
+        assert_eq!(erc20.claim_airdrop(0, 11, vec![charlie_leaf, dave_leaf]), Err(Error::InvalidProof));
+        assert_eq!(erc20.claim_airdrop(0, 10, vec![charlie_leaf, dave_leaf]), Ok(()));
+        assert_eq!(erc20.balance_of(bob), 10);
+        assert_eq!(erc20.is_airdrop_claimed(0), true);
+        assert_eq!(erc20.claim_airdrop(0, 10, vec![charlie_leaf, dave_leaf]), Err(Error::AlreadyClaimed));
+        // The claim of Charlie is only paid to Charlie
+        assert_eq!(erc20.claim_airdrop(1, 20, vec![bob_leaf, dave_leaf]), Err(Error::InvalidProof));
+        env::test::set_caller::<Types>(charlie);
+        assert_eq!(erc20.claim_airdrop(1, 20, vec![bob_leaf, dave_leaf]), Ok(()));
+        assert_eq!(erc20.airdrop_pool(), 30);
+
+        // Claims close after the deadline, then the owner sweeps what is left
+        env::test::set_caller::<Types>(alice);
+        env::test::set_block_number::<Types>(10);
+        assert_eq!(erc20.sweep_airdrop(), false);
+        env::test::set_block_number::<Types>(11);
+        env::test::set_caller::<Types>(dave);
+        assert_eq!(erc20.claim_airdrop(2, 30, vec![node]), Err(Error::AirdropClosed));
+        assert_eq!(erc20.sweep_airdrop(), false);
+        env::test::set_caller::<Types>(alice);
+        assert_eq!(erc20.sweep_airdrop(), true);
+        assert_eq!(erc20.sweep_airdrop(), false);
+        assert_eq!(erc20.balance_of(alice), 70);
+        assert_eq!(erc20.airdrop_pool(), 0);
+        assert_eq!(erc20.total_supply(), 100);
+
+        // Only the owner can start an airdrop, and a new one starts with no claims paid
+        env::test::set_caller::<Types>(bob);
+        assert_eq!(erc20.start_airdrop(root, 10, 20), false);
+        env::test::set_caller::<Types>(alice);
+        assert_eq!(erc20.start_airdrop(root, 30, 20), true);
+        assert_eq!(erc20.is_airdrop_claimed(0), false);
+    }
+
+    #[test]
+    fn reward_math_does_not_overflow() {
+        let alice = AccountId::from([0x0; 32]);
+        let bob = AccountId::from([0x1; 32]);
+        let supply: Balance = 1_000_000_000_000_000_000_000_000_000_000;
+
+        // A distribution that can not be scaled by REWARD_PRECISION is refused and moves nothing
+        env::test::set_caller::<Types>(alice);
+        let mut erc20 = Erc20::deploy_mock(supply, supply, vec![(bob, 1)]);
+        assert_eq!(erc20.distribute(supply - 2), false);
+        assert_eq!(erc20.balance_of(alice), supply - 1);
+        assert_eq!(erc20.distribute(1000), true);
+
+        // Rewards of a block are capped, so they can be scaled, and the rest stays in the pool
+        env::test::set_block_number::<Types>(0);
+        let mut erc20 = Erc20::deploy_mock(supply, supply, vec![(bob, REWARD_PRECISION)]);
+        assert_eq!(erc20.set_reward_rate(supply), true);
+        assert_eq!(erc20.fund_rewards(supply - REWARD_PRECISION), true);
+        env::test::set_caller::<Types>(bob);
+        env::test::set_block_number::<Types>(1);
+        assert_eq!(erc20.stake(REWARD_PRECISION), Ok(()));
+        env::test::set_block_number::<Types>(2);
+        assert_eq!(erc20.pending_rewards(bob), Balance::max_value() / REWARD_PRECISION);
+    }
+
+    #[test]
+    fn deploy_allocations_work() {
+        let alice = AccountId::from([0x0; 32]);
+        let bob = AccountId::from([0x1; 32]);
+        let charlie = AccountId::from([0x2; 32]);
+
+        env::test::set_caller::<Types>(alice);
+        let erc20 = Erc20::deploy_mock(1000, 1_000_000, vec![(bob, 300), (charlie, 100), (bob, 50)]);
+        assert_eq!(erc20.balance_of(alice), 550);
+        assert_eq!(erc20.balance_of(bob), 350);
+        assert_eq!(erc20.balance_of(charlie), 100);
+        assert_eq!(erc20.total_supply(), 1000);
+        // One Transfer for the caller, then one per allocation
+        assert_eq!(env::test::emitted_events::<Types>().count(), 4);
+
+        // Allocations beyond `init_value` raise the initial supply, and the cap with it
+        let erc20 = Erc20::deploy_mock(100, 100, vec![(bob, 150)]);
+        assert_eq!(erc20.balance_of(alice), 0);
+        assert_eq!(erc20.balance_of(bob), 150);
+        assert_eq!(erc20.total_supply(), 150);
+        assert_eq!(erc20.cap(), 150);
+    }
+
+    #[test]
+    fn self_and_zero_value_transfers_work() {
+        let alice = AccountId::from([0x0; 32]);
+        let bob = AccountId::from([0x1; 32]);
+
+        env::test::set_caller::<Types>(alice);
+        let mut erc20 = Erc20::deploy_mock(100, 1_000_000, Vec::new());
+        // Sending to yourself changes nothing, but the full balance has to be there
+        assert_eq!(erc20.transfer(alice, 100), Ok(()));
+        assert_eq!(erc20.transfer(alice, 101), Err(Error::InsufficientBalance));
+        assert_eq!(erc20.balance_of(alice), 100);
+        // Zero-value transfers succeed and still emit a Transfer event, like in the ERC20 specification
+        assert_eq!(erc20.transfer(bob, 0), Ok(()));
+        assert_eq!(env::test::emitted_events::<Types>().count(), 3);
+        env::test::set_caller::<Types>(bob);
+        assert_eq!(erc20.transfer(alice, 0), Ok(()));
+        assert_eq!(erc20.transfer_from(alice, bob, 0), Ok(()));
+        assert_eq!(erc20.transfer_from(alice, bob, 1), Err(Error::InsufficientAllowance));
+        assert_eq!(erc20.balance_of(alice), 100);
+        assert_eq!(erc20.balance_of(bob), 0);
+    }
+
+    #[test]
+    fn mint_at_balance_limit_works() {
+        let alice = AccountId::from([0x0; 32]);
+        let bob = AccountId::from([0x1; 32]);
+        let max = Balance::max_value();
+
+        env::test::set_caller::<Types>(alice);
+        let mut erc20 = Erc20::deploy_mock(0, max, Vec::new());
+        assert_eq!(erc20.mint(alice, max - 1), true);
+        assert_eq!(erc20.mint(bob, 2), false);
+        assert_eq!(erc20.mint(bob, 1), true);
+        assert_eq!(erc20.total_supply(), max);
+        // The supply is at the limit of the balance type, so neither mint nor transfers may overflow
+        assert_eq!(erc20.mint(bob, 1), false);
+        assert_eq!(erc20.transfer(bob, max - 1), Ok(()));
+        assert_eq!(erc20.balance_of(bob), max);
+        assert_eq!(erc20.burn(bob, max), true);
+        assert_eq!(erc20.total_supply(), 0);
+    }
+
+    #[test]
+    fn balances_add_up_to_total_supply() {
+        let accounts = [
+            AccountId::from([0x0; 32]),
+            AccountId::from([0x1; 32]),
+            AccountId::from([0x2; 32]),
+            AccountId::from([0x3; 32]),
+        ];
+        // A fixed-seed linear congruential generator, so that failures can be reproduced
+        let mut seed: u64 = 0x5eed;
+        let mut next = |bound: u64| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (seed >> 33) % bound
+        };
+
+        env::test::set_caller::<Types>(accounts[0]);
+        let mut erc20 = Erc20::deploy_mock(1000, 1_000_000, vec![(accounts[1], 200), (accounts[2], 100)]);
+        for _ in 0..500 {
+            let caller = accounts[next(4) as usize];
+            let other = accounts[next(4) as usize];
+            let target = accounts[next(4) as usize];
+            let value = Balance::from(next(300));
+            env::test::set_caller::<Types>(caller);
+            match next(6) {
+                0 => { let _ = erc20.transfer(other, value); }
+                1 => { erc20.approve(other, value); }
+                2 => { let _ = erc20.transfer_from(other, target, value); }
+                3 => { erc20.mint(other, value); }
+                4 => { erc20.burn(other, value); }
+                _ => { let _ = erc20.burn_from(other, value); }
+            }
+            let sum: Balance = accounts.iter().map(|account| erc20.balance_of(*account)).sum();
+            assert_eq!(sum, erc20.total_supply());
+        }
+    }
+
+    #[test]
+    fn ownership_transfer_works() {
+        let alice = AccountId::from([0x0; 32]);
+        let bob = AccountId::from([0x1; 32]);
+        let charlie = AccountId::from([0x2; 32]);
+
+        env::test::set_caller::<Types>(alice);
+        let mut erc20 = Erc20::deploy_mock(100, 1_000_000, Vec::new());
+        // Only the owner can offer the ownership
+        env::test::set_caller::<Types>(bob);
+        assert_eq!(erc20.transfer_ownership(bob), false);
+        env::test::set_caller::<Types>(alice);
+        assert_eq!(erc20.transfer_ownership(bob), true);
+        assert_eq!(erc20.pending_owner(), Some(bob));
+
+        // Until Bob accepts, Alice is still the owner
+        assert_eq!(erc20.owner(), alice);
+        assert_eq!(erc20.mint(alice, 10), true);
+        env::test::set_caller::<Types>(bob);
+        assert_eq!(erc20.mint(bob, 10), false);
+        env::test::set_caller::<Types>(charlie);
+        assert_eq!(erc20.accept_ownership(), false);
+
+        env::test::set_caller::<Types>(bob);
+        assert_eq!(erc20.accept_ownership(), true);
+        assert_eq!(erc20.owner(), bob);
+        assert_eq!(erc20.pending_owner(), None);
+        assert_eq!(erc20.mint(bob, 10), true);
+        env::test::set_caller::<Types>(alice);
+        assert_eq!(erc20.mint(alice, 10), false);
+        assert_eq!(erc20.accept_ownership(), false);
+    }
+
+    #[test]
+    fn cap_works() {
+        let alice = AccountId::from([0x0; 32]);
+
+        env::test::set_caller::<Types>(alice);
+        let mut erc20 = Erc20::deploy_mock(100, 150, Vec::new());
+        assert_eq!(erc20.cap(), 150);
+        // Minting exactly to the cap works, one more unit does not
+        assert_eq!(erc20.mint(alice, 50), true);
+        assert_eq!(erc20.total_supply(), 150);
+        assert_eq!(erc20.mint(alice, 1), false);
+        assert_eq!(erc20.total_supply(), 150);
+        // Burning makes room below the cap again
+        assert_eq!(erc20.burn(alice, 1), true);
+        assert_eq!(erc20.mint(alice, 1), true);
+
+        // A cap below the initial supply is raised to it
+        let erc20 = Erc20::deploy_mock(100, 10, Vec::new());
+        assert_eq!(erc20.cap(), 100);
     }
 
     #[test]
@@ -242,13 +2558,31 @@ mod tests {
         env::test::set_caller::<Types>(alice);
         assert_eq!(env::test::emitted_events::<Types>().count(), 0);
         // Event should be emitted for initial minting
-        let mut erc20 = Erc20::deploy_mock(1234);
+        let mut erc20 = Erc20::deploy_mock(1234, 1_000_000, Vec::new());
         assert_eq!(env::test::emitted_events::<Types>().count(), 1);
         // Event should be emitted for transfers
-        assert_eq!(erc20.transfer(bob, 10), true);
+        assert_eq!(erc20.transfer(bob, 10), Ok(()));
         assert_eq!(env::test::emitted_events::<Types>().count(), 2);
+        // Events are encoded as the variant index of the event in the contract, then the fields
+        let expected = |index: u8, fields: Vec<u8>| [vec![index], fields].concat();
+        assert_eq!(
+            env::test::emitted_events::<Types>().last().unwrap(),
+            expected(0, (Some(alice), Some(bob), 10u128).encode())
+        );
         // Event should be emitted for approvals
         assert_eq!(erc20.approve(bob, 20), true);
         assert_eq!(env::test::emitted_events::<Types>().count(), 3);
+        assert_eq!(
+            env::test::emitted_events::<Types>().last().unwrap(),
+            expected(1, (alice, bob, 20u128).encode())
+        );
+        // Spending an allowance emits a Transfer and an Approval with what is left
+        env::test::set_caller::<Types>(bob);
+        assert_eq!(erc20.transfer_from(alice, bob, 5), Ok(()));
+        assert_eq!(env::test::emitted_events::<Types>().count(), 5);
+        assert_eq!(
+            env::test::emitted_events::<Types>().last().unwrap(),
+            expected(1, (alice, bob, 15u128).encode())
+        );
     }
 }
@@ -0,0 +1,416 @@
+#![cfg_attr(not(any(test, feature = "test-env")), no_std)]
+
+use ink_core::{
+    env::DefaultSrmlTypes,
+    memory::{format, vec::Vec},
+    storage,
+};
+use ink_lang::contract;
+use parity_codec::{Encode, Decode};
+
+/// The most owners a wallet can have
+const MAX_OWNERS: u32 = 16;
+
+/// What a wallet transaction does once enough owners confirmed it. The ink version this contract is pinned
+/// to has no way for a contract to send value, so there is no transfer action yet, see the README of
+/// collect-ink.
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone)]
+pub enum Action<AccountId> {
+    /// Add an owner
+    AddOwner(AccountId),
+    /// Remove an owner. The requirement is lowered if it would exceed the remaining owners.
+    RemoveOwner(AccountId),
+    /// Change the number of confirmations needed to execute a transaction
+    ChangeRequirement(u32),
+}
+
+/// Why a wallet operation failed
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Error {
+    /// The caller is not an owner of the wallet
+    NotOwner,
+    /// No transaction with this id exists
+    TransactionNotFound,
+    /// The transaction was already executed
+    AlreadyExecuted,
+    /// The caller already confirmed the transaction
+    AlreadyConfirmed,
+    /// The caller has not confirmed the transaction
+    NotConfirmed,
+    /// Fewer owners than required confirmed the transaction
+    NotEnoughConfirmations,
+    /// The action can not be applied to the current owners, e.g. adding an existing owner
+    InvalidAction,
+}
+
+contract! {
+    #![env = DefaultSrmlTypes]
+
+    // Event emitted when an owner submits a transaction
+    event Submission {
+        id: u32,
+        owner: AccountId,
+    }
+
+    // Event emitted when an owner confirms a transaction
+    event Confirmation {
+        id: u32,
+        owner: AccountId,
+    }
+
+    // Event emitted when an owner takes back its confirmation
+    event Revocation {
+        id: u32,
+        owner: AccountId,
+    }
+
+    // Event emitted when a transaction is executed
+    event Execution {
+        id: u32,
+    }
+
+    /// An M-of-N multisig wallet: any owner submits a transaction, and it can be executed once `required`
+    /// owners confirmed it. This is the contract-level counterpart of the approve runtime module.
+    struct Multisig {
+        /// Index of the owners, so they can be enumerated and removed without a scan
+        owners: storage::HashMap<u32, AccountId>,
+        owner_count: storage::Value<u32>,
+        owner_index: storage::HashMap<AccountId, u32>,
+        /// The number of owner confirmations that executes a transaction
+        required: storage::Value<u32>,
+        /// Submitted transactions by id, and whether each was executed
+        transactions: storage::HashMap<u32, Action<AccountId>>,
+        executed: storage::HashMap<u32, bool>,
+        transaction_count: storage::Value<u32>,
+        /// Confirmations by (transaction id, owner). Confirmations of removed owners no longer count.
+        confirmations: storage::HashMap<(u32, AccountId), bool>,
+    }
+
+    impl Deploy for Multisig {
+        /// Without owners the deployer is the only owner. The requirement is kept between 1 and the
+        /// number of owners.
+        fn deploy(&mut self, owners: Vec<AccountId>, required: u32) {
+            self.owner_count.set(0);
+            self.transaction_count.set(0);
+            if owners.is_empty() {
+                self.add_owner_impl(env.caller());
+            }
+            for owner in owners.into_iter().take(MAX_OWNERS as usize) {
+                if !self.is_owner_impl(&owner) {
+                    self.add_owner_impl(owner);
+                }
+            }
+            let count = *self.owner_count;
+            self.required.set(if required == 0 { 1 } else if required > count { count } else { required });
+        }
+    }
+
+    impl Multisig {
+        /// Returns true if the account is an owner of the wallet.
+        pub(external) fn is_owner(&self, who: AccountId) -> bool {
+            let is_owner = self.is_owner_impl(&who);
+            env.println(&format!("Multisig::is_owner(who = {:?}) = {:?}", who, is_owner));
+            is_owner
+        }
+
+        /// Returns the owner at `index`.
+        pub(external) fn owner_by_index(&self, index: u32) -> Option<AccountId> {
+            let owner = self.owners.get(&index).cloned();
+            env.println(&format!("Multisig::owner_by_index(index = {:?}) = {:?}", index, owner));
+            owner
+        }
+
+        /// Returns the number of owners.
+        pub(external) fn owner_count(&self) -> u32 {
+            let count = *self.owner_count;
+            env.println(&format!("Multisig::owner_count = {:?}", count));
+            count
+        }
+
+        /// Returns the number of confirmations that executes a transaction.
+        pub(external) fn required(&self) -> u32 {
+            let required = *self.required;
+            env.println(&format!("Multisig::required = {:?}", required));
+            required
+        }
+
+        /// Returns the action of a transaction, or None if it does not exist.
+        pub(external) fn transaction(&self, id: u32) -> Option<Action<AccountId>> {
+            let action = self.transactions.get(&id).cloned();
+            env.println(&format!("Multisig::transaction(id = {:?}) = {:?}", id, action));
+            action
+        }
+
+        /// Returns true once the transaction was executed.
+        pub(external) fn is_executed(&self, id: u32) -> bool {
+            let executed = *self.executed.get(&id).unwrap_or(&false);
+            env.println(&format!("Multisig::is_executed(id = {:?}) = {:?}", id, executed));
+            executed
+        }
+
+        /// Returns the number of current owners that confirmed the transaction.
+        pub(external) fn confirmation_count(&self, id: u32) -> u32 {
+            let count = self.confirmation_count_impl(id);
+            env.println(&format!("Multisig::confirmation_count(id = {:?}) = {:?}", id, count));
+            count
+        }
+
+        /// Submit a transaction, which the caller confirms right away. Returns the transaction id.
+        pub(external) fn submit(&mut self, action: Action<AccountId>) -> Result<u32, Error> {
+            let caller = env.caller();
+            if !self.is_owner_impl(&caller) {
+                return Err(Error::NotOwner)
+            }
+            let id = *self.transaction_count;
+            self.transactions.insert(id, action);
+            self.transaction_count.set(id + 1);
+            self.confirmations.insert((id, caller), true);
+            env.emit(Submission {
+                id,
+                owner: caller
+            });
+            env.emit(Confirmation {
+                id,
+                owner: caller
+            });
+            Ok(id)
+        }
+
+        /// Confirm a pending transaction.
+        pub(external) fn confirm(&mut self, id: u32) -> Result<(), Error> {
+            let caller = env.caller();
+            self.ensure_pending(&caller, id)?;
+            if self.is_confirmed_by(id, &caller) {
+                return Err(Error::AlreadyConfirmed)
+            }
+            self.confirmations.insert((id, caller), true);
+            env.emit(Confirmation {
+                id,
+                owner: caller
+            });
+            Ok(())
+        }
+
+        /// Take back the caller's confirmation of a pending transaction.
+        pub(external) fn revoke(&mut self, id: u32) -> Result<(), Error> {
+            let caller = env.caller();
+            self.ensure_pending(&caller, id)?;
+            if !self.is_confirmed_by(id, &caller) {
+                return Err(Error::NotConfirmed)
+            }
+            self.confirmations.remove(&(id, caller));
+            env.emit(Revocation {
+                id,
+                owner: caller
+            });
+            Ok(())
+        }
+
+        /// Execute a transaction that enough owners confirmed. Any owner can execute it.
+        pub(external) fn execute(&mut self, id: u32) -> Result<(), Error> {
+            let caller = env.caller();
+            self.ensure_pending(&caller, id)?;
+            if self.confirmation_count_impl(id) < *self.required {
+                return Err(Error::NotEnoughConfirmations)
+            }
+            let action = self.transactions.get(&id).cloned().ok_or(Error::TransactionNotFound)?;
+            match action {
+                Action::AddOwner(who) => {
+                    if self.is_owner_impl(&who) || *self.owner_count >= MAX_OWNERS {
+                        return Err(Error::InvalidAction)
+                    }
+                    self.add_owner_impl(who);
+                }
+                Action::RemoveOwner(who) => {
+                    if !self.is_owner_impl(&who) || *self.owner_count == 1 {
+                        return Err(Error::InvalidAction)
+                    }
+                    self.remove_owner_impl(&who);
+                    if *self.required > *self.owner_count {
+                        self.required.set(*self.owner_count);
+                    }
+                }
+                Action::ChangeRequirement(required) => {
+                    if required == 0 || required > *self.owner_count {
+                        return Err(Error::InvalidAction)
+                    }
+                    self.required.set(required);
+                }
+            }
+            self.executed.insert(id, true);
+            env.emit(Execution {
+                id
+            });
+            Ok(())
+        }
+    }
+
+    impl Multisig {
+        /// Returns true if the account is in the owner index.
+        fn is_owner_impl(&self, who: &AccountId) -> bool {
+            self.owner_index.get(who).is_some()
+        }
+
+        /// Returns true if the owner confirmed the transaction.
+        fn is_confirmed_by(&self, id: u32, owner: &AccountId) -> bool {
+            *self.confirmations.get(&(id, *owner)).unwrap_or(&false)
+        }
+
+        /// Counts the confirmations of the current owners only.
+        fn confirmation_count_impl(&self, id: u32) -> u32 {
+            let mut count = 0;
+            for index in 0..*self.owner_count {
+                if let Some(owner) = self.owners.get(&index) {
+                    if self.is_confirmed_by(id, owner) {
+                        count += 1;
+                    }
+                }
+            }
+            count
+        }
+
+        /// Checks that the caller is an owner and the transaction exists and was not executed.
+        fn ensure_pending(&self, caller: &AccountId, id: u32) -> Result<(), Error> {
+            if !self.is_owner_impl(caller) {
+                return Err(Error::NotOwner)
+            }
+            if self.transactions.get(&id).is_none() {
+                return Err(Error::TransactionNotFound)
+            }
+            if *self.executed.get(&id).unwrap_or(&false) {
+                return Err(Error::AlreadyExecuted)
+            }
+            Ok(())
+        }
+
+        /// Appends the account to the owner index.
+        fn add_owner_impl(&mut self, who: AccountId) {
+            let count = *self.owner_count;
+            self.owners.insert(count, who);
+            self.owner_index.insert(who, count);
+            self.owner_count.set(count + 1);
+        }
+
+        /// Removes the account from the owner index. The last owner takes its place.
+        fn remove_owner_impl(&mut self, who: &AccountId) {
+            let last_index = *self.owner_count - 1;
+            let index = *self.owner_index.get(who).unwrap_or(&last_index);
+            if index != last_index {
+                let last = *self.owners.get(&last_index).unwrap_or(who);
+                self.owners.insert(index, last);
+                self.owner_index.insert(last, index);
+            }
+            self.owners.remove(&last_index);
+            self.owner_index.remove(who);
+            self.owner_count.set(last_index);
+        }
+    }
+}
+
+
+#[cfg(all(test, feature = "test-env"))]
+mod tests {
+    use super::*;
+    use ink_core::env;
+    type Types = ink_core::env::DefaultSrmlTypes;
+
+    #[test]
+    fn deployment_works() {
+        let alice = AccountId::from([0x0; 32]);
+        let bob = AccountId::from([0x1; 32]);
+
+        env::test::set_caller::<Types>(alice);
+        let wallet = Multisig::deploy_mock(vec![], 2);
+        assert_eq!(wallet.is_owner(alice), true);
+        assert_eq!(wallet.owner_count(), 1);
+        assert_eq!(wallet.required(), 1);
+
+        let wallet = Multisig::deploy_mock(vec![alice, bob, alice], 0);
+        assert_eq!(wallet.owner_count(), 2);
+        assert_eq!(wallet.required(), 1);
+    }
+
+    #[test]
+    fn confirm_and_execute_work() {
+        let alice = AccountId::from([0x0; 32]);
+        let bob = AccountId::from([0x1; 32]);
+        let charlie = AccountId::from([0x2; 32]);
+        let dave = AccountId::from([0x3; 32]);
+
+        env::test::set_caller::<Types>(alice);
+        let mut wallet = Multisig::deploy_mock(vec![alice, bob, charlie], 2);
+        assert_eq!(wallet.submit(Action::ChangeRequirement(3)), Ok(0));
+        assert_eq!(wallet.confirmation_count(0), 1);
+        assert_eq!(wallet.confirm(0), Err(Error::AlreadyConfirmed));
+        assert_eq!(wallet.execute(0), Err(Error::NotEnoughConfirmations));
+
+        // Only owners can take part
+        env::test::set_caller::<Types>(dave);
+        assert_eq!(wallet.submit(Action::AddOwner(dave)), Err(Error::NotOwner));
+        assert_eq!(wallet.confirm(0), Err(Error::NotOwner));
+
+        env::test::set_caller::<Types>(bob);
+        assert_eq!(wallet.revoke(0), Err(Error::NotConfirmed));
+        assert_eq!(wallet.confirm(0), Ok(()));
+        assert_eq!(wallet.revoke(0), Ok(()));
+        assert_eq!(wallet.execute(0), Err(Error::NotEnoughConfirmations));
+        assert_eq!(wallet.confirm(0), Ok(()));
+        assert_eq!(wallet.execute(0), Ok(()));
+        assert_eq!(wallet.is_executed(0), true);
+        assert_eq!(wallet.required(), 3);
+        assert_eq!(wallet.execute(0), Err(Error::AlreadyExecuted));
+        assert_eq!(wallet.confirm(1), Err(Error::TransactionNotFound));
+    }
+
+    #[test]
+    fn owner_management_works() {
+        let alice = AccountId::from([0x0; 32]);
+        let bob = AccountId::from([0x1; 32]);
+        let charlie = AccountId::from([0x2; 32]);
+
+        env::test::set_caller::<Types>(alice);
+        let mut wallet = Multisig::deploy_mock(vec![alice, bob], 2);
+        assert_eq!(wallet.submit(Action::AddOwner(charlie)), Ok(0));
+        env::test::set_caller::<Types>(bob);
+        assert_eq!(wallet.confirm(0), Ok(()));
+        assert_eq!(wallet.execute(0), Ok(()));
+        assert_eq!(wallet.owner_count(), 3);
+        assert_eq!(wallet.is_owner(charlie), true);
+
+        // Removing alice moves charlie into her slot, and her confirmations stop counting
+        assert_eq!(wallet.submit(Action::ChangeRequirement(3)), Ok(1));
+        assert_eq!(wallet.submit(Action::RemoveOwner(alice)), Ok(2));
+        env::test::set_caller::<Types>(alice);
+        assert_eq!(wallet.confirm(1), Ok(()));
+        assert_eq!(wallet.confirm(2), Ok(()));
+        assert_eq!(wallet.execute(2), Ok(()));
+        assert_eq!(wallet.is_owner(alice), false);
+        assert_eq!(wallet.owner_by_index(0), Some(charlie));
+        assert_eq!(wallet.owner_count(), 2);
+        assert_eq!(wallet.confirmation_count(1), 1);
+
+        // A requirement above the number of owners can not be set
+        env::test::set_caller::<Types>(charlie);
+        assert_eq!(wallet.confirm(1), Ok(()));
+        assert_eq!(wallet.execute(1), Err(Error::InvalidAction));
+        assert_eq!(wallet.is_executed(1), false);
+    }
+
+    #[test]
+    fn events_work() {
+        let alice = AccountId::from([0x0; 32]);
+        let bob = AccountId::from([0x1; 32]);
+
+        env::test::set_caller::<Types>(alice);
+        let mut wallet = Multisig::deploy_mock(vec![alice], 1);
+        assert_eq!(env::test::emitted_events::<Types>().count(), 0);
+        assert_eq!(wallet.submit(Action::AddOwner(bob)), Ok(0));
+        // Submission and the confirmation of the submitter
+        assert_eq!(env::test::emitted_events::<Types>().count(), 2);
+        assert_eq!(wallet.execute(0), Ok(()));
+        assert_eq!(
+            env::test::emitted_events::<Types>().last().unwrap(),
+            [vec![3u8], 0u32.encode()].concat()
+        );
+    }
+}
@@ -0,0 +1 @@
+cargo test --features test-env
@@ -0,0 +1,334 @@
+#![cfg_attr(not(any(test, feature = "test-env")), no_std)]
+
+use ink_core::{
+    env::DefaultSrmlTypes,
+    memory::format,
+    storage,
+};
+use ink_lang::contract;
+use parity_codec::{Encode, Decode};
+
+/// Why a token operation failed
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Error {
+    /// No token with this id exists
+    TokenNotFound,
+    /// A token with this id already exists
+    TokenExists,
+    /// The caller is not the owner of the contract or of the token
+    NotOwner,
+    /// The caller is neither the owner of the token nor approved for it
+    NotApproved,
+}
+
+contract! {
+    #![env = DefaultSrmlTypes]
+
+    // Event emitted when a token is minted (from is None) or changes owner
+    event Transfer {
+        from: Option<AccountId>,
+        to: Option<AccountId>,
+        id: u32,
+    }
+
+    // Event emitted when the owner of a token approves an account to transfer it
+    event Approval {
+        owner: AccountId,
+        approved: AccountId,
+        id: u32,
+    }
+
+    /// ERC721 style non-fungible tokens. Tokens are enumerable through a global index and a per-owner
+    /// index, like AllKittiesArray and OwnedKittiesArray in the kitties runtime module.
+    struct Erc721 {
+        /// The account that deployed the contract. Only it can mint.
+        owner: storage::Value<AccountId>,
+        /// The owner of each token
+        token_owner: storage::HashMap<u32, AccountId>,
+        /// The account approved to transfer each token. Cleared when the token changes owner.
+        token_approvals: storage::HashMap<u32, AccountId>,
+        /// Global index of all tokens
+        all_tokens: storage::HashMap<u32, u32>,
+        all_tokens_count: storage::Value<u32>,
+        /// Index of the tokens of each owner: (owner, index) -> token id, and the position of each token
+        /// in the index of its owner, so a token can be removed without a scan
+        owned_tokens: storage::HashMap<(AccountId, u32), u32>,
+        owned_tokens_count: storage::HashMap<AccountId, u32>,
+        owned_tokens_index: storage::HashMap<u32, u32>,
+    }
+
+    impl Deploy for Erc721 {
+        fn deploy(&mut self) {
+            self.owner.set(env.caller());
+            self.all_tokens_count.set(0);
+        }
+    }
+
+    impl Erc721 {
+        /// Returns the number of tokens in existence.
+        pub(external) fn total_supply(&self) -> u32 {
+            let total_supply = *self.all_tokens_count;
+            env.println(&format!("Erc721::total_supply = {:?}", total_supply));
+            total_supply
+        }
+
+        /// Returns the number of tokens owned by the given AccountId.
+        pub(external) fn balance_of(&self, owner: AccountId) -> u32 {
+            let balance = self.balance_of_or_zero(&owner);
+            env.println(&format!("Erc721::balance_of(owner = {:?}) = {:?}", owner, balance));
+            balance
+        }
+
+        /// Returns the owner of the token, or None if it does not exist.
+        pub(external) fn owner_of(&self, id: u32) -> Option<AccountId> {
+            let owner = self.token_owner.get(&id).cloned();
+            env.println(&format!("Erc721::owner_of(id = {:?}) = {:?}", id, owner));
+            owner
+        }
+
+        /// Returns the account approved to transfer the token, if any.
+        pub(external) fn get_approved(&self, id: u32) -> Option<AccountId> {
+            let approved = self.token_approvals.get(&id).cloned();
+            env.println(&format!("Erc721::get_approved(id = {:?}) = {:?}", id, approved));
+            approved
+        }
+
+        /// Returns the token at `index` of the global index.
+        pub(external) fn token_by_index(&self, index: u32) -> Option<u32> {
+            let id = self.all_tokens.get(&index).cloned();
+            env.println(&format!("Erc721::token_by_index(index = {:?}) = {:?}", index, id));
+            id
+        }
+
+        /// Returns the token at `index` of the tokens of `owner`.
+        pub(external) fn token_of_owner_by_index(&self, owner: AccountId, index: u32) -> Option<u32> {
+            let id = self.owned_tokens.get(&(owner, index)).cloned();
+            env.println(&format!(
+                "Erc721::token_of_owner_by_index(owner = {:?}, index = {:?}) = {:?}",
+                owner, index, id
+            ));
+            id
+        }
+
+        /// Create the token `id` for the `to` AccountId. Only the owner of the contract can mint.
+        pub(external) fn mint(&mut self, to: AccountId, id: u32) -> Result<(), Error> {
+            if *self.owner != env.caller() {
+                return Err(Error::NotOwner)
+            }
+            if self.token_owner.get(&id).is_some() {
+                return Err(Error::TokenExists)
+            }
+            let count = *self.all_tokens_count;
+            self.all_tokens.insert(count, id);
+            self.all_tokens_count.set(count + 1);
+            self.token_owner.insert(id, to);
+            self.add_to_owner(to, id);
+            env.emit(Transfer {
+                from: None,
+                to: Some(to),
+                id
+            });
+            Ok(())
+        }
+
+        /// Transfers the token from the sender to the `to` AccountId.
+        pub(external) fn transfer(&mut self, to: AccountId, id: u32) -> Result<(), Error> {
+            let owner = self.token_owner.get(&id).cloned().ok_or(Error::TokenNotFound)?;
+            if owner != env.caller() {
+                return Err(Error::NotOwner)
+            }
+            self.transfer_impl(env, owner, to, id);
+            Ok(())
+        }
+
+        /// Transfers the token from `from` to `to`. The caller must own the token or be approved for it.
+        pub(external) fn transfer_from(&mut self, from: AccountId, to: AccountId, id: u32) -> Result<(), Error> {
+            let owner = self.token_owner.get(&id).cloned().ok_or(Error::TokenNotFound)?;
+            if owner != from {
+                return Err(Error::NotOwner)
+            }
+            let caller = env.caller();
+            if caller != owner && self.token_approvals.get(&id) != Some(&caller) {
+                return Err(Error::NotApproved)
+            }
+            self.transfer_impl(env, from, to, id);
+            Ok(())
+        }
+
+        /// Approve the `to` AccountId to transfer the token. Only one account is approved at a time.
+        pub(external) fn approve(&mut self, to: AccountId, id: u32) -> Result<(), Error> {
+            let owner = self.token_owner.get(&id).cloned().ok_or(Error::TokenNotFound)?;
+            if owner != env.caller() {
+                return Err(Error::NotOwner)
+            }
+            self.token_approvals.insert(id, to);
+            env.emit(Approval {
+                owner,
+                approved: to,
+                id
+            });
+            Ok(())
+        }
+    }
+
+    impl Erc721 {
+        /// Returns the number of tokens of the AccountId or 0 if it has none.
+        fn balance_of_or_zero(&self, of: &AccountId) -> u32 {
+            *self.owned_tokens_count.get(of).unwrap_or(&0)
+        }
+
+        /// Appends the token to the index of its new owner.
+        fn add_to_owner(&mut self, owner: AccountId, id: u32) {
+            let count = self.balance_of_or_zero(&owner);
+            self.owned_tokens.insert((owner, count), id);
+            self.owned_tokens_index.insert(id, count);
+            self.owned_tokens_count.insert(owner, count + 1);
+        }
+
+        /// Removes the token from the index of its owner. The last token of the owner takes its place.
+        fn remove_from_owner(&mut self, owner: AccountId, id: u32) {
+            let last_index = self.balance_of_or_zero(&owner) - 1;
+            let index = *self.owned_tokens_index.get(&id).unwrap_or(&last_index);
+            if index != last_index {
+                let last_id = *self.owned_tokens.get(&(owner, last_index)).unwrap_or(&id);
+                self.owned_tokens.insert((owner, index), last_id);
+                self.owned_tokens_index.insert(last_id, index);
+            }
+            self.owned_tokens.remove(&(owner, last_index));
+            self.owned_tokens_index.remove(&id);
+            self.owned_tokens_count.insert(owner, last_index);
+        }
+
+        /// Moves a token whose ownership was already checked.
+        fn transfer_impl(&mut self, env: &mut ink_model::EnvHandler<ink_core::env::ContractEnv<DefaultSrmlTypes>>, from: AccountId, to: AccountId, id: u32) {
+            self.token_approvals.remove(&id);
+            self.remove_from_owner(from, id);
+            self.add_to_owner(to, id);
+            self.token_owner.insert(id, to);
+            env.emit(Transfer {
+                from: Some(from),
+                to: Some(to),
+                id
+            });
+        }
+    }
+}
+
+
+#[cfg(all(test, feature = "test-env"))]
+mod tests {
+    use super::*;
+    use ink_core::env;
+    type Types = ink_core::env::DefaultSrmlTypes;
+
+    #[test]
+    fn mint_works() {
+        let alice = AccountId::from([0x0; 32]);
+        let bob = AccountId::from([0x1; 32]);
+
+        env::test::set_caller::<Types>(alice);
+        let mut erc721 = Erc721::deploy_mock();
+        assert_eq!(erc721.total_supply(), 0);
+        assert_eq!(erc721.mint(bob, 1), Ok(()));
+        assert_eq!(erc721.mint(bob, 1), Err(Error::TokenExists));
+        assert_eq!(erc721.owner_of(1), Some(bob));
+        assert_eq!(erc721.owner_of(2), None);
+        assert_eq!(erc721.balance_of(bob), 1);
+        assert_eq!(erc721.total_supply(), 1);
+
+        // Only the owner of the contract can mint
+        env::test::set_caller::<Types>(bob);
+        assert_eq!(erc721.mint(bob, 2), Err(Error::NotOwner));
+    }
+
+    #[test]
+    fn transfer_works() {
+        let alice = AccountId::from([0x0; 32]);
+        let bob = AccountId::from([0x1; 32]);
+
+        env::test::set_caller::<Types>(alice);
+        let mut erc721 = Erc721::deploy_mock();
+        assert_eq!(erc721.mint(alice, 1), Ok(()));
+        assert_eq!(erc721.transfer(bob, 2), Err(Error::TokenNotFound));
+        assert_eq!(erc721.transfer(bob, 1), Ok(()));
+        assert_eq!(erc721.owner_of(1), Some(bob));
+        assert_eq!(erc721.balance_of(alice), 0);
+        assert_eq!(erc721.balance_of(bob), 1);
+        // Alice no longer owns it
+        assert_eq!(erc721.transfer(alice, 1), Err(Error::NotOwner));
+    }
+
+    #[test]
+    fn approve_works() {
+        let alice = AccountId::from([0x0; 32]);
+        let bob = AccountId::from([0x1; 32]);
+        let charlie = AccountId::from([0x2; 32]);
+
+        env::test::set_caller::<Types>(alice);
+        let mut erc721 = Erc721::deploy_mock();
+        assert_eq!(erc721.mint(alice, 1), Ok(()));
+        env::test::set_caller::<Types>(bob);
+        assert_eq!(erc721.transfer_from(alice, bob, 1), Err(Error::NotApproved));
+        assert_eq!(erc721.approve(bob, 1), Err(Error::NotOwner));
+
+        env::test::set_caller::<Types>(alice);
+        assert_eq!(erc721.approve(bob, 1), Ok(()));
+        assert_eq!(erc721.get_approved(1), Some(bob));
+        env::test::set_caller::<Types>(charlie);
+        assert_eq!(erc721.transfer_from(alice, charlie, 1), Err(Error::NotApproved));
+        env::test::set_caller::<Types>(bob);
+        assert_eq!(erc721.transfer_from(charlie, bob, 1), Err(Error::NotOwner));
+        assert_eq!(erc721.transfer_from(alice, charlie, 1), Ok(()));
+        assert_eq!(erc721.owner_of(1), Some(charlie));
+        // The approval is cleared by the transfer
+        assert_eq!(erc721.get_approved(1), None);
+        assert_eq!(erc721.transfer_from(charlie, bob, 1), Err(Error::NotApproved));
+    }
+
+    #[test]
+    fn enumeration_works() {
+        let alice = AccountId::from([0x0; 32]);
+        let bob = AccountId::from([0x1; 32]);
+
+        env::test::set_caller::<Types>(alice);
+        let mut erc721 = Erc721::deploy_mock();
+        for id in 10..13 {
+            assert_eq!(erc721.mint(alice, id), Ok(()));
+        }
+        assert_eq!(erc721.token_by_index(0), Some(10));
+        assert_eq!(erc721.token_by_index(2), Some(12));
+        assert_eq!(erc721.token_by_index(3), None);
+
+        // Moving the first token swaps the last one into its place
+        assert_eq!(erc721.transfer(bob, 10), Ok(()));
+        assert_eq!(erc721.balance_of(alice), 2);
+        assert_eq!(erc721.token_of_owner_by_index(alice, 0), Some(12));
+        assert_eq!(erc721.token_of_owner_by_index(alice, 1), Some(11));
+        assert_eq!(erc721.token_of_owner_by_index(alice, 2), None);
+        assert_eq!(erc721.token_of_owner_by_index(bob, 0), Some(10));
+        // The global index does not change on transfers
+        assert_eq!(erc721.token_by_index(0), Some(10));
+    }
+
+    #[test]
+    fn events_work() {
+        let alice = AccountId::from([0x0; 32]);
+        let bob = AccountId::from([0x1; 32]);
+
+        env::test::set_caller::<Types>(alice);
+        let mut erc721 = Erc721::deploy_mock();
+        assert_eq!(env::test::emitted_events::<Types>().count(), 0);
+        assert_eq!(erc721.mint(alice, 1), Ok(()));
+        assert_eq!(erc721.approve(bob, 1), Ok(()));
+        assert_eq!(
+            env::test::emitted_events::<Types>().last().unwrap(),
+            [vec![1u8], (alice, bob, 1u32).encode()].concat()
+        );
+        assert_eq!(erc721.transfer(bob, 1), Ok(()));
+        assert_eq!(env::test::emitted_events::<Types>().count(), 3);
+        assert_eq!(
+            env::test::emitted_events::<Types>().last().unwrap(),
+            [vec![0u8], (Some(alice), Some(bob), 1u32).encode()].concat()
+        );
+    }
+}
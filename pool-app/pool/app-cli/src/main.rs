@@ -0,0 +1,79 @@
+//! substrate-app-cli prints the app state of a running pool node as plain text reports, so the groups,
+//! kitties, pools and proposals can be checked without a web UI. It talks to the HTTP RPC port of the node
+//! and only uses the runtime APIs, see rpc.rs.
+
+mod report;
+mod rpc;
+
+use primitives::{crypto::Ss58Codec, sr25519, Pair};
+use pool_runtime::AccountId;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "substrate-app-cli", about = "Reports of the app state of a pool node")]
+struct Opt {
+	/// The HTTP RPC endpoint of the node
+	#[structopt(long = "url", default_value = "http://localhost:9933")]
+	url: String,
+
+	#[structopt(subcommand)]
+	cmd: Command,
+}
+
+#[derive(Debug, StructOpt)]
+enum Command {
+	/// The groups owned by an account, with their members
+	#[structopt(name = "groups")]
+	Groups {
+		/// SS58 address, or a dev seed such as //Alice
+		account: String,
+	},
+	/// The kitties of an account, with the traits encoded in their dna
+	#[structopt(name = "kitties")]
+	Kitties {
+		/// SS58 address, or a dev seed such as //Alice
+		account: String,
+	},
+	/// Every pool with its owner and balances
+	#[structopt(name = "pools")]
+	Pools,
+	/// The pending proposals with their votes
+	#[structopt(name = "proposals")]
+	Proposals {
+		/// Only list the proposals waiting on a vote of this account
+		#[structopt(long = "voter")]
+		voter: Option<String>,
+	},
+}
+
+fn parse_account(s: &str) -> rpc::Result<AccountId> {
+	if s.starts_with("//") {
+		return sr25519::Pair::from_string(s, None)
+			.map(|pair| pair.public())
+			.map_err(|e| format!("Invalid seed {}: {:?}", s, e));
+	}
+	AccountId::from_ss58check(s).map_err(|e| format!("Invalid address {}: {:?}", s, e))
+}
+
+fn run(opt: Opt) -> rpc::Result<()> {
+	let client = rpc::Client::new(&opt.url);
+	match opt.cmd {
+		Command::Groups { account } => report::groups(&client, parse_account(&account)?),
+		Command::Kitties { account } => report::kitties(&client, parse_account(&account)?),
+		Command::Pools => report::pools(&client),
+		Command::Proposals { voter } => {
+			let voter = match voter {
+				Some(voter) => Some(parse_account(&voter)?),
+				None => None,
+			};
+			report::proposals(&client, voter)
+		}
+	}
+}
+
+fn main() {
+	if let Err(e) = run(Opt::from_args()) {
+		eprintln!("{}", e);
+		std::process::exit(1);
+	}
+}
@@ -0,0 +1,134 @@
+//! The reports printed by each subcommand. They only read through the runtime APIs, see rpc.rs.
+
+use primitives::crypto::Ss58Codec;
+use pool_runtime::{AccountId, Balance, Hash, Moment};
+use pool_runtime::approve::ProposalStatus;
+use pool_runtime::groups::Group;
+use crate::rpc::{Client, Result};
+
+/// How many pools or proposals are fetched per runtime API call
+const PAGE_SIZE: u64 = 50;
+
+/// The appearance of a kitty, as the kitty avatar of the Substratekitties UI reads it from the first dna bytes
+#[derive(Debug, PartialEq)]
+pub struct KittyTraits {
+	pub body: u8,
+	pub eyes: u8,
+	pub accessory: u8,
+	pub fur: u8,
+	pub mouth: u8,
+}
+
+impl KittyTraits {
+	pub fn from_dna(dna: &Hash) -> Self {
+		let dna = dna.as_bytes();
+		KittyTraits {
+			body: dna[0] % 15,
+			eyes: dna[1] % 15,
+			accessory: dna[2] % 20,
+			fur: dna[3] % 10,
+			mouth: dna[4] % 10,
+		}
+	}
+}
+
+pub fn groups(client: &Client, owner: AccountId) -> Result<()> {
+	let group_ids: Vec<Hash> = client.call("GroupsApi_groups_by_owner", &owner)?;
+	println!("{} owns {} groups", owner.to_ss58check(), group_ids.len());
+	for group_id in group_ids {
+		let group: Option<Group<Hash, Moment>> = client.call("GroupsApi_group", group_id)?;
+		let group = match group {
+			Some(group) => group,
+			None => continue,
+		};
+		println!();
+		println!("{} {:?}", String::from_utf8_lossy(&group.name), group_id);
+		let members: Option<Vec<AccountId>> = client.call("GroupsApi_members", group_id)?;
+		println!("  created at {}, updated at {}", group.created_at, group.updated_at);
+		println!("  members: {} of {}", group.member_count, group.max_size);
+		for member in members.unwrap_or_default() {
+			println!("  - {}", member.to_ss58check());
+		}
+	}
+	Ok(())
+}
+
+pub fn kitties(client: &Client, owner: AccountId) -> Result<()> {
+	let kitties: Vec<(Hash, Hash, u64)> = client.call("KittiesApi_kitties_of", &owner)?;
+	println!("{} owns {} kitties", owner.to_ss58check(), kitties.len());
+	for (kitty_id, dna, gen) in kitties {
+		let traits = KittyTraits::from_dna(&dna);
+		let listing: Option<(AccountId, Balance)> = client.call("MarketplaceApi_listing", kitty_id)?;
+		let price = match listing {
+			Some((_, price)) => format!("for sale at {}", price),
+			None => "not for sale".to_string(),
+		};
+		println!();
+		println!("{:?}", kitty_id);
+		println!("  generation {}, {}", gen, price);
+		println!("  body {}, eyes {}, accessory {}, fur {}, mouth {}",
+			traits.body, traits.eyes, traits.accessory, traits.fur, traits.mouth);
+	}
+	Ok(())
+}
+
+pub fn pools(client: &Client) -> Result<()> {
+	let mut start = 0;
+	loop {
+		let page: Vec<(Hash, Option<AccountId>, Balance, Balance, bool)> = client.call("PoolApi_pools", (start, PAGE_SIZE))?;
+		for (pool_id, owner, contributed, donated, drained) in page.iter() {
+			let owner = owner.as_ref().map(|o| o.to_ss58check()).unwrap_or_else(|| "no owner".to_string());
+			let state = if *drained { ", drained" } else { "" };
+			println!("{:?}", pool_id);
+			println!("  owner {}", owner);
+			println!("  contributed {}, donated {}{}", contributed, donated, state);
+		}
+		if (page.len() as u64) < PAGE_SIZE {
+			return Ok(());
+		}
+		start += PAGE_SIZE;
+	}
+}
+
+/// The proposals waiting on a vote of the account, or all pending proposals without one
+pub fn proposals(client: &Client, voter: Option<AccountId>) -> Result<()> {
+	let proposal_ids: Vec<Hash> = match voter {
+		Some(ref voter) => client.call("ApproveApi_pending_for", voter)?,
+		None => {
+			let mut ids = Vec::new();
+			loop {
+				let page: Vec<Hash> = client.call("ApproveApi_active_proposals", (ids.len() as u64, PAGE_SIZE))?;
+				let done = (page.len() as u64) < PAGE_SIZE;
+				ids.extend(page);
+				if done {
+					break ids;
+				}
+			}
+		}
+	};
+	match voter {
+		Some(voter) => println!("{} pending proposals wait on a vote of {}", proposal_ids.len(), voter.to_ss58check()),
+		None => println!("{} pending proposals", proposal_ids.len()),
+	}
+	for proposal_id in proposal_ids {
+		let status: Option<ProposalStatus> = client.call("ApproveApi_proposal_status", proposal_id)?;
+		let votes: Vec<(AccountId, bool)> = client.call("ApproveApi_votes_of", proposal_id)?;
+		let ayes = votes.iter().filter(|(_, aye)| *aye).count();
+		println!();
+		println!("{:?}", proposal_id);
+		println!("  status {:?}, {} ayes and {} nays", status, ayes, votes.len() - ayes);
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn kitty_traits_should_read_the_first_dna_bytes() {
+		let mut dna = [0u8; 32];
+		dna[..5].copy_from_slice(&[16, 14, 25, 9, 123]);
+		assert_eq!(KittyTraits::from_dna(&Hash::from(dna)), KittyTraits { body: 1, eyes: 14, accessory: 5, fur: 9, mouth: 3 });
+	}
+}
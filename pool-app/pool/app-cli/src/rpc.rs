@@ -0,0 +1,44 @@
+//! A minimal JSON-RPC client for the runtime APIs of the node. Every query goes through the `state_call` RPC
+//! with the SCALE encoded arguments, and the SCALE encoded result is decoded with the runtime types, so the
+//! node needs no custom RPC methods.
+
+use parity_codec::{Decode, Encode};
+use serde_json::{json, Value};
+
+pub type Result<T> = std::result::Result<T, String>;
+
+pub struct Client {
+	url: String,
+	http: reqwest::Client,
+}
+
+impl Client {
+	pub fn new(url: &str) -> Self {
+		Client {
+			url: url.to_string(),
+			http: reqwest::Client::new(),
+		}
+	}
+
+	/// Call a runtime API method at the best block, e.g. "GroupsApi_groups_by_owner"
+	pub fn call<A: Encode, R: Decode>(&self, method: &str, args: A) -> Result<R> {
+		let data = format!("0x{}", hex::encode(args.encode()));
+		let result = self.request("state_call", json!([method, data]))?;
+		let hex_result = result.as_str().ok_or_else(|| format!("{} returned {} instead of bytes", method, result))?;
+		let bytes = hex::decode(hex_result.trim_start_matches("0x")).map_err(|e| format!("{}: {}", method, e))?;
+		R::decode(&mut &bytes[..]).ok_or_else(|| format!("Can not decode the result of {}, is the node on this runtime?", method))
+	}
+
+	fn request(&self, method: &str, params: Value) -> Result<Value> {
+		let body = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+		let mut response: Value = self.http.post(&self.url)
+			.json(&body)
+			.send()
+			.and_then(|mut r| r.json())
+			.map_err(|e| format!("RPC request to {} failed: {}", self.url, e))?;
+		if let Some(error) = response.get("error") {
+			return Err(format!("{} failed: {}", method, error));
+		}
+		Ok(response["result"].take())
+	}
+}
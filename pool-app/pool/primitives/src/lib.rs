@@ -0,0 +1,225 @@
+//! Types and traits shared by the custom modules of the pool runtime. A module that works with the objects of
+//! another module takes one of the traits below as an associated type of its Trait, so it only depends on
+//! this crate and the runtime picks the implementation.
+//!
+//! The ids are newtypes over the hash type of the runtime. They encode exactly like the hash they wrap, so
+//! storage written with a raw hash reads back as an id and the other way around.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use parity_codec::{Encode, Decode};
+#[cfg(feature = "std")]
+use serde::{Serialize, Deserialize};
+use rstd::prelude::Vec;
+use rstd::result;
+
+macro_rules! hash_id {
+	($(#[$attr:meta])* $name:ident) => {
+		$(#[$attr])*
+		#[derive(Encode, Decode, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+		#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+		pub struct $name<H>(pub H);
+
+		impl<H> From<H> for $name<H> {
+			fn from(hash: H) -> Self {
+				$name(hash)
+			}
+		}
+
+		impl<H: AsRef<[u8]>> AsRef<[u8]> for $name<H> {
+			fn as_ref(&self) -> &[u8] {
+				self.0.as_ref()
+			}
+		}
+	}
+}
+
+hash_id!(
+	/// The id of a group of the groups module
+	GroupId
+);
+hash_id!(
+	/// The id of a kitty of the substratekitties module
+	KittyId
+);
+hash_id!(
+	/// The id of a pool of the pool module
+	PoolId
+);
+hash_id!(
+	/// The id of a proposal of the approve module
+	ProposalId
+);
+
+/// Lookup of the current members of a group, implemented by the groups module
+pub trait GroupInspector<AccountId, Hash> {
+	/// The current members of the group, or None if the group does not exist
+	fn members(group_id: GroupId<Hash>) -> Option<Vec<AccountId>>;
+
+	/// True if the account is a member of the group
+	fn is_member(group_id: GroupId<Hash>, who: &AccountId) -> bool;
+
+	/// The owner of the group, or None if the group does not exist
+	fn owner(group_id: GroupId<Hash>) -> Option<AccountId>;
+}
+
+/// For runtimes without a groups module
+impl<AccountId, Hash> GroupInspector<AccountId, Hash> for () {
+	fn members(_group_id: GroupId<Hash>) -> Option<Vec<AccountId>> {
+		None
+	}
+
+	fn is_member(_group_id: GroupId<Hash>, _who: &AccountId) -> bool {
+		false
+	}
+
+	fn owner(_group_id: GroupId<Hash>) -> Option<AccountId> {
+		None
+	}
+}
+
+/// Adds members to groups on behalf of another module, such as one that brings in new members by referral.
+/// Implemented by the groups module.
+pub trait GroupJoiner<AccountId, Hash> {
+	/// Add the account to the group under the rules of a join by the account itself, including its fee
+	fn join(group_id: GroupId<Hash>, who: AccountId) -> result::Result<(), &'static str>;
+}
+
+/// For runtimes without a groups module. Joining always fails.
+impl<AccountId, Hash> GroupJoiner<AccountId, Hash> for () {
+	fn join(_group_id: GroupId<Hash>, _who: AccountId) -> result::Result<(), &'static str> {
+		Err("Groups are not available")
+	}
+}
+
+/// Lets other modules gate a sensitive operation on an approval instead of embedding their own voting. The
+/// consuming module requests an approval for the hash of the operation, keeps the returned id and checks it
+/// before it goes ahead. Implemented by the approve module.
+pub trait ApprovalProvider<AccountId, Hash> {
+	/// Create a proposal for the payload hash on behalf of the account and return the proposal id
+	fn request_approval(who: AccountId, payload_hash: Hash) -> result::Result<ProposalId<Hash>, &'static str>;
+
+	/// True once the proposal has been approved
+	fn is_approved(proposal_id: ProposalId<Hash>) -> bool;
+}
+
+/// For runtimes without an approval module. Requests always fail, so gated operations are never allowed.
+impl<AccountId, Hash> ApprovalProvider<AccountId, Hash> for () {
+	fn request_approval(_who: AccountId, _payload_hash: Hash) -> result::Result<ProposalId<Hash>, &'static str> {
+		Err("Approvals are not available")
+	}
+
+	fn is_approved(_proposal_id: ProposalId<Hash>) -> bool {
+		false
+	}
+}
+
+/// Checks the origin of governance calls, such as config changes and emergency actions, so modules do not
+/// depend on the module that decides them. Implemented by the Council of the approve module.
+pub trait GovernanceOrigin<Origin> {
+	/// Ok if the origin is allowed to make governance calls
+	fn ensure_governance(origin: Origin) -> result::Result<(), &'static str>;
+}
+
+/// For runtimes without a council. Governance calls always fail.
+impl<Origin> GovernanceOrigin<Origin> for () {
+	fn ensure_governance(_origin: Origin) -> result::Result<(), &'static str> {
+		Err("Governance is not available")
+	}
+}
+
+/// The app calls that pay a fee on top of the transaction fee, as they add state that others have to iterate
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+pub enum FeeCall {
+	CreateKitty,
+	BreedKitty,
+	CreateGroup,
+	JoinGroup,
+}
+
+/// Charges the fee of an app call, implemented by the fees module. Modules call it before they change any
+/// storage, so a call that can not pay the fee changes nothing.
+pub trait FeeCharger<AccountId> {
+	/// Take the fee of the call from the account
+	fn charge_fee(who: &AccountId, call: FeeCall) -> result::Result<(), &'static str>;
+}
+
+/// For runtimes without app fees. Every call is free.
+impl<AccountId> FeeCharger<AccountId> for () {
+	fn charge_fee(_who: &AccountId, _call: FeeCall) -> result::Result<(), &'static str> {
+		Ok(())
+	}
+}
+
+/// Ownership and transfer of non-fungible tokens, implemented by the substratekitties module and the collections of
+/// the nft module. The marketplace module trades them through it.
+pub trait NftProvider<AccountId, Hash> {
+	/// The owner of the token, or None if it does not exist
+	fn owner_of(kitty_id: KittyId<Hash>) -> Option<AccountId>;
+
+	/// Move the token from its owner to another account. Fails if `from` is not the owner.
+	fn transfer(from: AccountId, to: AccountId, kitty_id: KittyId<Hash>) -> result::Result<(), &'static str>;
+}
+
+/// For runtimes without an NFT module
+impl<AccountId, Hash> NftProvider<AccountId, Hash> for () {
+	fn owner_of(_kitty_id: KittyId<Hash>) -> Option<AccountId> {
+		None
+	}
+
+	fn transfer(_from: AccountId, _to: AccountId, _kitty_id: KittyId<Hash>) -> result::Result<(), &'static str> {
+		Err("NFTs are not available")
+	}
+}
+
+/// A reference price of the native currency in a stable unit, implemented by the oracle module. The marketplace
+/// converts the listings priced in the stable unit with it.
+pub trait PriceOracle<Balance> {
+	/// The native balance worth one stable unit, or None while there are not enough fresh prices
+	fn price() -> Option<Balance>;
+}
+
+/// For runtimes without an oracle. There is never a price.
+impl<Balance> PriceOracle<Balance> for () {
+	fn price() -> Option<Balance> {
+		None
+	}
+}
+
+/// Dispatches a call of a module in a later block, implemented by the scheduler module. A module that acts after
+/// a delay schedules one of its own calls with it instead of keeping a queue of its own. The call is dispatched
+/// as root, so the module can keep it out of reach of signed accounts with ensure_root.
+pub trait CallScheduler<BlockNumber, Call> {
+	/// Dispatch the call in block `when` and return the id of the scheduled call
+	fn schedule(when: BlockNumber, call: Call) -> result::Result<u64, &'static str>;
+
+	/// Drop a scheduled call before it is dispatched
+	fn cancel(id: u64) -> result::Result<(), &'static str>;
+}
+
+/// For runtimes without a scheduler. Scheduling always fails.
+impl<BlockNumber, Call> CallScheduler<BlockNumber, Call> for () {
+	fn schedule(_when: BlockNumber, _call: Call) -> result::Result<u64, &'static str> {
+		Err("Scheduling is not available")
+	}
+
+	fn cancel(_id: u64) -> result::Result<(), &'static str> {
+		Err("Scheduling is not available")
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use primitives::H256;
+
+	#[test]
+	fn ids_should_encode_like_the_hash() {
+		let hash = H256::from([7; 32]);
+		assert_eq!(GroupId(hash).encode(), hash.encode());
+		assert_eq!(KittyId(hash).encode(), hash.encode());
+		assert_eq!(PoolId(hash).encode(), hash.encode());
+		assert_eq!(ProposalId::<H256>::decode(&mut &hash.encode()[..]), Some(ProposalId(hash)));
+	}
+}
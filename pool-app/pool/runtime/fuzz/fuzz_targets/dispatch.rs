@@ -0,0 +1,145 @@
+//! Decodes the input into a sequence of (origin, Call) pairs and dispatches the calls of the custom modules
+//! against fresh externalities. A panic in a dispatchable halts block production, so any panic is a finding,
+//! as is a broken index after a call. Each call is prefixed with one byte that picks the origin:
+//! 0 to 3 sign with one of the endowed accounts, 4 is unsigned and 5 is root.
+//!
+//! Run with `cargo +nightly fuzz run dispatch` from the runtime directory.
+
+#![no_main]
+#[macro_use] extern crate libfuzzer_sys;
+
+use parity_codec::Decode;
+use primitives::Blake2Hasher;
+use runtime_io::{with_externalities, TestExternalities};
+use runtime_primitives::{BuildStorage, traits::Dispatchable};
+use pool_runtime::{
+	AccountId, Call, Origin, Runtime, System, Groups, Substratekitties,
+	SystemConfig, BalancesConfig, GroupsConfig, PoolConfig, ApproveConfig, SubstratekittiesConfig, FeesConfig, FeeCall,
+};
+
+const ACCOUNTS: u8 = 4;
+// Enough calls to reach most states, while keeping each run fast
+const MAX_CALLS: usize = 16;
+
+fn account(n: u8) -> AccountId {
+	AccountId::from_raw([n; 32])
+}
+
+fn new_test_ext() -> TestExternalities<Blake2Hasher> {
+	let accounts: Vec<AccountId> = (0..ACCOUNTS).map(account).collect();
+	let mut t = SystemConfig::default().build_storage().unwrap().0;
+	t.extend(BalancesConfig {
+		transaction_base_fee: 0,
+		transaction_byte_fee: 0,
+		existential_deposit: 0,
+		transfer_fee: 0,
+		creation_fee: 0,
+		balances: accounts.iter().cloned().map(|a| (a, 1 << 40)).collect(),
+		vesting: vec![],
+	}.build_storage().unwrap().0);
+	t.extend(GroupsConfig {
+		max_group_size: 4,
+		max_groups_per_owner: 3,
+		max_name_size: 8,
+		initial_groups: vec![(account(0), b"Genesis".to_vec(), 4, vec![account(1)])],
+	}.build_storage().unwrap().0);
+	t.extend(PoolConfig {
+		max_snapshots: 2,
+		large_payout: 1 << 20,
+		spend_limit: 1 << 21,
+		spend_period: 10,
+	}.build_storage().unwrap().0);
+	t.extend(ApproveConfig {
+		threshold: 2,
+		threshold_percent: 0,
+		proposal_lifetime: 10,
+		reminder_blocks: 3,
+		proposal_bond: 100,
+		cancel_grace: 2,
+		max_failure_percent: 75,
+		min_recorded_proposals: 2,
+		initial_approvers: accounts.iter().cloned().map(|a| (a, 1)).collect(),
+	}.build_storage().unwrap().0);
+	t.extend(SubstratekittiesConfig {
+		kitties: vec![(account(0), [1; 32].into())],
+	}.build_storage().unwrap().0);
+	t.extend(FeesConfig {
+		call_fees: vec![(FeeCall::CreateKitty, 10), (FeeCall::BreedKitty, 10), (FeeCall::CreateGroup, 10), (FeeCall::JoinGroup, 10)],
+		call_limits: vec![(FeeCall::CreateKitty, 2), (FeeCall::JoinGroup, 2)],
+		rate_window: 5,
+	}.build_storage().unwrap().0);
+	t.into()
+}
+
+// Only the custom modules are fuzzed, the SRML modules have fuzzing of their own upstream
+fn is_custom(call: &Call) -> bool {
+	match call {
+		Call::Groups(_) | Call::Substratekitties(_) | Call::Marketplace(_) | Call::Pool(_) | Call::Approve(_)
+		| Call::Fees(_) => true,
+		_ => false,
+	}
+}
+
+// Every group is in AllGroupsArray and in the array of its owner, with its indexes pointing back to it
+fn check_groups() {
+	let count = Groups::all_groups_count();
+	for i in 0..count {
+		let group_id = Groups::group_by_index(i);
+		let owner = Groups::owner_of(group_id).expect("every indexed group has an owner");
+		let owned_index = Groups::owned_groups_index(group_id);
+		assert!(owned_index < Groups::owned_group_count(&owner));
+		assert_eq!(Groups::owned_group_by_index(&owner, owned_index), group_id);
+
+		let group = Groups::group(group_id);
+		assert!(group.member_count <= group.max_size);
+		let members = Groups::members_of(group_id).expect("every indexed group exists");
+		assert_eq!(members.len() as u32, group.member_count);
+		for member in members {
+			assert!(Groups::is_group_member(group_id, member));
+		}
+	}
+}
+
+// Every kitty is in AllKittiesArray and in the array of its owner, with its indexes pointing back to it
+fn check_kitties() {
+	let count = Substratekitties::num_of_kitties();
+	for i in 0..count {
+		let kitty_id = Substratekitties::kitty_id(i);
+		assert_eq!(Substratekitties::index_of(kitty_id), i);
+		let owner = Substratekitties::owner_of(kitty_id).expect("every indexed kitty has an owner");
+		let owned_index = Substratekitties::owned_kitties_index(kitty_id);
+		assert!(owned_index < Substratekitties::owned_kitty_count(&owner));
+		assert_eq!(Substratekitties::kitty_of_owner_by_index(&owner, owned_index), kitty_id);
+	}
+}
+
+fuzz_target!(|data: &[u8]| {
+	with_externalities(&mut new_test_ext(), || {
+		System::set_block_number(1);
+		let mut input = data;
+		for _ in 0..MAX_CALLS {
+			let (signer, rest) = match input.split_first() {
+				Some((signer, rest)) => (*signer, rest),
+				None => break,
+			};
+			input = rest;
+			let call = match Call::decode(&mut input) {
+				Some(call) => call,
+				None => break,
+			};
+			if !is_custom(&call) {
+				continue;
+			}
+
+			let origin = match signer % (ACCOUNTS + 2) {
+				n if n < ACCOUNTS => Origin::signed(account(n)),
+				n if n == ACCOUNTS => Origin::NONE,
+				_ => Origin::ROOT,
+			};
+			let _ = call.dispatch(origin);
+
+			check_groups();
+			check_kitties();
+		}
+	});
+});
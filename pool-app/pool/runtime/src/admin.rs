@@ -0,0 +1,296 @@
+/// Admin is a small module for changing the config values of the other custom modules on a live chain. Without
+/// it they can only be set in the genesis config. Every call is a council motion, see approve::Council, so no
+/// single key can change them.
+///
+/// The pool module charges no call fees, so its payout limits, snapshot count and protocol fee are what can be
+/// changed there.
+
+use runtime_primitives::Permill;
+use support::{decl_module, decl_event, ensure, dispatch::Result};
+use support::traits::Currency;
+use app_primitives::GovernanceOrigin;
+use crate::{groups, ids, marketplace, pool, substratekitties};
+
+/// The balance type of the currency held by pools
+type PoolBalanceOf<T> = <<T as pool::Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+
+pub trait Trait: groups::Trait + substratekitties::Trait + pool::Trait + marketplace::Trait {
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	/// Checks the origin of every call. In the runtime this is the Council of the approve module.
+	type Council: GovernanceOrigin<Self::Origin>;
+}
+
+decl_event!(
+	pub enum Event<T> where
+		PoolBalance = PoolBalanceOf<T>
+	{
+		/// The groups config changed to (max_group_size, max_groups_per_owner, max_name_size)
+		GroupsConfigChanged(u32, u64, u32),
+
+		/// The kitty supply cap changed, None is no cap
+		KittySupplyCapChanged(Option<u64>),
+
+		/// The pool config changed to (max_snapshots, large_payout, spend_limit, spend_period)
+		PoolConfigChanged(u32, PoolBalance, PoolBalance, u32),
+
+		/// New groups, kitties and pools get sequential ids when true, random ids when false
+		IdModeChanged(bool),
+
+		/// The cuts that go to the treasury changed to (marketplace sale cut, pool protocol fee)
+		TreasuryCutsChanged(Permill, Permill),
+	}
+);
+
+decl_module! {
+	/// The module declaration.
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+
+		fn deposit_event<T>() = default;
+
+		/// Change the limits of the groups module. Existing groups keep their size, the new limits apply when
+		/// groups are created, renamed or resized.
+		pub fn set_groups_config(origin, max_group_size: u32, max_groups_per_owner: u64, max_name_size: u32) -> Result {
+			<T as Trait>::Council::ensure_governance(origin)?;
+
+			<groups::Module<T>>::set_config(max_group_size, max_groups_per_owner, max_name_size);
+			Self::deposit_event(RawEvent::GroupsConfigChanged(max_group_size, max_groups_per_owner, max_name_size));
+			Ok(())
+		}
+
+		/// Cap the number of kitties, or remove the cap with None
+		pub fn set_max_kitties(origin, max_kitties: Option<u64>) -> Result {
+			<T as Trait>::Council::ensure_governance(origin)?;
+
+			<substratekitties::Module<T>>::set_max_kitties(max_kitties);
+			Self::deposit_event(RawEvent::KittySupplyCapChanged(max_kitties));
+			Ok(())
+		}
+
+		/// Change the snapshot count and payout limits of the pool module
+		pub fn set_pool_config(origin, max_snapshots: u32, large_payout: PoolBalanceOf<T>, spend_limit: PoolBalanceOf<T>,
+			spend_period: u32) -> Result
+		{
+			<T as Trait>::Council::ensure_governance(origin)?;
+			ensure!(max_snapshots > 0, "max_snapshots must be greater than zero");
+			ensure!(spend_period > 0, "spend_period must be greater than zero");
+
+			<pool::Module<T>>::set_config(max_snapshots, large_payout, spend_limit, spend_period);
+			Self::deposit_event(RawEvent::PoolConfigChanged(max_snapshots, large_payout, spend_limit, spend_period));
+			Ok(())
+		}
+
+		/// Switch the ids of new groups, kitties and pools between sequential and random, see the ids module.
+		/// Existing items keep their ids.
+		pub fn set_sequential_ids(origin, sequential: bool) -> Result {
+			<T as Trait>::Council::ensure_governance(origin)?;
+
+			<ids::Module<T>>::set_sequential(sequential);
+			Self::deposit_event(RawEvent::IdModeChanged(sequential));
+			Ok(())
+		}
+
+		/// Change the fraction of each marketplace sale and of each pool donation that goes to the treasury
+		pub fn set_treasury_cuts(origin, sale_cut: Permill, protocol_fee: Permill) -> Result {
+			<T as Trait>::Council::ensure_governance(origin)?;
+
+			<marketplace::Module<T>>::set_sale_cut(sale_cut);
+			<pool::Module<T>>::set_protocol_fee(protocol_fee);
+			Self::deposit_event(RawEvent::TreasuryCutsChanged(sale_cut, protocol_fee));
+			Ok(())
+		}
+	}
+}
+
+// *****************************************************************************************************
+// Unit Tests!
+// *****************************************************************************************************
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use runtime_io::{with_externalities};
+	use primitives::{H256, Blake2Hasher};
+	use support::{impl_outer_origin, assert_ok, assert_noop};
+	use runtime_primitives::{
+		BuildStorage,
+		traits::{BlakeTwo256, IdentityLookup},
+		testing::{Digest, DigestItem, Header}
+	};
+
+	use crate::approve;
+
+	impl_outer_origin! {
+		pub enum Origin for AdminTest {
+			approve
+		}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct AdminTest;
+	impl system::Trait for AdminTest {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type Digest = Digest;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type Log = DigestItem;
+	}
+	impl timestamp::Trait for AdminTest {
+		type Moment = u64;
+		type OnTimestampSet = ();
+	}
+	impl balances::Trait for AdminTest {
+		type Balance = u64;
+		type OnFreeBalanceZero = ();
+		type OnNewAccount = ();
+		type Event = ();
+		type TransactionPayment = ();
+		type TransferPayment = ();
+		type DustRemoval = ();
+	}
+	impl groups::Trait for AdminTest {
+		type Event = ();
+		type Fees = ();
+		type Accounts = ();
+	}
+	impl substratekitties::Trait for AdminTest {
+		type Event = ();
+		type Fees = ();
+	}
+	impl pool::Trait for AdminTest {
+		type Event = ();
+		type Currency = balances::Module<AdminTest>;
+		type Approval = ();
+		type Treasury = ();
+		type Council = approve::Council;
+	}
+	impl marketplace::Trait for AdminTest {
+		type Event = ();
+		type Currency = balances::Module<AdminTest>;
+		type Nft = Kitties;
+		type Treasury = ();
+		type Oracle = ();
+	}
+	impl Trait for AdminTest {
+		type Event = ();
+		type Council = approve::Council;
+	}
+	type Admin = Module<AdminTest>;
+	type Groups = groups::Module<AdminTest>;
+	type Kitties = substratekitties::Module<AdminTest>;
+	type Pool = pool::Module<AdminTest>;
+	type Marketplace = marketplace::Module<AdminTest>;
+
+	// No config values in genesis, so every value below is set by the admin module
+	fn build_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+		system::GenesisConfig::<AdminTest>::default().build_storage().unwrap().0.into()
+	}
+
+	// The origin an approved council motion is dispatched with
+	fn council() -> Origin {
+		Origin::from(approve::Origin::Approved(2))
+	}
+
+	/// Groups config test objectives:
+	/// * Only a council motion can change the config, root can not
+	/// * The new limits apply to the next group that is created
+	#[test]
+	fn set_groups_config_should_work() {
+		with_externalities(&mut build_ext(), || {
+			assert_noop!(Groups::create_group(Origin::signed(1), b"Name".to_vec(), 4), "Config max_name_size not set");
+			assert!(Admin::set_groups_config(Origin::signed(1), 4, 1, 4).is_err());
+			assert_noop!(Admin::set_groups_config(Origin::ROOT, 4, 1, 4), "bad origin: expected to be an approved call");
+
+			assert_ok!(Admin::set_groups_config(council(), 4, 1, 4));
+			assert_eq!(Groups::max_group_size(), Some(4));
+			assert_eq!(Groups::max_groups_per_owner(), Some(1));
+			assert_eq!(Groups::max_name_size(), Some(4));
+			assert_noop!(Groups::create_group(Origin::signed(1), b"Longer".to_vec(), 4), "Name is too long");
+			assert_ok!(Groups::create_group(Origin::signed(1), b"Name".to_vec(), 4));
+			assert_noop!(Groups::create_group(Origin::signed(1), b"Two".to_vec(), 4), "Groups limit reached for this Account");
+
+			assert_ok!(Admin::set_groups_config(council(), 4, 2, 4));
+			assert_ok!(Groups::create_group(Origin::signed(1), b"Two".to_vec(), 4));
+		});
+	}
+
+	/// Kitty supply cap test objectives:
+	/// * Only a council motion can set the cap
+	/// * No kitty is minted past the cap, and removing the cap allows minting again
+	#[test]
+	fn set_max_kitties_should_work() {
+		with_externalities(&mut build_ext(), || {
+			assert!(Admin::set_max_kitties(Origin::ROOT, Some(1)).is_err());
+			assert_ok!(Admin::set_max_kitties(council(), Some(1)));
+			assert_eq!(Kitties::max_kitties(), Some(1));
+
+			assert_ok!(Kitties::create_kitty(Origin::signed(1)));
+			assert_noop!(Kitties::create_kitty(Origin::signed(2)), "Supply cap reached");
+			assert_eq!(Kitties::num_of_kitties(), 1);
+
+			assert_ok!(Admin::set_max_kitties(council(), None));
+			assert_eq!(Kitties::max_kitties(), None);
+			assert_ok!(Kitties::create_kitty(Origin::signed(2)));
+		});
+	}
+
+	/// Pool config test objectives:
+	/// * Only a council motion can change the config, and zero snapshots or a zero spend period are rejected
+	/// * The new values are stored
+	#[test]
+	fn set_pool_config_should_work() {
+		with_externalities(&mut build_ext(), || {
+			assert!(Admin::set_pool_config(Origin::ROOT, 2, 50, 100, 10).is_err());
+			assert_noop!(Admin::set_pool_config(council(), 0, 50, 100, 10), "max_snapshots must be greater than zero");
+			assert_noop!(Admin::set_pool_config(council(), 2, 50, 100, 0), "spend_period must be greater than zero");
+
+			assert_ok!(Admin::set_pool_config(council(), 2, 50, 100, 10));
+			assert_eq!(Pool::max_snapshots(), Some(2));
+			assert_eq!(Pool::large_payout(), Some(50));
+			assert_eq!(Pool::spend_limit(), Some(100));
+			assert_eq!(Pool::spend_period(), Some(10));
+		});
+	}
+
+	/// Treasury cuts test objectives:
+	/// * Only a council motion can change the cuts
+	/// * The sale cut is stored in the kitties module and the protocol fee in the pool module
+	#[test]
+	fn set_treasury_cuts_should_work() {
+		with_externalities(&mut build_ext(), || {
+			assert!(Admin::set_treasury_cuts(Origin::signed(1), Permill::from_percent(5), Permill::from_percent(1)).is_err());
+
+			assert_ok!(Admin::set_treasury_cuts(council(), Permill::from_percent(5), Permill::from_percent(1)));
+			assert_eq!(Marketplace::sale_cut(), Permill::from_percent(5));
+			assert_eq!(Pool::protocol_fee(), Permill::from_percent(1));
+		});
+	}
+
+	/// Sequential ids test objectives:
+	/// * Only a council motion can switch the id mode
+	/// * Groups and kitties created after the switch get the next id of their kind
+	#[test]
+	fn set_sequential_ids_should_work() {
+		with_externalities(&mut build_ext(), || {
+			assert!(Admin::set_sequential_ids(Origin::ROOT, true).is_err());
+			assert_ok!(Admin::set_sequential_ids(council(), true));
+			assert!(<ids::Module<AdminTest>>::sequential());
+
+			assert_ok!(Admin::set_groups_config(council(), 4, 2, 4));
+			assert_ok!(Groups::create_group(Origin::signed(1), b"One".to_vec(), 4));
+			assert_ok!(Groups::create_group(Origin::signed(1), b"Two".to_vec(), 4));
+			assert_ok!(Kitties::create_kitty(Origin::signed(1)));
+
+			let group_1 = Groups::owned_group_by_index(1, 1);
+			assert_eq!(<ids::Module<AdminTest>>::sequence_of(group_1), Some((ids::IdKind::Group, 1)));
+			assert_eq!(<ids::Module<AdminTest>>::sequence_of(Kitties::kitty_id(0)), Some((ids::IdKind::Kitty, 0)));
+		});
+	}
+}
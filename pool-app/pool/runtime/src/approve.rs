@@ -1,11 +1,27 @@
-/// Approve is an experimental module for managing pooled funds
+/// Approve is an experimental module for recording approval decisions. An account creates a proposal for a
+/// payload hash (the payload itself lives off-chain) and other accounts approve or reject it until one side
+/// reaches the configured threshold, which finalizes the proposal.
+///
+/// A proposal can also carry a runtime Call, which is dispatched as soon as the proposal is approved. This turns
+/// the module into a multisig-style execution engine.
+///
+/// Creating a proposal reserves a bond from the proposer. It is returned when the proposal is approved or
+/// rejected by vote, and slashed when the proposal expires or is vetoed as spam.
 
 use parity_codec::{Encode, Decode};
-use runtime_primitives::traits::{Hash};
-use support::{decl_module, decl_storage, decl_event, ensure, dispatch::Result, StorageMap, StorageValue};
-use system::ensure_signed;
+use rstd::marker::PhantomData;
+use rstd::prelude::Box;
+use rstd::result;
+use runtime_primitives::traits::{As, Dispatchable, Hash};
+use runtime_primitives::transaction_validity::TransactionValidity;
+use support::{decl_module, decl_storage, decl_event, ensure, dispatch::Result, Parameter, StorageMap, StorageValue};
+use support::traits::{Currency, OnUnbalanced, ReservableCurrency};
+use system::{ensure_signed, ensure_root, ensure_none};
+use app_primitives::{ApprovalProvider, GovernanceOrigin, GroupId, GroupInspector, ProposalId};
+use crate::deadline::{DeadlineStorage, Deadlines};
 
-// use runtime_io::{with_storage, StorageOverlay, ChildrenStorageOverlay};
+#[cfg(feature = "std")]
+use runtime_io::{with_storage, StorageOverlay, ChildrenStorageOverlay};
 
 #[cfg(not(feature = "std"))]
 use rstd::prelude::Vec;
@@ -17,49 +33,448 @@ use core::str;
 use std::str;
 
 
+/// The balance type of the currency used for proposal bonds
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+type NegativeImbalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::NegativeImbalance;
+
 pub trait Trait: system::Trait + timestamp::Trait {
     type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	/// The currency that proposal bonds are reserved in
+	type Currency: ReservableCurrency<Self::AccountId>;
+
+	/// Handler for slashed bonds. Use () to burn them, or the treasury to keep them.
+	type BondSlashed: OnUnbalanced<NegativeImbalanceOf<Self>>;
+
+	/// Submits the reminder calls of the off-chain worker, see offchain_worker()
+	type SubmitReminder: SubmitReminder<Call<Self>>;
+
+	/// The origin that approved calls are dispatched with. It can be built from this module's Origin or from a
+	/// signed system origin. In the runtime this is the outer Origin.
+	type ExecutionOrigin: From<Origin> + From<system::RawOrigin<Self::AccountId>>;
+
+	/// A runtime Call that a proposal can carry
+	type Proposal: Parameter + Dispatchable<Origin = Self::ExecutionOrigin>;
+
+	/// Resolves group members for proposals that use a group as their approver set. In the runtime this is
+	/// the Groups module.
+	type Groups: GroupInspector<Self::AccountId, Self::Hash>;
+
+	/// Checks the origin of freeze(). In the runtime this is the Council of this module.
+	type Council: GovernanceOrigin<Self::Origin>;
+}
+
+/// Origin for calls dispatched by this module once a proposal is approved
+#[derive(PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum Origin {
+	/// The call was approved by the approver set. The u32 is the approval weight it received.
+	Approved(u32),
+}
+
+/// Ensure that the origin is a call approved through this module, returning the number of approvals
+pub fn ensure_approved<OuterOrigin>(o: OuterOrigin) -> result::Result<u32, &'static str>
+	where OuterOrigin: Into<Option<Origin>>
+{
+	match o.into() {
+		Some(Origin::Approved(n)) => Ok(n),
+		_ => Err("bad origin: expected to be an approved call"),
+	}
+}
+
+/// The approver set acting as a council. A motion is a proposal created with create_call_proposal() and
+/// ExecuteAs::Approvers. It is voted on until it reaches the threshold or expires after `proposal_lifetime`
+/// blocks, and once approved its call is dispatched with the Approved origin, which is the only origin the
+/// Council accepts. Modules take it as their GovernanceOrigin instead of requiring root.
+pub struct Council;
+
+impl<OuterOrigin> GovernanceOrigin<OuterOrigin> for Council
+	where OuterOrigin: Into<Option<Origin>>
+{
+	fn ensure_governance(origin: OuterOrigin) -> result::Result<(), &'static str> {
+		ensure_approved(origin).map(|_| ())
+	}
+}
+
+/// Hands a call from the off-chain worker to the transaction pool. Modules do not know the extrinsic format,
+/// so the runtime implements this for its own UncheckedExtrinsic.
+pub trait SubmitReminder<C> {
+	fn submit(call: C);
+}
+
+/// For runtimes that do not run the reminder off-chain worker
+impl<C> SubmitReminder<C> for () {
+	fn submit(_call: C) {}
+}
+
+/// Who an approved call is dispatched as
+#[derive(Encode, Decode, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum ExecuteAs {
+	/// A signed origin of the account that created the proposal
+	Proposer,
+	/// This module's Approved origin, which other modules can check with ensure_approved()
+	Approvers,
+}
+
+#[derive(Encode, Decode, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum ProposalStatus {
+	/// Still accepting votes
+	Pending,
+	/// Final state: the approval threshold was reached
+	Approved,
+	/// Final state: the rejection threshold was reached
+	Rejected,
+	/// Final state: the expiry block was reached while the proposal was still pending
+	Expired,
+	/// Final state: the proposer withdrew the proposal, see cancel()
+	Cancelled,
+}
+
+impl Default for ProposalStatus {
+	fn default() -> Self {
+		ProposalStatus::Pending
+	}
 }
 
 #[derive(Encode, Decode, Default, Clone, PartialEq)]
 #[cfg_attr(feature = "std", derive(Debug))]
-pub struct Decision<A, H> {
+pub struct Proposal<A, H, N> {
 	/// Hash unique random id
     id: H,
-	/// Reference to the Group
-	group_id: H,
-	/// Vec of AccountIds
+	/// The account that created the proposal
+	proposer: A,
+	/// Hash of the payload that is being decided on. The payload itself is stored off-chain.
+	payload_hash: H,
+	/// Approval weight that counted at the last vote. Votes from accounts that are no longer approvers
+	/// do not count, see tally().
+	approvals: u32,
+	/// Rejection weight that counted at the last vote
+	rejections: u32,
+	/// Votes are only accepted while the proposal is Pending
+	status: ProposalStatus,
+	/// Votes are only accepted before this block. At this block the proposal becomes Expired.
+	expiry: N,
+	/// The block in which the proposal was created
+	created: N,
+	/// Index of the current stage of a staged proposal, see create_staged_proposal(). Always 0 otherwise.
+	stage: u32,
+}
+
+/// One step of an approval workflow. A staged proposal moves to the next stage once the approvers of the
+/// current stage reach its threshold.
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Stage<A> {
+	/// The accounts that vote in this stage, each with a weight of 1
 	approvers: Vec<A>,
-	/// Maximum number of members in group. Note that there is no min size of group since that is
-	/// likely a business rule that can be handled in the dapp or external systems.
-	/// Example: number of players required to start a game.
-	record: H,
+	/// The number of approvals (or rejections) that completes this stage
+	threshold: u32,
+}
+
+/// Who can vote on a new proposal, see new_proposal()
+enum VoterSet<A, H> {
+	Approvers,
+	Group(H, Vec<A>),
+	Stages(Vec<Stage<A>>),
+}
+
+/// The most stages in one workflow
+const MAX_STAGES: usize = 5;
+
+/// Information for UIs to render a proposal. It is set when the proposal is created and never changes.
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct ProposalMetadata<H> {
+	/// Hash of the title. The title itself is stored off-chain.
+	title_hash: H,
+	/// IPFS CID of the description. The length is limited by MAX_CID_LENGTH.
+	description_cid: Vec<u8>,
+	/// Application defined category, for example to sort approval queues
+	category: u32,
+}
+
+/// The longest description CID accepted in ProposalMetadata
+const MAX_CID_LENGTH: usize = 64;
+
+/// One line of the audit log of a proposal, see audit_log()
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct AuditEntry<A, M> {
+	/// The approver the vote counts for
+	who: A,
+	/// True for an approval, false for a rejection, None when the vote was withdrawn
+	vote: Option<bool>,
+	/// The timestamp of the block in which the vote was recorded
+	time: M,
 }
 
+/// The polkadot-js definitions of the types above, see the types module
+#[cfg(feature = "std")]
+pub fn type_definitions() -> Vec<crate::types::TypeDef> {
+	use crate::types::TypeDef;
+	vec![
+		TypeDef::Enum("ExecuteAs", vec!["Proposer", "Approvers"]),
+		TypeDef::Enum("ProposalStatus", vec!["Pending", "Approved", "Rejected", "Expired", "Cancelled"]),
+		TypeDef::Struct("Proposal", vec![
+			("id", "Hash"),
+			("proposer", "AccountId"),
+			("payload_hash", "Hash"),
+			("approvals", "u32"),
+			("rejections", "u32"),
+			("status", "ProposalStatus"),
+			("expiry", "BlockNumber"),
+			("created", "BlockNumber"),
+			("stage", "u32"),
+		]),
+		TypeDef::Struct("Stage", vec![("approvers", "Vec<AccountId>"), ("threshold", "u32")]),
+		TypeDef::Struct("ProposalMetadata", vec![("title_hash", "Hash"), ("description_cid", "Vec<u8>"), ("category", "u32")]),
+		TypeDef::Struct("AuditEntry", vec![("who", "AccountId"), ("vote", "Option<bool>"), ("time", "Moment")]),
+	]
+}
+
+/// The most entries kept in the audit log of one proposal. Once full, the oldest entry is dropped.
+const MAX_AUDIT_ENTRIES: usize = 100;
+
+/// The most proposals that on_initialize() will expire in one block. The rest expire in the next blocks.
+const MAX_EXPIRIES_PER_BLOCK: usize = 20;
+
+/// How many blocks an unsigned remind() stays valid in the transaction pool, see validate_remind()
+pub const REMINDER_LONGEVITY: u64 = 5;
+
+/// The code of the Invalid transaction validity of a remind() with a proposal that is not due
+pub const INVALID_REMINDER: i8 = 65;
+
+/// The most proposals that approve_many() or reject_many() accept in one call
+pub(crate) const MAX_BATCH_VOTES: usize = 20;
+
 decl_storage! {
 
 	// The Approve storage needs to follow model similar to SubstrateKitties example. In order to fetched
 	// owned groups later, additional arrays and maps make it possible to find the number of groups owned by an
 	// AccountId and lookup the Hash of a group based on the index values.
 	trait Store for Module<T: Trait> as Approve {
+		// Config value that matches the value in the testnet_genesis in chain_spec.rs
+		// This is the approval (or rejection) weight that finalizes a proposal. With every approver at the
+		// default weight of 1, this is simply the number of votes.
+		Threshold get(threshold) config(): Option<u32>;
+		// When set above 0, the weight that finalizes a proposal is this percentage of the total approver
+		// weight instead of the fixed threshold. See required_weight().
+		ThresholdPercent get(threshold_percent) config(): Option<u32>;
+		// The number of blocks a proposal stays open for voting
+		ProposalLifetime get(proposal_lifetime) config(): Option<u32>;
+		// How many blocks before the expiry of a pending proposal the off-chain worker sends a reminder
+		ReminderBlocks get(reminder_blocks) config(): Option<u32>;
+		// The amount reserved from the proposer for every new proposal
+		ProposalBond get(proposal_bond) config(): Option<BalanceOf<T>>;
+		// The number of blocks after creation in which the proposer can still cancel a proposal that already
+		// has approvals. After that it can only be cancelled while nobody approved it. See cancel().
+		CancelGrace get(cancel_grace) config(): Option<u32>;
+		// A proposer is suspended once more than max_failure_percent of its finalized proposals were rejected
+		// or expired, counted only after it has min_recorded_proposals of them. A max_failure_percent of 0
+		// turns suspension off.
+		MaxFailurePercent get(max_failure_percent) config(): Option<u32>;
+		MinRecordedProposals get(min_recorded_proposals) config(): Option<u32>;
+
+		// The accounts that are allowed to vote, and the account that can manage them besides root
+		Approvers get(approvers): Vec<T::AccountId>;
+		// Voting weight of each approver that root changed from the default of 1, see approver_weight()
+		ApproverWeights get(weight_of): map T::AccountId => Option<u32>;
+		Owner get(owner): Option<T::AccountId>;
+		// Emergency switch set by a council motion and cleared by root. While it is set no proposal can be created,
+		// voted on, resolved or executed, and expiry is put on hold. Managing the approver set still works.
+		Frozen get(is_frozen): bool;
+		// An approver can hand its vote to a delegate. Both directions are stored so that chains can be refused
+		// without a scan: a principal can not be a delegate, and a delegate can not delegate.
+		Delegations get(delegate_of): map T::AccountId => Option<T::AccountId>;
+		Principals get(principals_of): map T::AccountId => Vec<T::AccountId>;
+
+		// Accounts that can kill any pending proposal, see veto()
+		VetoHolders get(veto_holders): Vec<T::AccountId>;
+
+		// These are the primary storage vars for storing the Proposal struct and the vote of each account
+		Proposals get(proposal): map T::Hash => Proposal<T::AccountId, T::Hash, T::BlockNumber>;
+		Votes get(vote_of): map (T::Hash, T::AccountId) => Option<bool>;
+		Voters get(voters_of): map T::Hash => Vec<T::AccountId>;
+		// Optional metadata of a proposal, see create_proposal_with_metadata()
+		Metadata get(metadata_of): map T::Hash => Option<ProposalMetadata<T::Hash>>;
+		// Who voted what and when, per proposal. Kept after the proposal is finalized.
+		AuditLog get(audit_log): map T::Hash => Vec<AuditEntry<T::AccountId, T::Moment>>;
+		// The Call carried by a proposal, removed when it is dispatched
+		ProposalCalls get(call_of): map T::Hash => Option<(T::Proposal, ExecuteAs)>;
+
+		// Proposals that use a group as their approver set, with the weight that finalizes them. The members are
+		// fixed when the proposal is created, so that the group owner can not add accounts to push a proposal
+		// through. Members who leave the group lose their vote right away. With threshold_percent set, the stored
+		// weight is only informational and group_required() recomputes it from the remaining members.
+		ProposalGroups get(group_of): map T::Hash => Option<(T::Hash, u32)>;
+		GroupSnapshots get(group_snapshot_of): map T::Hash => Vec<T::AccountId>;
+
+		// Approval workflows set by root, and the copy of the workflow taken by each staged proposal when it was
+		// created. Changing a workflow does not affect proposals that already use it.
+		Workflows get(workflow): map u32 => Vec<Stage<T::AccountId>>;
+		ProposalStages get(stages_of): map T::Hash => Vec<Stage<T::AccountId>>;
+
+		// Queue of proposals by expiry block, processed in on_initialize(). Use expiring_at() to read all pages
+		// of a block, see the deadline module.
+		ExpiringAt: map T::BlockNumber => Vec<T::Hash>;
+		ExpiringAtPages: map (T::BlockNumber, u32) => Vec<T::Hash>;
+		ExpiringAtPageCount: map T::BlockNumber => u32;
+		ExpiringAtCursor: Option<(T::BlockNumber, u32)>;
+		// Set once the expiry reminder of a proposal is on chain, so that it is only sent once
+		Reminded get(reminded): map T::Hash => bool;
+
+		// The inbox of each account: pending proposals it can vote on and has not voted on yet. Filled when a
+		// proposal is created, so approvers added later do not see older proposals here.
+		PendingForApprover get(pending_for): map T::AccountId => Vec<T::Hash>;
 
+		// Global index of all proposals, similar to AllKittiesArray in the kitties example
+		AllProposalsArray get(proposal_by_index): map u64 => T::Hash;
+		AllProposalsCount get(all_proposals_count): u64;
 
-		Nonce: u64;
+		// Index of the pending proposals only. A finalized proposal is swapped with the last entry and the array
+		// shrinks, so the entries 0 to ActiveProposalsCount - 1 can be paged through, see active_proposals().
+		ActiveProposalsArray get(active_proposal_by_index): map u64 => T::Hash;
+		ActiveProposalsCount get(active_proposals_count): u64;
+		ActiveProposalsIndex get(active_proposal_index): map T::Hash => u64;
+
+		// The bond reserved for each proposal, removed when it is returned or slashed
+		Bonds get(bond_of): map T::Hash => Option<BalanceOf<T>>;
+
+		// Pending proposal for each payload hash, so the same payload can not be proposed twice at once.
+		// The entry is removed when the proposal is finalized.
+		PendingByPayload get(pending_by_payload): map T::Hash => Option<T::Hash>;
+
+		// The number of approved and of failed (rejected or expired) proposals of each proposer. Cancelled
+		// proposals are not counted. Suspended accounts can not create proposals until root reinstates them.
+		ProposerRecord get(record_of): map T::AccountId => (u32, u32);
+		Suspended get(is_suspended): map T::AccountId => bool;
+
+		// Proposal ids are derived from (proposer, nonce, payload hash), with a nonce per proposer
+		ProposerNonce get(nonce_of): map T::AccountId => u64;
+	}
+
+	// The approval committee the chain starts with, as (account, voting weight) pairs. Together with the
+	// threshold, proposal_lifetime and proposal_bond Config, a testnet can approve proposals from block 1.
+	add_extra_genesis {
+		config(initial_approvers): Vec<(T::AccountId, u32)>;
+
+		build(|storage: &mut StorageOverlay, _: &mut ChildrenStorageOverlay, config: &GenesisConfig<T>| {
+			with_storage(storage, || {
+				let mut approvers = Vec::new();
+				for &(ref who, weight) in &config.initial_approvers {
+					assert!(!approvers.contains(who), "Duplicate account in initial_approvers");
+					assert!(weight > 0, "Initial approver weight must be greater than zero");
+					if weight != 1 {
+						<ApproverWeights<T>>::insert(who, weight);
+					}
+					approvers.push(who.clone());
+				}
+				<Approvers<T>>::put(approvers);
+			});
+		});
 	}
 }
 
 
 /*
-Approve events TODO:
-–
-
+The events declared here are meant to be used by an external event listener to record state information
+in an external datastore.
 */
 decl_event!(
 	pub enum Event<T> where
 		<T as system::Trait>::AccountId,
-        <T as system::Trait>::Hash
+        <T as system::Trait>::Hash,
+		<T as system::Trait>::BlockNumber,
+		Balance = BalanceOf<T>
 	{
-		ApprovalReceived(Hash, AccountId, u32),
+		/// A proposal was created with the given id and proposer. The u32 is the weight that finalizes the
+		/// proposal at creation time, followed by the expiry block.
+		ProposalCreated(Hash, AccountId, u32, BlockNumber),
+
+		/// Metadata was attached to a new proposal: the title hash, the description CID and the category
+		ProposalMetadataSet(Hash, Hash, Vec<u8>, u32),
+
+		/// A vote was recorded for the approver. The bool is true for an approval and the u32 is the weight
+		/// of the vote.
+		Voted(Hash, AccountId, bool, u32),
+
+		/// A staged proposal completed a stage and moved on to the given stage index
+		StageAdvanced(Hash, u32),
+
+		/// Root set the stages of the given workflow
+		WorkflowSet(u32),
+
+		/// The proposal reached the approval threshold
+		Approved(Hash),
+
+		/// The proposal reached the rejection threshold
+		Rejected(Hash),
+
+		/// The proposal reached its expiry block while still pending
+		ProposalExpired(Hash),
+
+		/// The pending proposal expires at the given block soon. Dapps use this to alert approvers.
+		ExpiryReminder(Hash, BlockNumber),
+
+		/// The approver changed its vote on a pending proposal. The new vote is None when it was withdrawn.
+		VoteChanged(Hash, AccountId, Option<bool>),
+
+		/// The Call of an approved proposal was dispatched. The bool is true if the dispatch succeeded.
+		Executed(Hash, bool),
+
+		/// An account was added to the approver set
+		ApproverAdded(AccountId),
+
+		/// An account was removed from the approver set. Its votes on pending proposals no longer count.
+		ApproverRemoved(AccountId),
+
+		/// Root set the account that can manage approvers
+		OwnerChanged(AccountId),
+
+		/// Root changed the voting weight of an approver
+		ApproverWeightChanged(AccountId, u32),
+
+		/// An approver (first) handed its vote to a delegate (second)
+		DelegationSet(AccountId, AccountId),
+
+		/// An approver (first) took its vote back from a delegate (second)
+		DelegationRevoked(AccountId, AccountId),
+
+		/// Root gave an account the power to veto proposals
+		VetoHolderAdded(AccountId),
+
+		/// Root took the veto power away from an account
+		VetoHolderRemoved(AccountId),
+
+		/// A veto holder rejected the proposal. The last Hash is the reason hash for off-chain records.
+		ProposalVetoed(Hash, AccountId, Hash),
+
+		/// ROOT OVERRIDE: root finalized a stuck proposal. The bool is true if it was approved, and the last
+		/// Hash is the justification hash for off-chain records.
+		ProposalForceResolved(Hash, bool, Hash),
+
+		/// The proposer cancelled the proposal
+		ProposalCancelled(Hash, AccountId),
+
+		/// EMERGENCY: a council motion froze the approval system. The Hash is the reason given in the motion.
+		SystemFrozen(Hash),
+
+		/// Root unfroze the approval system
+		SystemUnfrozen(),
+
+		/// Too many proposals of the account failed, and it can no longer create proposals
+		ProposerSuspended(AccountId),
+
+		/// Root lifted the suspension of the account and cleared its record
+		ProposerReinstated(AccountId),
+
+		/// The bond of a proposal was returned to the proposer
+		BondReturned(Hash, AccountId, Balance),
+
+		/// The bond of a proposal was slashed because it expired or was vetoed as spam
+		BondSlashed(Hash, AccountId, Balance),
     }
 );
 
@@ -69,25 +484,1006 @@ decl_module! {
 
 		fn deposit_event<T>() = default;
 
+		fn on_initialize(n: T::BlockNumber) {
+			if Self::is_frozen() {
+				// Nothing expires while frozen, the queue is taken from where it stopped once unfrozen
+				<Expiries<T>>::hold(n);
+				return;
+			}
+			Self::expire_proposals(n);
+		}
+
+		// Looks for pending proposals that expire in reminder_blocks and submits an unsigned remind() for
+		// them. Nothing is written here, the reminder only lands on chain through the transaction.
+		fn offchain_worker(n: T::BlockNumber) {
+			if Self::is_frozen() {
+				return;
+			}
+			let due = Self::due_reminders(n);
+			if !due.is_empty() {
+				T::SubmitReminder::submit(Call::remind(due));
+			}
+		}
+
+		/// Flag pending proposals that are about to expire with an ExpiryReminder event. Sent as an unsigned
+		/// transaction by the off-chain worker. Proposals that are not due, or already reminded, are skipped,
+		/// since several nodes may send the same reminder.
+		pub fn remind(origin, proposal_ids: Vec<T::Hash>) -> Result {
+			ensure_none(origin)?;
+			ensure!(proposal_ids.len() <= MAX_EXPIRIES_PER_BLOCK, "Too many proposals in one reminder");
+			let reminder_blocks = Self::reminder_blocks().ok_or("Config reminder_blocks not set")?;
+			let now = <system::Module<T>>::block_number();
+
+			for proposal_id in proposal_ids {
+				if Self::is_due_for_reminder(proposal_id, now, reminder_blocks) {
+					<Reminded<T>>::insert(proposal_id, true);
+					Self::deposit_event(RawEvent::ExpiryReminder(proposal_id, Self::proposal(proposal_id).expiry));
+				}
+			}
+			Ok(())
+		}
+
+		/// Create a proposal for the given payload hash. Any account can propose.
+		pub fn create_proposal(origin, payload_hash: T::Hash) -> Result {
+			let sender = ensure_signed(origin)?;
+			Self::new_proposal(sender, payload_hash, Self::required_weight()?, VoterSet::Approvers)?;
+			Ok(())
+		}
+
+		/// Create a proposal with a title hash, description CID and category for UIs. The metadata can not be
+		/// changed afterwards.
+		pub fn create_proposal_with_metadata(origin, payload_hash: T::Hash, title_hash: T::Hash,
+			description_cid: Vec<u8>, category: u32) -> Result
+		{
+			let sender = ensure_signed(origin)?;
+			ensure!(description_cid.len() <= MAX_CID_LENGTH, "Description CID is too long");
+
+			let proposal_id = Self::new_proposal(sender, payload_hash, Self::required_weight()?, VoterSet::Approvers)?;
+			let metadata = ProposalMetadata {
+				title_hash: title_hash,
+				description_cid: description_cid.clone(),
+				category: category,
+			};
+			<Metadata<T>>::insert(proposal_id, metadata);
+
+			Self::deposit_event(RawEvent::ProposalMetadataSet(proposal_id, title_hash, description_cid, category));
+			Ok(())
+		}
+
+		/// Create a proposal that carries a Call. The payload hash is the hash of the encoded Call, and the
+		/// Call is dispatched as soon as the proposal is approved.
+		pub fn create_call_proposal(origin, call: Box<T::Proposal>, execute_as: ExecuteAs) -> Result {
+			let sender = ensure_signed(origin)?;
+			let payload_hash = call.using_encoded(<T as system::Trait>::Hashing::hash);
+
+			let proposal_id = Self::new_proposal(sender, payload_hash, Self::required_weight()?, VoterSet::Approvers)?;
+			<ProposalCalls<T>>::insert(proposal_id, (*call, execute_as));
+			Ok(())
+		}
+
+		/// Create a proposal that is decided by the members of a group instead of the approver set. Each
+		/// member has a weight of 1. Only accounts that are members both now and when they vote can vote.
+		pub fn create_group_proposal(origin, group_id: T::Hash, payload_hash: T::Hash) -> Result {
+			let sender = ensure_signed(origin)?;
+			let members = T::Groups::members(GroupId(group_id)).ok_or("This group does not exist")?;
+			ensure!(!members.is_empty(), "This group has no members");
+			let required = Self::required_for(members.len() as u32)?;
+
+			Self::new_proposal(sender, payload_hash, required, VoterSet::Group(group_id, members))?;
+			Ok(())
+		}
+
+		/// Create a proposal that goes through the stages of a workflow in order. Each stage has its own
+		/// approvers and threshold, and a fresh proposal_lifetime. A rejection in any stage rejects the proposal.
+		pub fn create_staged_proposal(origin, workflow_id: u32, payload_hash: T::Hash) -> Result {
+			let sender = ensure_signed(origin)?;
+			let stages = Self::workflow(workflow_id);
+			ensure!(!stages.is_empty(), "This workflow does not exist");
+
+			let threshold = stages[0].threshold;
+			Self::new_proposal(sender, payload_hash, threshold, VoterSet::Stages(stages))?;
+			Ok(())
+		}
+
+		/// Set the stages of a workflow, for example a team lead, then finance, then a root key. Rule: root only.
+		pub fn set_workflow(origin, workflow_id: u32, stages: Vec<Stage<T::AccountId>>) -> Result {
+			ensure_root(origin)?;
+			ensure!(!stages.is_empty(), "A workflow needs at least one stage");
+			ensure!(stages.len() <= MAX_STAGES, "Too many stages in this workflow");
+			for stage in stages.iter() {
+				ensure!(stage.threshold > 0, "Stage threshold must be greater than zero");
+				ensure!(stage.threshold as usize <= stage.approvers.len(), "Stage threshold exceeds its approvers");
+			}
+
+			<Workflows<T>>::insert(workflow_id, stages);
+			Self::deposit_event(RawEvent::WorkflowSet(workflow_id));
+			Ok(())
+		}
+
+		/// EMERGENCY: halt the approval system during an incident. The reason hash is only passed through to
+		/// the SystemFrozen event. Rule: a council motion, see Council.
+		pub fn freeze(origin, reason_hash: T::Hash) -> Result {
+			T::Council::ensure_governance(origin)?;
+			ensure!(!Self::is_frozen(), "The approval system is already frozen");
+
+			<Frozen<T>>::put(true);
+			Self::deposit_event(RawEvent::SystemFrozen(reason_hash));
+			Ok(())
+		}
+
+		/// Resume the approval system. Proposals whose expiry passed while frozen expire over the next blocks.
+		/// Rule: root only, as the council can not vote while the system is frozen.
+		pub fn unfreeze(origin) -> Result {
+			ensure_root(origin)?;
+			ensure!(Self::is_frozen(), "The approval system is not frozen");
+
+			<Frozen<T>>::kill();
+			Self::deposit_event(RawEvent::SystemUnfrozen());
+			Ok(())
+		}
+
+		/// Set the account that can manage the approver set besides root. Rule: root only.
+		pub fn set_owner(origin, owner: T::AccountId) -> Result {
+			ensure_root(origin)?;
+			<Owner<T>>::put(&owner);
+			Self::deposit_event(RawEvent::OwnerChanged(owner));
+			Ok(())
+		}
+
+		/// Add an account to the approver set. Rule: root or the owner.
+		pub fn add_approver(origin, who: T::AccountId) -> Result {
+			Self::ensure_root_or_owner(origin)?;
+			ensure!(!Self::is_approver(&who), "Account is already an approver");
+
+			<Approvers<T>>::mutate(|approvers| approvers.push(who.clone()));
+			Self::deposit_event(RawEvent::ApproverAdded(who));
+			Ok(())
+		}
+
+		/// Remove an account from the approver set. Votes it already cast on pending proposals stay recorded
+		/// but are no longer counted. Rule: root or the owner.
+		pub fn remove_approver(origin, who: T::AccountId) -> Result {
+			Self::ensure_root_or_owner(origin)?;
+			ensure!(Self::is_approver(&who), "Account is not an approver");
+
+			<Approvers<T>>::mutate(|approvers| approvers.retain(|a| *a != who));
+			<ApproverWeights<T>>::remove(&who);
+			// Group and staged proposals keep their own voters, so only the proposals of the approvers leave the inbox
+			<PendingForApprover<T>>::mutate(&who, |ids| ids.retain(|id| Self::current_voters(*id).contains(&who)));
+			if let Some(delegate) = Self::clear_delegation(&who) {
+				Self::deposit_event(RawEvent::DelegationRevoked(who.clone(), delegate));
+			}
+			Self::deposit_event(RawEvent::ApproverRemoved(who));
+			Ok(())
+		}
+
+		/// Change the voting weight of an approver. The new weight applies to the next tally of every pending
+		/// proposal, including votes it already cast. Rule: root only.
+		pub fn set_approver_weight(origin, who: T::AccountId, weight: u32) -> Result {
+			ensure_root(origin)?;
+			ensure!(Self::is_approver(&who), "Account is not an approver");
+			ensure!(weight > 0, "Weight must be greater than zero");
+
+			<ApproverWeights<T>>::insert(&who, weight);
+			Self::deposit_event(RawEvent::ApproverWeightChanged(who, weight));
+			Ok(())
+		}
+
+		/// Give an account the power to veto proposals. Rule: root only.
+		pub fn add_veto_holder(origin, who: T::AccountId) -> Result {
+			ensure_root(origin)?;
+			ensure!(!Self::is_veto_holder(&who), "Account is already a veto holder");
+
+			<VetoHolders<T>>::mutate(|holders| holders.push(who.clone()));
+			Self::deposit_event(RawEvent::VetoHolderAdded(who));
+			Ok(())
+		}
+
+		/// Take the veto power away from an account. Rule: root only.
+		pub fn remove_veto_holder(origin, who: T::AccountId) -> Result {
+			ensure_root(origin)?;
+			ensure!(Self::is_veto_holder(&who), "Account is not a veto holder");
+
+			<VetoHolders<T>>::mutate(|holders| holders.retain(|h| *h != who));
+			Self::deposit_event(RawEvent::VetoHolderRemoved(who));
+			Ok(())
+		}
+
+		/// Kill a pending proposal, finalizing it as Rejected regardless of the votes. The reason hash is
+		/// only passed through to the ProposalVetoed event. When spam is true the bond of the proposer is
+		/// slashed, otherwise it is returned. Rule: veto holders only.
+		pub fn veto(origin, proposal_id: T::Hash, reason_hash: T::Hash, spam: bool) -> Result {
+			let sender = ensure_signed(origin)?;
+			Self::ensure_not_frozen()?;
+			ensure!(Self::is_veto_holder(&sender), "Account is not a veto holder");
+			ensure!(<Proposals<T>>::exists(proposal_id), "This proposal does not exist");
+
+			let mut proposal = Self::proposal(proposal_id);
+			ensure!(proposal.status == ProposalStatus::Pending, "This proposal is no longer pending");
+
+			proposal.status = ProposalStatus::Rejected;
+			Self::clear_indexes(&proposal);
+			Self::record_outcome(&proposal);
+			Self::settle_bond(proposal_id, &proposal.proposer, spam);
+			<Proposals<T>>::insert(proposal_id, proposal);
+			<ProposalCalls<T>>::remove(proposal_id);
 
-		/*
-		Functions TODO:
-		– register topic
-		– record choice (approve, deny)
-		–
+			Self::deposit_event(RawEvent::ProposalVetoed(proposal_id, sender, reason_hash));
+			Ok(())
+		}
+
+		/// The proposer withdraws its pending proposal. This is allowed while it has no approvals, or within
+		/// cancel_grace blocks of its creation. The bond is returned and the votes are removed. The audit log
+		/// is kept.
+		pub fn cancel(origin, proposal_id: T::Hash) -> Result {
+			let sender = ensure_signed(origin)?;
+			Self::ensure_not_frozen()?;
+			ensure!(<Proposals<T>>::exists(proposal_id), "This proposal does not exist");
+
+			let mut proposal = Self::proposal(proposal_id);
+			ensure!(proposal.proposer == sender, "Only the proposer can cancel a proposal");
+			ensure!(proposal.status == ProposalStatus::Pending, "This proposal is no longer pending");
+			let (approvals, _) = Self::tally(proposal_id);
+			if approvals > 0 {
+				let grace = T::BlockNumber::sa(Self::cancel_grace().unwrap_or(0) as u64);
+				ensure!(<system::Module<T>>::block_number() < proposal.created + grace,
+					"This proposal already has approvals and can no longer be cancelled");
+			}
 
-		*/
-		fn register_topic(origin, group_id: T::Hash, max_size: u32) -> Result {
+			proposal.status = ProposalStatus::Cancelled;
+			Self::clear_indexes(&proposal);
+			Self::settle_bond(proposal_id, &proposal.proposer, false);
+			<Proposals<T>>::insert(proposal_id, proposal);
+			<ProposalCalls<T>>::remove(proposal_id);
+			<Reminded<T>>::remove(proposal_id);
+			for voter in <Voters<T>>::take(proposal_id) {
+				<Votes<T>>::remove((proposal_id, voter));
+			}
+
+			Self::deposit_event(RawEvent::ProposalCancelled(proposal_id, sender));
 			Ok(())
 		}
 
+		/// Let a suspended account create proposals again. Its record starts over, so that one more failure
+		/// does not suspend it right away. Rule: root only.
+		pub fn reinstate(origin, who: T::AccountId) -> Result {
+			ensure_root(origin)?;
+			ensure!(Self::is_suspended(&who), "Account is not suspended");
+
+			<Suspended<T>>::remove(&who);
+			<ProposerRecord<T>>::remove(&who);
+			Self::deposit_event(RawEvent::ProposerReinstated(who));
+			Ok(())
+		}
+
+		/// Escalation path for a proposal that is stuck because approvers do not respond. Finalizes it as
+		/// Approved (dispatching its Call) or Rejected regardless of the votes. The bond is returned. A
+		/// justification hash must be given. Rule: root only.
+		pub fn force_resolve(origin, proposal_id: T::Hash, approve: bool, justification_hash: T::Hash) -> Result {
+			ensure_root(origin)?;
+			Self::ensure_not_frozen()?;
+			ensure!(justification_hash != T::Hash::default(), "A justification hash is required");
+			ensure!(<Proposals<T>>::exists(proposal_id), "This proposal does not exist");
+
+			let mut proposal = Self::proposal(proposal_id);
+			ensure!(proposal.status == ProposalStatus::Pending, "This proposal is no longer pending");
+
+			let (approvals, rejections) = Self::tally(proposal_id);
+			proposal.approvals = approvals;
+			proposal.rejections = rejections;
+			if approve {
+				proposal.status = ProposalStatus::Approved;
+			} else {
+				proposal.status = ProposalStatus::Rejected;
+				<ProposalCalls<T>>::remove(proposal_id);
+			}
+			Self::clear_indexes(&proposal);
+			Self::record_outcome(&proposal);
+			Self::settle_bond(proposal_id, &proposal.proposer, false);
+			let proposer = proposal.proposer.clone();
+			<Proposals<T>>::insert(proposal_id, proposal);
+
+			Self::deposit_event(RawEvent::ProposalForceResolved(proposal_id, approve, justification_hash));
+			if approve {
+				Self::execute(proposal_id, proposer, approvals);
+			}
+			Ok(())
+		}
+
+		/// Vote to approve a pending proposal
+		pub fn approve(origin, proposal_id: T::Hash) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(Self::delegate_of(&sender).is_none(), "Account has delegated its vote");
+			Self::record_vote(proposal_id, sender, true)
+		}
+
+		/// Vote to reject a pending proposal
+		pub fn reject(origin, proposal_id: T::Hash) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(Self::delegate_of(&sender).is_none(), "Account has delegated its vote");
+			Self::record_vote(proposal_id, sender, false)
+		}
+
+		/// Vote to approve several pending proposals. Every vote is checked first, and if any of them fails
+		/// nothing is recorded.
+		/// Weight: per proposal about 5 storage writes plus a scan of the voter's pending inbox, at most
+		/// MAX_BATCH_VOTES proposals. See the approve_many benchmark.
+		pub fn approve_many(origin, proposal_ids: Vec<T::Hash>) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(Self::delegate_of(&sender).is_none(), "Account has delegated its vote");
+			Self::record_votes(proposal_ids, sender, true)
+		}
+
+		/// Vote to reject several pending proposals. Every vote is checked first, and if any of them fails
+		/// nothing is recorded.
+		pub fn reject_many(origin, proposal_ids: Vec<T::Hash>) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(Self::delegate_of(&sender).is_none(), "Account has delegated its vote");
+			Self::record_votes(proposal_ids, sender, false)
+		}
+
+		/// Change the sender's vote on a pending proposal. Rule: only before the proposal is finalized.
+		pub fn change_vote(origin, proposal_id: T::Hash, aye: bool) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(Self::delegate_of(&sender).is_none(), "Account has delegated its vote");
+			Self::update_vote(proposal_id, sender, Some(aye))
+		}
+
+		/// Withdraw the sender's vote on a pending proposal. The account can vote again later.
+		pub fn withdraw_vote(origin, proposal_id: T::Hash) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(Self::delegate_of(&sender).is_none(), "Account has delegated its vote");
+			Self::update_vote(proposal_id, sender, None)
+		}
+
+		/// Hand the sender's vote to another account. The delegate then votes with approve_for() and
+		/// reject_for(), and those votes count as the sender's own. Rule: approvers only, and no chains.
+		pub fn delegate_to(origin, who: T::AccountId) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(Self::is_approver(&sender), "Account is not an approver");
+			ensure!(who != sender, "Account can not delegate to itself");
+			ensure!(Self::delegate_of(&sender).is_none(), "Account has already delegated, revoke it first");
+			ensure!(Self::delegate_of(&who).is_none(), "Delegation chains are not allowed");
+			ensure!(Self::principals_of(&sender).is_empty(), "Delegation chains are not allowed");
+
+			<Delegations<T>>::insert(&sender, &who);
+			<Principals<T>>::mutate(&who, |principals| principals.push(sender.clone()));
+			Self::deposit_event(RawEvent::DelegationSet(sender, who));
+			Ok(())
+		}
+
+		/// Take the sender's vote back from its delegate. Votes the delegate already cast stay recorded.
+		pub fn revoke_delegation(origin) -> Result {
+			let sender = ensure_signed(origin)?;
+			let delegate = Self::clear_delegation(&sender).ok_or("Account has not delegated its vote")?;
+			Self::deposit_event(RawEvent::DelegationRevoked(sender, delegate));
+			Ok(())
+		}
+
+		/// Vote to approve a pending proposal on behalf of an approver that delegated to the sender
+		pub fn approve_for(origin, principal: T::AccountId, proposal_id: T::Hash) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(Self::delegate_of(&principal) == Some(sender), "Account is not the delegate of this approver");
+			Self::record_vote(proposal_id, principal, true)
+		}
+
+		/// Vote to reject a pending proposal on behalf of an approver that delegated to the sender
+		pub fn reject_for(origin, principal: T::AccountId, proposal_id: T::Hash) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(Self::delegate_of(&principal) == Some(sender), "Account is not the delegate of this approver");
+			Self::record_vote(proposal_id, principal, false)
+		}
 	}
 }
 
+impl<T: Trait> ApprovalProvider<T::AccountId, T::Hash> for Module<T> {
+	fn request_approval(who: T::AccountId, payload_hash: T::Hash) -> result::Result<ProposalId<T::Hash>, &'static str> {
+		Self::new_proposal(who, payload_hash, Self::required_weight()?, VoterSet::Approvers).map(ProposalId)
+	}
+
+	// Payouts gated on an approval are held while the system is frozen
+	fn is_approved(proposal_id: ProposalId<T::Hash>) -> bool {
+		!Self::is_frozen() && Self::is_approved(proposal_id.0)
+	}
+}
+
+/// The queue of proposals by expiry block, see the deadline module
+struct ProposalExpiryQueue<T>(PhantomData<T>);
+
+impl<T: Trait> DeadlineStorage for ProposalExpiryQueue<T> {
+	type BlockNumber = T::BlockNumber;
+	type Id = T::Hash;
+	type Queue = ExpiringAt<T>;
+	type Pages = ExpiringAtPages<T>;
+	type PageCount = ExpiringAtPageCount<T>;
+	type Cursor = ExpiringAtCursor<T>;
+	const BUDGET: usize = MAX_EXPIRIES_PER_BLOCK;
+}
+
+type Expiries<T> = Deadlines<ProposalExpiryQueue<T>>;
+
 /// Custom methods – public and private
 impl<T: Trait> Module<T> {
+	/// The proposals queued to expire in block n that were not taken yet
+	pub fn expiring_at(n: T::BlockNumber) -> Vec<T::Hash> {
+		<Expiries<T>>::due_at(n)
+	}
+
+	// Private method called by all the ways to create a proposal.
+	// For approver set proposals the threshold is only reported in the ProposalCreated event, since the weight
+	// that finalizes them is worked out again at every vote.
+	fn new_proposal(proposer: T::AccountId, payload_hash: T::Hash, threshold: u32,
+		voter_set: VoterSet<T::AccountId, T::Hash>) -> result::Result<T::Hash, &'static str>
+	{
+		Self::ensure_not_frozen()?;
+		ensure!(!Self::is_suspended(&proposer), "Account is suspended from creating proposals");
+		ensure!(!<PendingByPayload<T>>::exists(payload_hash), "A proposal for this payload is already pending");
+
+        let nonce = Self::nonce_of(&proposer);
+        let proposal_id = (&proposer, nonce, payload_hash)
+            .using_encoded(<T as system::Trait>::Hashing::hash);
+
+		ensure!(!<Proposals<T>>::exists(proposal_id), "Proposal Id already exists");
+		let new_nonce = nonce.checked_add(1).ok_or("Overflow incrementing the proposer nonce")?;
+		let bond = Self::proposal_bond().ok_or("Config proposal_bond not set")?;
+
+		let all_proposals_count = Self::all_proposals_count();
+		let new_all_proposals_count = all_proposals_count.checked_add(1).ok_or("Overflow adding a new proposal")?;
+		let active_count = Self::active_proposals_count();
+		let new_active_count = active_count.checked_add(1).ok_or("Overflow adding an active proposal")?;
+
+		let lifetime = Self::proposal_lifetime().ok_or("Config proposal_lifetime not set")?;
+		let expiry = <system::Module<T>>::block_number() + T::BlockNumber::sa(lifetime as u64);
+
+		// Last check, since it is the first write
+		T::Currency::reserve(&proposer, bond).map_err(|_| "Proposer can not pay the proposal bond")?;
+		<Bonds<T>>::insert(proposal_id, bond);
+
+		let proposal = Proposal {
+			id: proposal_id,
+			proposer: proposer.clone(),
+			payload_hash: payload_hash,
+			approvals: 0,
+			rejections: 0,
+			status: ProposalStatus::Pending,
+			expiry: expiry,
+			created: <system::Module<T>>::block_number(),
+			stage: 0,
+		};
+		<Proposals<T>>::insert(proposal_id, proposal);
+		<AllProposalsArray<T>>::insert(all_proposals_count, proposal_id);
+		<AllProposalsCount<T>>::put(new_all_proposals_count);
+		<ActiveProposalsArray<T>>::insert(active_count, proposal_id);
+		<ActiveProposalsCount<T>>::put(new_active_count);
+		<ActiveProposalsIndex<T>>::insert(proposal_id, active_count);
+		<Expiries<T>>::schedule(expiry, proposal_id);
+		<PendingByPayload<T>>::insert(payload_hash, proposal_id);
+
+		match voter_set {
+			VoterSet::Approvers => {}
+			VoterSet::Group(group_id, members) => {
+				<ProposalGroups<T>>::insert(proposal_id, (group_id, threshold));
+				<GroupSnapshots<T>>::insert(proposal_id, members);
+			}
+			VoterSet::Stages(stages) => <ProposalStages<T>>::insert(proposal_id, stages),
+		}
+		for voter in Self::current_voters(proposal_id).iter() {
+			<PendingForApprover<T>>::mutate(voter, |ids| ids.push(proposal_id));
+		}
+
+		<ProposerNonce<T>>::insert(&proposer, new_nonce);
+
+		Self::deposit_event(RawEvent::ProposalCreated(proposal_id, proposer, threshold, expiry));
+		Ok(proposal_id)
+	}
+
+	// Private method called by: record_vote()
+	// Dispatches the Call of an approved proposal, if it has one. The proposal is already finalized, so a failed
+	// dispatch is only reported through the Executed event.
+	fn execute(proposal_id: T::Hash, proposer: T::AccountId, approvals: u32) {
+		if let Some((call, execute_as)) = <ProposalCalls<T>>::take(proposal_id) {
+			let origin = match execute_as {
+				ExecuteAs::Proposer => T::ExecutionOrigin::from(system::RawOrigin::Signed(proposer)),
+				ExecuteAs::Approvers => T::ExecutionOrigin::from(Origin::Approved(approvals)),
+			};
+			let ok = match call.dispatch(origin) {
+				Ok(_) => true,
+				Err(e) => {
+					runtime_io::print(e);
+					false
+				}
+			};
+			Self::deposit_event(RawEvent::Executed(proposal_id, ok));
+		}
+	}
+
+	// Private method called by: add_approver() and remove_approver()
+	fn ensure_root_or_owner(origin: T::Origin) -> Result {
+		match origin.into() {
+			Some(system::RawOrigin::Root) => Ok(()),
+			Some(system::RawOrigin::Signed(who)) => {
+				ensure!(Self::owner() == Some(who), "Only root or the owner can manage approvers");
+				Ok(())
+			}
+			_ => Err("bad origin: expected root or a signed owner"),
+		}
+	}
+
+	// Private method called by: remove_approver() and revoke_delegation()
+	// Returns the delegate the account had, if any
+	fn clear_delegation(principal: &T::AccountId) -> Option<T::AccountId> {
+		let delegate = <Delegations<T>>::take(principal)?;
+		<Principals<T>>::mutate(&delegate, |principals| principals.retain(|p| p != principal));
+		Some(delegate)
+	}
+
+	// Private method called by: approve(), reject(), approve_for() and reject_for()
+	// The voter is always the approver the vote counts for, also when a delegate sent it.
+	fn record_vote(proposal_id: T::Hash, voter: T::AccountId, aye: bool) -> Result {
+		let (weight, threshold) = Self::check_vote(proposal_id, &voter)?;
+		Self::apply_vote(proposal_id, voter, aye, weight, threshold);
+		Ok(())
+	}
+
+	// Private method called by: record_vote() and record_votes()
+	// All checks for a new vote, without writing to storage. Returns the weight of the vote and the weight that
+	// finalizes the proposal.
+	fn check_vote(proposal_id: T::Hash, voter: &T::AccountId) -> result::Result<(u32, u32), &'static str> {
+		let checked = Self::check_voter(proposal_id, voter)?;
+		ensure!(Self::vote_of((proposal_id, voter.clone())).is_none(), "Account has already voted on this proposal");
+		Ok(checked)
+	}
+
+	// Private method called by: check_vote() and update_vote()
+	// Checks that the proposal is open and that the account can vote on it
+	fn check_voter(proposal_id: T::Hash, voter: &T::AccountId) -> result::Result<(u32, u32), &'static str> {
+		Self::ensure_not_frozen()?;
+		ensure!(<Proposals<T>>::exists(proposal_id), "This proposal does not exist");
+		let proposal = Self::proposal(proposal_id);
+		let stages = Self::stages_of(proposal_id);
+		let (weight, threshold) = match Self::group_of(proposal_id) {
+			Some((_, required)) => {
+				let weight = Self::voting_weight(proposal_id, voter).ok_or("Account is not an eligible member of this group")?;
+				(weight, Self::group_required(proposal_id, required)?)
+			}
+			None if !stages.is_empty() => {
+				let weight = Self::voting_weight(proposal_id, voter).ok_or("Account is not an approver of the current stage")?;
+				let stage = stages.get(proposal.stage as usize).ok_or("Proposal stage is out of range")?;
+				(weight, stage.threshold)
+			}
+			None => {
+				let weight = Self::voting_weight(proposal_id, voter).ok_or("Account is not an approver")?;
+				(weight, Self::required_weight()?)
+			}
+		};
+
+		ensure!(proposal.status == ProposalStatus::Pending, "This proposal is no longer pending");
+		// The on_initialize() sweep may not have reached this proposal yet, so check the expiry here too
+		ensure!(<system::Module<T>>::block_number() < proposal.expiry, "This proposal has expired");
+		Ok((weight, threshold))
+	}
+
+	// Private method called by: record_vote() and record_votes()
+	// Writes a vote that passed check_vote(). A proposal can only stop being Pending in between when an
+	// executed Call in the same batch changed it, and then the vote is skipped.
+	fn apply_vote(proposal_id: T::Hash, voter: T::AccountId, aye: bool, weight: u32, threshold: u32) {
+		let proposal = Self::proposal(proposal_id);
+		if proposal.status != ProposalStatus::Pending {
+			return;
+		}
+
+		<Votes<T>>::insert((proposal_id, voter.clone()), aye);
+		<Voters<T>>::mutate(proposal_id, |voters| voters.push(voter.clone()));
+		<PendingForApprover<T>>::mutate(&voter, |ids| ids.retain(|id| *id != proposal_id));
+		Self::append_audit(proposal_id, &voter, Some(aye));
+		Self::deposit_event(RawEvent::Voted(proposal_id, voter, aye, weight));
+
+		Self::finalize_if_decided(proposal_id, proposal, threshold);
+	}
+
+	// Private method called by: change_vote() and withdraw_vote()
+	fn update_vote(proposal_id: T::Hash, voter: T::AccountId, vote: Option<bool>) -> Result {
+		let (_, threshold) = Self::check_voter(proposal_id, &voter)?;
+		let previous = Self::vote_of((proposal_id, voter.clone())).ok_or("Account has not voted on this proposal")?;
+		ensure!(Some(previous) != vote, "This is already the vote of the account");
+
+		match vote {
+			Some(aye) => <Votes<T>>::insert((proposal_id, voter.clone()), aye),
+			None => {
+				<Votes<T>>::remove((proposal_id, voter.clone()));
+				<Voters<T>>::mutate(proposal_id, |voters| voters.retain(|v| *v != voter));
+				<PendingForApprover<T>>::mutate(&voter, |ids| ids.push(proposal_id));
+			}
+		}
+		Self::append_audit(proposal_id, &voter, vote);
+		Self::deposit_event(RawEvent::VoteChanged(proposal_id, voter, vote));
 
-	// Unused right now. Still considering timestamps for some record-keeping
+		Self::finalize_if_decided(proposal_id, Self::proposal(proposal_id), threshold);
+		Ok(())
+	}
+
+	// Private method called by: apply_vote() and update_vote()
+	fn finalize_if_decided(proposal_id: T::Hash, mut proposal: Proposal<T::AccountId, T::Hash, T::BlockNumber>, threshold: u32) {
+		// Recount instead of incrementing, so that votes from removed approvers drop out and a changed vote
+		// is never counted twice
+		let (approvals, rejections) = Self::tally(proposal_id);
+		proposal.approvals = approvals;
+		proposal.rejections = rejections;
+
+		let approved = approvals >= threshold;
+		let stages = Self::stages_of(proposal_id);
+		if approved && (proposal.stage as usize) + 1 < stages.len() {
+			Self::advance_stage(proposal_id, proposal);
+			return;
+		}
+
+		if approved {
+			proposal.status = ProposalStatus::Approved;
+			Self::deposit_event(RawEvent::Approved(proposal_id));
+		} else if rejections >= threshold {
+			proposal.status = ProposalStatus::Rejected;
+			<ProposalCalls<T>>::remove(proposal_id);
+			Self::deposit_event(RawEvent::Rejected(proposal_id));
+		}
+
+		if proposal.status != ProposalStatus::Pending {
+			Self::clear_indexes(&proposal);
+			Self::record_outcome(&proposal);
+			Self::settle_bond(proposal_id, &proposal.proposer, false);
+		}
+		let proposer = proposal.proposer.clone();
+		<Proposals<T>>::insert(proposal_id, proposal);
+
+		// Storage is up to date before the Call runs, so it sees the proposal as Approved
+		if approved {
+			Self::execute(proposal_id, proposer, approvals);
+		}
+	}
+
+	// Private method called by: finalize_if_decided()
+	// Votes of the completed stage are cleared, so an account that is in several stages votes again. The audit
+	// log keeps them. The next stage gets a fresh proposal_lifetime, and the old ExpiringAt entry is ignored
+	// by expire_proposals() because the expiry no longer matches.
+	fn advance_stage(proposal_id: T::Hash, mut proposal: Proposal<T::AccountId, T::Hash, T::BlockNumber>) {
+		for voter in Self::current_voters(proposal_id).iter() {
+			<PendingForApprover<T>>::mutate(voter, |ids| ids.retain(|id| *id != proposal_id));
+		}
+		for voter in <Voters<T>>::take(proposal_id) {
+			<Votes<T>>::remove((proposal_id, voter));
+		}
+
+		proposal.stage += 1;
+		proposal.approvals = 0;
+		proposal.rejections = 0;
+		if let Some(lifetime) = Self::proposal_lifetime() {
+			proposal.expiry = <system::Module<T>>::block_number() + T::BlockNumber::sa(lifetime as u64);
+			<Expiries<T>>::schedule(proposal.expiry, proposal_id);
+			<Reminded<T>>::remove(proposal_id);
+		}
+		let stage = proposal.stage;
+		<Proposals<T>>::insert(proposal_id, proposal);
+
+		for voter in Self::current_voters(proposal_id).iter() {
+			<PendingForApprover<T>>::mutate(voter, |ids| ids.push(proposal_id));
+		}
+		Self::deposit_event(RawEvent::StageAdvanced(proposal_id, stage));
+	}
+
+	// Private method called by: approve_many() and reject_many()
+	// Every vote is checked before any is written, so the batch is applied completely or not at all.
+	fn record_votes(proposal_ids: Vec<T::Hash>, voter: T::AccountId, aye: bool) -> Result {
+		ensure!(!proposal_ids.is_empty(), "No proposals given");
+		ensure!(proposal_ids.len() <= MAX_BATCH_VOTES, "Too many proposals in one batch");
+
+		let mut checked = Vec::with_capacity(proposal_ids.len());
+		for (i, proposal_id) in proposal_ids.iter().enumerate() {
+			ensure!(!proposal_ids[..i].contains(proposal_id), "Proposal is listed more than once");
+			checked.push(Self::check_vote(*proposal_id, &voter)?);
+		}
+
+		for (proposal_id, (weight, threshold)) in proposal_ids.into_iter().zip(checked.into_iter()) {
+			Self::apply_vote(proposal_id, voter.clone(), aye, weight, threshold);
+		}
+		Ok(())
+	}
+
+	// Private method called whenever a proposal is finalized. Its payload can be proposed again, and it leaves
+	// the active index and the inboxes of the accounts that could vote on it. Accounts that stopped being
+	// approvers in the meantime had their inbox removed already.
+	fn clear_indexes(proposal: &Proposal<T::AccountId, T::Hash, T::BlockNumber>) {
+		if Self::pending_by_payload(proposal.payload_hash) == Some(proposal.id) {
+			<PendingByPayload<T>>::remove(proposal.payload_hash);
+		}
+
+		if <ActiveProposalsIndex<T>>::exists(proposal.id) {
+			// Move the last active proposal into the freed slot
+			let index = <ActiveProposalsIndex<T>>::take(proposal.id);
+			let last_index = Self::active_proposals_count().saturating_sub(1);
+			if index != last_index {
+				let last_id = Self::active_proposal_by_index(last_index);
+				<ActiveProposalsArray<T>>::insert(index, last_id);
+				<ActiveProposalsIndex<T>>::insert(last_id, index);
+			}
+			<ActiveProposalsArray<T>>::remove(last_index);
+			<ActiveProposalsCount<T>>::put(last_index);
+		}
+
+		for voter in Self::current_voters(proposal.id).iter() {
+			<PendingForApprover<T>>::mutate(voter, |ids| ids.retain(|id| *id != proposal.id));
+		}
+	}
+
+	// Private method called by: remove_approver(), new_proposal(), advance_stage() and clear_indexes()
+	// The accounts whose inbox holds the proposal: the group at creation, the current stage, or the approvers.
+	fn current_voters(proposal_id: T::Hash) -> Vec<T::AccountId> {
+		if Self::group_of(proposal_id).is_some() {
+			return Self::group_snapshot_of(proposal_id);
+		}
+		let stages = Self::stages_of(proposal_id);
+		if !stages.is_empty() {
+			let stage = Self::proposal(proposal_id).stage as usize;
+			return stages.get(stage).map(|s| s.approvers.clone()).unwrap_or_default();
+		}
+		Self::approvers()
+	}
+
+	// Private method called whenever a proposal is finalized. Returns the bond to the proposer, or slashes it
+	// into the BondSlashed handler.
+	fn settle_bond(proposal_id: T::Hash, proposer: &T::AccountId, slash: bool) {
+		if let Some(bond) = <Bonds<T>>::take(proposal_id) {
+			if slash {
+				let (imbalance, _) = T::Currency::slash_reserved(proposer, bond);
+				T::BondSlashed::on_unbalanced(imbalance);
+				Self::deposit_event(RawEvent::BondSlashed(proposal_id, proposer.clone(), bond));
+			} else {
+				T::Currency::unreserve(proposer, bond);
+				Self::deposit_event(RawEvent::BondReturned(proposal_id, proposer.clone(), bond));
+			}
+		}
+	}
+
+	// Private method called whenever a proposal is finalized, except when it was cancelled
+	fn record_outcome(proposal: &Proposal<T::AccountId, T::Hash, T::BlockNumber>) {
+		let (approved, failed) = Self::record_of(&proposal.proposer);
+		let (approved, failed) = match proposal.status {
+			ProposalStatus::Approved => (approved.saturating_add(1), failed),
+			ProposalStatus::Rejected | ProposalStatus::Expired => (approved, failed.saturating_add(1)),
+			_ => return,
+		};
+		<ProposerRecord<T>>::insert(&proposal.proposer, (approved, failed));
+
+		let max_percent = Self::max_failure_percent().unwrap_or(0);
+		let total = approved as u64 + failed as u64;
+		if max_percent == 0 || total < Self::min_recorded_proposals().unwrap_or(0) as u64 {
+			return;
+		}
+		if failed as u64 * 100 > max_percent as u64 * total && !Self::is_suspended(&proposal.proposer) {
+			<Suspended<T>>::insert(&proposal.proposer, true);
+			Self::deposit_event(RawEvent::ProposerSuspended(proposal.proposer.clone()));
+		}
+	}
+
+	// Private method called by: new_proposal(), check_voter(), veto(), cancel() and force_resolve()
+	fn ensure_not_frozen() -> Result {
+		ensure!(!Self::is_frozen(), "The approval system is frozen");
+		Ok(())
+	}
+
+	// Private method called by: apply_vote() and update_vote()
+	fn append_audit(proposal_id: T::Hash, who: &T::AccountId, vote: Option<bool>) {
+		let entry = AuditEntry { who: who.clone(), vote, time: Self::get_time() };
+		<AuditLog<T>>::mutate(proposal_id, |log| {
+			if log.len() >= MAX_AUDIT_ENTRIES {
+				log.remove(0);
+			}
+			log.push(entry);
+		});
+	}
+
+	// Private method called by: on_initialize()
+	// Work is bounded by MAX_EXPIRIES_PER_BLOCK. Anything beyond that is taken in the next blocks, and in the
+	// meantime record_vote() already refuses votes for those proposals.
+	fn expire_proposals(n: T::BlockNumber) {
+		for proposal_id in <Expiries<T>>::take_due(n) {
+			let mut proposal = Self::proposal(proposal_id);
+			// A staged proposal that moved on has a later expiry than the queue entry
+			if proposal.status == ProposalStatus::Pending && proposal.expiry <= n {
+				proposal.status = ProposalStatus::Expired;
+				Self::clear_indexes(&proposal);
+				Self::record_outcome(&proposal);
+				Self::settle_bond(proposal_id, &proposal.proposer, true);
+				<Proposals<T>>::insert(proposal_id, proposal);
+				<ProposalCalls<T>>::remove(proposal_id);
+				Self::deposit_event(RawEvent::ProposalExpired(proposal_id));
+			}
+		}
+	}
+
+	/// The pending proposals that expire reminder_blocks after block n and have not been reminded yet. Used by
+	/// the off-chain worker.
+	pub fn due_reminders(n: T::BlockNumber) -> Vec<T::Hash> {
+		let reminder_blocks = match Self::reminder_blocks() {
+			Some(blocks) if blocks > 0 => blocks,
+			_ => return Vec::new(),
+		};
+		Self::expiring_at(n + T::BlockNumber::sa(reminder_blocks as u64))
+			.into_iter()
+			.filter(|id| Self::proposal(*id).status == ProposalStatus::Pending && !Self::reminded(*id))
+			.take(MAX_EXPIRIES_PER_BLOCK)
+			.collect()
+	}
+
+	/// The validity of an unsigned remind() for the transaction pool, which has no sender to check. Valid only if
+	/// every proposal is due for a reminder, so the free transaction can not be filled with other ids. Reminders
+	/// of the same proposals provide the same tag, so the pool keeps one of those that the nodes send.
+	pub fn validate_remind(proposal_ids: &[T::Hash]) -> TransactionValidity {
+		let now = <system::Module<T>>::block_number();
+		let reminder_blocks = Self::reminder_blocks().unwrap_or(0);
+		let due = !proposal_ids.is_empty()
+			&& proposal_ids.len() <= MAX_EXPIRIES_PER_BLOCK
+			&& proposal_ids.iter().all(|id| Self::is_due_for_reminder(*id, now, reminder_blocks));
+		if !due {
+			return TransactionValidity::Invalid(INVALID_REMINDER);
+		}
+		TransactionValidity::Valid {
+			priority: 0,
+			requires: Vec::new(),
+			provides: vec![proposal_ids.encode()],
+			longevity: REMINDER_LONGEVITY,
+		}
+	}
+
+	// Private method called by: remind() and validate_remind(). A pending proposal that expires within
+	// reminder_blocks after now and was not reminded yet.
+	fn is_due_for_reminder(proposal_id: T::Hash, now: T::BlockNumber, reminder_blocks: u32) -> bool {
+		if !<Proposals<T>>::exists(proposal_id) || Self::reminded(proposal_id) {
+			return false;
+		}
+		let proposal = Self::proposal(proposal_id);
+		let due = now + T::BlockNumber::sa(reminder_blocks as u64) >= proposal.expiry;
+		proposal.status == ProposalStatus::Pending && now < proposal.expiry && due
+	}
+
+	/// Sum the approval and rejection weight of a proposal, only including accounts that can currently vote on it
+	pub fn tally(proposal_id: T::Hash) -> (u32, u32) {
+		let mut approvals = 0u32;
+		let mut rejections = 0u32;
+		for voter in Self::voters_of(proposal_id).iter() {
+			let weight = match Self::voting_weight(proposal_id, voter) {
+				Some(weight) => weight,
+				None => continue,
+			};
+			match Self::vote_of((proposal_id, voter.clone())) {
+				Some(true) => approvals = approvals.saturating_add(weight),
+				Some(false) => rejections = rejections.saturating_add(weight),
+				None => {}
+			}
+		}
+		(approvals, rejections)
+	}
+
+	/// The weight the account votes with on the proposal, or None if it can not vote on it right now.
+	/// For a group proposal the account must be a member now and when the proposal was created. For a staged
+	/// proposal it must be an approver of the current stage.
+	pub fn voting_weight(proposal_id: T::Hash, who: &T::AccountId) -> Option<u32> {
+		let stages = Self::stages_of(proposal_id);
+		if !stages.is_empty() {
+			let stage = Self::proposal(proposal_id).stage as usize;
+			return match stages.get(stage) {
+				Some(s) if s.approvers.contains(who) => Some(1),
+				_ => None,
+			};
+		}
+
+		match Self::group_of(proposal_id) {
+			Some((group_id, _)) => {
+				if T::Groups::is_member(GroupId(group_id), who) && Self::group_snapshot_of(proposal_id).contains(who) {
+					Some(1)
+				} else {
+					None
+				}
+			}
+			None => {
+				if Self::is_approver(who) {
+					Some(Self::approver_weight(who))
+				} else {
+					None
+				}
+			}
+		}
+	}
+
+	/// The voting weight of an account. Approvers have a weight of 1 unless root changed it.
+	pub fn approver_weight(who: &T::AccountId) -> u32 {
+		Self::weight_of(who).unwrap_or(1)
+	}
+
+	/// The sum of the weights of all current approvers
+	pub fn total_weight() -> u32 {
+		Self::approvers().iter().fold(0u32, |total, a| total.saturating_add(Self::approver_weight(a)))
+	}
+
+	/// The approval (or rejection) weight that finalizes an approver set proposal right now. With
+	/// threshold_percent set, this is that share of the total weight, rounded up and never less than 1.
+	pub fn required_weight() -> result::Result<u32, &'static str> {
+		Self::required_for(Self::total_weight())
+	}
+
+	/// The members of the group of a group proposal that can still vote on it: members when it was created
+	/// that are still in the group now.
+	pub fn eligible_members(proposal_id: T::Hash) -> Vec<T::AccountId> {
+		match Self::group_of(proposal_id) {
+			Some((group_id, _)) => {
+				Self::group_snapshot_of(proposal_id).into_iter().filter(|m| T::Groups::is_member(GroupId(group_id), m)).collect()
+			}
+			None => Vec::new(),
+		}
+	}
+
+	// Private method called by: check_voter()
+	// A percentage quorum is worked out against the members that can still vote, so members leaving the group
+	// lower the weight needed, just like removing approvers does for the approver set.
+	fn group_required(proposal_id: T::Hash, fixed: u32) -> result::Result<u32, &'static str> {
+		match Self::threshold_percent() {
+			Some(percent) if percent > 0 => Self::required_for(Self::eligible_members(proposal_id).len() as u32),
+			_ => Ok(fixed),
+		}
+	}
+
+	// Private method called by: required_weight(), group_required() and create_group_proposal()
+	// Rounding rule for the percentage quorum: the share of the total is rounded up, so 50% of 5 is 3, and
+	// it is at least 1 even for an empty set.
+	fn required_for(total: u32) -> result::Result<u32, &'static str> {
+		match Self::threshold_percent() {
+			Some(percent) if percent > 0 => {
+				ensure!(percent <= 100, "Config threshold_percent must be at most 100");
+				let required = (total as u64 * percent as u64 + 99) / 100;
+				Ok(rstd::cmp::max(required, 1) as u32)
+			}
+			_ => Self::threshold().ok_or("Config threshold not set"),
+		}
+	}
+
+	/// Helper method that can be used from UI code to verify a veto holder
+	pub fn is_veto_holder(who: &T::AccountId) -> bool {
+		Self::veto_holders().contains(who)
+	}
+
+	/// Helper method that can be used from UI code to verify an approver
+	pub fn is_approver(who: &T::AccountId) -> bool {
+		Self::approvers().contains(who)
+	}
+
+	/// The status of a proposal, or None if it does not exist. Used by the ApproveApi runtime API.
+	pub fn proposal_status(proposal_id: T::Hash) -> Option<ProposalStatus> {
+		if <Proposals<T>>::exists(proposal_id) {
+			Some(Self::proposal(proposal_id).status)
+		} else {
+			None
+		}
+	}
+
+	/// A page of at most `limit` pending proposals, starting at index `start` of the active index. The order
+	/// changes as proposals are finalized, so indexers should page through it within one block.
+	pub fn active_proposals(start: u64, limit: u64) -> Vec<T::Hash> {
+		let end = rstd::cmp::min(start.saturating_add(limit), Self::active_proposals_count());
+		(start..end).map(Self::active_proposal_by_index).collect()
+	}
+
+	/// Every recorded vote on a proposal, including votes that no longer count. Used by the ApproveApi runtime API.
+	pub fn votes(proposal_id: T::Hash) -> Vec<(T::AccountId, bool)> {
+		Self::voters_of(proposal_id)
+			.into_iter()
+			.filter_map(|voter| Self::vote_of((proposal_id, voter.clone())).map(|aye| (voter, aye)))
+			.collect()
+	}
+
+	/// Helper method that can be used from other modules and UI code
+	pub fn is_approved(proposal_id: T::Hash) -> bool {
+		Self::proposal(proposal_id).status == ProposalStatus::Approved
+	}
+
+	// Used for the timestamps in the audit log
 	pub fn get_time() -> T::Moment {
 		let now = <timestamp::Module<T>>::get();
 		now
@@ -107,12 +1503,41 @@ mod tests {
 	use support::{impl_outer_origin, assert_ok, assert_noop};
 	use runtime_primitives::{
 		BuildStorage,
-		traits::{BlakeTwo256, IdentityLookup},
+		traits::{BlakeTwo256, IdentityLookup, OnInitialize},
 		testing::{Digest, DigestItem, Header}
 	};
 
+	use crate::approve;
+
 	impl_outer_origin! {
-		pub enum Origin for ApproveTest {}
+		pub enum Origin for ApproveTest {
+			approve
+		}
+	}
+
+	// A stand-in for the runtime Call. Record writes the origin it was dispatched with to storage, so tests can
+	// check how an approved call was executed.
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+	pub enum MockCall {
+		Record,
+		Fail,
+	}
+	impl Dispatchable for MockCall {
+		type Origin = Origin;
+		type Trait = ApproveTest;
+		fn dispatch(self, origin: Origin) -> Result {
+			match self {
+				MockCall::Record => {
+					let marker = match ensure_signed(origin.clone()) {
+						Ok(who) => (0u8, who as u32),
+						Err(_) => (1u8, ensure_approved(origin)?),
+					};
+					runtime_io::set_storage(b"mock_call", &marker.encode());
+					Ok(())
+				}
+				MockCall::Fail => Err("Mock call failed"),
+			}
+		}
 	}
 
 	// For testing the module, we construct most of a mock runtime. This means
@@ -137,25 +1562,928 @@ mod tests {
 		type Moment = u64;
 		type OnTimestampSet = ();
 	}
+	impl balances::Trait for ApproveTest {
+		type Balance = u64;
+		type OnFreeBalanceZero = ();
+		type OnNewAccount = ();
+		type Event = ();
+		type TransactionPayment = ();
+		type TransferPayment = ();
+		type DustRemoval = ();
+	}
 	impl Trait for ApproveTest {
 		type Event = ();
+		type Currency = balances::Module<ApproveTest>;
+		type BondSlashed = ();
+		type SubmitReminder = ();
+		type ExecutionOrigin = Origin;
+		type Proposal = MockCall;
+		type Groups = MockGroups;
+		type Council = Council;
+	}
+
+	// Group members are read from storage under the group id, see set_mock_group()
+	pub struct MockGroups;
+	impl GroupInspector<u64, H256> for MockGroups {
+		fn members(group_id: GroupId<H256>) -> Option<Vec<u64>> {
+			runtime_io::storage(group_id.as_ref()).and_then(|v| Vec::<u64>::decode(&mut &v[..]))
+		}
+
+		fn is_member(group_id: GroupId<H256>, who: &u64) -> bool {
+			Self::members(group_id).map_or(false, |members| members.contains(who))
+		}
+
+		fn owner(_group_id: GroupId<H256>) -> Option<u64> {
+			None
+		}
+	}
+
+	fn set_mock_group(group_id: H256, members: Vec<u64>) {
+		runtime_io::set_storage(group_id.as_ref(), &members.encode());
 	}
 	type Approve = Module<ApproveTest>;
+	type Balances = balances::Module<ApproveTest>;
 
 	// This function basically just builds a genesis storage key/value store according to
-	// our desired mockup.
-	// TODO: _genesis_phantom_data: Default::default() can be removed later if using latest substrate fixes
-	// Error: missing field `_genesis_phantom_data` in initializer of `groups::GenesisConfig<groups::tests::ApproveTest>`
-	// See also: https://github.com/paritytech/substrate/pull/2913 and Issue #2219
+	// our desired mockup. Accounts 1 and 7 can pay proposal bonds.
 	fn build_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
-		let t = system::GenesisConfig::<ApproveTest>::default().build_storage().unwrap().0;
-		// t.extend(
-		// 	GenesisConfig::<ApproveTest> {
-		// 		max_group_size: 12,
-		// 		max_groups_per_owner: 5,
-		// 		max_name_size: 40,
-		// 		_genesis_phantom_data: Default::default(),
-		// 	}.build_storage().unwrap().0);
+		build_ext_with_approvers(vec![])
+	}
+
+	fn build_ext_with_approvers(initial_approvers: Vec<(u64, u32)>) -> runtime_io::TestExternalities<Blake2Hasher> {
+		let mut t = system::GenesisConfig::<ApproveTest>::default().build_storage().unwrap().0;
+		t.extend(
+			GenesisConfig::<ApproveTest> {
+				threshold: 2,
+				threshold_percent: 0,
+				proposal_lifetime: 10,
+				reminder_blocks: 3,
+				proposal_bond: 10,
+				cancel_grace: 2,
+				max_failure_percent: 0,
+				min_recorded_proposals: 2,
+				initial_approvers: initial_approvers,
+			}.build_storage().unwrap().0);
+		t.extend(
+			balances::GenesisConfig::<ApproveTest> {
+				balances: vec![(1, 100), (7, 100)],
+				transaction_base_fee: 0,
+				transaction_byte_fee: 0,
+				existential_deposit: 0,
+				transfer_fee: 0,
+				creation_fee: 0,
+				vesting: vec![],
+			}.build_storage().unwrap().0);
 		t.into()
 	}
+
+	// Adds accounts 2 to 5 to the approver set
+	fn setup_approvers() {
+		for who in 2..6 {
+			assert_ok!(Approve::add_approver(Origin::ROOT, who));
+		}
+	}
+
+	/// Type definitions test objectives:
+	/// * Each struct encodes like its fields in the order of type_definitions()
+	/// * Enum variants encode as their index in type_definitions()
+	#[test]
+	fn type_definitions_should_match_encoding() {
+		let hash = H256::from([1; 32]);
+		let proposal = Proposal {
+			id: hash, proposer: 2u64, payload_hash: H256::from([3; 32]), approvals: 4, rejections: 5,
+			status: ProposalStatus::Expired, expiry: 6u64, created: 7u64, stage: 8,
+		};
+		assert_eq!(proposal.encode(), (
+			proposal.id, proposal.proposer, proposal.payload_hash, proposal.approvals, proposal.rejections,
+			proposal.status.clone(), proposal.expiry, proposal.created, proposal.stage,
+		).encode());
+		let stage = Stage { approvers: vec![9u64, 10], threshold: 2 };
+		assert_eq!(stage.encode(), (stage.approvers.clone(), stage.threshold).encode());
+		let metadata = ProposalMetadata { title_hash: hash, description_cid: b"cid".to_vec(), category: 11 };
+		assert_eq!(metadata.encode(), (metadata.title_hash, metadata.description_cid.clone(), metadata.category).encode());
+		let entry = AuditEntry { who: 12u64, vote: Some(false), time: 13u64 };
+		assert_eq!(entry.encode(), (entry.who, entry.vote, entry.time).encode());
+		assert_eq!((ExecuteAs::Proposer, ExecuteAs::Approvers).encode(), vec![0, 1]);
+		assert_eq!((
+			ProposalStatus::Pending, ProposalStatus::Approved, ProposalStatus::Rejected,
+			ProposalStatus::Expired, ProposalStatus::Cancelled,
+		).encode(), vec![0, 1, 2, 3, 4]);
+
+		let expected: Vec<Vec<&str>> = vec![
+			vec!["Proposer", "Approvers"],
+			vec!["Pending", "Approved", "Rejected", "Expired", "Cancelled"],
+			vec!["id", "proposer", "payload_hash", "approvals", "rejections", "status", "expiry", "created", "stage"],
+			vec!["approvers", "threshold"],
+			vec!["title_hash", "description_cid", "category"],
+			vec!["who", "vote", "time"],
+		];
+		let names: Vec<Vec<&str>> = type_definitions().iter().map(|def| match def {
+			crate::types::TypeDef::Struct(_, fields) => fields.iter().map(|(name, _)| *name).collect(),
+			crate::types::TypeDef::Enum(_, variants) => variants.clone(),
+			crate::types::TypeDef::Alias(_, ty) => vec![*ty],
+		}).collect();
+		assert_eq!(names, expected);
+	}
+
+	/// Approval test objectives:
+	/// * Create a proposal and look it up through the global index
+	/// * An account can only vote once
+	/// * The proposal is Approved once the threshold is reached and then stops accepting votes
+	#[test]
+	fn approve_proposal_should_work() {
+		with_externalities(&mut build_ext(), || {
+			setup_approvers();
+			assert_ok!(Approve::create_proposal(Origin::signed(1), H256::repeat_byte(1)));
+			assert_eq!(Approve::all_proposals_count(), 1);
+			let proposal_id = Approve::proposal_by_index(0);
+			assert_eq!(Approve::proposal(proposal_id).proposer, 1);
+
+			assert_ok!(Approve::approve(Origin::signed(2), proposal_id));
+			assert_noop!(Approve::approve(Origin::signed(2), proposal_id), "Account has already voted on this proposal");
+			assert_ok!(Approve::reject(Origin::signed(3), proposal_id));
+			assert_eq!(Approve::proposal(proposal_id).status, ProposalStatus::Pending);
+
+			assert_ok!(Approve::approve(Origin::signed(4), proposal_id));
+			assert_eq!(Approve::proposal(proposal_id).approvals, 2);
+			assert!(Approve::is_approved(proposal_id));
+			assert_eq!(Approve::vote_of((proposal_id, 3)), Some(false));
+
+			assert_noop!(Approve::reject(Origin::signed(5), proposal_id), "This proposal is no longer pending");
+		});
+	}
+
+	/// Rejection test objectives:
+	/// * The proposal is Rejected once the rejection threshold is reached
+	/// * Voting on an unknown proposal is an error
+	#[test]
+	fn reject_proposal_should_work() {
+		with_externalities(&mut build_ext(), || {
+			setup_approvers();
+			assert_ok!(Approve::create_proposal(Origin::signed(1), H256::repeat_byte(2)));
+			let proposal_id = Approve::proposal_by_index(0);
+
+			assert_ok!(Approve::reject(Origin::signed(2), proposal_id));
+			assert_ok!(Approve::reject(Origin::signed(3), proposal_id));
+			assert_eq!(Approve::proposal(proposal_id).status, ProposalStatus::Rejected);
+			assert!(!Approve::is_approved(proposal_id));
+
+			assert_noop!(Approve::approve(Origin::signed(2), H256::zero()), "This proposal does not exist");
+		});
+	}
+
+	/// Approver set test objectives:
+	/// * Only root or the owner can manage approvers
+	/// * Accounts outside the approver set can not vote
+	/// * Votes from a removed approver no longer count towards the threshold
+	#[test]
+	fn approver_set_management_should_work() {
+		with_externalities(&mut build_ext(), || {
+			assert_noop!(Approve::add_approver(Origin::signed(1), 2), "Only root or the owner can manage approvers");
+			assert_ok!(Approve::set_owner(Origin::ROOT, 1));
+			assert_ok!(Approve::add_approver(Origin::signed(1), 2));
+			assert_ok!(Approve::add_approver(Origin::ROOT, 3));
+			assert_ok!(Approve::add_approver(Origin::ROOT, 4));
+			assert_noop!(Approve::add_approver(Origin::ROOT, 4), "Account is already an approver");
+
+			assert_ok!(Approve::create_proposal(Origin::signed(1), H256::repeat_byte(3)));
+			let proposal_id = Approve::proposal_by_index(0);
+			assert_noop!(Approve::approve(Origin::signed(9), proposal_id), "Account is not an approver");
+
+			assert_ok!(Approve::approve(Origin::signed(2), proposal_id));
+			assert_ok!(Approve::remove_approver(Origin::signed(1), 2));
+			assert_eq!(Approve::tally(proposal_id), (0, 0));
+
+			assert_ok!(Approve::approve(Origin::signed(3), proposal_id));
+			assert_eq!(Approve::proposal(proposal_id).approvals, 1);
+			assert_eq!(Approve::proposal(proposal_id).status, ProposalStatus::Pending);
+
+			assert_ok!(Approve::approve(Origin::signed(4), proposal_id));
+			assert!(Approve::is_approved(proposal_id));
+		});
+	}
+
+	/// Expiry test objectives:
+	/// * A proposal can not be voted on from its expiry block onwards, even before the sweep
+	/// * The on_initialize() sweep moves pending proposals to Expired
+	/// * Proposals that were finalized before their expiry are left alone
+	#[test]
+	fn proposal_expiry_should_work() {
+		with_externalities(&mut build_ext(), || {
+			setup_approvers();
+			<system::Module<ApproveTest>>::set_block_number(1);
+			assert_ok!(Approve::create_proposal(Origin::signed(1), H256::repeat_byte(4)));
+			assert_ok!(Approve::create_proposal(Origin::signed(1), H256::repeat_byte(5)));
+			let pending_id = Approve::proposal_by_index(0);
+			let approved_id = Approve::proposal_by_index(1);
+			assert_eq!(Approve::proposal(pending_id).expiry, 11);
+			assert_eq!(Approve::expiring_at(11).len(), 2);
+
+			assert_ok!(Approve::approve(Origin::signed(2), approved_id));
+			assert_ok!(Approve::approve(Origin::signed(3), approved_id));
+
+			<system::Module<ApproveTest>>::set_block_number(11);
+			assert_noop!(Approve::approve(Origin::signed(2), pending_id), "This proposal has expired");
+
+			Approve::on_initialize(11);
+			assert_eq!(Approve::proposal(pending_id).status, ProposalStatus::Expired);
+			assert_eq!(Approve::proposal(approved_id).status, ProposalStatus::Approved);
+			assert!(Approve::expiring_at(11).is_empty());
+		});
+	}
+
+	/// Veto test objectives:
+	/// * Only root can hand out the veto power
+	/// * Only veto holders can veto, and only pending proposals
+	/// * A vetoed proposal is Rejected and stops accepting votes
+	#[test]
+	fn veto_should_work() {
+		with_externalities(&mut build_ext(), || {
+			setup_approvers();
+			assert!(Approve::add_veto_holder(Origin::signed(1), 7).is_err());
+			assert_ok!(Approve::add_veto_holder(Origin::ROOT, 7));
+
+			assert_ok!(Approve::create_proposal(Origin::signed(1), H256::repeat_byte(6)));
+			let proposal_id = Approve::proposal_by_index(0);
+			assert_ok!(Approve::approve(Origin::signed(2), proposal_id));
+
+			assert_noop!(Approve::veto(Origin::signed(2), proposal_id, H256::zero(), false), "Account is not a veto holder");
+			assert_ok!(Approve::veto(Origin::signed(7), proposal_id, H256::repeat_byte(9), false));
+			assert_eq!(Approve::proposal(proposal_id).status, ProposalStatus::Rejected);
+
+			assert_noop!(Approve::approve(Origin::signed(3), proposal_id), "This proposal is no longer pending");
+			assert_noop!(Approve::veto(Origin::signed(7), proposal_id, H256::zero(), true), "This proposal is no longer pending");
+		});
+	}
+
+	/// Call execution test objectives:
+	/// * The Call of a proposal is only dispatched once it is approved
+	/// * The Call is dispatched as the proposer or with the Approved origin, as chosen at creation
+	/// * A failing Call still leaves the proposal Approved and removes the Call
+	#[test]
+	fn approved_call_should_execute() {
+		with_externalities(&mut build_ext(), || {
+			setup_approvers();
+			let marker = || runtime_io::storage(b"mock_call").and_then(|v| <(u8, u32)>::decode(&mut &v[..]));
+
+			assert_ok!(Approve::create_call_proposal(Origin::signed(1), Box::new(MockCall::Record), ExecuteAs::Proposer));
+			let proposal_id = Approve::proposal_by_index(0);
+			assert!(Approve::call_of(proposal_id).is_some());
+			assert_ok!(Approve::approve(Origin::signed(2), proposal_id));
+			assert_eq!(marker(), None);
+			assert_ok!(Approve::approve(Origin::signed(3), proposal_id));
+			assert_eq!(marker(), Some((0, 1)));
+			assert!(Approve::call_of(proposal_id).is_none());
+
+			assert_ok!(Approve::create_call_proposal(Origin::signed(1), Box::new(MockCall::Record), ExecuteAs::Approvers));
+			let proposal_id = Approve::proposal_by_index(1);
+			assert_ok!(Approve::approve(Origin::signed(4), proposal_id));
+			assert_ok!(Approve::approve(Origin::signed(5), proposal_id));
+			assert_eq!(marker(), Some((1, 2)));
+
+			assert_ok!(Approve::create_call_proposal(Origin::signed(1), Box::new(MockCall::Fail), ExecuteAs::Approvers));
+			let proposal_id = Approve::proposal_by_index(2);
+			assert_ok!(Approve::approve(Origin::signed(2), proposal_id));
+			assert_ok!(Approve::approve(Origin::signed(3), proposal_id));
+			assert!(Approve::is_approved(proposal_id));
+			assert!(Approve::call_of(proposal_id).is_none());
+		});
+	}
+
+	/// Weighted approvers test objectives:
+	/// * Only root can change the weight of an approver, and only to a positive weight
+	/// * Votes are tallied by weight, and removing an approver drops its custom weight
+	/// * With threshold_percent set, the required weight follows the total approver weight
+	#[test]
+	fn weighted_approvers_should_work() {
+		with_externalities(&mut build_ext(), || {
+			setup_approvers();
+			assert!(Approve::set_approver_weight(Origin::signed(2), 2, 4).is_err());
+			assert_noop!(Approve::set_approver_weight(Origin::ROOT, 1, 4), "Account is not an approver");
+			assert_noop!(Approve::set_approver_weight(Origin::ROOT, 2, 0), "Weight must be greater than zero");
+			assert_ok!(Approve::set_approver_weight(Origin::ROOT, 2, 4));
+			assert_eq!(Approve::approver_weight(&2), 4);
+			assert_eq!(Approve::total_weight(), 7);
+
+			// 60% of 7 rounds up to 5
+			<ThresholdPercent<ApproveTest>>::put(60);
+			assert_eq!(Approve::required_weight(), Ok(5));
+
+			assert_ok!(Approve::create_proposal(Origin::signed(1), H256::repeat_byte(1)));
+			let proposal_id = Approve::proposal_by_index(0);
+			assert_ok!(Approve::approve(Origin::signed(2), proposal_id));
+			assert_eq!(Approve::proposal(proposal_id).approvals, 4);
+			assert!(!Approve::is_approved(proposal_id));
+			assert_ok!(Approve::approve(Origin::signed(3), proposal_id));
+			assert!(Approve::is_approved(proposal_id));
+
+			assert_ok!(Approve::remove_approver(Origin::ROOT, 2));
+			assert_eq!(Approve::weight_of(2), None);
+			assert_eq!(Approve::total_weight(), 2);
+		});
+	}
+
+	/// Delegation test objectives:
+	/// * Only an approver can delegate, and delegation chains are refused
+	/// * A delegated vote is recorded and tallied as the principal's vote
+	/// * The principal can not vote directly while delegated, and the delegate loses the vote on revoke
+	#[test]
+	fn delegation_should_work() {
+		with_externalities(&mut build_ext(), || {
+			setup_approvers();
+			assert_noop!(Approve::delegate_to(Origin::signed(1), 2), "Account is not an approver");
+			assert_noop!(Approve::delegate_to(Origin::signed(2), 2), "Account can not delegate to itself");
+			assert_ok!(Approve::delegate_to(Origin::signed(2), 9));
+			assert_eq!(Approve::delegate_of(2), Some(9));
+			assert_eq!(Approve::principals_of(9), vec![2]);
+			assert_noop!(Approve::delegate_to(Origin::signed(3), 2), "Delegation chains are not allowed");
+			assert_noop!(Approve::delegate_to(Origin::signed(2), 3), "Account has already delegated, revoke it first");
+
+			assert_ok!(Approve::create_proposal(Origin::signed(1), H256::repeat_byte(1)));
+			let proposal_id = Approve::proposal_by_index(0);
+			assert_noop!(Approve::approve(Origin::signed(2), proposal_id), "Account has delegated its vote");
+			assert_noop!(Approve::approve_for(Origin::signed(3), 2, proposal_id), "Account is not the delegate of this approver");
+			assert_ok!(Approve::approve_for(Origin::signed(9), 2, proposal_id));
+			assert_eq!(Approve::vote_of((proposal_id, 2)), Some(true));
+			assert_eq!(Approve::vote_of((proposal_id, 9)), None);
+			assert_eq!(Approve::tally(proposal_id), (1, 0));
+
+			assert_ok!(Approve::revoke_delegation(Origin::signed(2)));
+			assert_noop!(Approve::revoke_delegation(Origin::signed(2)), "Account has not delegated its vote");
+			assert!(Approve::principals_of(9).is_empty());
+			assert_noop!(Approve::reject_for(Origin::signed(9), 2, proposal_id), "Account is not the delegate of this approver");
+		});
+	}
+
+	/// Group proposal test objectives:
+	/// * Only members of the group at creation time can vote, approvers outside the group can not
+	/// * Members added after creation can not vote, members who left lose their vote
+	/// * The required weight is fixed at creation
+	#[test]
+	fn group_proposal_should_work() {
+		with_externalities(&mut build_ext(), || {
+			setup_approvers();
+			let group_id = H256::repeat_byte(9);
+			assert_noop!(Approve::create_group_proposal(Origin::signed(1), group_id, H256::zero()), "This group does not exist");
+			set_mock_group(group_id, vec![]);
+			assert_noop!(Approve::create_group_proposal(Origin::signed(1), group_id, H256::zero()), "This group has no members");
+
+			// Two thirds of three members rounds up to 2
+			<ThresholdPercent<ApproveTest>>::put(66);
+			set_mock_group(group_id, vec![7, 8, 9]);
+			assert_ok!(Approve::create_group_proposal(Origin::signed(1), group_id, H256::zero()));
+			let proposal_id = Approve::proposal_by_index(0);
+			assert_eq!(Approve::group_of(proposal_id), Some((group_id, 2)));
+
+			let not_eligible = "Account is not an eligible member of this group";
+			assert_noop!(Approve::approve(Origin::signed(2), proposal_id), not_eligible);
+			set_mock_group(group_id, vec![7, 8, 9, 10, 11]);
+			assert_noop!(Approve::approve(Origin::signed(10), proposal_id), not_eligible);
+			assert_eq!(Approve::group_of(proposal_id), Some((group_id, 2)));
+
+			assert_ok!(Approve::approve(Origin::signed(7), proposal_id));
+			set_mock_group(group_id, vec![8, 9, 10, 11]);
+			assert_eq!(Approve::tally(proposal_id), (0, 0));
+			assert_ok!(Approve::approve(Origin::signed(8), proposal_id));
+			assert!(!Approve::is_approved(proposal_id));
+			assert_ok!(Approve::approve(Origin::signed(9), proposal_id));
+			assert!(Approve::is_approved(proposal_id));
+		});
+	}
+
+	/// Batch voting test objectives:
+	/// * A batch with any invalid or repeated proposal records no votes at all
+	/// * A valid batch records a vote on every listed proposal
+	#[test]
+	fn batch_voting_should_work() {
+		with_externalities(&mut build_ext(), || {
+			setup_approvers();
+			for i in 1..4 {
+				assert_ok!(Approve::create_proposal(Origin::signed(1), H256::repeat_byte(i)));
+			}
+			let ids: Vec<H256> = (0..3).map(|i| Approve::proposal_by_index(i)).collect();
+
+			assert_noop!(Approve::approve_many(Origin::signed(2), vec![]), "No proposals given");
+			assert_noop!(Approve::approve_many(Origin::signed(2), vec![ids[0], ids[0]]), "Proposal is listed more than once");
+			assert_ok!(Approve::reject(Origin::signed(2), ids[2]));
+			assert_noop!(Approve::approve_many(Origin::signed(2), ids.clone()), "Account has already voted on this proposal");
+			assert_eq!(Approve::vote_of((ids[0], 2)), None);
+
+			assert_ok!(Approve::approve_many(Origin::signed(2), vec![ids[0], ids[1]]));
+			assert_ok!(Approve::approve_many(Origin::signed(3), vec![ids[0], ids[1]]));
+			assert!(Approve::is_approved(ids[0]));
+			assert!(Approve::is_approved(ids[1]));
+
+			assert_ok!(Approve::reject_many(Origin::signed(3), vec![ids[2]]));
+			assert_eq!(Approve::proposal(ids[2]).status, ProposalStatus::Rejected);
+		});
+	}
+
+	/// Audit log test objectives:
+	/// * Every vote is logged with the voter, the decision and the block timestamp
+	/// * Delegated votes are logged for the principal
+	#[test]
+	fn audit_log_should_work() {
+		with_externalities(&mut build_ext(), || {
+			setup_approvers();
+			assert_ok!(Approve::delegate_to(Origin::signed(3), 9));
+			assert_ok!(Approve::create_proposal(Origin::signed(1), H256::repeat_byte(1)));
+			let proposal_id = Approve::proposal_by_index(0);
+
+			<timestamp::Module<ApproveTest>>::set_timestamp(42);
+			assert_ok!(Approve::reject(Origin::signed(2), proposal_id));
+			<timestamp::Module<ApproveTest>>::set_timestamp(50);
+			assert_ok!(Approve::approve_for(Origin::signed(9), 3, proposal_id));
+
+			assert_eq!(Approve::audit_log(proposal_id), vec![
+				AuditEntry { who: 2, vote: Some(false), time: 42 },
+				AuditEntry { who: 3, vote: Some(true), time: 50 },
+			]);
+		});
+	}
+
+	/// Vote change test objectives:
+	/// * Only an existing vote can be changed, and only to a different value
+	/// * Changed and withdrawn votes are tallied once, and a change can finalize the proposal
+	/// * Votes can not be changed after the proposal is finalized
+	#[test]
+	fn change_vote_should_work() {
+		with_externalities(&mut build_ext(), || {
+			setup_approvers();
+			assert_ok!(Approve::create_proposal(Origin::signed(1), H256::repeat_byte(1)));
+			let proposal_id = Approve::proposal_by_index(0);
+
+			assert_noop!(Approve::change_vote(Origin::signed(2), proposal_id, true), "Account has not voted on this proposal");
+			assert_ok!(Approve::reject(Origin::signed(2), proposal_id));
+			assert_noop!(Approve::change_vote(Origin::signed(2), proposal_id, false), "This is already the vote of the account");
+			assert_ok!(Approve::change_vote(Origin::signed(2), proposal_id, true));
+			assert_eq!(Approve::tally(proposal_id), (1, 0));
+
+			assert_ok!(Approve::withdraw_vote(Origin::signed(2), proposal_id));
+			assert_eq!(Approve::tally(proposal_id), (0, 0));
+			assert!(Approve::voters_of(proposal_id).is_empty());
+
+			assert_ok!(Approve::reject(Origin::signed(2), proposal_id));
+			assert_ok!(Approve::approve(Origin::signed(3), proposal_id));
+			assert_ok!(Approve::change_vote(Origin::signed(2), proposal_id, true));
+			assert!(Approve::is_approved(proposal_id));
+			assert_noop!(Approve::withdraw_vote(Origin::signed(3), proposal_id), "This proposal is no longer pending");
+			assert_eq!(Approve::audit_log(proposal_id).len(), 6);
+		});
+	}
+
+	/// Metadata test objectives:
+	/// * Metadata is stored with the proposal and can be read back
+	/// * A description CID longer than MAX_CID_LENGTH is refused
+	#[test]
+	fn proposal_metadata_should_work() {
+		with_externalities(&mut build_ext(), || {
+			let cid = b"QmYwAPJzv5CZsnA625s3Xf2nemtYgPpHdWEz79ojWnPbdG".to_vec();
+			assert_noop!(Approve::create_proposal_with_metadata(Origin::signed(1), H256::zero(), H256::zero(),
+				vec![0u8; MAX_CID_LENGTH + 1], 1), "Description CID is too long");
+
+			assert_ok!(Approve::create_proposal_with_metadata(Origin::signed(1), H256::zero(), H256::repeat_byte(3),
+				cid.clone(), 7));
+			let proposal_id = Approve::proposal_by_index(0);
+			assert_eq!(Approve::metadata_of(proposal_id), Some(ProposalMetadata {
+				title_hash: H256::repeat_byte(3),
+				description_cid: cid,
+				category: 7,
+			}));
+
+			assert_ok!(Approve::create_proposal(Origin::signed(1), H256::repeat_byte(1)));
+			assert_eq!(Approve::metadata_of(Approve::proposal_by_index(1)), None);
+		});
+	}
+
+	/// Duplicate proposal test objectives:
+	/// * Proposal ids follow from the proposer, its nonce and the payload hash
+	/// * A payload can not be proposed again while a proposal for it is pending, by anyone
+	/// * Once that proposal is finalized, the payload can be proposed again
+	#[test]
+	fn duplicate_proposal_should_fail() {
+		with_externalities(&mut build_ext(), || {
+			setup_approvers();
+			let payload = H256::repeat_byte(1);
+			assert_ok!(Approve::create_proposal(Origin::signed(1), payload));
+			let proposal_id = Approve::proposal_by_index(0);
+			assert_eq!(proposal_id, (1u64, 0u64, payload).using_encoded(BlakeTwo256::hash));
+			assert_eq!(Approve::nonce_of(1), 1);
+			assert_eq!(Approve::pending_by_payload(payload), Some(proposal_id));
+
+			let pending = "A proposal for this payload is already pending";
+			assert_noop!(Approve::create_proposal(Origin::signed(1), payload), pending);
+			assert_noop!(Approve::create_proposal(Origin::signed(7), payload), pending);
+
+			assert_ok!(Approve::reject(Origin::signed(2), proposal_id));
+			assert_ok!(Approve::reject(Origin::signed(3), proposal_id));
+			assert_eq!(Approve::pending_by_payload(payload), None);
+			assert_ok!(Approve::create_proposal(Origin::signed(1), payload));
+			assert_eq!(Approve::proposal_by_index(1), (1u64, 1u64, payload).using_encoded(BlakeTwo256::hash));
+		});
+	}
+
+	/// Proposal bond test objectives:
+	/// * Creating a proposal reserves the bond, and fails if the proposer can not pay it
+	/// * The bond is returned when the proposal is approved or rejected by vote
+	/// * The bond is slashed when the proposal expires or is vetoed as spam
+	#[test]
+	fn proposal_bond_should_work() {
+		with_externalities(&mut build_ext(), || {
+			setup_approvers();
+			<system::Module<ApproveTest>>::set_block_number(1);
+			assert_ok!(Approve::add_veto_holder(Origin::ROOT, 7));
+			assert_noop!(Approve::create_proposal(Origin::signed(2), H256::zero()), "Proposer can not pay the proposal bond");
+
+			for i in 1..5 {
+				assert_ok!(Approve::create_proposal(Origin::signed(1), H256::repeat_byte(i)));
+			}
+			let ids: Vec<H256> = (0..4).map(|i| Approve::proposal_by_index(i)).collect();
+			assert_eq!(Balances::reserved_balance(&1), 40);
+			assert_eq!(Approve::bond_of(ids[0]), Some(10));
+
+			assert_ok!(Approve::approve_many(Origin::signed(2), vec![ids[0]]));
+			assert_ok!(Approve::approve_many(Origin::signed(3), vec![ids[0]]));
+			assert_ok!(Approve::reject_many(Origin::signed(2), vec![ids[1]]));
+			assert_ok!(Approve::reject_many(Origin::signed(3), vec![ids[1]]));
+			assert_eq!(Balances::reserved_balance(&1), 20);
+			assert_eq!(Balances::free_balance(&1), 80);
+			assert_eq!(Approve::bond_of(ids[0]), None);
+
+			assert_ok!(Approve::veto(Origin::signed(7), ids[2], H256::zero(), true));
+			assert_eq!(Balances::reserved_balance(&1), 10);
+			assert_eq!(Balances::free_balance(&1), 80);
+
+			Approve::on_initialize(11);
+			assert_eq!(Approve::proposal(ids[3]).status, ProposalStatus::Expired);
+			assert_eq!(Balances::reserved_balance(&1), 0);
+			assert_eq!(Balances::free_balance(&1), 80);
+		});
+	}
+
+	/// Force resolve test objectives:
+	/// * Only root can force a proposal, and only with a justification hash
+	/// * A forced approval dispatches the Call and returns the bond
+	/// * Finalized proposals can not be forced again
+	#[test]
+	fn force_resolve_should_work() {
+		with_externalities(&mut build_ext(), || {
+			setup_approvers();
+			assert_ok!(Approve::create_call_proposal(Origin::signed(1), Box::new(MockCall::Record), ExecuteAs::Proposer));
+			let proposal_id = Approve::proposal_by_index(0);
+			assert_ok!(Approve::approve(Origin::signed(2), proposal_id));
+
+			let reason = H256::repeat_byte(5);
+			assert!(Approve::force_resolve(Origin::signed(1), proposal_id, true, reason).is_err());
+			assert_noop!(Approve::force_resolve(Origin::ROOT, proposal_id, true, H256::zero()), "A justification hash is required");
+			assert_ok!(Approve::force_resolve(Origin::ROOT, proposal_id, true, reason));
+			assert!(Approve::is_approved(proposal_id));
+			assert_eq!(Approve::proposal(proposal_id).approvals, 1);
+			assert!(runtime_io::storage(b"mock_call").is_some());
+			assert_eq!(Balances::reserved_balance(&1), 0);
+
+			assert_noop!(Approve::force_resolve(Origin::ROOT, proposal_id, false, reason), "This proposal is no longer pending");
+		});
+	}
+
+	/// Reminder test objectives:
+	/// * Only pending proposals that expire reminder_blocks from now are due
+	/// * remind() only accepts an unsigned origin, and flags each proposal once
+	/// * The transaction pool accepts an unsigned reminder only while all of its proposals are due
+	#[test]
+	fn expiry_reminder_should_work() {
+		with_externalities(&mut build_ext(), || {
+			setup_approvers();
+			<system::Module<ApproveTest>>::set_block_number(1);
+			assert_ok!(Approve::create_proposal(Origin::signed(1), H256::repeat_byte(1)));
+			assert_ok!(Approve::create_proposal(Origin::signed(1), H256::repeat_byte(2)));
+			let pending_id = Approve::proposal_by_index(0);
+			let approved_id = Approve::proposal_by_index(1);
+			assert_ok!(Approve::approve(Origin::signed(2), approved_id));
+			assert_ok!(Approve::approve(Origin::signed(3), approved_id));
+
+			// Both expire at block 11, so reminders are due at block 8
+			assert!(Approve::due_reminders(7).is_empty());
+			assert_eq!(Approve::due_reminders(8), vec![pending_id]);
+
+			<system::Module<ApproveTest>>::set_block_number(8);
+			assert_eq!(Approve::validate_remind(&[pending_id]), TransactionValidity::Valid {
+				priority: 0,
+				requires: vec![],
+				provides: vec![vec![pending_id].encode()],
+				longevity: REMINDER_LONGEVITY,
+			});
+			assert_eq!(Approve::validate_remind(&[pending_id, approved_id]), TransactionValidity::Invalid(INVALID_REMINDER));
+			assert_eq!(Approve::validate_remind(&[]), TransactionValidity::Invalid(INVALID_REMINDER));
+			assert!(Approve::remind(Origin::signed(1), vec![pending_id]).is_err());
+			assert_ok!(Approve::remind(system::RawOrigin::None.into(), vec![pending_id, approved_id]));
+			assert!(Approve::reminded(pending_id));
+			assert!(!Approve::reminded(approved_id));
+			assert!(Approve::due_reminders(8).is_empty());
+			assert_eq!(Approve::validate_remind(&[pending_id]), TransactionValidity::Invalid(INVALID_REMINDER));
+		});
+	}
+
+	/// Inbox test objectives:
+	/// * A new proposal shows up in the inbox of every approver
+	/// * Voting removes it from the inbox of the voter, withdrawing the vote puts it back
+	/// * Finalizing the proposal removes it from every inbox
+	/// * A removed approver keeps only the group proposals it can still vote on
+	#[test]
+	fn approver_inbox_should_work() {
+		with_externalities(&mut build_ext(), || {
+			setup_approvers();
+			assert_ok!(Approve::create_proposal(Origin::signed(1), H256::repeat_byte(1)));
+			assert_ok!(Approve::create_proposal(Origin::signed(1), H256::repeat_byte(2)));
+			let first = Approve::proposal_by_index(0);
+			let second = Approve::proposal_by_index(1);
+			assert_eq!(Approve::pending_for(2), vec![first, second]);
+			assert_eq!(Approve::pending_for(5), vec![first, second]);
+
+			assert_ok!(Approve::approve(Origin::signed(2), first));
+			assert_eq!(Approve::pending_for(2), vec![second]);
+			assert_ok!(Approve::withdraw_vote(Origin::signed(2), first));
+			assert_eq!(Approve::pending_for(2), vec![second, first]);
+
+			assert_ok!(Approve::approve(Origin::signed(3), first));
+			assert_ok!(Approve::approve(Origin::signed(4), first));
+			assert!(Approve::is_approved(first));
+			assert_eq!(Approve::pending_for(2), vec![second]);
+			assert_eq!(Approve::pending_for(5), vec![second]);
+
+			let group_id = H256::repeat_byte(9);
+			set_mock_group(group_id, vec![5, 7]);
+			assert_ok!(Approve::create_group_proposal(Origin::signed(1), group_id, H256::repeat_byte(3)));
+			let group_proposal = Approve::proposal_by_index(2);
+			assert_eq!(Approve::pending_for(5), vec![second, group_proposal]);
+
+			assert_ok!(Approve::remove_approver(Origin::ROOT, 5));
+			assert_eq!(Approve::pending_for(5), vec![group_proposal]);
+		});
+	}
+
+	/// Query helper test objectives:
+	/// * proposal_status() is None for unknown proposals and follows the proposal otherwise
+	/// * votes() lists every recorded vote in order
+	#[test]
+	fn query_helpers_should_work() {
+		with_externalities(&mut build_ext(), || {
+			setup_approvers();
+			assert_eq!(Approve::proposal_status(H256::zero()), None);
+			assert_ok!(Approve::create_proposal(Origin::signed(1), H256::repeat_byte(1)));
+			let proposal_id = Approve::proposal_by_index(0);
+			assert_eq!(Approve::proposal_status(proposal_id), Some(ProposalStatus::Pending));
+
+			assert_ok!(Approve::reject(Origin::signed(3), proposal_id));
+			assert_ok!(Approve::approve(Origin::signed(2), proposal_id));
+			assert_eq!(Approve::votes(proposal_id), vec![(3, false), (2, true)]);
+		});
+	}
+
+	/// Staged proposal test objectives:
+	/// * Only root can set a workflow, and stages are validated
+	/// * Only approvers of the current stage can vote, and completing a stage moves to the next one
+	/// * Votes are reset between stages, and the last stage approves the proposal
+	#[test]
+	fn staged_proposal_should_work() {
+		with_externalities(&mut build_ext(), || {
+			<system::Module<ApproveTest>>::set_block_number(1);
+			let lead = Stage { approvers: vec![2], threshold: 1 };
+			let finance = Stage { approvers: vec![2, 3, 4], threshold: 2 };
+			assert!(Approve::set_workflow(Origin::signed(1), 1, vec![lead.clone()]).is_err());
+			assert_noop!(Approve::set_workflow(Origin::ROOT, 1, vec![Stage { approvers: vec![2], threshold: 2 }]),
+				"Stage threshold exceeds its approvers");
+			assert_ok!(Approve::set_workflow(Origin::ROOT, 1, vec![lead, finance]));
+
+			assert_noop!(Approve::create_staged_proposal(Origin::signed(1), 2, H256::zero()), "This workflow does not exist");
+			assert_ok!(Approve::create_staged_proposal(Origin::signed(1), 1, H256::zero()));
+			let proposal_id = Approve::proposal_by_index(0);
+			assert_eq!(Approve::pending_for(2), vec![proposal_id]);
+			assert!(Approve::pending_for(3).is_empty());
+
+			assert_noop!(Approve::approve(Origin::signed(3), proposal_id), "Account is not an approver of the current stage");
+			<system::Module<ApproveTest>>::set_block_number(5);
+			assert_ok!(Approve::approve(Origin::signed(2), proposal_id));
+			assert_eq!(Approve::proposal(proposal_id).stage, 1);
+			assert_eq!(Approve::proposal(proposal_id).expiry, 15);
+			assert_eq!(Approve::vote_of((proposal_id, 2)), None);
+			assert_eq!(Approve::pending_for(3), vec![proposal_id]);
+
+			// The queue entry of the first stage no longer expires the proposal
+			Approve::on_initialize(11);
+			assert_eq!(Approve::proposal_status(proposal_id), Some(ProposalStatus::Pending));
+
+			assert_ok!(Approve::approve(Origin::signed(2), proposal_id));
+			assert!(!Approve::is_approved(proposal_id));
+			assert_ok!(Approve::approve(Origin::signed(4), proposal_id));
+			assert!(Approve::is_approved(proposal_id));
+			assert!(Approve::pending_for(3).is_empty());
+		});
+	}
+
+	/// Cancel test objectives:
+	/// * Only the proposer can cancel, and only a pending proposal
+	/// * A proposal with approvals can only be cancelled within cancel_grace blocks
+	/// * Cancelling returns the bond and removes the votes and index entries
+	#[test]
+	fn cancel_proposal_should_work() {
+		with_externalities(&mut build_ext(), || {
+			<system::Module<ApproveTest>>::set_block_number(1);
+			setup_approvers();
+			assert_ok!(Approve::create_proposal(Origin::signed(1), H256::zero()));
+			let proposal_id = Approve::proposal_by_index(0);
+			assert_ok!(Approve::approve(Origin::signed(2), proposal_id));
+
+			assert_noop!(Approve::cancel(Origin::signed(2), proposal_id), "Only the proposer can cancel a proposal");
+			<system::Module<ApproveTest>>::set_block_number(2);
+			assert_ok!(Approve::cancel(Origin::signed(1), proposal_id));
+			assert_eq!(Approve::proposal_status(proposal_id), Some(ProposalStatus::Cancelled));
+			assert_eq!(Balances::reserved_balance(&1), 0);
+			assert_eq!(Approve::vote_of((proposal_id, 2)), None);
+			assert!(Approve::voters_of(proposal_id).is_empty());
+			assert!(Approve::pending_for(3).is_empty());
+			assert_noop!(Approve::cancel(Origin::signed(1), proposal_id), "This proposal is no longer pending");
+
+			// The payload can be proposed again, but once the grace period passed an approval blocks cancelling
+			assert_ok!(Approve::create_proposal(Origin::signed(1), H256::zero()));
+			let proposal_id = Approve::proposal_by_index(1);
+			<system::Module<ApproveTest>>::set_block_number(4);
+			assert_ok!(Approve::approve(Origin::signed(2), proposal_id));
+			assert_noop!(Approve::cancel(Origin::signed(1), proposal_id),
+				"This proposal already has approvals and can no longer be cancelled");
+			assert_ok!(Approve::withdraw_vote(Origin::signed(2), proposal_id));
+			assert_ok!(Approve::cancel(Origin::signed(1), proposal_id));
+		});
+	}
+
+	/// Percentage quorum test objectives:
+	/// * The required weight is the percentage of the set rounded up, and at least 1
+	/// * A percentage above 100 is refused
+	/// * For a group proposal the quorum is recomputed from the members that can still vote
+	#[test]
+	fn percentage_quorum_should_work() {
+		with_externalities(&mut build_ext(), || {
+			<ThresholdPercent<ApproveTest>>::put(50);
+			assert_eq!(Approve::required_for(4), Ok(2));
+			assert_eq!(Approve::required_for(5), Ok(3));
+			assert_eq!(Approve::required_for(0), Ok(1));
+			<ThresholdPercent<ApproveTest>>::put(66);
+			assert_eq!(Approve::required_for(3), Ok(2));
+			<ThresholdPercent<ApproveTest>>::put(67);
+			assert_eq!(Approve::required_for(3), Ok(3));
+			<ThresholdPercent<ApproveTest>>::put(1);
+			assert_eq!(Approve::required_for(10), Ok(1));
+			<ThresholdPercent<ApproveTest>>::put(100);
+			assert_eq!(Approve::required_for(7), Ok(7));
+			<ThresholdPercent<ApproveTest>>::put(101);
+			assert_eq!(Approve::required_for(7), Err("Config threshold_percent must be at most 100"));
+
+			// 60% of 5 members is 3. Once two members leave, 60% of 3 is 2.
+			<ThresholdPercent<ApproveTest>>::put(60);
+			let group_id = H256::repeat_byte(9);
+			set_mock_group(group_id, vec![2, 3, 4, 5, 6]);
+			assert_ok!(Approve::create_group_proposal(Origin::signed(1), group_id, H256::zero()));
+			let proposal_id = Approve::proposal_by_index(0);
+			assert_eq!(Approve::group_of(proposal_id), Some((group_id, 3)));
+			assert_ok!(Approve::approve(Origin::signed(2), proposal_id));
+
+			set_mock_group(group_id, vec![2, 3, 4]);
+			assert_eq!(Approve::eligible_members(proposal_id), vec![2, 3, 4]);
+			assert_ok!(Approve::approve(Origin::signed(3), proposal_id));
+			assert!(Approve::is_approved(proposal_id));
+		});
+	}
+
+	/// Active proposal index test objectives:
+	/// * New proposals are added to the active index
+	/// * Finalized proposals are removed and the last entry takes their slot
+	/// * Pages stop at the end of the index
+	#[test]
+	fn active_proposal_index_should_work() {
+		with_externalities(&mut build_ext(), || {
+			setup_approvers();
+			for i in 1..4 {
+				assert_ok!(Approve::create_proposal(Origin::signed(1), H256::repeat_byte(i)));
+			}
+			let first = Approve::proposal_by_index(0);
+			let second = Approve::proposal_by_index(1);
+			let third = Approve::proposal_by_index(2);
+			assert_eq!(Approve::active_proposals_count(), 3);
+			assert_eq!(Approve::active_proposals(0, 2), vec![first, second]);
+			assert_eq!(Approve::active_proposals(2, 2), vec![third]);
+			assert!(Approve::active_proposals(5, 2).is_empty());
+
+			assert_ok!(Approve::reject(Origin::signed(2), first));
+			assert_ok!(Approve::reject(Origin::signed(3), first));
+			assert_eq!(Approve::active_proposals_count(), 2);
+			assert_eq!(Approve::active_proposals(0, 10), vec![third, second]);
+			assert_eq!(Approve::active_proposal_index(third), 0);
+			assert!(!<ActiveProposalsIndex<ApproveTest>>::exists(first));
+
+			assert_ok!(Approve::cancel(Origin::signed(1), second));
+			assert_eq!(Approve::active_proposals(0, 10), vec![third]);
+		});
+	}
+
+	/// Proposer reputation test objectives:
+	/// * Approved, rejected and expired proposals are counted per proposer, cancelled ones are not
+	/// * A proposer is suspended once its failure ratio exceeds max_failure_percent
+	/// * Only root can reinstate a suspended proposer, which clears its record
+	#[test]
+	fn proposer_reputation_should_work() {
+		with_externalities(&mut build_ext(), || {
+			<system::Module<ApproveTest>>::set_block_number(1);
+			<MaxFailurePercent<ApproveTest>>::put(50);
+			setup_approvers();
+
+			assert_ok!(Approve::create_proposal(Origin::signed(1), H256::repeat_byte(1)));
+			let first = Approve::proposal_by_index(0);
+			assert_ok!(Approve::approve(Origin::signed(2), first));
+			assert_ok!(Approve::approve(Origin::signed(3), first));
+			assert_eq!(Approve::record_of(1), (1, 0));
+
+			assert_ok!(Approve::create_proposal(Origin::signed(1), H256::repeat_byte(2)));
+			assert_ok!(Approve::cancel(Origin::signed(1), Approve::proposal_by_index(1)));
+			assert_eq!(Approve::record_of(1), (1, 0));
+
+			// One failure out of two is not more than 50%
+			assert_ok!(Approve::create_proposal(Origin::signed(1), H256::repeat_byte(3)));
+			let third = Approve::proposal_by_index(2);
+			assert_ok!(Approve::reject(Origin::signed(2), third));
+			assert_ok!(Approve::reject(Origin::signed(3), third));
+			assert_eq!(Approve::record_of(1), (1, 1));
+			assert!(!Approve::is_suspended(1));
+
+			// The expiry brings it to two out of three
+			assert_ok!(Approve::create_proposal(Origin::signed(1), H256::repeat_byte(4)));
+			Approve::on_initialize(11);
+			assert_eq!(Approve::record_of(1), (1, 2));
+			assert!(Approve::is_suspended(1));
+			assert_noop!(Approve::create_proposal(Origin::signed(1), H256::repeat_byte(5)),
+				"Account is suspended from creating proposals");
+
+			assert!(Approve::reinstate(Origin::signed(2), 1).is_err());
+			assert_ok!(Approve::reinstate(Origin::ROOT, 1));
+			assert_noop!(Approve::reinstate(Origin::ROOT, 1), "Account is not suspended");
+			assert_eq!(Approve::record_of(1), (0, 0));
+			assert_ok!(Approve::create_proposal(Origin::signed(1), H256::repeat_byte(5)));
+		});
+	}
+
+	/// Freeze test objectives:
+	/// * Only a council motion can freeze the approval system, and only root can unfreeze it
+	/// * While frozen, proposals can not be created, voted on or cancelled, and they do not expire
+	/// * Approved proposals are not reported as approved to other modules while frozen
+	#[test]
+	fn freeze_should_halt_approvals() {
+		with_externalities(&mut build_ext(), || {
+			<system::Module<ApproveTest>>::set_block_number(1);
+			setup_approvers();
+			assert_ok!(Approve::create_proposal(Origin::signed(1), H256::repeat_byte(1)));
+			let approved = Approve::proposal_by_index(0);
+			assert_ok!(Approve::approve(Origin::signed(2), approved));
+			assert_ok!(Approve::approve(Origin::signed(3), approved));
+			assert_ok!(Approve::create_proposal(Origin::signed(1), H256::repeat_byte(2)));
+			let pending = Approve::proposal_by_index(1);
+
+			let council = Origin::from(super::Origin::Approved(2));
+			assert!(Approve::freeze(Origin::signed(2), H256::zero()).is_err());
+			assert_noop!(Approve::freeze(Origin::ROOT, H256::zero()), "bad origin: expected to be an approved call");
+			assert_noop!(Approve::unfreeze(Origin::ROOT), "The approval system is not frozen");
+			assert_ok!(Approve::freeze(council.clone(), H256::zero()));
+			assert_noop!(Approve::freeze(council.clone(), H256::zero()), "The approval system is already frozen");
+			assert!(Approve::unfreeze(council).is_err());
+
+			assert_noop!(Approve::create_proposal(Origin::signed(1), H256::repeat_byte(3)), "The approval system is frozen");
+			assert_noop!(Approve::approve(Origin::signed(2), pending), "The approval system is frozen");
+			assert_noop!(Approve::cancel(Origin::signed(1), pending), "The approval system is frozen");
+			assert!(Approve::is_approved(approved));
+			assert!(!<Module<ApproveTest> as ApprovalProvider<u64, H256>>::is_approved(approved));
+
+			Approve::on_initialize(11);
+			assert_eq!(Approve::proposal_status(pending), Some(ProposalStatus::Pending));
+			assert_eq!(Approve::expiring_at(11), vec![approved, pending]);
+
+			assert_ok!(Approve::unfreeze(Origin::ROOT));
+			Approve::on_initialize(12);
+			assert_eq!(Approve::proposal_status(pending), Some(ProposalStatus::Expired));
+		});
+	}
+
+	/// Genesis approvers test objectives:
+	/// * The initial approvers and their weights are set at genesis
+	/// * A proposal can be approved by them without any setup
+	#[test]
+	fn genesis_approvers_should_work() {
+		with_externalities(&mut build_ext_with_approvers(vec![(2, 1), (3, 2)]), || {
+			assert_eq!(Approve::approvers(), vec![2, 3]);
+			assert_eq!(Approve::weight_of(2), None);
+			assert_eq!(Approve::approver_weight(&3), 2);
+
+			assert_ok!(Approve::create_proposal(Origin::signed(1), H256::zero()));
+			let proposal_id = Approve::proposal_by_index(0);
+			assert_ok!(Approve::approve(Origin::signed(3), proposal_id));
+			assert!(Approve::is_approved(proposal_id));
+		});
+	}
 }
@@ -0,0 +1,102 @@
+/// Benchmarks of the calls whose storage work grows with their input or that write the most. The node charges a
+/// fixed base fee plus a fee per byte, and decl_module of this Substrate version has no weight annotations, so
+/// these are used to size the limits that bound the work of a call (max_group_size, MAX_BATCH_VOTES) and to
+/// check the "Weight:" notes on the calls. Run them on nightly with:
+/// `cargo +nightly bench -p pool-runtime --features bench`
+
+use test::Bencher;
+use runtime_io::with_externalities;
+use primitives::Blake2Hasher;
+use runtime_primitives::BuildStorage;
+use crate::{AccountId, Hash, Origin, Runtime, Groups, Substratekitties, Approve, approve, groups};
+
+fn account(n: u8) -> AccountId {
+	AccountId::from_raw([n; 32])
+}
+
+// Limits are raised where the benchmarks would otherwise hit them after enough iterations
+fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+	let mut t = system::GenesisConfig::<Runtime>::default().build_storage().unwrap().0;
+	t.extend(groups::GenesisConfig::<Runtime> {
+		max_group_size: 12,
+		max_groups_per_owner: u64::max_value(),
+		max_name_size: 40,
+		initial_groups: vec![],
+	}.build_storage().unwrap().0);
+	t.extend(approve::GenesisConfig::<Runtime> {
+		threshold: 2,
+		threshold_percent: 0,
+		proposal_lifetime: 8640,
+		reminder_blocks: 360,
+		proposal_bond: 0,
+		cancel_grace: 30,
+		max_failure_percent: 75,
+		min_recorded_proposals: 4,
+		initial_approvers: vec![(account(1), 1), (account(2), 1)],
+	}.build_storage().unwrap().0);
+	t.into()
+}
+
+#[bench]
+fn create_group(b: &mut Bencher) {
+	with_externalities(&mut new_test_ext(), || {
+		b.iter(|| Groups::create_group(Origin::signed(account(1)), b"Benchmark".to_vec(), 12).unwrap());
+	});
+}
+
+// Creating and filling the group is part of every iteration, compare with create_group and add_member
+#[bench]
+fn remove_full_group(b: &mut Bencher) {
+	with_externalities(&mut new_test_ext(), || {
+		b.iter(|| {
+			Groups::create_group(Origin::signed(account(1)), b"Benchmark".to_vec(), 12).unwrap();
+			let group_id = Groups::owned_group_by_index(account(1), 0);
+			for n in 0..12 {
+				Groups::owner_add_member(Origin::signed(account(1)), group_id, account(100 + n)).unwrap();
+			}
+			Groups::owner_remove_group(Origin::signed(account(1)), group_id).unwrap();
+		});
+	});
+}
+
+#[bench]
+fn add_member(b: &mut Bencher) {
+	with_externalities(&mut new_test_ext(), || {
+		Groups::create_group(Origin::signed(account(1)), b"Benchmark".to_vec(), 12).unwrap();
+		let group_id = Groups::owned_group_by_index(account(1), 0);
+		b.iter(|| {
+			Groups::join_group(Origin::signed(account(2)), group_id).unwrap();
+			Groups::leave_group(Origin::signed(account(2)), group_id).unwrap();
+		});
+	});
+}
+
+#[bench]
+fn breed_kitty(b: &mut Bencher) {
+	with_externalities(&mut new_test_ext(), || {
+		Substratekitties::create_kitty(Origin::signed(account(1))).unwrap();
+		Substratekitties::create_kitty(Origin::signed(account(1))).unwrap();
+		let kitty_1 = Substratekitties::kitty_id(0);
+		let kitty_2 = Substratekitties::kitty_id(1);
+		b.iter(|| Substratekitties::breed_kitty(Origin::signed(account(1)), kitty_1, kitty_2).unwrap());
+	});
+}
+
+// Creating the proposals is part of every iteration. With a threshold of 2 they stay pending after one vote.
+#[bench]
+fn approve_many(b: &mut Bencher) {
+	with_externalities(&mut new_test_ext(), || {
+		let mut payload = 0u64;
+		b.iter(|| {
+			let mut proposal_ids = Vec::with_capacity(approve::MAX_BATCH_VOTES);
+			for _ in 0..approve::MAX_BATCH_VOTES {
+				payload += 1;
+				let mut bytes = [0u8; 32];
+				bytes[..8].copy_from_slice(&payload.to_le_bytes());
+				Approve::create_proposal(Origin::signed(account(3)), Hash::from(bytes)).unwrap();
+				proposal_ids.push(Approve::pending_by_payload(Hash::from(bytes)).unwrap());
+			}
+			Approve::approve_many(Origin::signed(account(1)), proposal_ids).unwrap();
+		});
+	});
+}
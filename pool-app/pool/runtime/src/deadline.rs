@@ -0,0 +1,240 @@
+/// Deadline is the queue shared by the modules that act on items in a given block, such as the timed groups that
+/// end, the proposals that expire and the refund requests that lapse. The queue holds the ids due in each block in
+/// pages of at most BUDGET ids, and the module takes the due ids in its on_initialize(n).
+///
+/// Every module has a budget of ids per block. A cursor keeps the next page to take, so the ids over the budget
+/// stay where they are and are taken in the next blocks, ahead of the ids of later blocks. Nothing is moved or
+/// rewritten, so each on_initialize() reads and removes at most BUDGET ids and looks at most BUDGET blocks ahead,
+/// however long the backlog is. While a module is frozen the cursor simply stays put.
+///
+/// Like the ownership registry, this keeps no storage of its own. A module declares the pages, the page counts
+/// and the cursor in its decl_storage and names them in a DeadlineStorage impl. The first page of a block is the
+/// map that held all ids of the block before the queue was paged, so ids scheduled before are still taken.
+///
+/// The queue does not remove ids that were scheduled for an item that changed since. The module checks that
+/// each id it takes is still due.
+
+use rstd::marker::PhantomData;
+use rstd::prelude::*;
+use parity_codec::Codec;
+use runtime_primitives::traits::{One, SimpleArithmetic};
+use support::{StorageMap, StorageValue};
+
+/// The queue of a module, declared by the module
+pub trait DeadlineStorage {
+	/// The block number of the runtime
+	type BlockNumber: Codec + SimpleArithmetic + Copy;
+	/// The id of an item in the queue
+	type Id: Codec;
+
+	/// The first page of the ids due in each block
+	type Queue: StorageMap<Self::BlockNumber, Vec<Self::Id>, Query = Vec<Self::Id>>;
+	/// The further pages of the ids due in each block, by (block, page) from page 1
+	type Pages: StorageMap<(Self::BlockNumber, u32), Vec<Self::Id>, Query = Vec<Self::Id>>;
+	/// The number of further pages of each block
+	type PageCount: StorageMap<Self::BlockNumber, u32, Query = u32>;
+	/// The next page to take as (block, page), None before the queue is first taken from or held
+	type Cursor: StorageValue<(Self::BlockNumber, u32), Query = Option<(Self::BlockNumber, u32)>>;
+
+	/// The most ids taken from the queue per block, which is also the size of a page
+	const BUDGET: usize;
+}
+
+/// The reads and writes of the queue kept in the storage items of S
+pub struct Deadlines<S>(PhantomData<S>);
+
+impl<S: DeadlineStorage> Deadlines<S> {
+	/// Adds the id to the last page of block n, or to a new page once that one is full
+	pub fn schedule(n: S::BlockNumber, id: S::Id) {
+		let last = S::PageCount::get(&n);
+		let mut ids = Self::page(n, last);
+		if ids.len() < S::BUDGET {
+			ids.push(id);
+			Self::set_page(n, last, ids);
+		} else {
+			S::PageCount::insert(&n, last + 1);
+			Self::set_page(n, last + 1, vec![id]);
+		}
+	}
+
+	/// Takes the ids due up to block n, at most BUDGET of them, starting at the cursor. The ids of a block that
+	/// do not fit are taken in the next blocks, ahead of the ids due in those.
+	pub fn take_due(n: S::BlockNumber) -> Vec<S::Id> {
+		let (mut block, mut page) = S::Cursor::get().unwrap_or((n, 0));
+		let mut due = Vec::new();
+		let mut blocks = 0;
+		while block <= n && blocks < S::BUDGET {
+			if page > S::PageCount::get(&block) {
+				S::PageCount::remove(&block);
+				block = block + One::one();
+				page = 0;
+				blocks += 1;
+				continue;
+			}
+			let ids = Self::page(block, page);
+			// A page of ids scheduled before the queue was paged can hold more than BUDGET, it is taken alone
+			if !due.is_empty() && due.len() + ids.len() > S::BUDGET {
+				break;
+			}
+			if !ids.is_empty() {
+				Self::set_page(block, page, Vec::new());
+				due.extend(ids);
+			}
+			page += 1;
+		}
+		S::Cursor::put((block, page));
+		due
+	}
+
+	/// Takes nothing in block n, such as while a module is frozen. The ids due stay in the queue and are taken
+	/// from the next take_due() on.
+	pub fn hold(n: S::BlockNumber) {
+		if S::Cursor::get().is_none() {
+			S::Cursor::put((n, 0));
+		}
+	}
+
+	/// The ids due in block n that were not taken yet, in the order they are taken. Reads every page of the
+	/// block, so it is meant for queries and the off-chain worker, not for on_initialize().
+	pub fn due_at(n: S::BlockNumber) -> Vec<S::Id> {
+		(0..=S::PageCount::get(&n)).flat_map(|page| Self::page(n, page)).collect()
+	}
+
+	// Private method called by: schedule(), take_due() and due_at(). Page 0 is the Queue map.
+	fn page(n: S::BlockNumber, page: u32) -> Vec<S::Id> {
+		if page == 0 { S::Queue::get(&n) } else { S::Pages::get(&(n, page)) }
+	}
+
+	// Private method called by: schedule() and take_due(). An empty page is removed.
+	fn set_page(n: S::BlockNumber, page: u32, ids: Vec<S::Id>) {
+		match (page, ids.is_empty()) {
+			(0, true) => S::Queue::remove(&n),
+			(0, false) => S::Queue::insert(&n, &ids),
+			(_, true) => S::Pages::remove(&(n, page)),
+			(_, false) => S::Pages::insert(&(n, page), &ids),
+		}
+	}
+}
+
+// *****************************************************************************************************
+// Unit Tests!
+// *****************************************************************************************************
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use runtime_io::{with_externalities, TestExternalities};
+	use primitives::{H256, Blake2Hasher};
+	use support::{decl_storage, impl_outer_origin};
+	use runtime_primitives::{
+		BuildStorage,
+		traits::{BlakeTwo256, IdentityLookup},
+		testing::{Digest, DigestItem, Header}
+	};
+
+	impl_outer_origin! {
+		pub enum Origin for DeadlineTest {}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct DeadlineTest;
+	impl system::Trait for DeadlineTest {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type Digest = Digest;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type Log = DigestItem;
+	}
+
+	pub trait Trait: system::Trait {}
+	impl Trait for DeadlineTest {}
+
+	// A queue of u32 ids, declared the way a module declares its own
+	pub struct Module<T>(PhantomData<T>);
+
+	decl_storage! {
+		trait Store for Module<T: Trait> as DeadlineTest {
+			DueAt: map T::BlockNumber => Vec<u32>;
+			DuePages: map (T::BlockNumber, u32) => Vec<u32>;
+			DuePageCount: map T::BlockNumber => u32;
+			DueCursor: Option<(T::BlockNumber, u32)>;
+		}
+	}
+
+	struct Queue;
+	impl DeadlineStorage for Queue {
+		type BlockNumber = u64;
+		type Id = u32;
+		type Queue = DueAt<DeadlineTest>;
+		type Pages = DuePages<DeadlineTest>;
+		type PageCount = DuePageCount<DeadlineTest>;
+		type Cursor = DueCursor<DeadlineTest>;
+		const BUDGET: usize = 3;
+	}
+	type Deadlines = super::Deadlines<Queue>;
+
+	fn build_ext() -> TestExternalities<Blake2Hasher> {
+		system::GenesisConfig::<DeadlineTest>::default().build_storage().unwrap().0.into()
+	}
+
+	/// Deadline queue test objectives:
+	/// * Ids are kept in pages of at most BUDGET ids per block
+	/// * At most BUDGET ids are taken per block, in the order they were scheduled
+	/// * The rest are taken in the next block, ahead of the ids scheduled for it, without being moved
+	/// * Holding a block leaves the queue as it is
+	#[test]
+	fn deadlines_should_keep_to_the_budget() {
+		with_externalities(&mut build_ext(), || {
+			for id in 1..6 {
+				Deadlines::schedule(2, id);
+			}
+			Deadlines::schedule(3, 6);
+			assert_eq!(<DueAt<DeadlineTest>>::get(2), vec![1, 2, 3]);
+			assert_eq!(<DuePages<DeadlineTest>>::get((2, 1)), vec![4, 5]);
+			assert_eq!(Deadlines::due_at(2), vec![1, 2, 3, 4, 5]);
+
+			assert_eq!(Deadlines::take_due(1), Vec::<u32>::new());
+			assert_eq!(Deadlines::take_due(2), vec![1, 2, 3]);
+			assert_eq!(Deadlines::due_at(2), vec![4, 5]);
+			assert_eq!(<DueAt<DeadlineTest>>::get(3), vec![6]);
+
+			Deadlines::schedule(4, 7);
+			Deadlines::hold(3);
+			assert_eq!(Deadlines::due_at(2), vec![4, 5]);
+			assert_eq!(Deadlines::take_due(4), vec![4, 5, 6]);
+			assert_eq!(Deadlines::take_due(5), vec![7]);
+			for n in 2..6 {
+				assert!(Deadlines::due_at(n).is_empty());
+				assert!(!<DuePageCount<DeadlineTest>>::exists(n));
+			}
+			assert!(!<DuePages<DeadlineTest>>::exists((2, 1)));
+		});
+	}
+
+	/// Deadline backlog test objectives:
+	/// * A block looks at most BUDGET blocks ahead of the cursor, so a long hold is caught up over several blocks
+	/// * A page written before the queue was paged is taken whole
+	#[test]
+	fn deadlines_should_catch_up_in_bounded_steps() {
+		with_externalities(&mut build_ext(), || {
+			<DueAt<DeadlineTest>>::insert(1, vec![1, 2, 3, 4]);
+			Deadlines::schedule(9, 5);
+			for n in 1..8 {
+				Deadlines::hold(n);
+			}
+			assert_eq!(Deadlines::take_due(8), vec![1, 2, 3, 4]);
+			assert_eq!(<DueCursor<DeadlineTest>>::get(), Some((2, 0)));
+			assert_eq!(Deadlines::take_due(9), Vec::<u32>::new());
+			assert_eq!(<DueCursor<DeadlineTest>>::get(), Some((5, 0)));
+			assert_eq!(Deadlines::take_due(10), Vec::<u32>::new());
+			assert_eq!(Deadlines::take_due(11), vec![5]);
+		});
+	}
+}
@@ -0,0 +1,398 @@
+/// Fees is a small module that charges the app calls which add state, such as creating a kitty or joining a
+/// group, a fee on top of the transaction fee. At this Substrate version every extrinsic pays the same base and
+/// byte fee, so without it spamming these calls costs no more than a transfer. Calls that only change or remove
+/// state are free.
+///
+/// The fee of each call is set in the genesis config and changed by a council motion. Fees are handed to the
+/// Treasury, which is the treasury module in the runtime.
+///
+/// The app calls are also rate limited per account, so they can not be spammed while their fee is low or zero.
+/// Each account makes at most call_limit() calls of a kind in a window of rate_window blocks. The limit is enforced
+/// when the call is dispatched, and the runtime checks it again in validate_transaction(), so the transaction pool
+/// rejects a call over the limit before it reaches a block. This Substrate version has no SignedExtension, so that
+/// check lives in the TaggedTransactionQueue API of the runtime.
+
+use parity_codec::Encode;
+use support::{decl_module, decl_storage, decl_event, ensure, dispatch::Result, StorageMap, StorageValue};
+use support::traits::{Currency, ExistenceRequirement, OnUnbalanced, WithdrawReason};
+use runtime_primitives::traits::{As, Zero};
+use rstd::prelude::*;
+use rstd::result;
+use app_primitives::{FeeCall, FeeCharger, GovernanceOrigin};
+
+/// The code of the Invalid transaction validity of a call over its rate limit
+pub const RATE_LIMITED: i8 = 64;
+
+/// The balance type of the currency that fees are paid in
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+type NegativeImbalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::NegativeImbalance;
+
+pub trait Trait: system::Trait {
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	/// The currency that fees are paid in
+	type Currency: Currency<Self::AccountId>;
+
+	/// Receives the fees. In the runtime this is the treasury module, () burns them.
+	type Treasury: OnUnbalanced<NegativeImbalanceOf<Self>>;
+
+	/// Checks the origin of the config calls. In the runtime this is the Council of the approve module.
+	type Council: GovernanceOrigin<Self::Origin>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Fees {
+		// The fee of each app call. Calls without an entry are free.
+		CallFee get(call_fee) config(call_fees): map FeeCall => BalanceOf<T>;
+
+		// Each account makes at most call_limit calls of each app call in a window of rate_window blocks. Calls
+		// without a limit are not rate limited, and a rate_window of 0 limits none.
+		CallLimit get(call_limit) config(call_limits): map FeeCall => u32;
+		RateWindow get(rate_window) config(): T::BlockNumber;
+		// The start of the current window of each account for each app call, and the calls made in it
+		CallWindow get(call_window): map (T::AccountId, FeeCall) => (T::BlockNumber, u32);
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where
+		<T as system::Trait>::AccountId,
+		<T as system::Trait>::BlockNumber,
+		Balance = BalanceOf<T>
+	{
+		/// An account paid the fee of an app call
+		FeeCharged(AccountId, FeeCall, Balance),
+
+		/// The fee of an app call changed
+		CallFeeChanged(FeeCall, Balance),
+
+		/// The rate limit of an app call changed to (call, calls per window), 0 for no limit
+		CallLimitChanged(FeeCall, u32),
+
+		/// The rate window changed to the given number of blocks
+		RateWindowChanged(BlockNumber),
+	}
+);
+
+decl_module! {
+	/// The module declaration.
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+
+		fn deposit_event<T>() = default;
+
+		/// Set the fee of an app call. A fee of zero makes the call free.
+		/// Rule: a council motion.
+		pub fn set_call_fee(origin, call: FeeCall, fee: BalanceOf<T>) -> Result {
+			T::Council::ensure_governance(origin)?;
+
+			if fee.is_zero() {
+				<CallFee<T>>::remove(call);
+			} else {
+				<CallFee<T>>::insert(call, fee);
+			}
+			Self::deposit_event(RawEvent::CallFeeChanged(call, fee));
+			Ok(())
+		}
+
+		/// Set the calls an account can make of an app call in each window. A limit of zero removes the limit.
+		/// Rule: a council motion.
+		pub fn set_call_limit(origin, call: FeeCall, limit: u32) -> Result {
+			T::Council::ensure_governance(origin)?;
+
+			if limit == 0 {
+				<CallLimit<T>>::remove(call);
+			} else {
+				<CallLimit<T>>::insert(call, limit);
+			}
+			Self::deposit_event(RawEvent::CallLimitChanged(call, limit));
+			Ok(())
+		}
+
+		/// Set the length of the rate limit windows in blocks. A window of zero turns the rate limits off.
+		/// Rule: a council motion.
+		pub fn set_rate_window(origin, window: T::BlockNumber) -> Result {
+			T::Council::ensure_governance(origin)?;
+
+			<RateWindow<T>>::put(window);
+			Self::deposit_event(RawEvent::RateWindowChanged(window));
+			Ok(())
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The tag that a transaction with the app call provides in the transaction pool, None if the call is not rate
+	/// limited, or an error if the account used up its limit. Used by validate_transaction() of the runtime, which
+	/// runs on the state of the best block while the call lands in a later one.
+	///
+	/// The tag is the slot the call takes in the window of the next block, as (account, call, window start, count).
+	/// Pending calls of one account are all checked against the calls already in blocks, so they would take the
+	/// same slot: the pool keeps only one of them, and the next is accepted once the first is in a block.
+	pub fn rate_tag(who: &T::AccountId, call: FeeCall) -> result::Result<Option<Vec<u8>>, &'static str> {
+		let next_block = <system::Module<T>>::block_number() + T::BlockNumber::sa(1);
+		let window = Self::next_window(who, call, next_block)?;
+		Ok(window.map(|(start, count)| (&b"fees_rate"[..], who, call, start, count).encode()))
+	}
+
+	// Private method called by: rate_tag() and charge_fee(). The window of the account after one more call, None
+	// if the call is not rate limited, or an error if the account used up the limit of the current window.
+	fn next_window(who: &T::AccountId, call: FeeCall, now: T::BlockNumber)
+		-> result::Result<Option<(T::BlockNumber, u32)>, &'static str>
+	{
+		let limit = Self::call_limit(call);
+		let window = Self::rate_window();
+		if limit == 0 || window.is_zero() {
+			return Ok(None);
+		}
+		let (start, count) = Self::call_window((who.clone(), call));
+		if start + window <= now {
+			return Ok(Some((now, 1)));
+		}
+		ensure!(count < limit, "Too many calls of this kind, wait for the next window");
+		Ok(Some((start, count + 1)))
+	}
+}
+
+impl<T: Trait> FeeCharger<T::AccountId> for Module<T> {
+	fn charge_fee(who: &T::AccountId, call: FeeCall) -> result::Result<(), &'static str> {
+		let window = Self::next_window(who, call, <system::Module<T>>::block_number())?;
+		let fee = Self::call_fee(call);
+		if !fee.is_zero() {
+			let imbalance = T::Currency::withdraw(who, fee, WithdrawReason::Fee, ExistenceRequirement::KeepAlive)
+				.map_err(|_| "Not enough balance to pay the fee of this call")?;
+			T::Treasury::on_unbalanced(imbalance);
+			Self::deposit_event(RawEvent::FeeCharged(who.clone(), call, fee));
+		}
+
+		if let Some(window) = window {
+			<CallWindow<T>>::insert((who.clone(), call), window);
+		}
+		Ok(())
+	}
+}
+
+// *****************************************************************************************************
+// Unit Tests!
+// *****************************************************************************************************
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use runtime_io::{with_externalities};
+	use primitives::{H256, Blake2Hasher};
+	use support::{impl_outer_origin, assert_ok, assert_noop};
+	use support::traits::Imbalance;
+	use runtime_primitives::{
+		BuildStorage,
+		traits::{BlakeTwo256, IdentityLookup},
+		testing::{Digest, DigestItem, Header}
+	};
+	use crate::{approve, groups, substratekitties};
+
+	impl_outer_origin! {
+		pub enum Origin for FeesTest {
+			approve
+		}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct FeesTest;
+	impl system::Trait for FeesTest {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type Digest = Digest;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type Log = DigestItem;
+	}
+	impl timestamp::Trait for FeesTest {
+		type Moment = u64;
+		type OnTimestampSet = ();
+	}
+	impl balances::Trait for FeesTest {
+		type Balance = u64;
+		type OnFreeBalanceZero = ();
+		type OnNewAccount = ();
+		type Event = ();
+		type TransactionPayment = ();
+		type TransferPayment = ();
+		type DustRemoval = ();
+	}
+	// Resolves the fees into the TREASURY account, so the tests can check what was handed over
+	pub struct MockTreasury;
+	impl OnUnbalanced<NegativeImbalanceOf<FeesTest>> for MockTreasury {
+		fn on_unbalanced(amount: NegativeImbalanceOf<FeesTest>) {
+			let _ = Balances::deposit_creating(&TREASURY, amount.peek());
+		}
+	}
+	impl groups::Trait for FeesTest {
+		type Event = ();
+		type Fees = Fees;
+		type Accounts = ();
+	}
+	impl substratekitties::Trait for FeesTest {
+		type Event = ();
+		type Fees = Fees;
+	}
+	impl Trait for FeesTest {
+		type Event = ();
+		type Currency = balances::Module<FeesTest>;
+		type Treasury = MockTreasury;
+		type Council = approve::Council;
+	}
+	type Fees = Module<FeesTest>;
+	type Balances = balances::Module<FeesTest>;
+	type Groups = groups::Module<FeesTest>;
+	type Kitties = substratekitties::Module<FeesTest>;
+
+	const TREASURY: u64 = 99;
+
+	// Accounts 1 and 2 can pay a few fees, account 3 can not pay any
+	fn build_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+		let mut t = system::GenesisConfig::<FeesTest>::default().build_storage().unwrap().0;
+		t.extend(balances::GenesisConfig::<FeesTest> {
+			balances: vec![(1, 100), (2, 100), (3, 5)],
+			transaction_base_fee: 0,
+			transaction_byte_fee: 0,
+			existential_deposit: 0,
+			transfer_fee: 0,
+			creation_fee: 0,
+			vesting: vec![],
+		}.build_storage().unwrap().0);
+		t.extend(groups::GenesisConfig::<FeesTest> {
+			max_group_size: 10,
+			max_groups_per_owner: 5,
+			max_name_size: 40,
+			initial_groups: vec![],
+		}.build_storage().unwrap().0);
+		t.extend(GenesisConfig::<FeesTest> {
+			call_fees: vec![(FeeCall::CreateKitty, 10), (FeeCall::CreateGroup, 20), (FeeCall::JoinGroup, 10)],
+			call_limits: vec![],
+			rate_window: 0,
+		}.build_storage().unwrap().0);
+		t.into()
+	}
+
+	// The origin an approved council motion is dispatched with
+	fn council() -> Origin {
+		Origin::from(approve::Origin::Approved(2))
+	}
+
+	/// Fee application test objectives:
+	/// * Calls that add state pay their genesis fee into the treasury, free calls pay nothing
+	/// * A call that can not pay its fee fails without changing storage
+	/// * A join that fails its checks is not charged, nor a kitty over the supply cap
+	#[test]
+	fn app_calls_should_pay_fees() {
+		with_externalities(&mut build_ext(), || {
+			assert_ok!(Kitties::create_kitty(Origin::signed(1)));
+			assert_eq!(Balances::free_balance(&1), 90);
+			assert_eq!(Balances::free_balance(&TREASURY), 10);
+
+			assert_ok!(Groups::create_group(Origin::signed(1), b"Table".to_vec(), 4));
+			let group_id = Groups::group_by_index(0);
+			assert_ok!(Groups::join_group(Origin::signed(2), group_id));
+			assert_noop!(Groups::join_group(Origin::signed(2), group_id), "Account is already a member of this group");
+			assert_eq!(Balances::free_balance(&1), 70);
+			assert_eq!(Balances::free_balance(&2), 90);
+			assert_eq!(Balances::free_balance(&TREASURY), 40);
+
+			// Renaming and leaving have no fee
+			assert_ok!(Groups::rename_group(Origin::signed(1), group_id, b"Bar".to_vec()));
+			assert_ok!(Groups::leave_group(Origin::signed(2), group_id));
+			assert_eq!(Balances::free_balance(&1), 70);
+			assert_eq!(Balances::free_balance(&2), 90);
+
+			assert_noop!(Kitties::create_kitty(Origin::signed(3)), "Not enough balance to pay the fee of this call");
+			assert_noop!(Groups::join_group(Origin::signed(3), group_id), "Not enough balance to pay the fee of this call");
+			assert_eq!(Kitties::num_of_kitties(), 1);
+			assert!(!Groups::is_group_member(group_id, 3));
+
+			Kitties::set_max_kitties(Some(1));
+			assert_noop!(Kitties::create_kitty(Origin::signed(1)), "Supply cap reached");
+		});
+	}
+
+	/// Fee config test objectives:
+	/// * Only a council motion can change a fee
+	/// * A zero fee makes the call free
+	#[test]
+	fn fee_config_should_work() {
+		with_externalities(&mut build_ext(), || {
+			assert!(Fees::set_call_fee(Origin::ROOT, FeeCall::CreateKitty, 0).is_err());
+
+			assert_ok!(Fees::set_call_fee(council(), FeeCall::CreateKitty, 0));
+			assert_ok!(Kitties::create_kitty(Origin::signed(3)));
+			assert_eq!(Balances::free_balance(&3), 5);
+			assert_eq!(Balances::free_balance(&TREASURY), 0);
+		});
+	}
+
+	/// Rate limit test objectives:
+	/// * An account makes at most call_limit calls of a kind per window, free calls included
+	/// * A call over the limit fails without paying its fee, and the next window allows new calls
+	/// * The tag check used by transaction validation sees the window of the next block
+	#[test]
+	fn app_calls_should_be_rate_limited() {
+		with_externalities(&mut build_ext(), || {
+			assert_ok!(Fees::set_call_limit(council(), FeeCall::CreateKitty, 2));
+			assert_ok!(Fees::set_call_fee(council(), FeeCall::CreateKitty, 0));
+			// No window means no limit
+			assert_eq!(Fees::rate_tag(&1, FeeCall::CreateKitty), Ok(None));
+			assert!(Fees::set_rate_window(Origin::signed(1), 10).is_err());
+			assert_ok!(Fees::set_rate_window(council(), 10));
+
+			<system::Module<FeesTest>>::set_block_number(1);
+			assert_ok!(Kitties::create_kitty(Origin::signed(1)));
+			assert_ok!(Kitties::create_kitty(Origin::signed(1)));
+			assert_noop!(Kitties::create_kitty(Origin::signed(1)), "Too many calls of this kind, wait for the next window");
+			assert!(Fees::rate_tag(&1, FeeCall::CreateKitty).is_err());
+			// Other accounts and other calls have limits of their own
+			assert_ok!(Kitties::create_kitty(Origin::signed(2)));
+			assert_ok!(Groups::create_group(Origin::signed(1), b"Table".to_vec(), 4));
+
+			assert_ok!(Fees::set_call_fee(council(), FeeCall::CreateKitty, 10));
+			<system::Module<FeesTest>>::set_block_number(10);
+			assert_noop!(Kitties::create_kitty(Origin::signed(1)), "Too many calls of this kind, wait for the next window");
+			assert!(Fees::rate_tag(&1, FeeCall::CreateKitty).unwrap().is_some());
+			assert_eq!(Balances::free_balance(&1), 80);
+
+			<system::Module<FeesTest>>::set_block_number(11);
+			assert_ok!(Kitties::create_kitty(Origin::signed(1)));
+			assert_eq!(Balances::free_balance(&1), 70);
+			assert_eq!(Fees::call_window((1, FeeCall::CreateKitty)), (11, 1));
+		});
+	}
+
+	/// Pending call test objectives:
+	/// * Two pending calls of one account validate to the same slot tag, so the pool keeps one of them
+	/// * Once that call is in a block, the other one takes the next slot, until the limit is used up
+	#[test]
+	fn pending_calls_should_share_a_rate_slot() {
+		with_externalities(&mut build_ext(), || {
+			assert_ok!(Fees::set_call_limit(council(), FeeCall::CreateKitty, 2));
+			assert_ok!(Fees::set_rate_window(council(), 10));
+			<system::Module<FeesTest>>::set_block_number(1);
+
+			let first = Fees::rate_tag(&1, FeeCall::CreateKitty).unwrap().unwrap();
+			let second = Fees::rate_tag(&1, FeeCall::CreateKitty).unwrap().unwrap();
+			assert_eq!(first, second);
+			assert_eq!(first, (&b"fees_rate"[..], 1u64, FeeCall::CreateKitty, 2u64, 1u32).encode());
+			assert_ne!(Fees::rate_tag(&2, FeeCall::CreateKitty).unwrap(), Some(first.clone()));
+
+			<system::Module<FeesTest>>::set_block_number(2);
+			assert_ok!(Kitties::create_kitty(Origin::signed(1)));
+			let next = Fees::rate_tag(&1, FeeCall::CreateKitty).unwrap().unwrap();
+			assert_eq!(next, (&b"fees_rate"[..], 1u64, FeeCall::CreateKitty, 2u64, 2u32).encode());
+
+			assert_ok!(Kitties::create_kitty(Origin::signed(1)));
+			assert!(Fees::rate_tag(&1, FeeCall::CreateKitty).is_err());
+		});
+	}
+}
@@ -11,11 +11,18 @@
 ///   current implementation does not check for uniqueness of the name field, which is out of scope.
 
 use parity_codec::{Encode, Decode};
-use runtime_primitives::traits::{Hash};
-use support::{decl_module, decl_storage, decl_event, ensure, dispatch::Result, StorageMap, StorageValue};
+use runtime_primitives::traits::{As, CheckedSub, Hash};
+use rstd::marker::PhantomData;
+use rstd::result;
+use support::{decl_module, decl_storage, decl_event, ensure, dispatch::Result, StorageDoubleMap, StorageMap, StorageValue};
 use system::ensure_signed;
+use app_primitives::{FeeCall, FeeCharger, GroupId, GroupInspector, GroupJoiner};
+use crate::deadline::{DeadlineStorage, Deadlines};
+use crate::ids::{self, IdKind};
+use crate::ownership::{self, OwnershipStorage};
 
-// use runtime_io::{with_storage, StorageOverlay, ChildrenStorageOverlay};
+#[cfg(feature = "std")]
+use runtime_io::{with_storage, StorageOverlay, ChildrenStorageOverlay};
 
 #[cfg(not(feature = "std"))]
 use rstd::prelude::Vec;
@@ -28,23 +35,112 @@ use std::str;
 
 pub trait Trait: system::Trait + timestamp::Trait {
 	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	/// Charges the fees of create_group() and join_group(). In the runtime this is the Fees module.
+	type Fees: FeeCharger<Self::AccountId>;
+
+	/// Lists the accounts of the chain for the migration from version 0, whose storage has no list of all groups.
+	/// In the runtime these are the accounts of the indices module.
+	type Accounts: AllAccounts<Self::AccountId>;
+}
+
+/// Lists every account that may own a group, one set at a time so that a block of the migration reads a few
+pub trait AllAccounts<AccountId> {
+	/// The accounts of set `index`, or None after the last set
+	fn account_set(index: u32) -> Option<Vec<AccountId>>;
+}
+
+/// For runtimes that never ran version 0 of the storage
+impl<AccountId> AllAccounts<AccountId> for () {
+	fn account_set(_index: u32) -> Option<Vec<AccountId>> {
+		None
+	}
 }
 
 #[derive(Encode, Decode, Default, Clone, PartialEq)]
 #[cfg_attr(feature = "std", derive(Debug))]
-pub struct Group<A, H> {
+pub struct Group<H, M> {
 	/// Hash unique random id
-    id: H,
+    pub id: H,
 	/// Arbitrary field that can be used for human-readable name or foreign key in other system.
 	/// The length of this field is limited by the max_name_size Config.
-	name: Vec<u8>,
-	/// Vec of AccountIds, where the owner is not automatically added and can just be an external actor
-	/// The size of this list is limited by the max_group_size Config.
-	members: Vec<A>,
+	pub name: Vec<u8>,
+	/// Number of members. The members themselves are kept in the MemberLinks list, where the owner is not
+	/// automatically added and can just be an external actor.
+	pub member_count: u32,
 	/// Maximum number of members in group. Note that there is no min size of group since that is
 	/// likely a business rule that can be handled in the dapp or external systems.
 	/// Example: number of players required to start a game.
-	max_size: u32,
+	pub max_size: u32,
+	/// Time of the block that created the group, 0 for groups created before version 4 of the storage
+	pub created_at: M,
+	/// Time of the block that last renamed or resized the group or changed its members
+	pub updated_at: M,
+}
+
+/// The neighbours of a member in the member list of its group, in join order
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct MemberLink<A> {
+	/// The member that joined before, None for the first member
+	pub prev: Option<A>,
+	/// The member that joined after, None for the last member
+	pub next: Option<A>,
+}
+
+/// When a timed group ends. Set by create_timed_group(), the group is archived or removed in the first block at
+/// or after end_block that has room for it, see MAX_EXPIRIES_PER_BLOCK.
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct GroupExpiry<B> {
+	/// The block in which the group expires
+	pub end_block: B,
+	/// Remove the group when it expires, instead of archiving it. An archived group stays readable, but no one
+	/// can join it.
+	pub remove: bool,
+}
+
+/// The storage layout version of this module. Chains started from genesis get this version, older chains are
+/// migrated to it in on_initialize(), one version step at a time and MAX_MIGRATED_PER_BLOCK groups per block:
+/// * 0: group members only in Group.members
+/// * 1: group members also in the GroupMembership map, so membership checks do not decode the group
+/// * 2: group members in the MemberLinks list only, so joining and leaving do not decode the member set
+/// * 3: OwnedGroupsArray is a double map keyed by owner and index, so the groups of an owner share a key prefix
+/// * 4: Group has created_at and updated_at timestamps
+pub const STORAGE_VERSION: u32 = 4;
+
+/// How many groups a block of the storage migration moves at most, or one account set while the groups of version
+/// 0 are indexed. Moving a group costs about one read and one write per member on top of 3.
+pub const MAX_MIGRATED_PER_BLOCK: u64 = 20;
+
+/// How many blocks GroupsChangedAt keeps, about 100 minutes with 10 second blocks
+pub const CHANGES_KEPT: u64 = 600;
+
+/// How many groups on_initialize() expires at most per block. The rest of the expiry queue of a block expires in the
+/// next blocks, so a burst of groups with the same end block does not make a block too heavy to import. Removing a
+/// group costs one storage read and one removal per member on top of about 10.
+pub const MAX_EXPIRIES_PER_BLOCK: usize = 20;
+
+/// The polkadot-js definitions of the types above, see the types module
+#[cfg(feature = "std")]
+pub fn type_definitions() -> Vec<crate::types::TypeDef> {
+	use crate::types::TypeDef;
+	vec![
+		TypeDef::Struct("Group", vec![("id", "Hash"), ("name", "Vec<u8>"), ("member_count", "u32"), ("max_size", "u32"), ("created_at", "Moment"), ("updated_at", "Moment")]),
+		TypeDef::Struct("MemberLink", vec![("prev", "Option<AccountId>"), ("next", "Option<AccountId>")]),
+		TypeDef::Struct("GroupExpiry", vec![("end_block", "BlockNumber"), ("remove", "bool")]),
+	]
+}
+
+/// Where the storage migration continues in the next block, see STORAGE_VERSION. Only the runtime reads it, so it
+/// has no type definition.
+#[derive(Encode, Decode, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum MigrationCursor {
+	/// The next account set whose groups go into AllGroupsArray, before the step from version 0
+	AccountSet(u32),
+	/// The index in AllGroupsArray of the next group of the current step
+	Group(u64),
 }
 
 decl_storage! {
@@ -60,52 +156,123 @@ decl_storage! {
 		MaxNameSize get(max_name_size) config(): Option<usize>;
 
 		// These are the primary storage vars for storing the Group struct and recording ownership of a Group
-		Groups get(group): map T::Hash => Group<T::AccountId, T::Hash>;
+		Groups get(group): map T::Hash => Group<T::Hash, T::Moment>;
 		GroupOwner get(owner_of): map T::Hash => Option<T::AccountId>;
 
 		// This is a generic counter of all groups created in the system.
 		AllGroupsCount get(all_groups_count): u64;
-		// TODO: Make this more useful by creating a lookup mapping of index to Hash?
-		// This might be useful for iterating through all known groups, but
+		// Index of all groups, so that every group can be listed (e.g. by export()). Removal swaps and pops.
+		AllGroupsArray get(group_by_index): map u64 => T::Hash;
+		AllGroupsIndex: map T::Hash => u64;
 
-		// These are the mappings that provide lookups for owned groups, given AccountId or Hash
-        OwnedGroupsArray get(owned_group_by_index): map (T::AccountId, u64) => T::Hash;
+		// These are the mappings that provide lookups for owned groups, given AccountId or Hash. The keys of
+		// OwnedGroupsArray start with the hashed owner, so clients list the groups of an owner by key prefix.
+        OwnedGroupsArray get(owned_group_by_index): double_map T::AccountId, blake2_256(u64) => T::Hash;
         OwnedGroupsCount get(owned_group_count): map T::AccountId => u64;
         OwnedGroupsIndex get(owned_groups_index): map T::Hash => u64;
 
-		Nonce: u64;
+		// The members of each group as a doubly linked list in join order, so joining and leaving touch a fixed
+		// number of entries instead of the whole member set. The link of an account exists while it is a member,
+		// so it is also the membership lookup. Iterate the list with members_iter().
+		MemberHead get(member_head): map T::Hash => Option<T::AccountId>;
+		MemberTail get(member_tail): map T::Hash => Option<T::AccountId>;
+		MemberLinks get(member_link): map (T::Hash, T::AccountId) => Option<MemberLink<T::AccountId>>;
+
+		// The groups that were created, changed or removed in a block, in the order of the first change. Clients
+		// subscribe to the key of a block instead of diffing the maps above. Pruned after CHANGES_KEPT blocks.
+		GroupsChangedAt get(groups_changed_at): map T::BlockNumber => Vec<T::Hash>;
+
+		// Timed groups: the expiry of each timed group, and the groups to expire in each block. Entries of groups
+		// that were removed before their end block stay in the queue and are skipped when it is processed.
+		GroupExpiries get(expiry_of): map T::Hash => Option<GroupExpiry<T::BlockNumber>>;
+		ExpiryQueue: map T::BlockNumber => Vec<T::Hash>;
+		ExpiryQueuePages: map (T::BlockNumber, u32) => Vec<T::Hash>;
+		ExpiryQueuePageCount: map T::BlockNumber => u32;
+		ExpiryQueueCursor: Option<(T::BlockNumber, u32)>;
+		// Expired groups that were not removed. No one can join them.
+		ArchivedGroups get(is_archived): map T::Hash => bool;
+
+		// The layout version of the storage above, see STORAGE_VERSION. Unset (0) on chains older than the versioning.
+		StorageVersion get(storage_version): u32;
+		// Where the migration continues. Set from its first block until the step to STORAGE_VERSION is done.
+		MigrationProgress get(migration_progress): Option<MigrationCursor>;
+	}
+
+	add_extra_genesis {
+		/// Groups that exist from the start, as (owner, name, max_size, members). The group ids are the hash
+		/// of the owner, the name and the position in this list, so they are the same on every chain.
+		config(initial_groups): Vec<(T::AccountId, Vec<u8>, u32, Vec<T::AccountId>)>;
+
+		build(|storage: &mut StorageOverlay, _: &mut ChildrenStorageOverlay, config: &GenesisConfig<T>| {
+			with_storage(storage, || {
+				<StorageVersion<T>>::put(STORAGE_VERSION);
+				for (index, (owner, name, max_size, members)) in config.initial_groups.iter().enumerate() {
+					assert!(members.len() as u32 <= *max_size, "Initial group has more members than its max_size");
+					let group_id = (owner, name, index as u64).using_encoded(<T as system::Trait>::Hashing::hash);
+					assert!(!<Groups<T>>::exists(group_id), "Duplicate initial group");
+
+					let now = <Module<T>>::get_time();
+					<Groups<T>>::insert(group_id, Group {
+						id: group_id,
+						name: name.clone(),
+						member_count: members.len() as u32,
+						max_size: *max_size,
+						created_at: now.clone(),
+						updated_at: now,
+					});
+					<Registry<T>>::insert(owner, group_id).expect("Too many initial groups");
+					for member in members {
+						assert!(!<MemberLinks<T>>::exists((group_id, member.clone())), "Duplicate member in an initial group");
+						<Module<T>>::link_member(group_id, member.clone());
+					}
+				}
+			});
+		});
 	}
 }
 
 
 /*
 The events declared here are meant to be used by an external event listener to record state information
-in an external datastore.
+in an external datastore. Events that change a group end with the time of the change, which is the new
+updated_at of the group, so the external record can be audited against the chain.
 */
 decl_event!(
 	pub enum Event<T> where
 		<T as system::Trait>::AccountId,
-        <T as system::Trait>::Hash
+        <T as system::Trait>::Hash,
+		<T as system::Trait>::BlockNumber,
+		<T as timestamp::Trait>::Moment
 	{
 		/// CreatedGroup should provide the AccountId and group_id Hash to get recorded in another system
-		CreatedGroup(Hash, AccountId, u32),
+		CreatedGroup(Hash, AccountId, u32, Moment),
 
 		/// This event allows event listener to update DB and UI with name change
-		GroupRenamed(Hash, Vec<u8>),
+		GroupRenamed(Hash, Vec<u8>, Moment),
 
 		/// This event allows event listener to update DB and UI with group size change.
 		/// The max_size and current_size values are also provided.
 		/// This would be useful for allowing more/less users to join the group.
-		GroupSizeChanged(Hash, u32, u32),
+		GroupSizeChanged(Hash, u32, u32, Moment),
+
+		/// Event fired when the owner removes a group, or a timed group with remove set expires.
+		GroupRemoved(Hash, Moment),
+
+		/// Event fired when a timed group is created, with its end block and whether it is removed at the end.
+		GroupExpirySet(Hash, BlockNumber, bool),
 
-		/// Event fired when the owner removes a group.
-		GroupRemoved(Hash),
+		/// Event fired when a timed group expires and is archived instead of removed.
+		GroupArchived(Hash, Moment),
 
 		/// Event fired when a member joins a group. The max_size and current_size values are also provided.
-		MemberJoinedGroup(Hash, AccountId, u32, u32),
+		MemberJoinedGroup(Hash, AccountId, u32, u32, Moment),
 
 		/// Event fired when a member leaves a group. The max_size and current_size values are also provided.
-		MemberLeftGroup(Hash, AccountId, u32, u32),
+		MemberLeftGroup(Hash, AccountId, u32, u32, Moment),
+
+		/// Event fired when a step of the storage migration is done, with the new storage version and the number of
+		/// groups migrated.
+		StorageMigrated(u32, u64),
 	}
 );
 
@@ -115,50 +282,41 @@ decl_module! {
 
 		fn deposit_event<T>() = default;
 
+		fn on_initialize(n: T::BlockNumber) {
+			if let Some(pruned) = n.checked_sub(&T::BlockNumber::sa(CHANGES_KEPT)) {
+				<GroupsChangedAt<T>>::remove(pruned);
+			}
+			if Self::storage_version() < STORAGE_VERSION {
+				// The layout is mixed until the migration is done, so nothing expires before
+				Self::migrate();
+				<Expiries<T>>::hold(n);
+				return;
+			}
+			Self::process_expiries(n);
+		}
+
 		/// Create a group owned by the current AccountId.
 		/// Usage: For name, use String::into_bytes();
+		/// Weight: constant, 9 storage writes, 3 more for a sequential id. See the create_group benchmark.
 		fn create_group(origin, name: Vec<u8>, max_size: u32) -> Result {
 			let sender = ensure_signed(origin)?;
+			Self::insert_group(sender, name, max_size)?;
+			Ok(())
+		}
 
-			let max_name_size = Self::max_name_size().ok_or("Config max_name_size not set")?;
-			ensure!(name.len() <= max_name_size, "Name is too long");
-
-            let nonce = <Nonce<T>>::get();
-            let group_id = (<system::Module<T>>::random_seed(), &sender, nonce)
-                .using_encoded(<T as system::Trait>::Hashing::hash);
-
-	        ensure!(!<Groups<T>>::exists(group_id), "Group Id already exists");
-	        ensure!(!<GroupOwner<T>>::exists(group_id), "GroupOwner already exists");
-
-			let total_groups = Self::all_groups_count();
-			let new_groups_count = total_groups.checked_add(1).ok_or("Overflow adding a new group")?;
-
-			let owned_group_count = Self::owned_group_count(&sender);
-			let new_owned_group_count = owned_group_count.checked_add(1).ok_or("Overflow adding a new group")?;
-
-			let max_groups_per_owner = Self::max_groups_per_owner().ok_or("Config max_groups_per_owner not set")?;
-			ensure!(owned_group_count < max_groups_per_owner, "Groups limit reached for this Account");
-
-			// FIXME: As conversion will be replaced by TryInto
-			// https://stackoverflow.com/questions/56081117/how-do-you-convert-between-substrate-specific-types-and-rust-primitive-types
-			// let ts = Self::get_time();
-			let group = Group {
-				id: group_id,
-				name: name,
-				members: Vec::new(),
-				max_size: max_size,
-			};
-			<Groups<T>>::insert(group_id, group);
-			<GroupOwner<T>>::insert(group_id, &sender);
-			<AllGroupsCount<T>>::put(new_groups_count);
-
-			<OwnedGroupsArray<T>>::insert((sender.clone(), owned_group_count), group_id);
-			<OwnedGroupsCount<T>>::insert(&sender, new_owned_group_count);
-			<OwnedGroupsIndex<T>>::insert(group_id, owned_group_count);
+		/// Create a group that expires in end_block, for time-bounded groups such as game lobbies and tournaments.
+		/// An expired group is archived, so no one can join it anymore, or removed when remove is true.
+		/// Rule: end_block is after the current block.
+		/// Weight: like create_group, plus 2 storage writes.
+		fn create_timed_group(origin, name: Vec<u8>, max_size: u32, end_block: T::BlockNumber, remove: bool) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(end_block > <system::Module<T>>::block_number(), "End block must be in the future");
 
-			<Nonce<T>>::mutate(|n| *n += 1);
+			let group_id = Self::insert_group(sender, name, max_size)?;
+			<GroupExpiries<T>>::insert(group_id, GroupExpiry { end_block, remove });
+			<Expiries<T>>::schedule(end_block, group_id);
 
-			Self::deposit_event(RawEvent::CreatedGroup(group_id, sender, max_size));
+			Self::deposit_event(RawEvent::GroupExpirySet(group_id, end_block, remove));
 			Ok(())
 		}
 
@@ -167,6 +325,7 @@ decl_module! {
 		/// Usage: For name, use String::into_bytes();
 		fn rename_group(origin, group_id: T::Hash, name: Vec<u8>) -> Result {
 			let sender = ensure_signed(origin)?;
+			Self::ensure_migrated()?;
 
 			let max_name_size = Self::max_name_size().ok_or("Config max_name_size not set")?;
 			ensure!(name.len() <= max_name_size, "Name is too long");
@@ -178,10 +337,13 @@ decl_module! {
 			let mut group = Self::group(group_id);
 
 			// TODO: ensure unchanged?
+			let now = Self::get_time();
 			group.name = name.clone();
+			group.updated_at = now.clone();
 			<Groups<T>>::insert(group.id, group);
+			Self::note_changed(group_id);
 
-			Self::deposit_event(RawEvent::GroupRenamed(group_id, name));
+			Self::deposit_event(RawEvent::GroupRenamed(group_id, name, now));
 			Ok(())
 		}
 
@@ -189,6 +351,7 @@ decl_module! {
 		/// for the owner of the group.
 		fn update_group_size(origin, group_id: T::Hash, max_size: u32) -> Result {
 			let sender = ensure_signed(origin)?;
+			Self::ensure_migrated()?;
 
 			ensure!(<Groups<T>>::exists(group_id), "This group does not exist");
             let owner = Self::owner_of(group_id).ok_or("No owner for this group")?;
@@ -198,43 +361,32 @@ decl_module! {
 			ensure!(max_size <= max_group_size, "Group size too large");
 
 			let mut group = Self::group(group_id);
-			let current_size = group.members.len() as u32;
+			let current_size = group.member_count;
 			ensure!(current_size <= max_size, "Current member count exceeds new group size");
 
 			// TODO: ensure unchanged?
+			let now = Self::get_time();
 			group.max_size = max_size;
+			group.updated_at = now.clone();
 			<Groups<T>>::insert(group.id, group);
+			Self::note_changed(group_id);
 
-			Self::deposit_event(RawEvent::GroupSizeChanged(group_id, max_size, current_size));
+			Self::deposit_event(RawEvent::GroupSizeChanged(group_id, max_size, current_size, now));
 			Ok(())
 		}
 
 		/// Remove group and update all storage with new values
 		/// Rule: only owner can remove a group
+		/// Weight: one storage read and one removal per member on top of about 10, at most max_group_size members.
+		/// See the remove_full_group benchmark.
 		fn owner_remove_group(origin, group_id: T::Hash) -> Result {
 			let sender = ensure_signed(origin)?;
+			Self::ensure_migrated()?;
 			ensure!(<Groups<T>>::exists(group_id), "This group does not exist");
             let owner = Self::owner_of(group_id).ok_or("No owner for this group")?;
             ensure!(owner == sender, "You do not own this group");
 
-			let total_groups = Self::all_groups_count();
-			let new_groups_count = total_groups.checked_sub(1).ok_or("Overflow subtracting a group")?;
-
-			let owned_group_count = Self::owned_group_count(&sender);
-			let new_owned_group_count = owned_group_count.checked_sub(1).ok_or("Overflow subtracting a group")?;
-			// Get the index position of the group, so it can be removed
-			let group_index = <OwnedGroupsIndex<T>>::get(group_id);
-
-			<Groups<T>>::remove(group_id);
-			<GroupOwner<T>>::remove(group_id);
-			<AllGroupsCount<T>>::put(new_groups_count);
-
-			<OwnedGroupsArray<T>>::remove((sender.clone(), group_index));
-			<OwnedGroupsCount<T>>::insert(&sender, new_owned_group_count);
-			<OwnedGroupsIndex<T>>::remove(group_id);
-
-			Self::deposit_event(RawEvent::GroupRemoved(group_id));
-			Ok(())
+			Self::delete_group(group_id)
 		}
 
 		/*
@@ -252,15 +404,13 @@ decl_module! {
 		/// Method for use case where user voluntarily joins a group
 		fn join_group(origin, group_id: T::Hash) -> Result {
 			let sender = ensure_signed(origin)?;
-			ensure!(<Groups<T>>::exists(group_id), "This group does not exist");
-
-			Self::add_member(group_id, sender)?;
-			Ok(())
+			Self::join(GroupId(group_id), sender)
 		}
 
 		/// Method for use case where user voluntarily leaves a group
 		fn leave_group(origin, group_id: T::Hash) -> Result {
 			let sender = ensure_signed(origin)?;
+			Self::ensure_migrated()?;
 			ensure!(<Groups<T>>::exists(group_id), "This group does not exist");
 
 			Self::remove_member(group_id, sender)?;
@@ -270,9 +420,11 @@ decl_module! {
 		/// Method for use case where owner adds a group member
 		fn owner_add_member(origin, group_id: T::Hash, user: T::AccountId) -> Result {
 			let sender = ensure_signed(origin)?;
+			Self::ensure_migrated()?;
 			ensure!(<Groups<T>>::exists(group_id), "This group does not exist");
             let owner = Self::owner_of(group_id).ok_or("No owner for this group")?;
             ensure!(owner == sender, "You do not own this group");
+			ensure!(!Self::is_archived(group_id), "This group is archived");
 
 			Self::add_member(group_id, user)?;
 			Ok(())
@@ -281,6 +433,7 @@ decl_module! {
 		/// Method for use case where owner removes a group member
 		fn owner_remove_member(origin, group_id: T::Hash, user: T::AccountId) -> Result {
 			let sender = ensure_signed(origin)?;
+			Self::ensure_migrated()?;
 			ensure!(<Groups<T>>::exists(group_id), "This group does not exist");
             let owner = Self::owner_of(group_id).ok_or("No owner for this group")?;
             ensure!(owner == sender, "You do not own this group");
@@ -291,53 +444,509 @@ decl_module! {
 	}
 }
 
+impl<T: Trait> GroupInspector<T::AccountId, T::Hash> for Module<T> {
+	fn members(group_id: GroupId<T::Hash>) -> Option<Vec<T::AccountId>> {
+		Self::members_of(group_id.0)
+	}
+
+	fn is_member(group_id: GroupId<T::Hash>, who: &T::AccountId) -> bool {
+		<MemberLinks<T>>::exists((group_id.0, who.clone()))
+	}
+
+	fn owner(group_id: GroupId<T::Hash>) -> Option<T::AccountId> {
+		Self::owner_of(group_id.0)
+	}
+}
+
+/// Used by join_group(), and by the referrals module for joins with a referrer
+impl<T: Trait> GroupJoiner<T::AccountId, T::Hash> for Module<T> {
+	fn join(group_id: GroupId<T::Hash>, who: T::AccountId) -> Result {
+		Self::ensure_migrated()?;
+		let group_id = group_id.0;
+		ensure!(<Groups<T>>::exists(group_id), "This group does not exist");
+		ensure!(!Self::is_archived(group_id), "This group is archived");
+		Self::ensure_room_for(group_id, &who)?;
+		// Charged after every check, since a failed call does not undo the fee
+		T::Fees::charge_fee(&who, FeeCall::JoinGroup)?;
+
+		Self::add_member(group_id, who)
+	}
+}
+
+/// The storage items that register the owner of each group, see the ownership module
+struct GroupOwnership<T>(PhantomData<T>);
+
+impl<T: Trait> OwnershipStorage for GroupOwnership<T> {
+	type Owner = T::AccountId;
+	type Id = T::Hash;
+	type OwnerOf = GroupOwner<T>;
+	type AllArray = AllGroupsArray<T>;
+	type AllCount = AllGroupsCount<T>;
+	type AllIndex = AllGroupsIndex<T>;
+	type OwnedArray = OwnedGroupsArray<T>;
+	type OwnedCount = OwnedGroupsCount<T>;
+	type OwnedIndex = OwnedGroupsIndex<T>;
+}
+
+type Registry<T> = ownership::Registry<GroupOwnership<T>>;
+
+/// The queue of timed groups by end block, see the deadline module
+struct GroupExpiryQueue<T>(PhantomData<T>);
+
+impl<T: Trait> DeadlineStorage for GroupExpiryQueue<T> {
+	type BlockNumber = T::BlockNumber;
+	type Id = T::Hash;
+	type Queue = ExpiryQueue<T>;
+	type Pages = ExpiryQueuePages<T>;
+	type PageCount = ExpiryQueuePageCount<T>;
+	type Cursor = ExpiryQueueCursor<T>;
+	const BUDGET: usize = MAX_EXPIRIES_PER_BLOCK;
+}
+
+type Expiries<T> = Deadlines<GroupExpiryQueue<T>>;
+
+/// Iterates over the members of a group in join order, reading one MemberLinks entry per member. Returned by
+/// members_iter(), for features such as payouts and votes that go through the members one at a time.
+pub struct MemberIter<T: Trait> {
+	group_id: T::Hash,
+	next: Option<T::AccountId>,
+}
+
+impl<T: Trait> Iterator for MemberIter<T> {
+	type Item = T::AccountId;
+
+	fn next(&mut self) -> Option<T::AccountId> {
+		let member = self.next.take()?;
+		// Read the next member before handing this one out, so the caller can unlink it while iterating
+		self.next = <Module<T>>::member_link((self.group_id, member.clone())).and_then(|link| link.next);
+		Some(member)
+	}
+}
+
 /// Custom methods – public and private
 impl<T: Trait> Module<T> {
+	// Private method called by: create_group() and create_timed_group(). Returns the id of the new group.
+	fn insert_group(sender: T::AccountId, name: Vec<u8>, max_size: u32) -> result::Result<T::Hash, &'static str> {
+		Self::ensure_migrated()?;
+		let max_name_size = Self::max_name_size().ok_or("Config max_name_size not set")?;
+		ensure!(name.len() <= max_name_size, "Name is too long");
+
+		let owned_group_count = Self::owned_group_count(&sender);
+		let max_groups_per_owner = Self::max_groups_per_owner().ok_or("Config max_groups_per_owner not set")?;
+		ensure!(owned_group_count < max_groups_per_owner, "Groups limit reached for this Account");
+		T::Fees::charge_fee(&sender, FeeCall::CreateGroup)?;
+
+		// Taken after the checks above, so a sequential id is only used by a group that is created
+		let group_id = <ids::Module<T>>::next_id(IdKind::Group, &sender)?;
+		if !<ids::Module<T>>::sequential() {
+			ensure!(!<Groups<T>>::exists(group_id), "Group Id already exists");
+			ensure!(!<GroupOwner<T>>::exists(group_id), "GroupOwner already exists");
+		}
+
+		let now = Self::get_time();
+		let group = Group {
+			id: group_id,
+			name: name,
+			member_count: 0,
+			max_size: max_size,
+			created_at: now.clone(),
+			updated_at: now.clone(),
+		};
+		<Registry<T>>::insert(&sender, group_id)?;
+		<Groups<T>>::insert(group_id, group);
+		Self::note_changed(group_id);
+
+		Self::deposit_event(RawEvent::CreatedGroup(group_id, sender, max_size, now));
+		Ok(group_id)
+	}
+
+	// Private method called by: owner_remove_group() and expire_group()
+	fn delete_group(group_id: T::Hash) -> Result {
+		<Registry<T>>::remove(group_id)?;
+		for member in Self::members_iter(group_id) {
+			<MemberLinks<T>>::remove((group_id, member));
+		}
+		<MemberHead<T>>::remove(group_id);
+		<MemberTail<T>>::remove(group_id);
+		<GroupExpiries<T>>::remove(group_id);
+		<ArchivedGroups<T>>::remove(group_id);
+		<Groups<T>>::remove(group_id);
+		Self::note_changed(group_id);
+
+		Self::deposit_event(RawEvent::GroupRemoved(group_id, Self::get_time()));
+		Ok(())
+	}
+
+	// Private method called by: on_initialize(). Expires the groups queued for this block, at most
+	// MAX_EXPIRIES_PER_BLOCK of them, see the deadline module.
+	fn process_expiries(n: T::BlockNumber) {
+		for group_id in <Expiries<T>>::take_due(n) {
+			Self::expire_group(group_id);
+		}
+	}
+
+	// Private method called by: process_expiries(). Skips groups that were removed before their end block.
+	fn expire_group(group_id: T::Hash) {
+		let expiry = match <GroupExpiries<T>>::take(group_id) {
+			Some(expiry) => expiry,
+			None => return,
+		};
+		if expiry.remove {
+			// Fails without writing anything when the group has no owner, so there is nothing to undo
+			let _ = Self::delete_group(group_id);
+		} else {
+			<ArchivedGroups<T>>::insert(group_id, true);
+			Self::note_changed(group_id);
+			Self::deposit_event(RawEvent::GroupArchived(group_id, Self::get_time()));
+		}
+	}
+
 	// Private method called by: join_group() and owner_add_member()
 	fn add_member(group_id: T::Hash, user: T::AccountId) -> Result {
+		Self::ensure_room_for(group_id, &user)?;
 		let mut group = Self::group(group_id);
-		ensure!((group.members.len() as u32) < group.max_size, "Group is already full");
-		ensure!(!group.members.contains(&user), "Account is already a member of this group");
-		group.members.push(user.clone());
+		Self::link_member(group_id, user.clone());
+		let now = Self::get_time();
+		group.member_count += 1;
+		group.updated_at = now.clone();
 
 		let max_size = group.max_size;
-		let current_size = group.members.len() as u32;
+		let current_size = group.member_count;
 		<Groups<T>>::insert(group_id, group);
+		Self::note_changed(group_id);
+
+		Self::deposit_event(RawEvent::MemberJoinedGroup(group_id, user, max_size, current_size, now));
+		Ok(())
+	}
 
-		Self::deposit_event(RawEvent::MemberJoinedGroup(group_id, user, max_size, current_size));
+	// Private method called by: join() and add_member(). Ok if the user can be added to the group.
+	fn ensure_room_for(group_id: T::Hash, user: &T::AccountId) -> Result {
+		let group = Self::group(group_id);
+		ensure!(group.member_count < group.max_size, "Group is already full");
+		ensure!(!<MemberLinks<T>>::exists((group_id, user.clone())), "Account is already a member of this group");
 		Ok(())
 	}
 
+	// Private method called by every call that writes a group
+	fn note_changed(group_id: T::Hash) {
+		<GroupsChangedAt<T>>::mutate(<system::Module<T>>::block_number(), |changed| {
+			if !changed.contains(&group_id) {
+				changed.push(group_id);
+			}
+		});
+	}
+
 	// Private method called by: leave_group() and owner_remove_member()
 	fn remove_member(group_id: T::Hash, user: T::AccountId) -> Result {
 		let mut group = Self::group(group_id);
 
-		ensure!(group.members.contains(&user), "Account is not a member of this group");
-		if let Some(index) = group.members.iter().position(|x| *x == user) {
-			group.members.remove(index);
-		}
+		let link = Self::member_link((group_id, user.clone())).ok_or("Account is not a member of this group")?;
+		Self::unlink_member(group_id, user.clone(), link);
+		let now = Self::get_time();
+		group.member_count = group.member_count.saturating_sub(1);
+		group.updated_at = now.clone();
 
 		let max_size = group.max_size;
-		let current_size = group.members.len() as u32;
+		let current_size = group.member_count;
 		<Groups<T>>::insert(group_id, group);
+		Self::note_changed(group_id);
 
-		Self::deposit_event(RawEvent::MemberLeftGroup(group_id, user, max_size, current_size));
+		Self::deposit_event(RawEvent::MemberLeftGroup(group_id, user, max_size, current_size, now));
 		Ok(())
 	}
 
+	// Private method called by: add_member() and the genesis config. Appends the account to the member list,
+	// the caller checks that it is not a member yet.
+	fn link_member(group_id: T::Hash, user: T::AccountId) {
+		let tail = Self::member_tail(group_id);
+		match tail {
+			Some(ref tail) => <MemberLinks<T>>::mutate((group_id, tail.clone()), |link| {
+				if let Some(link) = link.as_mut() {
+					link.next = Some(user.clone());
+				}
+			}),
+			None => <MemberHead<T>>::insert(group_id, &user),
+		}
+		<MemberLinks<T>>::insert((group_id, user.clone()), MemberLink { prev: tail, next: None });
+		<MemberTail<T>>::insert(group_id, &user);
+	}
+
+	// Private method called by: remove_member(). Points the neighbours of the account at each other.
+	fn unlink_member(group_id: T::Hash, user: T::AccountId, link: MemberLink<T::AccountId>) {
+		match link.prev.clone() {
+			Some(prev) => <MemberLinks<T>>::mutate((group_id, prev), |prev_link| {
+				if let Some(prev_link) = prev_link.as_mut() {
+					prev_link.next = link.next.clone();
+				}
+			}),
+			None => match link.next.clone() {
+				Some(next) => <MemberHead<T>>::insert(group_id, next),
+				None => <MemberHead<T>>::remove(group_id),
+			},
+		}
+		match link.next {
+			Some(next) => <MemberLinks<T>>::mutate((group_id, next), |next_link| {
+				if let Some(next_link) = next_link.as_mut() {
+					next_link.prev = link.prev.clone();
+				}
+			}),
+			None => match link.prev {
+				Some(prev) => <MemberTail<T>>::insert(group_id, prev),
+				None => <MemberTail<T>>::remove(group_id),
+			},
+		}
+		<MemberLinks<T>>::remove((group_id, user));
+	}
+
+	/// The timed groups queued to expire in block n that were not taken yet
+	pub fn expiring_at(n: T::BlockNumber) -> Vec<T::Hash> {
+		<Expiries<T>>::due_at(n)
+	}
+
+	/// The members of a group in join order, read one at a time. Empty if the group does not exist.
+	pub fn members_iter(group_id: T::Hash) -> MemberIter<T> {
+		MemberIter {
+			group_id,
+			next: Self::member_head(group_id),
+		}
+	}
+
+	/// The members of a group, or None if the group does not exist. Used by GroupInspector and the GroupsApi runtime API.
+	pub fn members_of(group_id: T::Hash) -> Option<Vec<T::AccountId>> {
+		if <Groups<T>>::exists(group_id) {
+			Some(Self::members_iter(group_id).collect())
+		} else {
+			None
+		}
+	}
+
+	/// The ids of the groups owned by an account, in OwnedGroupsArray order. Used by the GroupsApi runtime API.
+	pub fn groups_by_owner(owner: T::AccountId) -> Vec<T::Hash> {
+		<Registry<T>>::owned(&owner)
+	}
+
+	/// The group with the given id, or None if it does not exist. Used by the GroupsApi runtime API.
+	pub fn group_of(group_id: T::Hash) -> Option<Group<T::Hash, T::Moment>> {
+		if <Groups<T>>::exists(group_id) {
+			Some(Self::group(group_id))
+		} else {
+			None
+		}
+	}
+
+	/// Every group as (owner, name, max_size, members), the format of the initial_groups genesis config.
+	/// Used by the ExportApi runtime API.
+	pub fn export() -> Vec<(T::AccountId, Vec<u8>, u32, Vec<T::AccountId>)> {
+		<Registry<T>>::all()
+			.into_iter()
+			.filter_map(|group_id| {
+				let group = Self::group(group_id);
+				let members = Self::members_iter(group_id).collect();
+				Self::owner_of(group_id).map(|owner| (owner, group.name, group.max_size, members))
+			})
+			.collect()
+	}
+
 	/// Helper method that can be used from UI code to verify member.
 	pub fn is_group_member(group_id: T::Hash, user: T::AccountId) -> bool {
-		let group = Self::group(group_id);
-		group.members.contains(&user)
+		<MemberLinks<T>>::exists((group_id, user))
+	}
+
+	// Private method called by the calls that read or write groups, which would see a mixed layout while the
+	// storage is migrated. MigrationProgress is set from the first block of the migration until the last step.
+	fn ensure_migrated() -> Result {
+		ensure!(Self::migration_progress().is_none(), "The groups storage is being migrated");
+		Ok(())
+	}
+
+	// Called by: on_initialize() while the storage is older than STORAGE_VERSION. Runs the next
+	// MAX_MIGRATED_PER_BLOCK groups of the step from the current version, or indexes one account set before the
+	// step from version 0, and goes on from MigrationProgress in the next block. Every missing step runs, so a
+	// chain can skip runtime versions. Steps are written against the layout of their version and never change.
+	fn migrate() {
+		let version = Self::storage_version();
+		let cursor = Self::migration_progress().unwrap_or(if version == 0 {
+			MigrationCursor::AccountSet(0)
+		} else {
+			MigrationCursor::Group(0)
+		});
+		let start = match cursor {
+			MigrationCursor::AccountSet(set) => {
+				<MigrationProgress<T>>::put(Self::index_v0_groups(set));
+				return;
+			}
+			MigrationCursor::Group(start) => start,
+		};
+
+		let count = Self::all_groups_count();
+		let end = rstd::cmp::min(start.saturating_add(MAX_MIGRATED_PER_BLOCK), count);
+		for i in start..end {
+			let group_id = Self::group_by_index(i);
+			match version {
+				0 => Self::migrate_to_v1(group_id),
+				1 => Self::migrate_to_v2(group_id),
+				2 => Self::migrate_to_v3(group_id),
+				_ => Self::migrate_to_v4(group_id),
+			}
+		}
+		if end < count {
+			<MigrationProgress<T>>::put(MigrationCursor::Group(end));
+		} else {
+			if version + 1 < STORAGE_VERSION {
+				<MigrationProgress<T>>::put(MigrationCursor::Group(0));
+			} else {
+				<MigrationProgress<T>>::kill();
+			}
+			<StorageVersion<T>>::put(version + 1);
+			Self::deposit_event(RawEvent::StorageMigrated(version + 1, count));
+		}
+	}
+
+	// Version 0 has no AllGroupsArray, which every step walks. Adds the groups of one account set from the
+	// tuple-keyed OwnedGroupsArray to it and AllGroupsIndex, and counts them in AllGroupsCount. Returns where the
+	// migration continues: the next set, or the first group once there are no more sets.
+	fn index_v0_groups(set: u32) -> MigrationCursor {
+		let owners = match T::Accounts::account_set(set) {
+			Some(owners) => owners,
+			None => return MigrationCursor::Group(0),
+		};
+		// AllGroupsCount of version 0 also counted removed groups, so the index starts over
+		let mut count = if set == 0 { 0 } else { Self::all_groups_count() };
+		for owner in owners {
+			for i in 0..Self::owned_group_count(&owner) {
+				let group_id = <v2::OwnedGroupsArray<T>>::get((owner.clone(), i));
+				if <AllGroupsIndex<T>>::exists(group_id) {
+					continue;
+				}
+				<AllGroupsArray<T>>::insert(count, group_id);
+				<AllGroupsIndex<T>>::insert(group_id, count);
+				count += 1;
+			}
+		}
+		<AllGroupsCount<T>>::put(count);
+		MigrationCursor::AccountSet(set + 1)
+	}
+
+	// Version 1 adds GroupMembership. Fills it from the members of the group.
+	fn migrate_to_v1(group_id: T::Hash) {
+		for member in <v1::Groups<T>>::get(group_id).members {
+			<v1::GroupMembership<T>>::insert((group_id, member), true);
+		}
+	}
+
+	// Version 2 moves the members of the group into MemberLinks and drops GroupMembership.
+	fn migrate_to_v2(group_id: T::Hash) {
+		let old = <v1::Groups<T>>::get(group_id);
+		let mut member_count = 0;
+		for member in old.members {
+			<v1::GroupMembership<T>>::remove((group_id, member.clone()));
+			Self::link_member(group_id, member);
+			member_count += 1;
+		}
+		<v3::Groups<T>>::insert(group_id, v3::Group {
+			id: old.id,
+			name: old.name,
+			member_count,
+			max_size: old.max_size,
+		});
+	}
+
+	// Version 3 moves the OwnedGroupsArray entry of the group from the (owner, index) map to the double map.
+	fn migrate_to_v3(group_id: T::Hash) {
+		if let Some(owner) = Self::owner_of(group_id) {
+			let index = Self::owned_groups_index(group_id);
+			<v2::OwnedGroupsArray<T>>::remove((owner.clone(), index));
+			<OwnedGroupsArray<T>>::insert(&owner, &index, group_id);
+		}
+	}
+
+	// Version 4 adds created_at and updated_at to Group. The creation time of existing groups is not known, so
+	// both are 0 until the next change.
+	fn migrate_to_v4(group_id: T::Hash) {
+		let old = <v3::Groups<T>>::get(group_id);
+		<Groups<T>>::insert(group_id, Group {
+			id: old.id,
+			name: old.name,
+			member_count: old.member_count,
+			max_size: old.max_size,
+			created_at: T::Moment::default(),
+			updated_at: T::Moment::default(),
+		});
+	}
+
+	/// Sets the config values. Called by the admin module, which checks the origin.
+	pub(crate) fn set_config(max_group_size: u32, max_groups_per_owner: u64, max_name_size: u32) {
+		<MaxGroupSize<T>>::put(max_group_size);
+		<MaxGroupsPerOwner<T>>::put(max_groups_per_owner);
+		<MaxNameSize<T>>::put(max_name_size as usize);
 	}
 
-	// Unused right now. Still considering timestamps for some record-keeping
+	// Used for the created_at and updated_at timestamps of groups
 	pub fn get_time() -> T::Moment {
 		let now = <timestamp::Module<T>>::get();
 		now
 	}
 }
 
+/// The storage of versions 0 and 1, only used by the migration steps up to version 2. The items have the same names
+/// as in the module, so they read the same keys with the old types.
+mod v1 {
+	use super::*;
+
+	#[derive(Encode, Decode, Default)]
+	pub struct Group<A, H> {
+		pub id: H,
+		pub name: Vec<u8>,
+		pub members: Vec<A>,
+		pub max_size: u32,
+	}
+
+	pub struct Module<T>(rstd::marker::PhantomData<T>);
+
+	decl_storage! {
+		trait Store for Module<T: Trait> as Groups {
+			pub Groups: map T::Hash => Group<T::AccountId, T::Hash>;
+			pub GroupMembership: map (T::Hash, T::AccountId) => bool;
+		}
+	}
+}
+
+/// The storage of versions 0 to 2, only used by the migration step to version 3. Like in v1, the items read the
+/// same keys as the module with the old types.
+mod v2 {
+	use super::*;
+
+	pub struct Module<T>(rstd::marker::PhantomData<T>);
+
+	decl_storage! {
+		trait Store for Module<T: Trait> as Groups {
+			pub OwnedGroupsArray: map (T::AccountId, u64) => T::Hash;
+		}
+	}
+}
+
+/// The storage of versions 2 and 3, only used by the migration steps up to version 4. Like in v1, the items read the
+/// same keys as the module with the old types.
+mod v3 {
+	use super::*;
+
+	#[derive(Encode, Decode, Default)]
+	pub struct Group<H> {
+		pub id: H,
+		pub name: Vec<u8>,
+		pub member_count: u32,
+		pub max_size: u32,
+	}
+
+	pub struct Module<T>(rstd::marker::PhantomData<T>);
+
+	decl_storage! {
+		trait Store for Module<T: Trait> as Groups {
+			pub Groups: map T::Hash => Group<T::Hash>;
+		}
+	}
+}
+
 // *****************************************************************************************************
 // Unit Tests!
 // *****************************************************************************************************
@@ -351,7 +960,7 @@ mod tests {
 	use support::{impl_outer_origin, assert_ok, assert_noop};
 	use runtime_primitives::{
 		BuildStorage,
-		traits::{BlakeTwo256, IdentityLookup},
+		traits::{BlakeTwo256, IdentityLookup, OnInitialize},
 		testing::{Digest, DigestItem, Header}
 	};
 
@@ -383,22 +992,37 @@ mod tests {
 	}
 	impl Trait for GroupsTest {
 		type Event = ();
+		type Fees = ();
+		type Accounts = MockAccounts;
+	}
+
+	// The accounts that own groups in the tests
+	pub struct MockAccounts;
+	impl AllAccounts<u64> for MockAccounts {
+		fn account_set(index: u32) -> Option<Vec<u64>> {
+			match index {
+				0 => Some((1..=15).collect()),
+				1 => Some((16..=30).collect()),
+				_ => None,
+			}
+		}
 	}
 	type Groups = Module<GroupsTest>;
 
 	// This function basically just builds a genesis storage key/value store according to
 	// our desired mockup.
-	// TODO: _genesis_phantom_data: Default::default() can be removed later if using latest substrate fixes
-	// Error: missing field `_genesis_phantom_data` in initializer of `groups::GenesisConfig<groups::tests::GroupsTest>`
-	// See also: https://github.com/paritytech/substrate/pull/2913 and Issue #2219
 	fn build_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+		build_ext_with_groups(vec![])
+	}
+
+	fn build_ext_with_groups(initial_groups: Vec<(u64, Vec<u8>, u32, Vec<u64>)>) -> runtime_io::TestExternalities<Blake2Hasher> {
 		let mut t = system::GenesisConfig::<GroupsTest>::default().build_storage().unwrap().0;
 		t.extend(
 			GenesisConfig::<GroupsTest> {
 				max_group_size: 12,
 				max_groups_per_owner: 5,
 				max_name_size: 40,
-				_genesis_phantom_data: Default::default(),
+				initial_groups,
 			}.build_storage().unwrap().0);
 		t.into()
 	}
@@ -417,7 +1041,7 @@ mod tests {
             assert_eq!(Groups::all_groups_count(), 1);
 			assert_eq!(Groups::owned_group_count(10), 1);
 
-            let hash = Groups::owned_group_by_index((10, 0));
+            let hash = Groups::owned_group_by_index(10, 0);
 			let group = Groups::group(hash);
             assert_eq!(group.id, hash);
 
@@ -444,7 +1068,7 @@ mod tests {
             assert_ok!(Groups::create_group(owner.clone(), data, 8));
 			assert_eq!(Groups::owned_group_count(11), 1);
 
-            let group_id = Groups::owned_group_by_index((11, 0));
+            let group_id = Groups::owned_group_by_index(11, 0);
 			assert_ok!(Groups::rename_group(owner.clone(), group_id, "Renamed Group".as_bytes().to_vec()));
 
 			let group = Groups::group(group_id);
@@ -470,6 +1094,378 @@ mod tests {
 		});
 	}
 
+	/// Group queries test objectives:
+	/// * groups_by_owner lists the owned groups in index order and is empty for other accounts
+	/// * After a removal, the last group is swapped into the removed slot
+	/// * group_of and members_of return None for a removed group
+	/// * GroupInspector answers the same as the queries, for other modules
+	#[test]
+	fn group_queries_should_work() {
+		with_externalities(&mut build_ext(), || {
+			let owner = Origin::signed(11);
+			assert_ok!(Groups::create_group(owner.clone(), "One".as_bytes().to_vec(), 8));
+			assert_ok!(Groups::create_group(owner.clone(), "Two".as_bytes().to_vec(), 8));
+			assert_ok!(Groups::create_group(owner.clone(), "Three".as_bytes().to_vec(), 8));
+			let ids = Groups::groups_by_owner(11);
+			assert_eq!(ids.len(), 3);
+			assert_eq!(ids[1], Groups::owned_group_by_index(11, 1));
+			assert!(Groups::groups_by_owner(12).is_empty());
+
+			assert_ok!(Groups::owner_add_member(owner.clone(), ids[2], 20));
+			assert_eq!(Groups::group_of(ids[2]).map(|g| g.member_count), Some(1));
+			assert_eq!(Groups::members_of(ids[2]), Some(vec![20]));
+			assert_eq!(<Groups as GroupInspector<u64, H256>>::members(GroupId(ids[2])), Some(vec![20]));
+			assert!(<Groups as GroupInspector<u64, H256>>::is_member(GroupId(ids[2]), &20));
+			assert_eq!(<Groups as GroupInspector<u64, H256>>::owner(GroupId(ids[2])), Some(11));
+			assert!(!<Groups as GroupInspector<u64, H256>>::is_member(GroupId(ids[1]), &20));
+
+			assert_ok!(Groups::owner_remove_group(owner.clone(), ids[0]));
+			assert_eq!(Groups::groups_by_owner(11), vec![ids[2], ids[1]]);
+			assert_eq!(Groups::group_of(ids[0]), None);
+			assert_eq!(Groups::members_of(ids[0]), None);
+			assert_eq!(<Groups as GroupInspector<u64, H256>>::members(GroupId(ids[0])), None);
+		});
+	}
+
+	/// Type definitions test objectives:
+	/// * A Group, a MemberLink and a GroupExpiry encode like their fields in the order of type_definitions()
+	#[test]
+	fn type_definitions_should_match_encoding() {
+		let group = Group { id: H256::from([1; 32]), name: b"Name".to_vec(), member_count: 2, max_size: 4, created_at: 5u64, updated_at: 6 };
+		assert_eq!(group.encode(), (group.id, group.name.clone(), group.member_count, group.max_size, group.created_at, group.updated_at).encode());
+		let link = MemberLink { prev: Some(2u64), next: None };
+		assert_eq!(link.encode(), (link.prev, link.next).encode());
+		let expiry = GroupExpiry { end_block: 7u64, remove: true };
+		assert_eq!(expiry.encode(), (expiry.end_block, expiry.remove).encode());
+		let definitions = type_definitions();
+		match (&definitions[0], &definitions[1]) {
+			(crate::types::TypeDef::Struct("Group", group_fields), crate::types::TypeDef::Struct("MemberLink", link_fields)) => {
+				let names: Vec<_> = group_fields.iter().map(|(name, _)| *name).collect();
+				assert_eq!(names, vec!["id", "name", "member_count", "max_size", "created_at", "updated_at"]);
+				let names: Vec<_> = link_fields.iter().map(|(name, _)| *name).collect();
+				assert_eq!(names, vec!["prev", "next"]);
+				match &definitions[2] {
+					crate::types::TypeDef::Struct("GroupExpiry", expiry_fields) => {
+						let names: Vec<_> = expiry_fields.iter().map(|(name, _)| *name).collect();
+						assert_eq!(names, vec!["end_block", "remove"]);
+					},
+					_ => panic!("GroupExpiry is not the third type"),
+				}
+			},
+			_ => panic!("Group and MemberLink are not the first types"),
+		}
+	}
+
+	/// Genesis groups test objectives:
+	/// * initial_groups are created with their members and indexed under their owners
+	/// * The owner can manage a genesis group like any other
+	#[test]
+	fn genesis_groups_should_work() {
+		let initial_groups = vec![
+			(10, "Game".as_bytes().to_vec(), 4, vec![10, 11, 12]),
+			(20, "Pool".as_bytes().to_vec(), 6, vec![]),
+			(10, "Second game".as_bytes().to_vec(), 2, vec![13]),
+		];
+		with_externalities(&mut build_ext_with_groups(initial_groups), || {
+			assert_eq!(Groups::all_groups_count(), 3);
+			assert_eq!(Groups::owned_group_count(10), 2);
+			assert_eq!(Groups::owned_group_count(20), 1);
+
+			let group_id = Groups::owned_group_by_index(10, 0);
+			assert_eq!(Groups::owner_of(group_id), Some(10));
+			assert_eq!(Groups::members_of(group_id), Some(vec![10, 11, 12]));
+			assert!(Groups::is_group_member(Groups::owned_group_by_index(10, 1), 13));
+
+			assert_ok!(Groups::owner_add_member(Origin::signed(10), group_id, 14));
+			assert_noop!(Groups::join_group(Origin::signed(15), group_id), "Group is already full");
+		});
+	}
+
+	/// Export test objectives:
+	/// * export() lists every group in the initial_groups format
+	/// * After a removal, the remaining groups are still exported
+	/// * Exported groups can be loaded back as initial_groups
+	#[test]
+	fn export_should_round_trip_through_genesis() {
+		let exported = with_externalities(&mut build_ext(), || {
+			assert_ok!(Groups::create_group(Origin::signed(10), "One".as_bytes().to_vec(), 4));
+			assert_ok!(Groups::create_group(Origin::signed(11), "Two".as_bytes().to_vec(), 4));
+			assert_ok!(Groups::create_group(Origin::signed(10), "Three".as_bytes().to_vec(), 4));
+			let group_id = Groups::owned_group_by_index(10, 0);
+			assert_ok!(Groups::owner_add_member(Origin::signed(10), group_id, 12));
+			assert_ok!(Groups::owner_remove_group(Origin::signed(10), Groups::owned_group_by_index(10, 1)));
+
+			let exported = Groups::export();
+			assert_eq!(exported, vec![
+				(10, b"One".to_vec(), 4, vec![12]),
+				(11, b"Two".to_vec(), 4, vec![]),
+			]);
+			exported
+		});
+		with_externalities(&mut build_ext_with_groups(exported.clone()), || {
+			assert_eq!(Groups::export(), exported);
+		});
+	}
+
+	// Writes a group the way a version 0 runtime did: in Groups with its members, the owner indexes with the
+	// tuple-keyed OwnedGroupsArray, and only the count of all groups, without AllGroupsArray, GroupMembership or
+	// MemberLinks
+	fn insert_v0_group(owner: u64, seed: u8, members: Vec<u64>) -> H256 {
+		let group_id = H256::from([seed; 32]);
+		<v1::Groups<GroupsTest>>::insert(group_id, v1::Group { id: group_id, name: vec![seed], members, max_size: 4 });
+		<GroupOwner<GroupsTest>>::insert(group_id, owner);
+		<AllGroupsCount<GroupsTest>>::put(Groups::all_groups_count() + 1);
+		let owned_group_count = Groups::owned_group_count(owner);
+		<v2::OwnedGroupsArray<GroupsTest>>::insert((owner, owned_group_count), group_id);
+		<OwnedGroupsCount<GroupsTest>>::insert(owner, owned_group_count + 1);
+		<OwnedGroupsIndex<GroupsTest>>::insert(group_id, owned_group_count);
+		group_id
+	}
+
+	/// Storage migration test objectives:
+	/// * Genesis starts at STORAGE_VERSION, so on_initialize() has nothing to migrate
+	/// * A version 0 layout, which has no list of all groups, is migrated to MemberLinks over several blocks, one
+	///   account set and at most MAX_MIGRATED_PER_BLOCK groups per block, keeping the member order
+	/// * Calls are refused until the migration is done
+	/// * GroupMembership entries of version 1 are removed
+	/// * The owned groups index moves to the double map, so the owner still finds and removes its groups
+	/// * Migrated groups have no timestamps until their next change
+	/// * Members can leave and join migrated groups, and a later on_initialize() changes nothing
+	#[test]
+	fn storage_migration_should_work() {
+		with_externalities(&mut build_ext_with_groups(vec![(10, b"Genesis".to_vec(), 4, vec![11])]), || {
+			assert_eq!(Groups::storage_version(), STORAGE_VERSION);
+			let group_id = Groups::group_by_index(0);
+			assert!(Groups::is_group_member(group_id, 11));
+		});
+
+		with_externalities(&mut build_ext(), || {
+			<StorageVersion<GroupsTest>>::kill();
+			let first = insert_v0_group(10, 1, vec![11, 12]);
+			let second = insert_v0_group(20, 2, vec![21]);
+			let third = insert_v0_group(10, 3, vec![13]);
+			for owner in 0..MAX_MIGRATED_PER_BLOCK {
+				insert_v0_group(21 + owner % 10, 100 + owner as u8, vec![]);
+			}
+			assert!(!Groups::is_group_member(first, 11));
+
+			<timestamp::Module<GroupsTest>>::set_timestamp(30);
+			// Both account sets, then the end of the sets
+			for n in 1..4 {
+				Groups::on_initialize(n);
+			}
+			assert_eq!(Groups::all_groups_count(), MAX_MIGRATED_PER_BLOCK + 3);
+			assert_eq!(Groups::migration_progress(), Some(MigrationCursor::Group(0)));
+			assert_noop!(Groups::leave_group(Origin::signed(11), first), "The groups storage is being migrated");
+			assert_noop!(Groups::create_group(Origin::signed(1), b"Early".to_vec(), 4), "The groups storage is being migrated");
+
+			Groups::on_initialize(4);
+			assert_eq!(Groups::storage_version(), 0);
+			assert_eq!(Groups::migration_progress(), Some(MigrationCursor::Group(MAX_MIGRATED_PER_BLOCK)));
+			Groups::on_initialize(5);
+			assert_eq!(Groups::storage_version(), 1);
+			assert_eq!(Groups::migration_progress(), Some(MigrationCursor::Group(0)));
+			assert_noop!(Groups::join_group(Origin::signed(14), first), "The groups storage is being migrated");
+			// Two blocks for each of the steps to versions 2, 3 and 4
+			for n in 6..12 {
+				Groups::on_initialize(n);
+			}
+			assert_eq!(Groups::storage_version(), 4);
+			assert_eq!(Groups::migration_progress(), None);
+			assert_eq!(Groups::members_of(first), Some(vec![11, 12]));
+			assert_eq!(Groups::members_of(third), Some(vec![13]));
+			assert_eq!(Groups::group(first).member_count, 2);
+			assert!(Groups::is_group_member(second, 21));
+			assert!(!Groups::is_group_member(second, 11));
+			assert!(!<v1::GroupMembership<GroupsTest>>::exists((first, 11)));
+			assert_eq!(Groups::groups_by_owner(10), vec![first, third]);
+			assert_eq!(Groups::owned_group_by_index(20, 0), second);
+			assert!(!<v2::OwnedGroupsArray<GroupsTest>>::exists((10, 0)));
+			assert_eq!((Groups::group(first).created_at, Groups::group(first).updated_at), (0, 0));
+
+			assert_ok!(Groups::leave_group(Origin::signed(11), first));
+			assert!(!Groups::is_group_member(first, 11));
+			assert_eq!((Groups::group(first).created_at, Groups::group(first).updated_at), (0, 30));
+			assert_noop!(Groups::join_group(Origin::signed(21), second), "Account is already a member of this group");
+
+			Groups::on_initialize(12);
+			assert!(!Groups::is_group_member(first, 11));
+			assert_eq!(Groups::members_of(first), Some(vec![12]));
+			assert_eq!(Groups::group(first).member_count, 1);
+			assert_ok!(Groups::owner_remove_group(Origin::signed(20), second));
+			assert_eq!(Groups::owned_group_count(20), 0);
+		});
+	}
+
+	/// Timestamp test objectives:
+	/// * A new group is created_at and updated_at the time of its block
+	/// * Renaming, resizing, joining and leaving move updated_at and keep created_at
+	/// * A failed call does not touch updated_at
+	#[test]
+	fn group_timestamps_should_track_changes() {
+		with_externalities(&mut build_ext(), || {
+			<timestamp::Module<GroupsTest>>::set_timestamp(100);
+			assert_ok!(Groups::create_group(Origin::signed(10), b"Clock".to_vec(), 2));
+			let group_id = Groups::owned_group_by_index(10, 0);
+			assert_eq!((Groups::group(group_id).created_at, Groups::group(group_id).updated_at), (100, 100));
+
+			<timestamp::Module<GroupsTest>>::set_timestamp(110);
+			assert_ok!(Groups::rename_group(Origin::signed(10), group_id, b"Renamed".to_vec()));
+			assert_eq!((Groups::group(group_id).created_at, Groups::group(group_id).updated_at), (100, 110));
+
+			<timestamp::Module<GroupsTest>>::set_timestamp(120);
+			assert_ok!(Groups::update_group_size(Origin::signed(10), group_id, 1));
+			assert_eq!(Groups::group(group_id).updated_at, 120);
+
+			<timestamp::Module<GroupsTest>>::set_timestamp(130);
+			assert_ok!(Groups::join_group(Origin::signed(11), group_id));
+			assert_eq!(Groups::group(group_id).updated_at, 130);
+
+			<timestamp::Module<GroupsTest>>::set_timestamp(140);
+			assert_noop!(Groups::join_group(Origin::signed(12), group_id), "Group is already full");
+			assert_eq!(Groups::group(group_id).updated_at, 130);
+			assert_ok!(Groups::leave_group(Origin::signed(11), group_id));
+			assert_eq!((Groups::group(group_id).created_at, Groups::group(group_id).updated_at), (100, 140));
+		});
+	}
+
+	/// Timed group test objectives:
+	/// * The end block must be after the current block
+	/// * A timed group is archived in its end block: no one can join, members can still leave
+	/// * A timed group with remove set is removed in its end block, one removed earlier is skipped
+	#[test]
+	fn timed_groups_should_expire() {
+		with_externalities(&mut build_ext(), || {
+			<system::Module<GroupsTest>>::set_block_number(3);
+			assert_noop!(Groups::create_timed_group(Origin::signed(10), b"Late".to_vec(), 4, 3, false), "End block must be in the future");
+			assert_ok!(Groups::create_timed_group(Origin::signed(10), b"Lobby".to_vec(), 4, 5, false));
+			assert_ok!(Groups::create_timed_group(Origin::signed(10), b"Match".to_vec(), 4, 5, true));
+			assert_ok!(Groups::create_timed_group(Origin::signed(10), b"Cancelled".to_vec(), 4, 5, true));
+			let lobby = Groups::owned_group_by_index(10, 0);
+			let game = Groups::owned_group_by_index(10, 1);
+			let cancelled = Groups::owned_group_by_index(10, 2);
+			assert_eq!(Groups::expiry_of(lobby), Some(GroupExpiry { end_block: 5, remove: false }));
+			assert_eq!(Groups::expiring_at(5), vec![lobby, game, cancelled]);
+			assert_ok!(Groups::join_group(Origin::signed(11), lobby));
+			assert_ok!(Groups::join_group(Origin::signed(12), lobby));
+			assert_ok!(Groups::join_group(Origin::signed(11), game));
+			assert_ok!(Groups::owner_remove_group(Origin::signed(10), cancelled));
+
+			Groups::on_initialize(4);
+			assert!(!Groups::is_archived(lobby));
+
+			Groups::on_initialize(5);
+			assert!(Groups::is_archived(lobby));
+			assert_eq!(Groups::expiry_of(lobby), None);
+			assert_noop!(Groups::join_group(Origin::signed(13), lobby), "This group is archived");
+			assert_noop!(Groups::owner_add_member(Origin::signed(10), lobby, 13), "This group is archived");
+			assert_ok!(Groups::leave_group(Origin::signed(12), lobby));
+			assert_eq!(Groups::members_of(lobby), Some(vec![11]));
+
+			assert_eq!(Groups::group_of(game), None);
+			assert!(!Groups::is_group_member(game, 11));
+			assert_eq!(Groups::groups_by_owner(10), vec![lobby]);
+			assert!(Groups::expiring_at(5).is_empty());
+
+			assert_ok!(Groups::owner_remove_group(Origin::signed(10), lobby));
+			assert!(!Groups::is_archived(lobby));
+		});
+	}
+
+	/// Expiry cap test objectives:
+	/// * At most MAX_EXPIRIES_PER_BLOCK groups expire in a block, the rest expire in the next blocks
+	/// * Deferred groups stay in the queue of their block and go before the groups that end in the next block
+	#[test]
+	fn expiries_should_be_capped_per_block() {
+		with_externalities(&mut build_ext(), || {
+			let count = MAX_EXPIRIES_PER_BLOCK as u64 + 2;
+			for owner in 0..count {
+				assert_ok!(Groups::create_timed_group(Origin::signed(100 + owner), b"Table".to_vec(), 4, 5, false));
+			}
+			assert_ok!(Groups::create_timed_group(Origin::signed(99), b"Later".to_vec(), 4, 6, false));
+			let later = Groups::owned_group_by_index(99, 0);
+
+			Groups::on_initialize(5);
+			let archived = (0..count).filter(|owner| Groups::is_archived(Groups::owned_group_by_index(100 + owner, 0))).count();
+			assert_eq!(archived, MAX_EXPIRIES_PER_BLOCK);
+			let deferred = vec![Groups::owned_group_by_index(100 + count - 2, 0), Groups::owned_group_by_index(100 + count - 1, 0)];
+			assert_eq!(Groups::expiring_at(5), deferred);
+			assert_eq!(Groups::expiring_at(6), vec![later]);
+
+			Groups::on_initialize(6);
+			assert!(deferred.iter().all(|group_id| Groups::is_archived(*group_id)));
+			assert!(Groups::is_archived(later));
+			assert!(Groups::expiring_at(6).is_empty());
+		});
+	}
+
+	/// Change index test objectives:
+	/// * Every call that writes a group lists it once under the block of the change
+	/// * The entry of a block is pruned CHANGES_KEPT blocks later
+	#[test]
+	fn changed_groups_should_be_indexed_by_block() {
+		with_externalities(&mut build_ext(), || {
+			<system::Module<GroupsTest>>::set_block_number(5);
+			assert_ok!(Groups::create_group(Origin::signed(10), b"First".to_vec(), 4));
+			let first = Groups::group_by_index(0);
+			assert_ok!(Groups::join_group(Origin::signed(11), first));
+			assert_ok!(Groups::create_group(Origin::signed(20), b"Second".to_vec(), 4));
+			let second = Groups::group_by_index(1);
+			assert_eq!(Groups::groups_changed_at(5), vec![first, second]);
+
+			<system::Module<GroupsTest>>::set_block_number(6);
+			assert_eq!(Groups::groups_changed_at(6), vec![]);
+			assert_ok!(Groups::rename_group(Origin::signed(20), second, b"Renamed".to_vec()));
+			assert_ok!(Groups::owner_remove_group(Origin::signed(10), first));
+			assert_eq!(Groups::groups_changed_at(6), vec![second, first]);
+
+			Groups::on_initialize(5 + CHANGES_KEPT - 1);
+			assert_eq!(Groups::groups_changed_at(5), vec![first, second]);
+			Groups::on_initialize(5 + CHANGES_KEPT);
+			assert_eq!(Groups::groups_changed_at(5), vec![]);
+			assert_eq!(Groups::groups_changed_at(6), vec![second, first]);
+		});
+	}
+
+	/// Member list test objectives:
+	/// * members_iter() returns the members in join order
+	/// * Removing the first, a middle or the last member relinks its neighbours and the head and tail
+	/// * Removing a group removes every link, and a new member of an emptied group becomes head and tail
+	#[test]
+	fn member_list_should_keep_join_order() {
+		with_externalities(&mut build_ext(), || {
+			assert_ok!(Groups::create_group(Origin::signed(20), b"List".to_vec(), 8));
+			let group_id = Groups::owned_group_by_index(20, 0);
+			for member in 21..26 {
+				assert_ok!(Groups::join_group(Origin::signed(member), group_id));
+			}
+			assert_eq!(Groups::members_iter(group_id).collect::<Vec<_>>(), vec![21, 22, 23, 24, 25]);
+
+			assert_ok!(Groups::leave_group(Origin::signed(21), group_id));
+			assert_ok!(Groups::leave_group(Origin::signed(23), group_id));
+			assert_ok!(Groups::leave_group(Origin::signed(25), group_id));
+			assert_eq!(Groups::members_iter(group_id).collect::<Vec<_>>(), vec![22, 24]);
+			assert_eq!(Groups::member_head(group_id), Some(22));
+			assert_eq!(Groups::member_tail(group_id), Some(24));
+			assert_eq!(Groups::member_link((group_id, 24)), Some(MemberLink { prev: Some(22), next: None }));
+
+			assert_ok!(Groups::leave_group(Origin::signed(22), group_id));
+			assert_ok!(Groups::leave_group(Origin::signed(24), group_id));
+			assert_eq!(Groups::member_head(group_id), None);
+			assert_eq!(Groups::member_tail(group_id), None);
+			assert_ok!(Groups::join_group(Origin::signed(26), group_id));
+			assert_eq!(Groups::member_head(group_id), Some(26));
+			assert_eq!(Groups::member_tail(group_id), Some(26));
+
+			assert_ok!(Groups::join_group(Origin::signed(27), group_id));
+			assert_ok!(Groups::owner_remove_group(Origin::signed(20), group_id));
+			assert_eq!(Groups::member_head(group_id), None);
+			assert_eq!(Groups::member_link((group_id, 26)), None);
+			assert_eq!(Groups::member_link((group_id, 27)), None);
+			assert_eq!(Groups::members_iter(group_id).count(), 0);
+		});
+	}
+
 	/*
 		Join Group tests: success path
 		* Comprehensive test of all ways of adding and removing members from group (voluntarily and involuntary)
@@ -483,7 +1479,7 @@ mod tests {
             assert_ok!(Groups::create_group(owner.clone(), data, 4));
 
 			// Lookup group_id hash and verify
-            let group_id = Groups::owned_group_by_index((20, 0));
+            let group_id = Groups::owned_group_by_index(20, 0);
 			let group = Groups::group(group_id);
             assert_eq!(group.id, group_id);
 
@@ -495,7 +1491,7 @@ mod tests {
 
 			// Now verify group members count and membership
 			let group = Groups::group(group_id);
-            assert_eq!(group.members.len(), 4);
+            assert_eq!(group.member_count, 4);
 			assert!(Groups::is_group_member(group_id, 21));
 			assert!(Groups::is_group_member(group_id, 22));
 			assert!(Groups::is_group_member(group_id, 23));
@@ -504,19 +1500,19 @@ mod tests {
 			// 24 leaves group. Verify member count and not a member
             assert_ok!(Groups::leave_group(Origin::signed(24), group_id));
 			let group = Groups::group(group_id);
-            assert_eq!(group.members.len(), 3);
+            assert_eq!(group.member_count, 3);
 			assert!(!Groups::is_group_member(group_id, 24));
 
 			// Group owner adds 25 to group.
             assert_ok!(Groups::owner_add_member(owner.clone(), group_id, 25));
 			let group = Groups::group(group_id);
-            assert_eq!(group.members.len(), 4);
+            assert_eq!(group.member_count, 4);
 			assert!(Groups::is_group_member(group_id, 25));
 
 			// Group owner removes 21 from group.
             assert_ok!(Groups::owner_remove_member(owner.clone(), group_id, 21));
 			let group = Groups::group(group_id);
-            assert_eq!(group.members.len(), 3);
+            assert_eq!(group.member_count, 3);
 			assert!(!Groups::is_group_member(group_id, 21));
 
 		});
@@ -535,7 +1531,7 @@ mod tests {
             assert_ok!(Groups::create_group(owner.clone(), data, 4));
 
 			// Lookup group_id hash and verify
-            let group_id = Groups::owned_group_by_index((20, 0));
+            let group_id = Groups::owned_group_by_index(20, 0);
 			let group = Groups::group(group_id);
             assert_eq!(group.id, group_id);
 
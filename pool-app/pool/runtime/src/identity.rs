@@ -0,0 +1,291 @@
+/// Identity stores a small public profile per account, so UIs can show a name and an avatar next to the members
+/// of a group or the owner of a kitty instead of a raw address. The chain only keeps hashes and a content id:
+/// the display name and the external handles, such as a forum or chat account, are hashed by the client, and the
+/// avatar is stored off-chain, such as on IPFS, under its CID.
+///
+/// Registrars, added and removed by a council motion, can attest a profile after checking it off-chain, such as
+/// that the account controls the external handles. An attestation is for the exact content of the profile, so
+/// setting a new profile drops it.
+
+use parity_codec::{Encode, Decode};
+use rstd::prelude::*;
+use runtime_primitives::traits::Hash;
+use support::{decl_module, decl_storage, decl_event, ensure, dispatch::Result, StorageMap, StorageValue};
+use system::ensure_signed;
+
+use app_primitives::GovernanceOrigin;
+
+/// The longest avatar CID, long enough for a base32 CIDv1
+pub const MAX_CID_LEN: usize = 64;
+
+/// The most external handles of one profile
+pub const MAX_HANDLES: usize = 4;
+
+/// The most registrars
+pub const MAX_REGISTRARS: usize = 8;
+
+pub trait Trait: system::Trait {
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	/// Checks the origin of add_registrar() and remove_registrar(). In the runtime this is the Council of the
+	/// approve module.
+	type Council: GovernanceOrigin<Self::Origin>;
+}
+
+/// The public profile of an account
+#[derive(Encode, Decode, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Profile<AccountId, Hash> {
+	/// The hash of the display name
+	pub display_name: Hash,
+	/// The CID of the avatar image, empty for none
+	pub avatar_cid: Vec<u8>,
+	/// The hashes of the external handles, at most MAX_HANDLES
+	pub handles: Vec<Hash>,
+	/// The registrar that attested this content, if any
+	pub attested_by: Option<AccountId>,
+}
+
+/// The polkadot-js definitions of the types above, see the types module
+#[cfg(feature = "std")]
+pub fn type_definitions() -> Vec<crate::types::TypeDef> {
+	use crate::types::TypeDef;
+	vec![
+		TypeDef::Struct("Profile", vec![
+			("display_name", "Hash"), ("avatar_cid", "Vec<u8>"), ("handles", "Vec<Hash>"), ("attested_by", "Option<AccountId>"),
+		]),
+	]
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Identity {
+		// The profile of each account that set one
+		Profiles get(profile_of): map T::AccountId => Option<Profile<T::AccountId, T::Hash>>;
+
+		// The accounts that can attest profiles, at most MAX_REGISTRARS
+		Registrars get(registrars) config(): Vec<T::AccountId>;
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where
+		<T as system::Trait>::AccountId
+	{
+		/// An account set its profile
+		ProfileSet(AccountId),
+
+		/// An account cleared its profile
+		ProfileCleared(AccountId),
+
+		/// A registrar attested a profile as (account, registrar)
+		ProfileAttested(AccountId, AccountId),
+
+		/// A registrar was added
+		RegistrarAdded(AccountId),
+
+		/// A registrar was removed. The profiles it attested keep the attestation.
+		RegistrarRemoved(AccountId),
+	}
+);
+
+decl_module! {
+	/// The module declaration.
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+
+		fn deposit_event<T>() = default;
+
+		/// Set the profile of the sender, replacing the previous one and its attestation
+		pub fn set_profile(origin, display_name: T::Hash, avatar_cid: Vec<u8>, handles: Vec<T::Hash>) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(avatar_cid.len() <= MAX_CID_LEN, "Avatar CID is too long");
+			ensure!(handles.len() <= MAX_HANDLES, "Too many handles in this profile");
+
+			<Profiles<T>>::insert(&sender, Profile { display_name, avatar_cid, handles, attested_by: None });
+			Self::deposit_event(RawEvent::ProfileSet(sender));
+			Ok(())
+		}
+
+		/// Remove the profile of the sender
+		pub fn clear_profile(origin) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(<Profiles<T>>::exists(&sender), "No profile for this account");
+
+			<Profiles<T>>::remove(&sender);
+			Self::deposit_event(RawEvent::ProfileCleared(sender));
+			Ok(())
+		}
+
+		/// Attest the profile of an account. The content hash is content_hash() of the profile the registrar
+		/// checked, so a profile that changed in the meantime is not attested. Rule: registrars only.
+		pub fn attest_profile(origin, who: T::AccountId, content_hash: T::Hash) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(Self::registrars().contains(&sender), "Only registrars can attest profiles");
+			let mut profile = Self::profile_of(&who).ok_or("No profile for this account")?;
+			ensure!(Self::content_hash(&profile) == content_hash, "The profile changed since it was checked");
+
+			profile.attested_by = Some(sender.clone());
+			<Profiles<T>>::insert(&who, profile);
+			Self::deposit_event(RawEvent::ProfileAttested(who, sender));
+			Ok(())
+		}
+
+		/// Add a registrar. Rule: a council motion.
+		pub fn add_registrar(origin, who: T::AccountId) -> Result {
+			T::Council::ensure_governance(origin)?;
+			let mut registrars = Self::registrars();
+			ensure!(!registrars.contains(&who), "Already a registrar");
+			ensure!(registrars.len() < MAX_REGISTRARS, "Too many registrars");
+
+			registrars.push(who.clone());
+			<Registrars<T>>::put(registrars);
+			Self::deposit_event(RawEvent::RegistrarAdded(who));
+			Ok(())
+		}
+
+		/// Remove a registrar. Rule: a council motion.
+		pub fn remove_registrar(origin, who: T::AccountId) -> Result {
+			T::Council::ensure_governance(origin)?;
+			let mut registrars = Self::registrars();
+			let index = registrars.iter().position(|r| r == &who).ok_or("Not a registrar")?;
+
+			registrars.remove(index);
+			<Registrars<T>>::put(registrars);
+			Self::deposit_event(RawEvent::RegistrarRemoved(who));
+			Ok(())
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The hash of the content of a profile, without its attestation. Registrars pass it to attest_profile().
+	pub fn content_hash(profile: &Profile<T::AccountId, T::Hash>) -> T::Hash {
+		(&profile.display_name, &profile.avatar_cid, &profile.handles).using_encoded(<T as system::Trait>::Hashing::hash)
+	}
+
+	/// The accounts paired with their profiles, in the same order. Used by the GroupsApi and KittiesApi runtime
+	/// APIs, so member listings and owner displays come with names in one call.
+	pub fn with_profiles(accounts: Vec<T::AccountId>) -> Vec<(T::AccountId, Option<Profile<T::AccountId, T::Hash>>)> {
+		accounts.into_iter().map(|who| {
+			let profile = Self::profile_of(&who);
+			(who, profile)
+		}).collect()
+	}
+}
+
+// *****************************************************************************************************
+// Unit Tests!
+// *****************************************************************************************************
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use runtime_io::with_externalities;
+	use primitives::{H256, Blake2Hasher};
+	use support::{impl_outer_origin, assert_ok, assert_noop};
+	use runtime_primitives::{
+		BuildStorage,
+		traits::{BlakeTwo256, IdentityLookup},
+		testing::{Digest, DigestItem, Header}
+	};
+	use crate::approve;
+
+	impl_outer_origin! {
+		pub enum Origin for IdentityTest {
+			approve
+		}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct IdentityTest;
+	impl system::Trait for IdentityTest {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type Digest = Digest;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type Log = DigestItem;
+	}
+	impl Trait for IdentityTest {
+		type Event = ();
+		type Council = approve::Council;
+	}
+	type Identity = Module<IdentityTest>;
+
+	// Account 9 is a registrar
+	fn build_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+		let mut t = system::GenesisConfig::<IdentityTest>::default().build_storage().unwrap().0;
+		t.extend(GenesisConfig::<IdentityTest> {
+			registrars: vec![9],
+		}.build_storage().unwrap().0);
+		t.into()
+	}
+
+	// The origin an approved council motion is dispatched with
+	fn council() -> Origin {
+		Origin::from(approve::Origin::Approved(2))
+	}
+
+	/// Type definitions test objectives:
+	/// * Profile encodes like its fields in the order of type_definitions()
+	#[test]
+	fn type_definitions_should_match_encoding() {
+		let profile = Profile {
+			display_name: H256::repeat_byte(1),
+			avatar_cid: b"cid".to_vec(),
+			handles: vec![H256::repeat_byte(2)],
+			attested_by: Some(3u64),
+		};
+		assert_eq!(profile.encode(),
+			(profile.display_name, profile.avatar_cid.clone(), profile.handles.clone(), profile.attested_by).encode());
+	}
+
+	/// Profile test objectives:
+	/// * Profiles are bounded, and listed next to their accounts in the given order
+	/// * Only a registrar can attest, and only the content it checked
+	/// * Setting a new profile drops the attestation
+	#[test]
+	fn profiles_should_be_attested_by_registrars() {
+		with_externalities(&mut build_ext(), || {
+			let name = H256::repeat_byte(1);
+			assert_noop!(Identity::set_profile(Origin::signed(1), name, vec![0; MAX_CID_LEN + 1], vec![]), "Avatar CID is too long");
+			assert_noop!(Identity::set_profile(Origin::signed(1), name, vec![], vec![name; MAX_HANDLES + 1]), "Too many handles in this profile");
+			assert_ok!(Identity::set_profile(Origin::signed(1), name, b"cid".to_vec(), vec![]));
+			let listed = Identity::with_profiles(vec![2, 1]);
+			assert_eq!(listed[0], (2, None));
+			assert_eq!(listed[1].1.as_ref().map(|p| p.display_name), Some(name));
+
+			let checked = Identity::content_hash(&Identity::profile_of(1).unwrap());
+			assert_noop!(Identity::attest_profile(Origin::signed(2), 1, checked), "Only registrars can attest profiles");
+			assert_noop!(Identity::attest_profile(Origin::signed(9), 1, name), "The profile changed since it was checked");
+			assert_ok!(Identity::attest_profile(Origin::signed(9), 1, checked));
+			assert_eq!(Identity::profile_of(1).unwrap().attested_by, Some(9));
+
+			assert_ok!(Identity::set_profile(Origin::signed(1), name, b"other".to_vec(), vec![]));
+			assert_eq!(Identity::profile_of(1).unwrap().attested_by, None);
+			assert_ok!(Identity::clear_profile(Origin::signed(1)));
+			assert_noop!(Identity::clear_profile(Origin::signed(1)), "No profile for this account");
+		});
+	}
+
+	/// Registrar test objectives:
+	/// * Only a council motion can add or remove registrars
+	#[test]
+	fn registrars_should_be_set_by_the_council() {
+		with_externalities(&mut build_ext(), || {
+			assert!(Identity::add_registrar(Origin::signed(1), 1).is_err());
+			assert_ok!(Identity::add_registrar(council(), 8));
+			assert_noop!(Identity::add_registrar(council(), 8), "Already a registrar");
+			assert_eq!(Identity::registrars(), vec![9, 8]);
+
+			assert_ok!(Identity::remove_registrar(council(), 9));
+			assert_noop!(Identity::remove_registrar(council(), 9), "Not a registrar");
+			assert_eq!(Identity::registrars(), vec![8]);
+		});
+	}
+}
@@ -0,0 +1,226 @@
+/// Ids generates the ids of new items, such as kitties, groups and pools, for the modules of this runtime. An id
+/// is the hash of the random seed, a subject and the nonce of that subject, usually the account that creates the
+/// item. Every id taken increments the nonce of its subject, so the ids of one subject never repeat, even within
+/// a block where the random seed stays the same.
+///
+/// In sequential mode, switched on through the admin module, next_id() instead hands out the ids of each kind of
+/// item in order: 0, 1, 2 and so on, so clients page through the items by number. A sequential id is still a
+/// hash, the kind and the number written into its first bytes, and IdBySequence and SequenceOf map between the
+/// number and the hash. It can not collide with another sequential id, so the modules skip their "already
+/// exists" checks for it. Items created before the switch keep their random ids.
+///
+/// Modules call next_id() instead of keeping a nonce of their own. Every system::Trait runtime implements Trait,
+/// so no module needs an associated type for it. In random mode the caller still checks that the id is not taken.
+
+use parity_codec::{Decode, Encode};
+use rstd::prelude::*;
+use rstd::result;
+use runtime_primitives::traits::Hash;
+use support::{decl_module, decl_storage, StorageDoubleMap, StorageMap, StorageValue};
+
+pub trait Trait: system::Trait {}
+
+impl<T: system::Trait> Trait for T {}
+
+/// The kinds of items with ids, each with its own sequence
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum IdKind {
+	Group,
+	Kitty,
+	Pool,
+	TokenClass,
+}
+
+impl IdKind {
+	// The first byte of the sequential ids of the kind. Never 0, so a sequential id is never the zero hash.
+	fn tag(self) -> u8 {
+		self as u8 + 1
+	}
+}
+
+/// The polkadot-js definitions of the types above, see the types module
+#[cfg(feature = "std")]
+pub fn type_definitions() -> Vec<crate::types::TypeDef> {
+	use crate::types::TypeDef;
+	vec![
+		TypeDef::Enum("IdKind", vec!["Group", "Kitty", "Pool", "TokenClass"]),
+	]
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Ids {
+		// The number of ids taken by each subject, keyed by the encoded subject
+		Nonces get(nonce_of): map Vec<u8> => u64;
+
+		// Whether next_id() hands out sequential ids. Set through the admin module.
+		Sequential get(sequential): bool;
+		// The number of the next sequential id of each kind
+		NextSequence get(next_sequence): map IdKind => u64;
+		// The hash alias of each sequential id, and the kind and number of each alias. Removed items keep them.
+		IdBySequence get(id_by_sequence): double_map IdKind, blake2_256(u64) => Option<T::Hash>;
+		SequenceOf get(sequence_of): map T::Hash => Option<(IdKind, u64)>;
+	}
+}
+
+decl_module! {
+	/// The module declaration. It has no calls, ids are only taken by other modules.
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// A new id for an item of the kind created by the subject: the next sequential id of the kind in sequential
+	/// mode, or random_hash() of the subject
+	pub fn next_id<S: Encode>(kind: IdKind, subject: &S) -> result::Result<T::Hash, &'static str> {
+		if Self::sequential() {
+			return Self::next_sequential_id(kind);
+		}
+		Ok(Self::random_hash(subject))
+	}
+
+	/// Like next_id(), with the block number mixed into random ids, for ids that have to differ from the ids of
+	/// another chain or a replayed block with the same random seed
+	pub fn next_id_at_block<S: Encode>(kind: IdKind, subject: &S) -> result::Result<T::Hash, &'static str> {
+		if Self::sequential() {
+			return Self::next_sequential_id(kind);
+		}
+		let nonce = Self::take_nonce(subject);
+		Ok((<system::Module<T>>::random_seed(), <system::Module<T>>::block_number(), subject, nonce)
+			.using_encoded(<T as system::Trait>::Hashing::hash))
+	}
+
+	/// A hash from the random seed and the nonce of the subject, such as the dna of a kitty. These are the ids
+	/// of random mode.
+	pub fn random_hash<S: Encode>(subject: &S) -> T::Hash {
+		let nonce = Self::take_nonce(subject);
+		(<system::Module<T>>::random_seed(), subject, nonce)
+			.using_encoded(<T as system::Trait>::Hashing::hash)
+	}
+
+	/// Called by the admin module
+	pub fn set_sequential(sequential: bool) {
+		<Sequential<T>>::put(sequential);
+	}
+
+	// Private method called by: next_id() and next_id_at_block(). The id is the tag of the kind followed by the
+	// little endian number, in a hash that is otherwise zero.
+	fn next_sequential_id(kind: IdKind) -> result::Result<T::Hash, &'static str> {
+		let sequence = Self::next_sequence(kind);
+		let next_sequence = sequence.checked_add(1).ok_or("Overflow of the id sequence")?;
+
+		let mut id = T::Hash::default();
+		id.as_mut()[0] = kind.tag();
+		id.as_mut()[1..9].copy_from_slice(&sequence.encode());
+
+		<NextSequence<T>>::insert(kind, next_sequence);
+		<IdBySequence<T>>::insert(&kind, &sequence, id);
+		<SequenceOf<T>>::insert(id, (kind, sequence));
+		Ok(id)
+	}
+
+	// Private method called by: next_id_at_block() and random_hash(). Returns the nonce and increments it,
+	// wrapping around after u64::MAX ids, which no subject reaches.
+	fn take_nonce<S: Encode>(subject: &S) -> u64 {
+		<Nonces<T>>::mutate(subject.encode(), |nonce| {
+			let taken = *nonce;
+			*nonce = nonce.wrapping_add(1);
+			taken
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use runtime_io::with_externalities;
+	use primitives::{H256, Blake2Hasher};
+	use support::impl_outer_origin;
+	use runtime_primitives::{
+		BuildStorage,
+		traits::{BlakeTwo256, IdentityLookup},
+		testing::{Digest, DigestItem, Header}
+	};
+
+	impl_outer_origin! {
+		pub enum Origin for IdsTest {}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct IdsTest;
+
+	impl system::Trait for IdsTest {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type Digest = Digest;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type Log = DigestItem;
+	}
+
+	type Ids = Module<IdsTest>;
+
+	fn build_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+		system::GenesisConfig::<IdsTest>::default().build_storage().unwrap().0.into()
+	}
+
+	/// Next id test objectives:
+	/// * The ids of a subject differ within a block
+	/// * Each subject has its own nonce
+	/// * Mixing in the block number gives another id than the same nonce without it
+	#[test]
+	fn next_id_should_use_a_nonce_per_subject() {
+		with_externalities(&mut build_ext(), || {
+			let first = Ids::next_id(IdKind::Group, &1u64).unwrap();
+			let second = Ids::next_id(IdKind::Group, &1u64).unwrap();
+			assert!(first != second);
+			assert_eq!(Ids::nonce_of(1u64.encode()), 2);
+
+			let other = Ids::next_id(IdKind::Group, &2u64).unwrap();
+			assert!(other != first && other != second);
+			assert_eq!(Ids::nonce_of(2u64.encode()), 1);
+
+			// subject 3 takes nonce 0 both times, only the block number tells the ids apart
+			let plain = Ids::next_id(IdKind::Group, &3u64).unwrap();
+			<Nonces<IdsTest>>::remove(3u64.encode());
+			<system::Module<IdsTest>>::set_block_number(5);
+			let at_block = Ids::next_id_at_block(IdKind::Group, &3u64).unwrap();
+			assert!(plain != at_block);
+			assert_eq!(Ids::nonce_of(3u64.encode()), 1);
+			assert_eq!(Ids::sequence_of(plain), None);
+		});
+	}
+
+	/// Sequential id test objectives:
+	/// * Each kind counts from 0, whatever the subject
+	/// * The number and the hash alias map to each other
+	/// * Subject nonces are not taken
+	#[test]
+	fn sequential_ids_should_count_per_kind() {
+		with_externalities(&mut build_ext(), || {
+			Ids::set_sequential(true);
+			let group_0 = Ids::next_id(IdKind::Group, &1u64).unwrap();
+			let group_1 = Ids::next_id(IdKind::Group, &2u64).unwrap();
+			let kitty_0 = Ids::next_id_at_block(IdKind::Kitty, &1u64).unwrap();
+			assert!(group_0 != group_1 && group_0 != kitty_0);
+
+			assert_eq!(Ids::next_sequence(IdKind::Group), 2);
+			assert_eq!(Ids::next_sequence(IdKind::Kitty), 1);
+			assert_eq!(Ids::id_by_sequence(&IdKind::Group, &1), Some(group_1));
+			assert_eq!(Ids::sequence_of(group_1), Some((IdKind::Group, 1)));
+			assert_eq!(Ids::sequence_of(kitty_0), Some((IdKind::Kitty, 0)));
+			assert_eq!(Ids::nonce_of(1u64.encode()), 0);
+
+			Ids::set_sequential(false);
+			let random = Ids::next_id(IdKind::Group, &1u64).unwrap();
+			assert_eq!(Ids::sequence_of(random), None);
+			assert_eq!(Ids::next_sequence(IdKind::Group), 2);
+		});
+	}
+}
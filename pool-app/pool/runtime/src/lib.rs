@@ -4,6 +4,10 @@
 #![cfg_attr(not(feature = "std"), feature(alloc))]
 // `construct_runtime!` does a lot of recursion and requires us to increase the limit to 256.
 #![recursion_limit="256"]
+// The benchmarks use the unstable test crate, so they need nightly and the bench feature
+#![cfg_attr(all(test, feature = "bench"), feature(test))]
+#[cfg(all(test, feature = "bench"))]
+extern crate test;
 
 #[cfg(feature = "std")]
 use serde::{Serialize, Deserialize};
@@ -18,7 +22,7 @@ use runtime_primitives::{
 };
 use client::{
 	block_builder::api::{CheckInherentsResult, InherentData, self as block_builder_api},
-	runtime_api, impl_runtime_apis
+	runtime_api, impl_runtime_apis, decl_runtime_apis
 };
 use version::RuntimeVersion;
 #[cfg(feature = "std")]
@@ -33,6 +37,7 @@ pub use balances::Call as BalancesCall;
 pub use runtime_primitives::{Permill, Perbill};
 pub use timestamp::BlockPeriod;
 pub use support::{StorageValue, construct_runtime};
+pub use app_primitives::FeeCall;
 
 /// The type that is used for identifying authorities.
 pub type AuthorityId = <AuthoritySignature as Verify>::Signer;
@@ -55,10 +60,138 @@ pub type BlockNumber = u64;
 /// Index of an account's extrinsic in the chain.
 pub type Nonce = u64;
 
-/// Custom modules in this runtime
+/// Balance of an account.
+pub type Balance = u128;
+
+/// A timestamp: seconds since the unix epoch.
+pub type Moment = u64;
+
+/// Custom modules in this runtime. Approve and groups are public so that clients can decode the types of
+/// ApproveApi and GroupsApi.
 mod pool;
-mod approve;
-mod groups;
+pub mod approve;
+pub mod groups;
+pub mod identity;
+mod substratekitties;
+mod admin;
+mod fees;
+mod deadline;
+mod ids;
+mod marketplace;
+mod messages;
+mod nft;
+mod oracle;
+mod ownership;
+mod proxy;
+mod recovery;
+mod referrals;
+mod scheduler;
+mod tokens;
+mod treasury;
+mod utility;
+/// The polkadot-js type definitions of the custom modules, see pool/types.json
+#[cfg(feature = "std")]
+pub mod types;
+#[cfg(all(test, feature = "bench"))]
+mod benchmarks;
+
+decl_runtime_apis! {
+	/// Read-only queries of the approve module, so wallets can show the progress of a proposal without
+	/// decoding raw storage. Clients call these through the state_call RPC, e.g. "ApproveApi_proposal_status".
+	pub trait ApproveApi {
+		/// The status of a proposal, or None if it does not exist
+		fn proposal_status(proposal_id: Hash) -> Option<approve::ProposalStatus>;
+
+		/// Every recorded vote on a proposal. True is an approval.
+		fn votes_of(proposal_id: Hash) -> Vec<(AccountId, bool)>;
+
+		/// The pending proposals waiting on a vote from the account
+		fn pending_for(who: AccountId) -> Vec<Hash>;
+
+		/// A page of at most `limit` pending proposals, starting at index `start`
+		fn active_proposals(start: u64, limit: u64) -> Vec<Hash>;
+	}
+
+	/// Read-only queries of the groups module, so front-ends do not have to compute the storage keys of
+	/// the groups maps. Clients call these through the state_call RPC, e.g.
+	/// "GroupsApi_groups_by_owner".
+	pub trait GroupsApi {
+		/// The ids of the groups owned by the account
+		fn groups_by_owner(owner: AccountId) -> Vec<Hash>;
+
+		/// The group, or None if it does not exist
+		fn group(group_id: Hash) -> Option<groups::Group<Hash, Moment>>;
+
+		/// The members of the group, or None if it does not exist
+		fn members(group_id: Hash) -> Option<Vec<AccountId>>;
+
+		/// The members of the group with their identity profiles, or None if it does not exist
+		fn member_profiles(group_id: Hash) -> Option<Vec<(AccountId, Option<identity::Profile<AccountId, Hash>>)>>;
+	}
+
+	/// Read-only queries of the kitties module for inventory views, through the state_call RPC as
+	/// "KittiesApi_kitties_of".
+	pub trait KittiesApi {
+		/// The kitties of the account as (kitty id, dna, generation)
+		fn kitties_of(owner: AccountId) -> Vec<(Hash, Hash, u64)>;
+
+		/// The owner of the kitty with its identity profile, or None if the kitty does not exist
+		fn owner_profile(kitty_id: Hash) -> Option<(AccountId, Option<identity::Profile<AccountId, Hash>>)>;
+	}
+
+	/// Read-only queries of the marketplace module for marketplace UIs, through the state_call RPC as
+	/// "MarketplaceApi_listings".
+	pub trait MarketplaceApi {
+		/// A page of the items for sale as (item id, seller, price), from the listings at the positions
+		/// start..start + limit less the stale ones. Stable prices are converted to the native price of the block.
+		fn listings(start: u64, limit: u64) -> Vec<(Hash, AccountId, Balance)>;
+
+		/// The seller and native price of the item, or None if it is not for sale
+		fn listing(item_id: Hash) -> Option<(AccountId, Balance)>;
+
+		/// The open offers for the item as (buyer, amount)
+		fn offers(item_id: Hash) -> Vec<(AccountId, Balance)>;
+
+		/// The native balance worth one stable unit, or None while the oracle has too few fresh feeds
+		fn oracle_price() -> Option<Balance>;
+	}
+
+	/// Read-only queries of the pool module, through the state_call RPC as "PoolApi_pools"
+	pub trait PoolApi {
+		/// A page of at most `limit` pools as (pool id, owner, contributed, donated, drained), starting at
+		/// index `start`
+		fn pools(start: u64, limit: u64) -> Vec<(Hash, Option<AccountId>, Balance, Balance, bool)>;
+	}
+
+	/// Read-only queries of the messages module, through the state_call RPC as "MessagesApi_messages"
+	pub trait MessagesApi {
+		/// A page of at most `limit` message anchors of a group as (sequence number, author, content hash,
+		/// announcement, block), starting at sequence number `start`
+		fn messages(group_id: Hash, start: u64, limit: u64) -> Vec<(u64, AccountId, Hash, bool, BlockNumber)>;
+	}
+
+	/// Read-only queries of the tokens module for wallets, through the state_call RPC as "TokensApi_balances"
+	pub trait TokensApi {
+		/// The class as (issuer, name, symbol, decimals, supply), or None if it does not exist
+		fn class(class_id: Hash) -> Option<(AccountId, Vec<u8>, Vec<u8>, u8, Balance)>;
+
+		/// The balances of the account in the given classes as (class id, balance)
+		fn balances(who: AccountId, class_ids: Vec<Hash>) -> Vec<(Hash, Balance)>;
+	}
+
+	/// The app state in the format of the genesis config, used by the `export-app-state` subcommand of
+	/// the node to carry demo data over a testnet reset.
+	pub trait ExportApi {
+		/// All kitties as (owner, kitty id), as in the substratekitties genesis config
+		fn kitties() -> Vec<(AccountId, Hash)>;
+
+		/// All items for sale as (seller, item id, price), as in the marketplace genesis config
+		fn listings() -> Vec<(AccountId, Hash, Balance)>;
+
+		/// All groups as (owner, name, max_size, members), as in the groups genesis config
+		fn groups() -> Vec<(AccountId, Vec<u8>, u32, Vec<AccountId>)>;
+	}
+}
 
 /// Opaque types. These are used by the CLI to instantiate machinery that don't need to know
 /// the specifics of the runtime. They can then be made to be agnostic over specific formats
@@ -163,14 +296,13 @@ impl indices::Trait for Runtime {
 }
 
 impl timestamp::Trait for Runtime {
-	/// A timestamp: seconds since the unix epoch.
-	type Moment = u64;
+	type Moment = Moment;
 	type OnTimestampSet = Aura;
 }
 
 impl balances::Trait for Runtime {
 	/// The type for recording an account's balance.
-	type Balance = u128;
+	type Balance = Balance;
 	/// What to do if an account's free balance gets zeroed.
 	type OnFreeBalanceZero = ();
 	/// What to do if a new account is created.
@@ -191,12 +323,149 @@ impl sudo::Trait for Runtime {
 
 impl pool::Trait for Runtime {
 	type Event = Event;
+	type Currency = Balances;
+	type Approval = Approve;
+	type Treasury = Treasury;
+	type Council = approve::Council;
+}
+/// Submits the reminders of the approve off-chain worker as unsigned extrinsics
+pub struct ApproveReminders;
+impl approve::SubmitReminder<approve::Call<Runtime>> for ApproveReminders {
+	fn submit(call: approve::Call<Runtime>) {
+		let xt = UncheckedExtrinsic::new_unsigned(Call::Approve(call));
+		runtime_io::submit_extrinsic(&xt);
+	}
 }
+
 impl approve::Trait for Runtime {
 	type Event = Event;
+	type Currency = Balances;
+	type BondSlashed = Treasury;
+	type SubmitReminder = ApproveReminders;
+	type ExecutionOrigin = Origin;
+	type Proposal = Call;
+	type Groups = Groups;
+	type Council = approve::Council;
+}
+/// The enum sets of the indices module, for the groups migration from version 0
+pub struct IndexedAccounts;
+impl groups::AllAccounts<AccountId> for IndexedAccounts {
+	fn account_set(index: u32) -> Option<Vec<AccountId>> {
+		if index > Indices::next_enum_set() {
+			return None;
+		}
+		Some(Indices::enum_set(index))
+	}
 }
+
 impl groups::Trait for Runtime {
 	type Event = Event;
+	type Fees = Fees;
+	type Accounts = IndexedAccounts;
+}
+
+impl substratekitties::Trait for Runtime {
+	type Event = Event;
+	type Fees = Fees;
+}
+
+impl fees::Trait for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type Treasury = Treasury;
+	type Council = approve::Council;
+}
+
+impl treasury::Trait for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type Approval = Approve;
+	type Council = approve::Council;
+}
+
+impl utility::Trait for Runtime {
+	type Event = Event;
+	type Call = Call;
+}
+
+/// The calls of each proxy type. A Kitties or GroupMembership proxy can not batch, since a batch can carry any call.
+pub struct ProxyFilter;
+impl proxy::CallFilter<Call> for ProxyFilter {
+	fn allows(proxy_type: proxy::ProxyType, call: &Call) -> bool {
+		match proxy_type {
+			proxy::ProxyType::Any => true,
+			proxy::ProxyType::Kitties => match call {
+				Call::Substratekitties(_) | Call::Marketplace(_) => true,
+				_ => false,
+			},
+			proxy::ProxyType::GroupMembership => match call {
+				Call::Groups(groups::Call::join_group(..))
+				| Call::Groups(groups::Call::leave_group(..))
+				| Call::Groups(groups::Call::owner_add_member(..))
+				| Call::Groups(groups::Call::owner_remove_member(..)) => true,
+				_ => false,
+			},
+		}
+	}
+}
+
+impl proxy::Trait for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type Call = Call;
+	type Filter = ProxyFilter;
+}
+
+impl recovery::Trait for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type Groups = Groups;
+	type Call = Call;
+}
+
+impl identity::Trait for Runtime {
+	type Event = Event;
+	type Council = approve::Council;
+}
+
+impl messages::Trait for Runtime {
+	type Event = Event;
+	type Groups = Groups;
+}
+
+impl scheduler::Trait for Runtime {
+	type Event = Event;
+	type Call = Call;
+}
+
+impl referrals::Trait for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type Groups = Groups;
+}
+
+impl oracle::Trait for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type Council = approve::Council;
+}
+
+impl marketplace::Trait for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type Nft = Substratekitties;
+	type Treasury = Treasury;
+	type Oracle = Oracle;
+}
+
+impl tokens::Trait for Runtime {
+	type Event = Event;
+	type Balance = Balance;
+}
+
+impl admin::Trait for Runtime {
+	type Event = Event;
+	type Council = approve::Council;
 }
 
 construct_runtime!(
@@ -212,9 +481,24 @@ construct_runtime!(
 		Indices: indices,
 		Balances: balances,
 		Sudo: sudo,
-		Pool: pool::{Module, Call, Storage, Event<T>},
-		Approve: approve::{Module, Call, Storage, Event<T>},
+		Pool: pool::{Module, Call, Storage, Event<T>, Config<T>},
+		Approve: approve::{Module, Call, Storage, Event<T>, Config<T>, Origin},
 		Groups: groups::{Module, Call, Storage, Event<T>, Config<T>},
+		Substratekitties: substratekitties::{Module, Call, Storage, Event<T>, Config<T>},
+		Admin: admin::{Module, Call, Event<T>},
+		Fees: fees::{Module, Call, Storage, Event<T>, Config<T>},
+		Ids: ids::{Module, Storage},
+		Utility: utility::{Module, Call, Event},
+		Proxy: proxy::{Module, Call, Storage, Event<T>, Config<T>},
+		Recovery: recovery::{Module, Call, Storage, Event<T>, Config<T>},
+		Identity: identity::{Module, Call, Storage, Event<T>, Config<T>},
+		Messages: messages::{Module, Call, Storage, Event<T>, Config<T>},
+		Scheduler: scheduler::{Module, Call, Storage, Event<T>},
+		Treasury: treasury::{Module, Call, Storage, Event<T>, Config<T>},
+		Referrals: referrals::{Module, Call, Storage, Event<T>},
+		Oracle: oracle::{Module, Call, Storage, Event<T>, Config<T>},
+		Marketplace: marketplace::{Module, Call, Storage, Event<T>, Config<T>},
+		Tokens: tokens::{Module, Call, Storage, Event<T>},
 	}
 );
 
@@ -235,6 +519,19 @@ pub type CheckedExtrinsic = generic::CheckedExtrinsic<AccountId, Nonce, Call>;
 /// Executive: handles dispatch to the various modules.
 pub type Executive = executive::Executive<Runtime, Block, Context, Balances, AllModules>;
 
+/// The rate limited app call that a call makes directly, checked by validate_transaction(). A call wrapped in a
+/// batch or a proxy call is only checked when it is dispatched.
+fn fee_call(call: &Call) -> Option<FeeCall> {
+	match call {
+		Call::Substratekitties(substratekitties::Call::create_kitty(..)) => Some(FeeCall::CreateKitty),
+		Call::Substratekitties(substratekitties::Call::breed_kitty(..)) => Some(FeeCall::BreedKitty),
+		Call::Groups(groups::Call::create_group(..)) => Some(FeeCall::CreateGroup),
+		Call::Groups(groups::Call::join_group(..))
+		| Call::Referrals(referrals::Call::join_with_referral(..)) => Some(FeeCall::JoinGroup),
+		_ => None,
+	}
+}
+
 // Implement our runtime API endpoints. This is just a bunch of proxying.
 impl_runtime_apis! {
 	impl runtime_api::Core<Block> for Runtime {
@@ -285,7 +582,36 @@ impl_runtime_apis! {
 
 	impl runtime_api::TaggedTransactionQueue<Block> for Runtime {
 		fn validate_transaction(tx: <Block as BlockT>::Extrinsic) -> TransactionValidity {
-			Executive::validate_transaction(tx)
+			// The executive rejects every transaction without a sender, so the unsigned reminders of the approve
+			// off-chain worker are validated here
+			if tx.signature.is_none() {
+				if let Call::Approve(approve::Call::remind(ref proposal_ids)) = tx.function {
+					return Approve::validate_remind(proposal_ids);
+				}
+			}
+
+			let rate_limited = match (&tx.signature, fee_call(&tx.function)) {
+				(Some((address, _, _, _)), Some(call)) => Some((address.clone(), call)),
+				_ => None,
+			};
+			let validity = Executive::validate_transaction(tx);
+
+			// Only a valid transaction has a checked signature, so only then its sender is rate limited. The nonce
+			// tags of the executive are kept and the slot of the call in its rate window is added to them, so
+			// pending calls of the sender that would take the same slot exclude each other in the pool.
+			match (validity, rate_limited) {
+				(TransactionValidity::Valid { priority, requires, mut provides, longevity }, Some((address, call))) => {
+					if let Ok(who) = <Indices as StaticLookup>::lookup(address) {
+						match Fees::rate_tag(&who, call) {
+							Ok(Some(tag)) => provides.push(tag),
+							Ok(None) => {},
+							Err(_) => return TransactionValidity::Invalid(fees::RATE_LIMITED),
+						}
+					}
+					TransactionValidity::Valid { priority, requires, provides, longevity }
+				},
+				(validity, _) => validity,
+			}
 		}
 	}
 
@@ -301,6 +627,108 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl self::ApproveApi<Block> for Runtime {
+		fn proposal_status(proposal_id: Hash) -> Option<approve::ProposalStatus> {
+			Approve::proposal_status(proposal_id)
+		}
+
+		fn votes_of(proposal_id: Hash) -> Vec<(AccountId, bool)> {
+			Approve::votes(proposal_id)
+		}
+
+		fn pending_for(who: AccountId) -> Vec<Hash> {
+			Approve::pending_for(who)
+		}
+
+		fn active_proposals(start: u64, limit: u64) -> Vec<Hash> {
+			Approve::active_proposals(start, limit)
+		}
+	}
+
+	impl self::GroupsApi<Block> for Runtime {
+		fn groups_by_owner(owner: AccountId) -> Vec<Hash> {
+			Groups::groups_by_owner(owner)
+		}
+
+		fn group(group_id: Hash) -> Option<groups::Group<Hash, Moment>> {
+			Groups::group_of(group_id)
+		}
+
+		fn members(group_id: Hash) -> Option<Vec<AccountId>> {
+			Groups::members_of(group_id)
+		}
+
+		fn member_profiles(group_id: Hash) -> Option<Vec<(AccountId, Option<identity::Profile<AccountId, Hash>>)>> {
+			Groups::members_of(group_id).map(Identity::with_profiles)
+		}
+	}
+
+	impl self::KittiesApi<Block> for Runtime {
+		fn kitties_of(owner: AccountId) -> Vec<(Hash, Hash, u64)> {
+			Substratekitties::kitties_of(owner)
+		}
+
+		fn owner_profile(kitty_id: Hash) -> Option<(AccountId, Option<identity::Profile<AccountId, Hash>>)> {
+			Substratekitties::owner_of(kitty_id).map(|owner| (owner.clone(), Identity::profile_of(owner)))
+		}
+	}
+
+	impl self::MarketplaceApi<Block> for Runtime {
+		fn listings(start: u64, limit: u64) -> Vec<(Hash, AccountId, Balance)> {
+			Marketplace::listings(start, limit)
+		}
+
+		fn listing(item_id: Hash) -> Option<(AccountId, Balance)> {
+			Marketplace::listing(item_id)
+		}
+
+		fn offers(item_id: Hash) -> Vec<(AccountId, Balance)> {
+			Marketplace::offers_for(item_id)
+		}
+
+		fn oracle_price() -> Option<Balance> {
+			Oracle::price()
+		}
+	}
+
+	impl self::PoolApi<Block> for Runtime {
+		fn pools(start: u64, limit: u64) -> Vec<(Hash, Option<AccountId>, Balance, Balance, bool)> {
+			Pool::pools(start, limit)
+		}
+	}
+
+	impl self::TokensApi<Block> for Runtime {
+		fn class(class_id: Hash) -> Option<(AccountId, Vec<u8>, Vec<u8>, u8, Balance)> {
+			Tokens::class_of(class_id).map(|c| (c.issuer, c.name, c.symbol, c.decimals, c.supply))
+		}
+
+		fn balances(who: AccountId, class_ids: Vec<Hash>) -> Vec<(Hash, Balance)> {
+			Tokens::balances(who, class_ids)
+		}
+	}
+
+	impl self::MessagesApi<Block> for Runtime {
+		fn messages(group_id: Hash, start: u64, limit: u64) -> Vec<(u64, AccountId, Hash, bool, BlockNumber)> {
+			Messages::messages(group_id, start, limit).into_iter()
+				.map(|(sequence, a)| (sequence, a.author, a.content_hash, a.kind == messages::MessageKind::Announcement, a.block))
+				.collect()
+		}
+	}
+
+	impl self::ExportApi<Block> for Runtime {
+		fn kitties() -> Vec<(AccountId, Hash)> {
+			Substratekitties::export()
+		}
+
+		fn listings() -> Vec<(AccountId, Hash, Balance)> {
+			Marketplace::export()
+		}
+
+		fn groups() -> Vec<(AccountId, Vec<u8>, u32, Vec<AccountId>)> {
+			Groups::export()
+		}
+	}
+
 	impl consensus_authorities::AuthoritiesApi<Block> for Runtime {
 		fn authorities() -> Vec<AuthorityId> {
 			Consensus::authorities()
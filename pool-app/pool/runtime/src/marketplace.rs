@@ -0,0 +1,739 @@
+/// Marketplace trades the items of any module that implements NftProvider, such as kitties, for the currency of the
+/// chain. An item is sold at a fixed price, auctioned to the highest bidder, or sold to an account that made an offer
+/// for it. The asset modules only keep the ownership of their items, every sale goes through here and pays the sale
+/// cut to the treasury.
+///
+/// Listings and auctions do not hold the item, the seller keeps it until the sale. A sale checks that the seller
+/// still owns the item. The listing of an item that changed owner since is stale: it is not shown nor sold, and the
+/// next listing of the item replaces it. Bids and offers are reserved from the buyer until they are outbid,
+/// withdrawn or paid.
+///
+/// A listing is priced in the native currency, or in the stable unit of the price oracle. A stable price is
+/// converted when the item is bought, at the price of the oracle then, so kitties keep their value in the stable
+/// unit while the native currency moves.
+
+use parity_codec::{Encode, Decode};
+use rstd::marker::PhantomData;
+use rstd::prelude::*;
+use runtime_primitives::Permill;
+use runtime_primitives::traits::{CheckedMul, Zero};
+use support::{decl_module, decl_storage, decl_event, ensure, dispatch::Result, StorageMap, StorageValue};
+use support::traits::{Currency, ExistenceRequirement, Imbalance, OnUnbalanced, ReservableCurrency, WithdrawReason};
+use system::ensure_signed;
+
+use app_primitives::{KittyId, NftProvider, PriceOracle};
+use crate::ownership::{OwnershipStorage, Registry};
+
+#[cfg(feature = "std")]
+use runtime_io::{with_storage, StorageOverlay, ChildrenStorageOverlay};
+
+/// The most auctions that end in the same block, so settling them fits in one on_initialize()
+pub const MAX_AUCTIONS_ENDING: usize = 16;
+
+/// The most open offers for one item
+pub const MAX_OFFERS: usize = 16;
+
+/// The balance type of the currency of prices, bids and offers
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+type NegativeImbalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::NegativeImbalance;
+
+pub trait Trait: system::Trait {
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	/// The currency of prices, bids and offers. Bids and offers are reserved in it until they are paid.
+	type Currency: ReservableCurrency<Self::AccountId>;
+
+	/// The items traded here. In the runtime this is the Substratekitties module, the collections of the nft module
+	/// implement it too.
+	type Nft: NftProvider<Self::AccountId, Self::Hash>;
+
+	/// Receives the sale cut of every sale. In the runtime this is the treasury module.
+	type Treasury: OnUnbalanced<NegativeImbalanceOf<Self>>;
+
+	/// Converts the prices of the listings in the stable unit. In the runtime this is the oracle module.
+	type Oracle: PriceOracle<BalanceOf<Self>>;
+}
+
+/// The unit a listing is priced in
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum Denomination {
+	/// The native currency
+	Native,
+	/// The stable unit of the price oracle, converted to the native currency when the item is bought
+	Stable,
+}
+
+impl Default for Denomination {
+	fn default() -> Self {
+		Denomination::Native
+	}
+}
+
+/// An auction of an item, stored under the item id
+#[derive(Encode, Decode, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Auction<AccountId, Balance, BlockNumber> {
+	pub seller: AccountId,
+	/// The lowest first bid
+	pub min_bid: Balance,
+	/// The block the auction is settled in
+	pub end: BlockNumber,
+	/// The highest bid as (bidder, amount), reserved from the bidder
+	pub best_bid: Option<(AccountId, Balance)>,
+}
+
+/// The polkadot-js definitions of the types above, see the types module
+#[cfg(feature = "std")]
+pub fn type_definitions() -> Vec<crate::types::TypeDef> {
+	use crate::types::TypeDef;
+	vec![
+		TypeDef::Enum("Denomination", vec!["Native", "Stable"]),
+		TypeDef::Struct("Auction", vec![
+			("seller", "AccountId"), ("min_bid", "Balance"), ("end", "BlockNumber"), ("best_bid", "Option<(AccountId, Balance)>"),
+		]),
+	]
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Marketplace {
+		// The fraction of each sale price that goes to the Treasury instead of the seller. Set through the admin module.
+		SaleCut get(sale_cut): Permill;
+
+		// The fixed price listings. The sellers are kept in a registry like the owners of kitties, see the ownership
+		// module, so listings can be paged in the order they were listed.
+		ListingSeller get(seller_of): map T::Hash => Option<T::AccountId>;
+		AllListingsArray get(listing_by_index): map u64 => T::Hash;
+		AllListingsCount get(listing_count): u64;
+		AllListingsIndex: map T::Hash => u64;
+		SellerListingsArray: double_map T::AccountId, blake2_256(u64) => T::Hash;
+		SellerListingsCount: map T::AccountId => u64;
+		SellerListingsIndex: map T::Hash => u64;
+		// The price of each listed item, in the denomination of its listing. Only stable listings store a denomination.
+		Prices get(price_of): map T::Hash => BalanceOf<T>;
+		Denominations get(denomination_of): map T::Hash => Denomination;
+
+		// The open auctions by item id, and the items whose auction is settled in each block
+		Auctions get(auction_of): map T::Hash => Option<Auction<T::AccountId, BalanceOf<T>, T::BlockNumber>>;
+		AuctionsEnding get(auctions_ending): map T::BlockNumber => Vec<T::Hash>;
+
+		// The open offers for each item as (buyer, amount), in the order they were made
+		Offers get(offers_for): map T::Hash => Vec<(T::AccountId, BalanceOf<T>)>;
+	}
+
+	add_extra_genesis {
+		config(listings): Vec<(T::AccountId, T::Hash, BalanceOf<T>)>;
+
+		build(|storage: &mut StorageOverlay, _: &mut ChildrenStorageOverlay, config: &GenesisConfig<T>| {
+			with_storage(storage, || {
+				for &(ref seller, item_id, price) in &config.listings {
+					let _ = <Module<T>>::insert_listing(seller, item_id, price);
+				}
+			});
+		});
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where
+		<T as system::Trait>::AccountId,
+		<T as system::Trait>::Hash,
+		<T as system::Trait>::BlockNumber,
+		Balance = BalanceOf<T>
+	{
+		/// An item was listed at a fixed price as (seller, item id, price, denomination of the price)
+		Listed(AccountId, Hash, Balance, Denomination),
+
+		/// The listing of an item was removed by its seller
+		Unlisted(Hash),
+
+		/// An item was sold as (item id, seller, buyer, price), through a listing, an auction or an offer
+		Sold(Hash, AccountId, AccountId, Balance),
+
+		/// An auction started as (seller, item id, min bid, end block)
+		AuctionStarted(AccountId, Hash, Balance, BlockNumber),
+
+		/// A bid became the highest bid of an auction as (item id, bidder, amount)
+		BidPlaced(Hash, AccountId, Balance),
+
+		/// An auction without bids was cancelled by its seller
+		AuctionCancelled(Hash),
+
+		/// An auction ended without a sale, because it had no bids or the seller no longer owned the item
+		AuctionFailed(Hash),
+
+		/// An offer for an item was made as (item id, buyer, amount)
+		OfferMade(Hash, AccountId, Balance),
+
+		/// An offer for an item was withdrawn by its buyer
+		OfferWithdrawn(Hash, AccountId),
+	}
+);
+
+decl_module! {
+	/// The module declaration.
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+
+		fn deposit_event<T>() = default;
+
+		fn on_initialize(n: T::BlockNumber) {
+			for item_id in <AuctionsEnding<T>>::take(n) {
+				Self::settle_auction(item_id);
+			}
+		}
+
+		/// List an item at a fixed price in the native currency, or change the price of its listing.
+		/// Rule: the owner of the item.
+		pub fn list(origin, item_id: T::Hash, price: BalanceOf<T>) -> Result {
+			let sender = ensure_signed(origin)?;
+			Self::list_item(sender, item_id, price, Denomination::Native)
+		}
+
+		/// List an item at a fixed price in the stable unit of the price oracle, or change the price of its
+		/// listing. Rule: the owner of the item.
+		pub fn list_stable(origin, item_id: T::Hash, price: BalanceOf<T>) -> Result {
+			let sender = ensure_signed(origin)?;
+			Self::list_item(sender, item_id, price, Denomination::Stable)
+		}
+
+		/// Remove the fixed price listing of an item. Rule: the seller.
+		pub fn unlist(origin, item_id: T::Hash) -> Result {
+			let sender = ensure_signed(origin)?;
+			let seller = Self::seller_of(item_id).ok_or("This item is not listed")?;
+			ensure!(seller == sender, "Only the seller can unlist this item");
+
+			Self::remove_listing(item_id);
+			Self::deposit_event(RawEvent::Unlisted(item_id));
+			Ok(())
+		}
+
+		/// Buy a listed item at its price in the native currency, if it is not above max_price
+		pub fn buy(origin, item_id: T::Hash, max_price: BalanceOf<T>) -> Result {
+			let sender = ensure_signed(origin)?;
+			let seller = Self::seller_of(item_id).ok_or("This item is not listed")?;
+			ensure!(seller != sender, "You already own this item");
+			ensure!(T::Nft::owner_of(KittyId(item_id)) == Some(seller.clone()), "The seller no longer owns this item");
+			let price = Self::sale_price(item_id).ok_or("The oracle has no price for this listing")?;
+			ensure!(price <= max_price, "The item costs more than your max price");
+
+			let paid = T::Currency::withdraw(&sender, price, WithdrawReason::Transfer, ExistenceRequirement::KeepAlive)?;
+			Self::settle(&seller, &sender, item_id, paid)
+		}
+
+		/// Auction an item until `duration` blocks from now. The highest bid of at least min_bid buys the item when
+		/// the auction ends. Rule: the owner of the item, which is not listed at a fixed price.
+		pub fn start_auction(origin, item_id: T::Hash, min_bid: BalanceOf<T>, duration: T::BlockNumber) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(T::Nft::owner_of(KittyId(item_id)) == Some(sender.clone()), "You do not own this item");
+			ensure!(!<Auctions<T>>::exists(item_id), "This item is being auctioned");
+			ensure!(Self::seller_of(item_id) != Some(sender.clone()), "This item is listed, unlist it first");
+			ensure!(!duration.is_zero(), "An auction must last at least one block");
+			let end = <system::Module<T>>::block_number() + duration;
+			let mut ending = Self::auctions_ending(end);
+			ensure!(ending.len() < MAX_AUCTIONS_ENDING, "Too many auctions end in this block");
+
+			// A listing of a previous owner is stale
+			Self::remove_listing(item_id);
+			ending.push(item_id);
+			<AuctionsEnding<T>>::insert(end, ending);
+			<Auctions<T>>::insert(item_id, Auction { seller: sender.clone(), min_bid, end, best_bid: None });
+
+			Self::deposit_event(RawEvent::AuctionStarted(sender, item_id, min_bid, end));
+			Ok(())
+		}
+
+		/// Bid on an auction. The bid is reserved until it is outbid or the auction ends, and must be at least the
+		/// min bid and above the highest bid.
+		pub fn bid(origin, item_id: T::Hash, amount: BalanceOf<T>) -> Result {
+			let sender = ensure_signed(origin)?;
+			let mut auction = Self::auction_of(item_id).ok_or("This item is not auctioned")?;
+			ensure!(<system::Module<T>>::block_number() < auction.end, "This auction has ended");
+			ensure!(auction.seller != sender, "Can not bid on your own auction");
+			ensure!(!amount.is_zero() && amount >= auction.min_bid, "Bid is below the min bid");
+			if let Some((_, ref best)) = auction.best_bid {
+				ensure!(amount > *best, "Bid is not above the highest bid");
+			}
+			T::Currency::reserve(&sender, amount).map_err(|_| "Bidder can not pay the bid")?;
+
+			if let Some((bidder, best)) = auction.best_bid.take() {
+				T::Currency::unreserve(&bidder, best);
+			}
+			auction.best_bid = Some((sender.clone(), amount));
+			<Auctions<T>>::insert(item_id, auction);
+
+			Self::deposit_event(RawEvent::BidPlaced(item_id, sender, amount));
+			Ok(())
+		}
+
+		/// Cancel an auction that has no bids. Rule: the seller.
+		pub fn cancel_auction(origin, item_id: T::Hash) -> Result {
+			let sender = ensure_signed(origin)?;
+			let auction = Self::auction_of(item_id).ok_or("This item is not auctioned")?;
+			ensure!(auction.seller == sender, "Only the seller can cancel this auction");
+			ensure!(auction.best_bid.is_none(), "Can not cancel an auction with bids");
+
+			<Auctions<T>>::remove(item_id);
+			<AuctionsEnding<T>>::mutate(auction.end, |ending| ending.retain(|id| *id != item_id));
+			Self::deposit_event(RawEvent::AuctionCancelled(item_id));
+			Ok(())
+		}
+
+		/// Offer to buy an item, listed or not. The amount is reserved until the owner accepts the offer or it is
+		/// withdrawn. One offer per account and item, at most MAX_OFFERS per item.
+		pub fn make_offer(origin, item_id: T::Hash, amount: BalanceOf<T>) -> Result {
+			let sender = ensure_signed(origin)?;
+			let owner = T::Nft::owner_of(KittyId(item_id)).ok_or("This item does not exist")?;
+			ensure!(owner != sender, "You already own this item");
+			ensure!(!amount.is_zero(), "Offer must be greater than zero");
+			let mut offers = Self::offers_for(item_id);
+			ensure!(!offers.iter().any(|(buyer, _)| *buyer == sender), "You already made an offer for this item");
+			ensure!(offers.len() < MAX_OFFERS, "Too many offers for this item");
+			T::Currency::reserve(&sender, amount).map_err(|_| "Buyer can not pay the offer")?;
+
+			offers.push((sender.clone(), amount));
+			<Offers<T>>::insert(item_id, offers);
+			Self::deposit_event(RawEvent::OfferMade(item_id, sender, amount));
+			Ok(())
+		}
+
+		/// Withdraw an offer, which unreserves its amount
+		pub fn withdraw_offer(origin, item_id: T::Hash) -> Result {
+			let sender = ensure_signed(origin)?;
+			let amount = Self::take_offer(item_id, &sender).ok_or("No offer for this item")?;
+
+			T::Currency::unreserve(&sender, amount);
+			Self::deposit_event(RawEvent::OfferWithdrawn(item_id, sender));
+			Ok(())
+		}
+
+		/// Sell an item to the buyer of an offer, at the amount of the offer. Rule: the owner of the item, which is
+		/// not auctioned.
+		pub fn accept_offer(origin, item_id: T::Hash, buyer: T::AccountId) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(T::Nft::owner_of(KittyId(item_id)) == Some(sender.clone()), "You do not own this item");
+			ensure!(!<Auctions<T>>::exists(item_id), "This item is being auctioned");
+			let amount = Self::take_offer(item_id, &buyer).ok_or("No offer from this account")?;
+
+			let (paid, _) = T::Currency::slash_reserved(&buyer, amount);
+			Self::settle(&sender, &buyer, item_id, paid)
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// Items listed at a fixed price by their owner as (item id, seller, price). Reads only the listings at the
+	/// positions start..start + limit and leaves out the stale ones, so a page can hold fewer than `limit` items and
+	/// the next page starts at start + limit. Used by the MarketplaceApi runtime API, so marketplace UIs do not
+	/// have to fetch every item.
+	pub fn listings(start: u64, limit: u64) -> Vec<(T::Hash, T::AccountId, BalanceOf<T>)> {
+		let end = start.saturating_add(limit).min(Self::listing_count());
+		(start..end)
+			.map(Self::listing_by_index)
+			.filter_map(|item_id| Self::listing(item_id).map(|(seller, price)| (item_id, seller, price)))
+			.collect()
+	}
+
+	/// The seller and native price of an item, or None if it is not listed, the listing is stale, or it is priced
+	/// in the stable unit while the oracle has no price
+	pub fn listing(item_id: T::Hash) -> Option<(T::AccountId, BalanceOf<T>)> {
+		let seller = Self::seller_of(item_id)?;
+		if T::Nft::owner_of(KittyId(item_id)) != Some(seller.clone()) {
+			return None
+		}
+		Some((seller, Self::sale_price(item_id)?))
+	}
+
+	/// The native price a listed item sells at now. Stable prices are converted at the price of the oracle.
+	pub fn sale_price(item_id: T::Hash) -> Option<BalanceOf<T>> {
+		let price = Self::price_of(item_id);
+		match Self::denomination_of(item_id) {
+			Denomination::Native => Some(price),
+			Denomination::Stable => T::Oracle::price().and_then(|unit_price| unit_price.checked_mul(&price)),
+		}
+	}
+
+	/// Every listing as (seller, item id, price), the format of the listings genesis config. Stable listings are
+	/// exported at their native price in the block, and left out while the oracle has no price. Used by the
+	/// ExportApi runtime API.
+	pub fn export() -> Vec<(T::AccountId, T::Hash, BalanceOf<T>)> {
+		Self::listings(0, u64::max_value())
+			.into_iter()
+			.map(|(item_id, seller, price)| (seller, item_id, price))
+			.collect()
+	}
+
+	/// Sets the fraction of each sale price that goes to the treasury. Called by the admin module, which checks the
+	/// origin.
+	pub(crate) fn set_sale_cut(sale_cut: Permill) {
+		<SaleCut<T>>::put(sale_cut);
+	}
+
+	// Private method called by: list() and list_stable()
+	fn list_item(sender: T::AccountId, item_id: T::Hash, price: BalanceOf<T>, denomination: Denomination) -> Result {
+		ensure!(T::Nft::owner_of(KittyId(item_id)) == Some(sender.clone()), "You do not own this item");
+		ensure!(!price.is_zero(), "Price must be greater than zero");
+		ensure!(!<Auctions<T>>::exists(item_id), "This item is being auctioned");
+
+		match Self::seller_of(item_id) {
+			Some(ref seller) if *seller == sender => <Prices<T>>::insert(item_id, price),
+			_ => {
+				// A listing of a previous owner is stale and makes way for the new one
+				Self::remove_listing(item_id);
+				Self::insert_listing(&sender, item_id, price)?;
+			},
+		}
+		match denomination {
+			Denomination::Native => <Denominations<T>>::remove(item_id),
+			Denomination::Stable => <Denominations<T>>::insert(item_id, denomination),
+		}
+
+		Self::deposit_event(RawEvent::Listed(sender, item_id, price, denomination));
+		Ok(())
+	}
+
+	// Private method called by: buy(), accept_offer() and settle_auction(). Moves the item from the seller to the
+	// buyer, then pays the price held in `paid` to the seller, less the sale cut that goes to the treasury. If the
+	// item can not move, the funds go back to the buyer.
+	fn settle(seller: &T::AccountId, buyer: &T::AccountId, item_id: T::Hash, paid: NegativeImbalanceOf<T>) -> Result {
+		let price = paid.peek();
+		if let Err(e) = T::Nft::transfer(seller.clone(), buyer.clone(), KittyId(item_id)) {
+			let _ = T::Currency::deposit_creating(buyer, price);
+			return Err(e)
+		}
+		let (cut, proceeds) = paid.split(Self::sale_cut() * price);
+		let _ = T::Currency::deposit_creating(seller, proceeds.peek());
+		T::Treasury::on_unbalanced(cut);
+
+		// The listing is sold, or stale if the item was sold by auction or offer
+		Self::remove_listing(item_id);
+		Self::deposit_event(RawEvent::Sold(item_id, seller.clone(), buyer.clone(), price));
+		Ok(())
+	}
+
+	// Private method called by: on_initialize() in the end block of an auction. Sells the item to the highest
+	// bidder, or unreserves the bid if the seller no longer owns the item.
+	fn settle_auction(item_id: T::Hash) {
+		let auction = match Self::auction_of(item_id) {
+			Some(auction) => auction,
+			None => return,
+		};
+		<Auctions<T>>::remove(item_id);
+
+		let sold = match auction.best_bid {
+			Some((bidder, amount)) => {
+				if T::Nft::owner_of(KittyId(item_id)) == Some(auction.seller.clone()) {
+					let (paid, _) = T::Currency::slash_reserved(&bidder, amount);
+					Self::settle(&auction.seller, &bidder, item_id, paid).is_ok()
+				} else {
+					T::Currency::unreserve(&bidder, amount);
+					false
+				}
+			},
+			None => false,
+		};
+		if !sold {
+			Self::deposit_event(RawEvent::AuctionFailed(item_id));
+		}
+	}
+
+	// Private method called by: withdraw_offer() and accept_offer(). Removes the offer of the buyer and returns its
+	// amount, or None without changes if there is no offer.
+	fn take_offer(item_id: T::Hash, buyer: &T::AccountId) -> Option<BalanceOf<T>> {
+		let mut offers = Self::offers_for(item_id);
+		let position = offers.iter().position(|(b, _)| b == buyer)?;
+		let (_, amount) = offers.remove(position);
+		if offers.is_empty() {
+			<Offers<T>>::remove(item_id);
+		} else {
+			<Offers<T>>::insert(item_id, offers);
+		}
+		Some(amount)
+	}
+
+	// Called by: list_item() and the genesis config
+	fn insert_listing(seller: &T::AccountId, item_id: T::Hash, price: BalanceOf<T>) -> Result {
+		<Listings<T>>::insert(seller, item_id)?;
+		<Prices<T>>::insert(item_id, price);
+		Ok(())
+	}
+
+	// Private method called by: list_item(), unlist(), start_auction() and settle(). Does nothing if the item is not
+	// listed.
+	fn remove_listing(item_id: T::Hash) {
+		if <Listings<T>>::remove(item_id).is_ok() {
+			<Prices<T>>::remove(item_id);
+			<Denominations<T>>::remove(item_id);
+		}
+	}
+}
+
+/// The storage items that register the seller of each listed item, see the ownership module
+struct ListingStorage<T>(PhantomData<T>);
+
+impl<T: Trait> OwnershipStorage for ListingStorage<T> {
+	type Owner = T::AccountId;
+	type Id = T::Hash;
+	type OwnerOf = ListingSeller<T>;
+	type AllArray = AllListingsArray<T>;
+	type AllCount = AllListingsCount<T>;
+	type AllIndex = AllListingsIndex<T>;
+	type OwnedArray = SellerListingsArray<T>;
+	type OwnedCount = SellerListingsCount<T>;
+	type OwnedIndex = SellerListingsIndex<T>;
+}
+
+type Listings<T> = Registry<ListingStorage<T>>;
+
+// *****************************************************************************************************
+// Unit Tests!
+// *****************************************************************************************************
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use runtime_io::with_externalities;
+	use primitives::{H256, Blake2Hasher};
+	use support::{impl_outer_origin, assert_ok, assert_noop};
+	use runtime_primitives::{
+		BuildStorage,
+		traits::{BlakeTwo256, IdentityLookup, OnInitialize},
+		testing::{Digest, DigestItem, Header}
+	};
+
+	impl_outer_origin! {
+		pub enum Origin for MarketplaceTest {}
+	}
+
+	// The owner of an item is read from storage under the item id, see set_owner()
+	pub struct MockNft;
+	impl NftProvider<u64, H256> for MockNft {
+		fn owner_of(item_id: KittyId<H256>) -> Option<u64> {
+			runtime_io::storage(item_id.0.as_ref()).and_then(|v| Decode::decode(&mut &v[..]))
+		}
+
+		fn transfer(from: u64, to: u64, item_id: KittyId<H256>) -> std::result::Result<(), &'static str> {
+			ensure!(Self::owner_of(item_id) == Some(from), "From account is not the owner");
+			set_owner(item_id.0, to);
+			Ok(())
+		}
+	}
+
+	fn set_owner(item_id: H256, owner: u64) {
+		runtime_io::set_storage(item_id.as_ref(), &owner.encode());
+	}
+
+	// The oracle price is read from storage, see set_oracle_price()
+	pub struct MockOracle;
+	impl PriceOracle<u64> for MockOracle {
+		fn price() -> Option<u64> {
+			runtime_io::storage(b"oracle_price").and_then(|v| Decode::decode(&mut &v[..]))
+		}
+	}
+
+	fn set_oracle_price(price: u64) {
+		runtime_io::set_storage(b"oracle_price", &price.encode());
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct MarketplaceTest;
+	impl system::Trait for MarketplaceTest {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type Digest = Digest;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type Log = DigestItem;
+	}
+	impl balances::Trait for MarketplaceTest {
+		type Balance = u64;
+		type OnFreeBalanceZero = ();
+		type OnNewAccount = ();
+		type Event = ();
+		type TransactionPayment = ();
+		type TransferPayment = ();
+		type DustRemoval = ();
+	}
+	impl Trait for MarketplaceTest {
+		type Event = ();
+		type Currency = balances::Module<MarketplaceTest>;
+		type Nft = MockNft;
+		type Treasury = ();
+		type Oracle = MockOracle;
+	}
+	type Marketplace = Module<MarketplaceTest>;
+	type Balances = balances::Module<MarketplaceTest>;
+
+	// Account 1 owns the items 1 and 2, the accounts 2 and 3 have 200 each
+	fn build_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+		let mut t = system::GenesisConfig::<MarketplaceTest>::default().build_storage().unwrap().0;
+		t.extend(balances::GenesisConfig::<MarketplaceTest> {
+			balances: vec![(2, 200), (3, 200)],
+			transaction_base_fee: 0,
+			transaction_byte_fee: 0,
+			existential_deposit: 0,
+			transfer_fee: 0,
+			creation_fee: 0,
+			vesting: vec![],
+		}.build_storage().unwrap().0);
+		let mut ext: runtime_io::TestExternalities<Blake2Hasher> = t.into();
+		with_externalities(&mut ext, || {
+			set_owner(H256::repeat_byte(1), 1);
+			set_owner(H256::repeat_byte(2), 1);
+		});
+		ext
+	}
+
+	/// Fixed price test objectives:
+	/// * Only the owner lists an item, and a buyer pays the price to the seller less the sale cut
+	/// * A listing of an item that changed owner is stale: it is not listed nor sold, and the new owner replaces it
+	#[test]
+	fn listed_items_should_be_sold_at_their_price() {
+		with_externalities(&mut build_ext(), || {
+			let item = H256::repeat_byte(1);
+			assert_noop!(Marketplace::list(Origin::signed(2), item, 100), "You do not own this item");
+			assert_ok!(Marketplace::list(Origin::signed(1), item, 100));
+			assert_eq!(Marketplace::listings(0, 10), vec![(item, 1, 100)]);
+
+			assert_noop!(Marketplace::buy(Origin::signed(2), item, 50), "The item costs more than your max price");
+			Marketplace::set_sale_cut(Permill::from_percent(10));
+			assert_ok!(Marketplace::buy(Origin::signed(2), item, 100));
+			assert_eq!(MockNft::owner_of(KittyId(item)), Some(2));
+			assert_eq!(Balances::free_balance(&2), 100);
+			assert_eq!(Balances::free_balance(&1), 90);
+			// the () treasury of the test runtime burns the cut
+			assert_eq!(Balances::total_issuance(), 390);
+			assert_eq!(Marketplace::listings(0, 10), vec![]);
+
+			let other = H256::repeat_byte(2);
+			assert_ok!(Marketplace::list(Origin::signed(1), other, 50));
+			set_owner(other, 3);
+			assert_eq!(Marketplace::listing(other), None);
+			assert_noop!(Marketplace::buy(Origin::signed(2), other, 50), "The seller no longer owns this item");
+			assert_ok!(Marketplace::list(Origin::signed(3), other, 60));
+			assert_eq!(Marketplace::listings(0, 10), vec![(other, 3, 60)]);
+		});
+	}
+
+	/// Listing page test objectives:
+	/// * A page holds the listings at the positions start..start + limit
+	/// * A stale listing is left out of its page, and does not pull a later listing into it
+	#[test]
+	fn listings_should_be_paged_by_position() {
+		with_externalities(&mut build_ext(), || {
+			let (first, second) = (H256::repeat_byte(1), H256::repeat_byte(2));
+			assert_ok!(Marketplace::list(Origin::signed(1), first, 100));
+			assert_ok!(Marketplace::list(Origin::signed(1), second, 50));
+			assert_eq!(Marketplace::listings(0, 1), vec![(first, 1, 100)]);
+			assert_eq!(Marketplace::listings(1, 1), vec![(second, 1, 50)]);
+			assert_eq!(Marketplace::listings(1, u64::max_value()), vec![(second, 1, 50)]);
+			assert_eq!(Marketplace::listings(2, 10), vec![]);
+
+			set_owner(first, 3);
+			assert_eq!(Marketplace::listings(0, 1), vec![]);
+			assert_eq!(Marketplace::listings(0, 2), vec![(second, 1, 50)]);
+		});
+	}
+
+	/// Stable price test objectives:
+	/// * A stable listing is not listed nor sold while the oracle has no price
+	/// * The buyer pays the stable price at the oracle price of the sale, and max_price bounds the native price
+	/// * Listing at a native price again drops the stable denomination
+	#[test]
+	fn stable_listings_should_sell_at_the_oracle_price() {
+		with_externalities(&mut build_ext(), || {
+			let item = H256::repeat_byte(1);
+			assert_ok!(Marketplace::list_stable(Origin::signed(1), item, 20));
+			assert_eq!(Marketplace::listing(item), None);
+			assert_noop!(Marketplace::buy(Origin::signed(2), item, 200), "The oracle has no price for this listing");
+
+			set_oracle_price(3);
+			assert_eq!(Marketplace::listing(item), Some((1, 60)));
+			set_oracle_price(5);
+			assert_eq!(Marketplace::listings(0, 10), vec![(item, 1, 100)]);
+			assert_noop!(Marketplace::buy(Origin::signed(2), item, 60), "The item costs more than your max price");
+			assert_ok!(Marketplace::buy(Origin::signed(2), item, 100));
+			assert_eq!(Balances::free_balance(&2), 100);
+			assert_eq!(Balances::free_balance(&1), 100);
+
+			let other = H256::repeat_byte(2);
+			assert_ok!(Marketplace::list_stable(Origin::signed(1), other, 20));
+			assert_eq!(Marketplace::denomination_of(other), Denomination::Stable);
+			assert_ok!(Marketplace::list(Origin::signed(1), other, 20));
+			assert_eq!(Marketplace::denomination_of(other), Denomination::Native);
+			assert_eq!(Marketplace::listing(other), Some((1, 20)));
+		});
+	}
+
+	/// Auction test objectives:
+	/// * Each bid is reserved and must beat the highest bid, which is unreserved when it is outbid
+	/// * The highest bidder buys the item when the auction ends
+	/// * The bid is unreserved if the seller no longer owns the item at the end
+	#[test]
+	fn auctions_should_sell_to_the_highest_bidder() {
+		with_externalities(&mut build_ext(), || {
+			let item = H256::repeat_byte(1);
+			<system::Module<MarketplaceTest>>::set_block_number(1);
+			assert_ok!(Marketplace::start_auction(Origin::signed(1), item, 20, 5));
+			assert_noop!(Marketplace::list(Origin::signed(1), item, 100), "This item is being auctioned");
+
+			assert_noop!(Marketplace::bid(Origin::signed(1), item, 30), "Can not bid on your own auction");
+			assert_noop!(Marketplace::bid(Origin::signed(2), item, 10), "Bid is below the min bid");
+			assert_ok!(Marketplace::bid(Origin::signed(2), item, 30));
+			assert_noop!(Marketplace::bid(Origin::signed(3), item, 30), "Bid is not above the highest bid");
+			assert_ok!(Marketplace::bid(Origin::signed(3), item, 40));
+			assert_eq!(Balances::reserved_balance(&2), 0);
+			assert_eq!(Balances::reserved_balance(&3), 40);
+			assert_noop!(Marketplace::cancel_auction(Origin::signed(1), item), "Can not cancel an auction with bids");
+
+			Marketplace::on_initialize(6);
+			assert_eq!(MockNft::owner_of(KittyId(item)), Some(3));
+			assert_eq!(Balances::free_balance(&3), 160);
+			assert_eq!(Balances::reserved_balance(&3), 0);
+			assert_eq!(Balances::free_balance(&1), 40);
+			assert_eq!(Marketplace::auction_of(item), None);
+
+			let other = H256::repeat_byte(2);
+			<system::Module<MarketplaceTest>>::set_block_number(6);
+			assert_ok!(Marketplace::start_auction(Origin::signed(1), other, 10, 2));
+			assert_ok!(Marketplace::bid(Origin::signed(2), other, 10));
+			set_owner(other, 4);
+			Marketplace::on_initialize(8);
+			assert_eq!(MockNft::owner_of(KittyId(other)), Some(4));
+			assert_eq!(Balances::free_balance(&2), 200);
+			assert_eq!(Balances::reserved_balance(&2), 0);
+		});
+	}
+
+	/// Offer test objectives:
+	/// * An offer is reserved from the buyer until it is withdrawn or accepted
+	/// * The owner sells the item to the buyer of an offer at its amount
+	#[test]
+	fn offers_should_be_reserved_until_accepted() {
+		with_externalities(&mut build_ext(), || {
+			let item = H256::repeat_byte(1);
+			assert_noop!(Marketplace::make_offer(Origin::signed(1), item, 50), "You already own this item");
+			assert_ok!(Marketplace::make_offer(Origin::signed(2), item, 50));
+			assert_noop!(Marketplace::make_offer(Origin::signed(2), item, 60), "You already made an offer for this item");
+			assert_ok!(Marketplace::make_offer(Origin::signed(3), item, 70));
+			assert_eq!(Balances::reserved_balance(&3), 70);
+
+			assert_ok!(Marketplace::withdraw_offer(Origin::signed(3), item));
+			assert_eq!(Balances::reserved_balance(&3), 0);
+			assert_noop!(Marketplace::accept_offer(Origin::signed(2), item, 2), "You do not own this item");
+			assert_noop!(Marketplace::accept_offer(Origin::signed(1), item, 3), "No offer from this account");
+
+			assert_ok!(Marketplace::accept_offer(Origin::signed(1), item, 2));
+			assert_eq!(MockNft::owner_of(KittyId(item)), Some(2));
+			assert_eq!(Balances::free_balance(&2), 150);
+			assert_eq!(Balances::reserved_balance(&2), 0);
+			assert_eq!(Balances::free_balance(&1), 50);
+			assert_eq!(Marketplace::offers_for(item), vec![]);
+		});
+	}
+}
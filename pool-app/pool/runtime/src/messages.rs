@@ -0,0 +1,278 @@
+/// Messages anchors the messages of a group on chain, so dapps can show a chat or an announcement board whose
+/// history nobody can reorder, drop or forge. The content stays off-chain, the chain keeps its hash, the author
+/// and a sequence number per group. A client that fetched the content checks it against the anchor with the same
+/// number.
+///
+/// The members of a group can anchor chat messages, and only its owner announcements. Each author has a budget
+/// of messages per group in each window of blocks, so one member can not flood the history of a group.
+
+use parity_codec::{Encode, Decode};
+use rstd::prelude::*;
+use runtime_primitives::traits::Zero;
+use support::{decl_module, decl_storage, decl_event, ensure, dispatch::Result, StorageDoubleMap, StorageMap};
+use system::ensure_signed;
+
+use app_primitives::{GroupId, GroupInspector};
+
+/// The most anchors returned by one call of messages()
+pub const MAX_MESSAGES_PAGE: u64 = 100;
+
+pub trait Trait: system::Trait {
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	/// The groups that messages are anchored against. Implemented by the Groups module.
+	type Groups: GroupInspector<Self::AccountId, Self::Hash>;
+}
+
+/// What a message is to the dapps that show it
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum MessageKind {
+	/// A message of a member
+	Chat,
+	/// A message of the owner to all members
+	Announcement,
+}
+
+/// The anchor of a message, stored under its group and sequence number
+#[derive(Encode, Decode, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Anchor<AccountId, Hash, BlockNumber> {
+	pub author: AccountId,
+	/// The hash of the off-chain content
+	pub content_hash: Hash,
+	pub kind: MessageKind,
+	/// The block the message was anchored in
+	pub block: BlockNumber,
+}
+
+/// The polkadot-js definitions of the types above, see the types module
+#[cfg(feature = "std")]
+pub fn type_definitions() -> Vec<crate::types::TypeDef> {
+	use crate::types::TypeDef;
+	vec![
+		TypeDef::Enum("MessageKind", vec!["Chat", "Announcement"]),
+		TypeDef::Struct("Anchor", vec![
+			("author", "AccountId"), ("content_hash", "Hash"), ("kind", "MessageKind"), ("block", "BlockNumber"),
+		]),
+	]
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Messages {
+		// Config values that match the values in the testnet_genesis in chain_spec.rs
+		// Each author can anchor messages_per_window messages per group in each window of rate_window blocks.
+		MessagesPerWindow get(messages_per_window) config(): u32;
+		RateWindow get(rate_window) config(): T::BlockNumber;
+
+		// The sequence number of the next message of each group, which is also the number of its messages
+		MessageCount get(message_count): map T::Hash => u64;
+		// The anchors of each group by sequence number
+		Anchors get(anchor): double_map T::Hash, blake2_256(u64) => Option<Anchor<T::AccountId, T::Hash, T::BlockNumber>>;
+		// The start of the current window of each author in each group, and the messages anchored in it
+		AuthorWindow get(author_window): map (T::Hash, T::AccountId) => (T::BlockNumber, u32);
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where
+		<T as system::Trait>::AccountId,
+		<T as system::Trait>::Hash
+	{
+		/// A message was anchored as (group id, sequence number, author, content hash, kind)
+		MessageAnchored(Hash, u64, AccountId, Hash, MessageKind),
+	}
+);
+
+decl_module! {
+	/// The module declaration.
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+
+		fn deposit_event<T>() = default;
+
+		/// Anchor the hash of a message against a group, with the next sequence number of the group.
+		/// Rule: members for chat messages, the owner for announcements.
+		pub fn anchor_message(origin, group_id: T::Hash, content_hash: T::Hash, kind: MessageKind) -> Result {
+			let sender = ensure_signed(origin)?;
+			match kind {
+				MessageKind::Chat => {
+					ensure!(T::Groups::is_member(GroupId(group_id), &sender), "Only members can post to this group");
+				},
+				MessageKind::Announcement => {
+					let owner = T::Groups::owner(GroupId(group_id));
+					ensure!(owner.as_ref() == Some(&sender), "Only the owner can announce to this group");
+				},
+			}
+			let window = Self::check_rate(&group_id, &sender)?;
+			let sequence = Self::message_count(group_id);
+			let next_sequence = sequence.checked_add(1).ok_or("Overflow adding a new message")?;
+
+			let block = <system::Module<T>>::block_number();
+			<Anchors<T>>::insert(&group_id, &sequence, Anchor { author: sender.clone(), content_hash, kind, block });
+			<MessageCount<T>>::insert(group_id, next_sequence);
+			<AuthorWindow<T>>::insert((group_id, sender.clone()), window);
+
+			Self::deposit_event(RawEvent::MessageAnchored(group_id, sequence, sender, content_hash, kind));
+			Ok(())
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	// Private method called by: anchor_message(). The window of the author after one more message, or an error if
+	// the author used up the budget of the current window.
+	fn check_rate(group_id: &T::Hash, author: &T::AccountId) -> rstd::result::Result<(T::BlockNumber, u32), &'static str> {
+		let now = <system::Module<T>>::block_number();
+		let (start, count) = Self::author_window((*group_id, author.clone()));
+		let window = Self::rate_window();
+		if window.is_zero() || start + window <= now {
+			return Ok((now, 1));
+		}
+		ensure!(count < Self::messages_per_window(), "Too many messages to this group, wait for the next window");
+		Ok((start, count + 1))
+	}
+
+	/// A page of at most `limit` anchors of a group as (sequence number, anchor), starting at number `start`.
+	/// Used by the MessagesApi runtime API.
+	pub fn messages(group_id: T::Hash, start: u64, limit: u64) -> Vec<(u64, Anchor<T::AccountId, T::Hash, T::BlockNumber>)> {
+		let end = Self::message_count(group_id).min(start.saturating_add(limit.min(MAX_MESSAGES_PAGE)));
+		(start..end)
+			.filter_map(|sequence| Self::anchor(&group_id, &sequence).map(|anchor| (sequence, anchor)))
+			.collect()
+	}
+}
+
+// *****************************************************************************************************
+// Unit Tests!
+// *****************************************************************************************************
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use runtime_io::with_externalities;
+	use primitives::{H256, Blake2Hasher};
+	use support::{impl_outer_origin, assert_ok, assert_noop, StorageValue};
+	use runtime_primitives::{
+		BuildStorage,
+		traits::{BlakeTwo256, IdentityLookup},
+		testing::{Digest, DigestItem, Header}
+	};
+
+	impl_outer_origin! {
+		pub enum Origin for MessagesTest {}
+	}
+
+	// Group 1 is owned by account 1, with the members 2 and 3
+	pub struct MockGroups;
+	impl GroupInspector<u64, H256> for MockGroups {
+		fn members(group_id: GroupId<H256>) -> Option<Vec<u64>> {
+			if group_id.0 == H256::repeat_byte(1) { Some(vec![2, 3]) } else { None }
+		}
+
+		fn is_member(group_id: GroupId<H256>, who: &u64) -> bool {
+			Self::members(group_id).map_or(false, |members| members.contains(who))
+		}
+
+		fn owner(group_id: GroupId<H256>) -> Option<u64> {
+			if group_id.0 == H256::repeat_byte(1) { Some(1) } else { None }
+		}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct MessagesTest;
+	impl system::Trait for MessagesTest {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type Digest = Digest;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type Log = DigestItem;
+	}
+	impl Trait for MessagesTest {
+		type Event = ();
+		type Groups = MockGroups;
+	}
+	type Messages = Module<MessagesTest>;
+
+	fn build_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+		let mut t = system::GenesisConfig::<MessagesTest>::default().build_storage().unwrap().0;
+		t.extend(GenesisConfig::<MessagesTest> {
+			messages_per_window: 2,
+			rate_window: 10,
+		}.build_storage().unwrap().0);
+		t.into()
+	}
+
+	/// Type definitions test objectives:
+	/// * Anchor encodes like its fields in the order of type_definitions()
+	/// * MessageKind variants encode as their index in type_definitions()
+	#[test]
+	fn type_definitions_should_match_encoding() {
+		let anchor = Anchor {
+			author: 1u64,
+			content_hash: H256::repeat_byte(2),
+			kind: MessageKind::Announcement,
+			block: 3u64,
+		};
+		assert_eq!(anchor.encode(), (anchor.author, anchor.content_hash, anchor.kind, anchor.block).encode());
+		assert_eq!(MessageKind::Chat.encode(), vec![0]);
+		assert_eq!(MessageKind::Announcement.encode(), vec![1]);
+	}
+
+	/// Anchor test objectives:
+	/// * Members post chat messages and the owner announcements, with one sequence per group
+	/// * messages() pages through the anchors in order
+	#[test]
+	fn messages_should_be_anchored_in_order() {
+		with_externalities(&mut build_ext(), || {
+			let group_id = H256::repeat_byte(1);
+			assert_noop!(Messages::anchor_message(Origin::signed(1), group_id, H256::repeat_byte(9), MessageKind::Chat),
+				"Only members can post to this group");
+			assert_noop!(Messages::anchor_message(Origin::signed(2), group_id, H256::repeat_byte(9), MessageKind::Announcement),
+				"Only the owner can announce to this group");
+
+			assert_ok!(Messages::anchor_message(Origin::signed(1), group_id, H256::repeat_byte(10), MessageKind::Announcement));
+			assert_ok!(Messages::anchor_message(Origin::signed(2), group_id, H256::repeat_byte(11), MessageKind::Chat));
+			assert_ok!(Messages::anchor_message(Origin::signed(3), group_id, H256::repeat_byte(12), MessageKind::Chat));
+			assert_eq!(Messages::message_count(group_id), 3);
+
+			let page = Messages::messages(group_id, 1, 5);
+			assert_eq!(page.len(), 2);
+			assert_eq!((page[0].0, page[0].1.author, page[0].1.content_hash), (1, 2, H256::repeat_byte(11)));
+			assert_eq!((page[1].0, page[1].1.author), (2, 3));
+			assert!(Messages::messages(H256::repeat_byte(2), 0, 5).is_empty());
+		});
+	}
+
+	/// Rate limit test objectives:
+	/// * An author can post messages_per_window messages per window, then waits for the next window
+	/// * Each author has their own budget
+	#[test]
+	fn messages_should_be_rate_limited() {
+		with_externalities(&mut build_ext(), || {
+			let group_id = H256::repeat_byte(1);
+			let chat = |who: u64| Messages::anchor_message(Origin::signed(who), group_id, H256::repeat_byte(9), MessageKind::Chat);
+			<system::Module<MessagesTest>>::set_block_number(1);
+			assert_ok!(chat(2));
+			assert_ok!(chat(2));
+			assert_noop!(chat(2), "Too many messages to this group, wait for the next window");
+			assert_ok!(chat(3));
+
+			<system::Module<MessagesTest>>::set_block_number(11);
+			assert_ok!(chat(2));
+			assert_eq!(Messages::author_window((group_id, 2)), (11, 1));
+
+			// A window of 0 blocks turns the rate limit off
+			<RateWindow<MessagesTest>>::put(0);
+			for _ in 0..3 {
+				assert_ok!(chat(2));
+			}
+		});
+	}
+}
@@ -0,0 +1,258 @@
+/// Nft is the item logic shared by the collections of non-fungible assets, such as kitties. A collection is an
+/// NftCollection impl: it names the ownership registry and the metadata map of its items, which its module
+/// declares in its decl_storage like the items of an OwnershipStorage, and it sets its own rules. The metadata
+/// type is the schema of the collection, and max_supply() its supply rule. A new asset type is a new collection,
+/// not a copy of the kitties module.
+///
+/// Collection<C> mints, transfers and burns the items of C, and keeps the registry, the metadata and the supply
+/// in step. Extensions such as Breeding add behaviour on top for the collections that implement them. The module
+/// of a collection checks its own rules first, such as the sender owning the item, and emits its own events.
+
+use rstd::marker::PhantomData;
+use parity_codec::Codec;
+use support::{ensure, dispatch::Result, StorageMap, StorageValue};
+use app_primitives::{KittyId, NftProvider};
+use crate::ownership::{OwnershipStorage, Registry};
+
+/// The owner and the id of the items of a collection
+pub type ItemOwner<C> = <<C as NftCollection>::Ownership as OwnershipStorage>::Owner;
+pub type ItemId<C> = <<C as NftCollection>::Ownership as OwnershipStorage>::Id;
+
+/// A collection of items, declared by the module that holds its storage
+pub trait NftCollection {
+	/// The registry of the owners of the items
+	type Ownership: OwnershipStorage;
+	/// The metadata of an item, the schema of the collection
+	type Metadata: Codec + Default;
+	/// The metadata of each item, exists while the item is registered
+	type Items: StorageMap<ItemId<Self>, Self::Metadata, Query = Self::Metadata>;
+
+	/// The most items that can exist at the same time, or None for no cap. Items above a lowered cap are kept,
+	/// but no new ones are minted.
+	fn max_supply() -> Option<u64>;
+
+	/// Called after an item was minted, transferred or changed, so a module can index its changes
+	fn on_change(_id: &ItemId<Self>) {}
+}
+
+/// An extension for collections whose items are bred from two parents
+pub trait Breeding: NftCollection {
+	/// The metadata of a child with the given id. The seed is random and picks between the traits of the parents.
+	fn breed(child: &ItemId<Self>, parent_1: &Self::Metadata, parent_2: &Self::Metadata, seed: &[u8]) -> Self::Metadata;
+}
+
+/// The item logic of the collection C
+pub struct Collection<C>(PhantomData<C>);
+
+impl<C: NftCollection> Collection<C> {
+	/// Registers a new item to the owner. Fails if the id is taken or the supply cap is reached.
+	pub fn mint(owner: &ItemOwner<C>, id: ItemId<C>, metadata: C::Metadata) -> Result {
+		ensure!(!Self::exists(&id), "Item already exists");
+		Self::ensure_supply()?;
+
+		<Registry<C::Ownership>>::insert(owner, id.clone())?;
+		C::Items::insert(&id, metadata);
+		C::on_change(&id);
+		Ok(())
+	}
+
+	/// Moves an item from its owner `from` to `to`
+	pub fn transfer(from: &ItemOwner<C>, to: &ItemOwner<C>, id: ItemId<C>) -> Result {
+		let owner = Self::owner_of(&id).ok_or("No owner for this item")?;
+		ensure!(&owner == from, "From account is not the owner");
+
+		<Registry<C::Ownership>>::transfer(from, to, id.clone())?;
+		C::on_change(&id);
+		Ok(())
+	}
+
+	/// Removes an item and its metadata
+	pub fn burn(id: ItemId<C>) -> Result {
+		<Registry<C::Ownership>>::remove(id.clone())?;
+		C::Items::remove(&id);
+		Ok(())
+	}
+
+	/// Replaces the metadata of an existing item
+	pub fn set_metadata(id: &ItemId<C>, metadata: C::Metadata) -> Result {
+		ensure!(Self::exists(id), "No owner for this item");
+		C::Items::insert(id, metadata);
+		C::on_change(id);
+		Ok(())
+	}
+
+	/// Ok while the supply cap allows one more item. Modules call it before they take an id for a new item.
+	pub fn ensure_supply() -> Result {
+		if let Some(max_supply) = C::max_supply() {
+			ensure!(Self::supply() < max_supply, "Supply cap reached");
+		}
+		Ok(())
+	}
+
+	pub fn exists(id: &ItemId<C>) -> bool {
+		<C::Ownership as OwnershipStorage>::OwnerOf::exists(id)
+	}
+
+	pub fn owner_of(id: &ItemId<C>) -> Option<ItemOwner<C>> {
+		<C::Ownership as OwnershipStorage>::OwnerOf::get(id)
+	}
+
+	pub fn metadata(id: &ItemId<C>) -> C::Metadata {
+		C::Items::get(id)
+	}
+
+	/// The number of items
+	pub fn supply() -> u64 {
+		<C::Ownership as OwnershipStorage>::AllCount::get()
+	}
+}
+
+impl<C: Breeding> Collection<C> {
+	/// Mints the child of two items to the owner, with the metadata from C::breed()
+	pub fn breed(owner: &ItemOwner<C>, child: ItemId<C>, parent_1: &ItemId<C>, parent_2: &ItemId<C>, seed: &[u8]) -> Result {
+		ensure!(Self::exists(parent_1) && Self::exists(parent_2), "Parent does not exist");
+		let metadata = C::breed(&child, &Self::metadata(parent_1), &Self::metadata(parent_2), seed);
+		Self::mint(owner, child, metadata)
+	}
+}
+
+/// Lets other modules hold and trade the items of a collection with hash ids
+impl<C, H> NftProvider<ItemOwner<C>, H> for Collection<C> where
+	C: NftCollection,
+	C::Ownership: OwnershipStorage<Id = H>,
+{
+	fn owner_of(item_id: KittyId<H>) -> Option<ItemOwner<C>> {
+		Self::owner_of(&item_id.0)
+	}
+
+	fn transfer(from: ItemOwner<C>, to: ItemOwner<C>, item_id: KittyId<H>) -> Result {
+		Self::transfer(&from, &to, item_id.0)
+	}
+}
+
+// *****************************************************************************************************
+// Unit Tests!
+// *****************************************************************************************************
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use parity_codec::{Encode, Decode};
+	use runtime_io::{with_externalities, TestExternalities};
+	use primitives::{H256, Blake2Hasher};
+	use support::{decl_storage, impl_outer_origin, assert_ok};
+	use runtime_primitives::{
+		BuildStorage,
+		traits::{BlakeTwo256, IdentityLookup},
+		testing::{Digest, DigestItem, Header}
+	};
+
+	impl_outer_origin! {
+		pub enum Origin for NftTest {}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct NftTest;
+	impl system::Trait for NftTest {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type Digest = Digest;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type Log = DigestItem;
+	}
+
+	pub trait Trait: system::Trait {}
+	impl Trait for NftTest {}
+
+	// The metadata schema of the badges, a level that a child gets one above the higher parent
+	#[derive(Encode, Decode, Default, Clone, PartialEq, Debug)]
+	pub struct Badge {
+		level: u32,
+	}
+
+	// A collection of badges with u64 ids and at most 3 badges, declared the way a module declares its own
+	pub struct Module<T>(PhantomData<T>);
+
+	decl_storage! {
+		trait Store for Module<T: Trait> as NftTest {
+			BadgeMetadata: map u64 => Badge;
+			BadgeOwner: map u64 => Option<T::AccountId>;
+			AllBadgesArray: map u64 => u64;
+			AllBadgesCount: u64;
+			AllBadgesIndex: map u64 => u64;
+			OwnedBadgesArray: double_map T::AccountId, blake2_256(u64) => u64;
+			OwnedBadgesCount: map T::AccountId => u64;
+			OwnedBadgesIndex: map u64 => u64;
+		}
+	}
+
+	struct BadgeOwnership;
+	impl OwnershipStorage for BadgeOwnership {
+		type Owner = u64;
+		type Id = u64;
+		type OwnerOf = BadgeOwner<NftTest>;
+		type AllArray = AllBadgesArray<NftTest>;
+		type AllCount = AllBadgesCount<NftTest>;
+		type AllIndex = AllBadgesIndex<NftTest>;
+		type OwnedArray = OwnedBadgesArray<NftTest>;
+		type OwnedCount = OwnedBadgesCount<NftTest>;
+		type OwnedIndex = OwnedBadgesIndex<NftTest>;
+	}
+
+	struct BadgeCollection;
+	impl NftCollection for BadgeCollection {
+		type Ownership = BadgeOwnership;
+		type Metadata = Badge;
+		type Items = BadgeMetadata<NftTest>;
+
+		fn max_supply() -> Option<u64> {
+			Some(3)
+		}
+	}
+	impl Breeding for BadgeCollection {
+		fn breed(_child: &u64, parent_1: &Badge, parent_2: &Badge, _seed: &[u8]) -> Badge {
+			Badge { level: parent_1.level.max(parent_2.level) + 1 }
+		}
+	}
+	type Badges = Collection<BadgeCollection>;
+
+	fn build_ext() -> TestExternalities<Blake2Hasher> {
+		system::GenesisConfig::<NftTest>::default().build_storage().unwrap().0.into()
+	}
+
+	/// Collection test objectives:
+	/// * Minting registers the item with its metadata, up to the supply cap of the collection
+	/// * Only the owner transfers an item, and burning frees a place under the cap
+	/// * A collection with the Breeding extension mints children from two parents
+	#[test]
+	fn collection_should_follow_its_rules() {
+		with_externalities(&mut build_ext(), || {
+			assert_ok!(Badges::mint(&1, 10, Badge { level: 1 }));
+			assert_eq!(Badges::mint(&2, 10, Badge::default()), Err("Item already exists"));
+			assert_ok!(Badges::mint(&2, 20, Badge { level: 3 }));
+			assert_eq!(Badges::owner_of(&10), Some(1));
+			assert_eq!(Badges::metadata(&20), Badge { level: 3 });
+
+			assert_eq!(Badges::transfer(&2, &3, 10), Err("From account is not the owner"));
+			assert_ok!(Badges::transfer(&1, &3, 10));
+			assert_eq!(<Badges as NftProvider<u64, u64>>::owner_of(KittyId(10)), Some(3));
+
+			assert_eq!(Badges::breed(&3, 30, &10, &40, &[]), Err("Parent does not exist"));
+			assert_ok!(Badges::breed(&3, 30, &10, &20, &[]));
+			assert_eq!(Badges::metadata(&30), Badge { level: 4 });
+			assert_eq!(Badges::supply(), 3);
+			assert_eq!(Badges::mint(&1, 40, Badge::default()), Err("Supply cap reached"));
+
+			assert_ok!(Badges::burn(20));
+			assert_eq!(Badges::metadata(&20), Badge::default());
+			assert_ok!(Badges::mint(&1, 40, Badge::default()));
+		});
+	}
+}
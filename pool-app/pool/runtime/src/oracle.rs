@@ -0,0 +1,292 @@
+/// Oracle keeps a reference price of the native currency in a stable unit, such as a US cent, so the marketplace
+/// can list kitties at stable prices. The price is the native balance worth one stable unit.
+///
+/// A set of feeders, managed by the council, submit prices with feed_price(). Each feeder has one feed, the last
+/// price it sent. A feed is stale once it is older than max_age blocks. The price is the median of the fresh feeds,
+/// and there is no price while fewer than min_feeds are fresh. min_feeds is at least MIN_FEEDS, so with one wrong or
+/// malicious feed the median still lies between two honest feeds.
+///
+/// The off-chain workers of this Substrate version can not make HTTP requests nor sign transactions, so the feeders
+/// run outside the node: a script that reads a JSON price API and sends a signed feed_price().
+
+use rstd::prelude::*;
+use runtime_primitives::traits::{As, Zero};
+use support::{decl_module, decl_storage, decl_event, ensure, dispatch::Result, StorageMap, StorageValue};
+use support::traits::Currency;
+use system::ensure_signed;
+
+/// The lowest min_feeds, so one far off feed can not set the median on its own
+pub const MIN_FEEDS: u32 = 3;
+
+use app_primitives::{GovernanceOrigin, PriceOracle};
+
+/// The most feeders at the same time, so the median is computed over a bounded number of feeds
+pub const MAX_FEEDERS: usize = 16;
+
+/// The balance type of the currency that is priced
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+
+pub trait Trait: system::Trait {
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	/// The native currency, whose price in the stable unit is fed
+	type Currency: Currency<Self::AccountId>;
+
+	/// Checks the origin of the feeder and config calls. In the runtime this is the Council of the approve module.
+	type Council: GovernanceOrigin<Self::Origin>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Oracle {
+		// Config values that match the values in the testnet_genesis in chain_spec.rs
+		// Feeds older than max_age blocks are stale, and the price needs min_feeds fresh feeds.
+		MaxAge get(max_age) config(): T::BlockNumber;
+		MinFeeds get(min_feeds) config(): u32;
+
+		// The accounts allowed to feed prices, at most MAX_FEEDERS
+		Feeders get(feeders) config(): Vec<T::AccountId>;
+		// The last price of each feeder, with the block it was fed in
+		Feeds get(feed_of): map T::AccountId => Option<(BalanceOf<T>, T::BlockNumber)>;
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where
+		<T as system::Trait>::AccountId,
+		<T as system::Trait>::BlockNumber,
+		Balance = BalanceOf<T>
+	{
+		/// A feeder fed a price as (feeder, price)
+		PriceFed(AccountId, Balance),
+
+		/// An account became a feeder
+		FeederAdded(AccountId),
+
+		/// An account is no longer a feeder, and its feed was removed
+		FeederRemoved(AccountId),
+
+		/// The config changed to (max_age, min_feeds)
+		ConfigChanged(BlockNumber, u32),
+	}
+);
+
+decl_module! {
+	/// The module declaration.
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+
+		fn deposit_event<T>() = default;
+
+		/// Feed the native balance worth one stable unit. Replaces the previous feed of the sender.
+		/// Rule: feeders only.
+		pub fn feed_price(origin, price: BalanceOf<T>) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(Self::feeders().contains(&sender), "Only feeders can feed prices");
+			ensure!(!price.is_zero(), "Price must be greater than zero");
+
+			<Feeds<T>>::insert(&sender, (price, <system::Module<T>>::block_number()));
+			Self::deposit_event(RawEvent::PriceFed(sender, price));
+			Ok(())
+		}
+
+		/// Allow an account to feed prices. Rule: a council motion.
+		pub fn add_feeder(origin, who: T::AccountId) -> Result {
+			T::Council::ensure_governance(origin)?;
+			let mut feeders = Self::feeders();
+			ensure!(!feeders.contains(&who), "Account is already a feeder");
+			ensure!(feeders.len() < MAX_FEEDERS, "Too many feeders");
+
+			feeders.push(who.clone());
+			<Feeders<T>>::put(feeders);
+			Self::deposit_event(RawEvent::FeederAdded(who));
+			Ok(())
+		}
+
+		/// Remove a feeder and its feed, such as one that feeds wrong prices. Rule: a council motion.
+		pub fn remove_feeder(origin, who: T::AccountId) -> Result {
+			T::Council::ensure_governance(origin)?;
+			let mut feeders = Self::feeders();
+			ensure!(feeders.contains(&who), "Account is not a feeder");
+
+			feeders.retain(|feeder| *feeder != who);
+			<Feeders<T>>::put(feeders);
+			<Feeds<T>>::remove(&who);
+			Self::deposit_event(RawEvent::FeederRemoved(who));
+			Ok(())
+		}
+
+		/// Change the age at which feeds are stale and the fresh feeds needed for a price. Rule: a council motion.
+		pub fn set_config(origin, max_age: T::BlockNumber, min_feeds: u32) -> Result {
+			T::Council::ensure_governance(origin)?;
+			ensure!(min_feeds >= MIN_FEEDS, "min_feeds is below MIN_FEEDS");
+
+			<MaxAge<T>>::put(max_age);
+			<MinFeeds<T>>::put(min_feeds);
+			Self::deposit_event(RawEvent::ConfigChanged(max_age, min_feeds));
+			Ok(())
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The median of the fresh feeds, or None while fewer than min_feeds are fresh. With an even number of feeds it
+	/// is the mean of the two middle ones.
+	pub fn price() -> Option<BalanceOf<T>> {
+		let now = <system::Module<T>>::block_number();
+		let max_age = Self::max_age();
+		let mut prices: Vec<BalanceOf<T>> = Self::feeders()
+			.into_iter()
+			.filter_map(Self::feed_of)
+			.filter(|(_, fed_at)| *fed_at + max_age >= now)
+			.map(|(price, _)| price)
+			.collect();
+		if prices.is_empty() || (prices.len() as u32) < Self::min_feeds() {
+			return None
+		}
+
+		prices.sort();
+		let middle = prices.len() / 2;
+		if prices.len() % 2 == 1 {
+			Some(prices[middle])
+		} else {
+			let (low, high) = (prices[middle - 1], prices[middle]);
+			Some(low + (high - low) / BalanceOf::<T>::sa(2))
+		}
+	}
+}
+
+/// Lets the marketplace convert stable prices without depending on this module
+impl<T: Trait> PriceOracle<BalanceOf<T>> for Module<T> {
+	fn price() -> Option<BalanceOf<T>> {
+		Self::price()
+	}
+}
+
+// *****************************************************************************************************
+// Unit Tests!
+// *****************************************************************************************************
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use runtime_io::with_externalities;
+	use primitives::{H256, Blake2Hasher};
+	use support::{impl_outer_origin, assert_ok, assert_noop};
+	use runtime_primitives::{
+		BuildStorage,
+		traits::{BlakeTwo256, IdentityLookup},
+		testing::{Digest, DigestItem, Header}
+	};
+	use crate::approve;
+
+	impl_outer_origin! {
+		pub enum Origin for OracleTest {
+			approve
+		}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct OracleTest;
+	impl system::Trait for OracleTest {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type Digest = Digest;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type Log = DigestItem;
+	}
+	impl balances::Trait for OracleTest {
+		type Balance = u64;
+		type OnFreeBalanceZero = ();
+		type OnNewAccount = ();
+		type Event = ();
+		type TransactionPayment = ();
+		type TransferPayment = ();
+		type DustRemoval = ();
+	}
+	impl Trait for OracleTest {
+		type Event = ();
+		type Currency = balances::Module<OracleTest>;
+		type Council = approve::Council;
+	}
+	type Oracle = Module<OracleTest>;
+
+	// The accounts 1 to 4 are feeders, feeds are stale after 10 blocks and a price needs 3 fresh feeds
+	fn build_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+		let mut t = system::GenesisConfig::<OracleTest>::default().build_storage().unwrap().0;
+		t.extend(GenesisConfig::<OracleTest> {
+			max_age: 10,
+			min_feeds: 3,
+			feeders: vec![1, 2, 3, 4],
+		}.build_storage().unwrap().0);
+		t.into()
+	}
+
+	fn council() -> Origin {
+		Origin::from(approve::Origin::Approved(2))
+	}
+
+	/// Price test objectives:
+	/// * Only feeders feed prices, and the price is the median of their feeds
+	/// * Stale feeds are left out, and there is no price with fewer than min_feeds fresh feeds
+	#[test]
+	fn price_should_be_the_median_of_fresh_feeds() {
+		with_externalities(&mut build_ext(), || {
+			<system::Module<OracleTest>>::set_block_number(1);
+			assert_noop!(Oracle::feed_price(Origin::signed(5), 100), "Only feeders can feed prices");
+			assert_ok!(Oracle::feed_price(Origin::signed(1), 100));
+			assert_ok!(Oracle::feed_price(Origin::signed(2), 120));
+			assert_eq!(Oracle::price(), None);
+
+			// a far off feed does not set the median
+			assert_ok!(Oracle::feed_price(Origin::signed(3), 10_000));
+			assert_eq!(Oracle::price(), Some(120));
+
+			<system::Module<OracleTest>>::set_block_number(5);
+			assert_ok!(Oracle::feed_price(Origin::signed(4), 130));
+			assert_eq!(Oracle::price(), Some(125));
+
+			// the feeds of block 1 are stale at block 12
+			<system::Module<OracleTest>>::set_block_number(12);
+			assert_eq!(Oracle::price(), None);
+			assert_ok!(Oracle::feed_price(Origin::signed(1), 140));
+			assert_ok!(Oracle::feed_price(Origin::signed(2), 160));
+			assert_eq!(Oracle::price(), Some(140));
+		});
+	}
+
+	/// Feeder test objectives:
+	/// * Only a council motion adds and removes feeders and changes the config
+	/// * A removed feeder can no longer feed, and its feed is dropped
+	#[test]
+	fn feeders_should_be_managed_by_the_council() {
+		with_externalities(&mut build_ext(), || {
+			assert!(Oracle::add_feeder(Origin::signed(1), 5).is_err());
+			assert_ok!(Oracle::add_feeder(council(), 5));
+			assert_noop!(Oracle::add_feeder(council(), 5), "Account is already a feeder");
+			assert_ok!(Oracle::feed_price(Origin::signed(5), 100));
+
+			assert_ok!(Oracle::feed_price(Origin::signed(1), 200));
+			assert_ok!(Oracle::feed_price(Origin::signed(2), 300));
+			assert_eq!(Oracle::price(), Some(200));
+			assert_ok!(Oracle::remove_feeder(council(), 1));
+			assert_eq!(Oracle::feed_of(1), None);
+			assert_noop!(Oracle::feed_price(Origin::signed(1), 100), "Only feeders can feed prices");
+			assert_eq!(Oracle::price(), None);
+
+			assert_ok!(Oracle::feed_price(Origin::signed(3), 400));
+			assert_eq!(Oracle::price(), Some(300));
+
+			assert!(Oracle::set_config(Origin::signed(1), 10, 3).is_err());
+			assert_noop!(Oracle::set_config(council(), 10, 2), "min_feeds is below MIN_FEEDS");
+			assert_ok!(Oracle::set_config(council(), 20, 4));
+			assert_eq!(Oracle::min_feeds(), 4);
+			assert_eq!(Oracle::price(), None);
+		});
+	}
+}
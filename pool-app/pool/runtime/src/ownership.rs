@@ -0,0 +1,296 @@
+/// Ownership is the bookkeeping shared by the modules whose items are owned by accounts, such as kitties and
+/// groups. Every item is in a global array and in the array of its owner, each with an index map pointing back
+/// into the array, so items can be listed by position and removed by swap and pop without leaving holes.
+///
+/// This is not a module with storage of its own. A module declares the storage items in its decl_storage, names
+/// them in an OwnershipStorage impl, and calls Registry for every insert, removal and transfer, so the index
+/// arithmetic is written and tested once. Registry only checks for overflow, the module checks its own rules
+/// (the id is new, the sender owns the item) first and keeps its error messages.
+
+use rstd::marker::PhantomData;
+use rstd::prelude::*;
+use parity_codec::Codec;
+use support::{ensure, dispatch::Result, StorageDoubleMap, StorageMap, StorageValue};
+
+/// The storage items of a registry, declared by the module that owns the items
+pub trait OwnershipStorage {
+	/// The owner of the items, an account
+	type Owner: Codec + Clone + PartialEq;
+	/// The id of an item
+	type Id: Codec + Clone + PartialEq;
+
+	/// The owner of each item, exists while the item is registered
+	type OwnerOf: StorageMap<Self::Id, Self::Owner, Query = Option<Self::Owner>>;
+	/// Every item by position, at indexes 0..AllCount
+	type AllArray: StorageMap<u64, Self::Id, Query = Self::Id>;
+	type AllCount: StorageValue<u64, Query = u64>;
+	/// The position of each item in AllArray
+	type AllIndex: StorageMap<Self::Id, u64, Query = u64>;
+	/// The items of each owner by position, at indexes 0..OwnedCount of the owner
+	type OwnedArray: StorageDoubleMap<Self::Owner, u64, Self::Id, Query = Self::Id>;
+	type OwnedCount: StorageMap<Self::Owner, u64, Query = u64>;
+	/// The position of each item in the OwnedArray of its owner
+	type OwnedIndex: StorageMap<Self::Id, u64, Query = u64>;
+}
+
+/// The reads and writes of the registry kept in the storage items of S
+pub struct Registry<S>(PhantomData<S>);
+
+impl<S: OwnershipStorage> Registry<S> {
+	/// Appends a new item to the global array and the array of its owner. The caller checks that the id is new.
+	pub fn insert(owner: &S::Owner, id: S::Id) -> Result {
+		let all_count = S::AllCount::get();
+		let new_all_count = all_count.checked_add(1).ok_or("Overflow adding an owned item")?;
+		let owned_count = S::OwnedCount::get(owner);
+		let new_owned_count = owned_count.checked_add(1).ok_or("Overflow adding an owned item")?;
+
+		S::OwnerOf::insert(&id, owner);
+		S::AllArray::insert(&all_count, &id);
+		S::AllIndex::insert(&id, &all_count);
+		S::AllCount::put(&new_all_count);
+
+		S::OwnedArray::insert(owner, &owned_count, id.clone());
+		S::OwnedIndex::insert(&id, &owned_count);
+		S::OwnedCount::insert(owner, &new_owned_count);
+		Ok(())
+	}
+
+	/// Removes an item from both arrays, the last item of each array moves into its slot
+	pub fn remove(id: S::Id) -> Result {
+		let owner = S::OwnerOf::get(&id).ok_or("No owner for this item")?;
+		let new_all_count = S::AllCount::get().checked_sub(1).ok_or("Underflow removing an owned item")?;
+		let new_owned_count = S::OwnedCount::get(&owner).checked_sub(1).ok_or("Underflow removing an owned item")?;
+
+		let all_index = S::AllIndex::take(&id);
+		if all_index != new_all_count {
+			let last_id = S::AllArray::get(&new_all_count);
+			S::AllArray::insert(&all_index, &last_id);
+			S::AllIndex::insert(&last_id, &all_index);
+		}
+		S::AllArray::remove(&new_all_count);
+		S::AllCount::put(&new_all_count);
+
+		Self::pop_owned(&owner, &id, new_owned_count);
+		S::OwnedIndex::remove(&id);
+		S::OwnerOf::remove(&id);
+		Ok(())
+	}
+
+	/// Moves an item from the array of `from` to the end of the array of `to`. The caller checks that `from`
+	/// owns the item.
+	pub fn transfer(from: &S::Owner, to: &S::Owner, id: S::Id) -> Result {
+		// The swap and pop assumes two different arrays, a transfer to self would leave a hole
+		ensure!(from != to, "Can not transfer an item to its owner");
+		let new_from_count = S::OwnedCount::get(from).checked_sub(1).ok_or("Underflow removing an owned item")?;
+		let to_count = S::OwnedCount::get(to);
+		let new_to_count = to_count.checked_add(1).ok_or("Overflow adding an owned item")?;
+
+		Self::pop_owned(from, &id, new_from_count);
+		S::OwnedArray::insert(to, &to_count, id.clone());
+		S::OwnedIndex::insert(&id, &to_count);
+		S::OwnedCount::insert(to, &new_to_count);
+		S::OwnerOf::insert(&id, to);
+		Ok(())
+	}
+
+	/// The items of an owner, in the order of its array
+	pub fn owned(owner: &S::Owner) -> Vec<S::Id> {
+		(0..S::OwnedCount::get(owner))
+			.map(|i| S::OwnedArray::get(owner, &i))
+			.collect()
+	}
+
+	/// Every item, in the order of the global array
+	pub fn all() -> Vec<S::Id> {
+		(0..S::AllCount::get())
+			.map(|i| S::AllArray::get(&i))
+			.collect()
+	}
+
+	// Private method called by: remove() and transfer(). Moves the last item of the owner into the slot of the
+	// item and drops the last slot. new_owned_count is the count of the owner without the item.
+	fn pop_owned(owner: &S::Owner, id: &S::Id, new_owned_count: u64) {
+		let index = S::OwnedIndex::get(id);
+		if index != new_owned_count {
+			let last_id = S::OwnedArray::get(owner, &new_owned_count);
+			S::OwnedArray::insert(owner, &index, last_id.clone());
+			S::OwnedIndex::insert(&last_id, &index);
+		}
+		S::OwnedArray::remove(owner, &new_owned_count);
+		S::OwnedCount::insert(owner, &new_owned_count);
+	}
+}
+
+// *****************************************************************************************************
+// Unit Tests!
+// *****************************************************************************************************
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use proptest::prelude::*;
+	use runtime_io::{with_externalities, TestExternalities};
+	use primitives::{H256, Blake2Hasher};
+	use support::{decl_storage, impl_outer_origin, assert_ok};
+	use runtime_primitives::{
+		BuildStorage,
+		traits::{BlakeTwo256, IdentityLookup},
+		testing::{Digest, DigestItem, Header}
+	};
+
+	impl_outer_origin! {
+		pub enum Origin for OwnershipTest {}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct OwnershipTest;
+	impl system::Trait for OwnershipTest {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type Digest = Digest;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type Log = DigestItem;
+	}
+
+	pub trait Trait: system::Trait {}
+	impl Trait for OwnershipTest {}
+
+	// A registry of items with u64 ids, declared the way a module declares its own
+	pub struct Module<T>(PhantomData<T>);
+
+	decl_storage! {
+		trait Store for Module<T: Trait> as OwnershipTest {
+			ItemOwner: map u64 => Option<T::AccountId>;
+			AllItemsArray: map u64 => u64;
+			AllItemsCount: u64;
+			AllItemsIndex: map u64 => u64;
+			OwnedItemsArray: double_map T::AccountId, blake2_256(u64) => u64;
+			OwnedItemsCount: map T::AccountId => u64;
+			OwnedItemsIndex: map u64 => u64;
+		}
+	}
+
+	struct Items;
+	impl OwnershipStorage for Items {
+		type Owner = u64;
+		type Id = u64;
+		type OwnerOf = ItemOwner<OwnershipTest>;
+		type AllArray = AllItemsArray<OwnershipTest>;
+		type AllCount = AllItemsCount<OwnershipTest>;
+		type AllIndex = AllItemsIndex<OwnershipTest>;
+		type OwnedArray = OwnedItemsArray<OwnershipTest>;
+		type OwnedCount = OwnedItemsCount<OwnershipTest>;
+		type OwnedIndex = OwnedItemsIndex<OwnershipTest>;
+	}
+	type Registry = super::Registry<Items>;
+
+	// Owners used by the tests
+	const OWNERS: u64 = 4;
+
+	fn build_ext() -> TestExternalities<Blake2Hasher> {
+		system::GenesisConfig::<OwnershipTest>::default().build_storage().unwrap().0.into()
+	}
+
+	// Both arrays hold every item exactly once, with no holes, and agree with their indexes and the owners
+	fn check_registry() {
+		let count = <AllItemsCount<OwnershipTest>>::get();
+		for i in 0..count {
+			let id = <AllItemsArray<OwnershipTest>>::get(i);
+			assert_eq!(<AllItemsIndex<OwnershipTest>>::get(id), i);
+			assert!(<ItemOwner<OwnershipTest>>::get(id).is_some());
+		}
+		assert!(!<AllItemsArray<OwnershipTest>>::exists(count));
+
+		let mut owned_total = 0;
+		for owner in 0..OWNERS {
+			let owned = Registry::owned(&owner);
+			for (j, id) in owned.iter().enumerate() {
+				assert_eq!(<ItemOwner<OwnershipTest>>::get(id), Some(owner));
+				assert_eq!(<OwnedItemsIndex<OwnershipTest>>::get(id), j as u64);
+			}
+			assert!(!<OwnedItemsArray<OwnershipTest>>::exists(&owner, &(owned.len() as u64)));
+			owned_total += owned.len() as u64;
+		}
+		assert_eq!(owned_total, count);
+	}
+
+	/// Registry test objectives:
+	/// * Inserted items are appended to both arrays
+	/// * Removing an item moves the last item of each array into its slot
+	/// * A transfer appends the item to the array of the new owner, a transfer to the owner fails
+	#[test]
+	fn registry_should_swap_and_pop() {
+		with_externalities(&mut build_ext(), || {
+			for id in 1..5 {
+				assert_ok!(Registry::insert(&0, id));
+			}
+			assert_ok!(Registry::insert(&1, 5));
+			assert_eq!(Registry::all(), vec![1, 2, 3, 4, 5]);
+			assert_eq!(Registry::owned(&0), vec![1, 2, 3, 4]);
+
+			assert_ok!(Registry::remove(2));
+			assert_eq!(Registry::all(), vec![1, 5, 3, 4]);
+			assert_eq!(Registry::owned(&0), vec![1, 4, 3]);
+			assert_eq!(<ItemOwner<OwnershipTest>>::get(2), None);
+			assert_eq!(Registry::remove(2), Err("No owner for this item"));
+
+			assert_ok!(Registry::transfer(&0, &1, 1));
+			assert_eq!(Registry::owned(&0), vec![3, 4]);
+			assert_eq!(Registry::owned(&1), vec![5, 1]);
+			assert_eq!(Registry::transfer(&1, &1, 5), Err("Can not transfer an item to its owner"));
+			check_registry();
+		});
+	}
+
+	// A registry call of the property test. Items are picked by an index into the global array, modulo the count.
+	#[derive(Debug, Clone)]
+	enum Op {
+		Insert(u64),
+		Remove(u64),
+		// (item, to), from the owner of the item
+		Transfer(u64, u64),
+	}
+
+	fn op() -> impl Strategy<Value = Op> {
+		prop_oneof![
+			(0..OWNERS).prop_map(Op::Insert),
+			any::<u64>().prop_map(Op::Remove),
+			(any::<u64>(), 0..OWNERS).prop_map(|(item, to)| Op::Transfer(item, to)),
+		]
+	}
+
+	proptest! {
+		#[test]
+		fn registry_should_hold_after_every_call(ops in proptest::collection::vec(op(), 1..60)) {
+			with_externalities(&mut build_ext(), || {
+				let mut next_id = 0;
+				for op in ops {
+					let count = <AllItemsCount<OwnershipTest>>::get();
+					let pick = |item: u64| <AllItemsArray<OwnershipTest>>::get(item % count);
+					let _ = match op {
+						Op::Insert(owner) => {
+							next_id += 1;
+							Registry::insert(&owner, next_id)
+						},
+						Op::Remove(_) if count == 0 => Ok(()),
+						Op::Remove(item) => Registry::remove(pick(item)),
+						Op::Transfer(_, _) if count == 0 => Ok(()),
+						Op::Transfer(item, to) => {
+							let id = pick(item);
+							let from = <ItemOwner<OwnershipTest>>::get(id).unwrap();
+							Registry::transfer(&from, &to, id)
+						},
+					};
+					check_registry();
+				}
+			});
+		}
+	}
+}
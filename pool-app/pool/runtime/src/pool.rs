@@ -1,9 +1,14 @@
 /// Pool is an experimental module for managing pooled funds
 
 use parity_codec::{Encode, Decode};
-use runtime_primitives::traits::{Hash};
+use runtime_primitives::Permill;
+use runtime_primitives::traits::{As, CheckedAdd, CheckedSub, Hash, Zero};
 use support::{decl_module, decl_storage, decl_event, ensure, dispatch::Result, StorageMap, StorageValue};
+use support::traits::{Currency, ExistenceRequirement, Imbalance, OnUnbalanced, WithdrawReason};
 use system::ensure_signed;
+use app_primitives::{ApprovalProvider, GovernanceOrigin, PoolId, ProposalId};
+use crate::deadline::{DeadlineStorage, Deadlines};
+use crate::ids::{self, IdKind};
 
 // use runtime_io::{with_storage, StorageOverlay, ChildrenStorageOverlay};
 
@@ -11,32 +16,105 @@ use system::ensure_signed;
 use rstd::prelude::Vec;
 #[cfg(feature = "std")]
 use std::vec::Vec;
+use rstd::collections::btree_map::BTreeMap;
+use rstd::marker::PhantomData;
+use rstd::result;
 #[cfg(not(feature = "std"))]
 use core::str;
 #[cfg(feature = "std")]
 use std::str;
 
 
-pub trait Trait: balances::Trait + timestamp::Trait {
+/// How many blocks a requested refund waits for its approval and claim, about two days with 10 second blocks.
+/// After that the request lapses and the contributor has to request the refund again.
+pub const REFUND_CLAIM_BLOCKS: u64 = 17280;
+
+/// The most refund requests that on_initialize() lapses in one block. The rest lapse in the next blocks.
+const MAX_LAPSES_PER_BLOCK: usize = 20;
+
+/// The balance type of the currency held by the pool
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+type NegativeImbalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::NegativeImbalance;
+
+pub trait Trait: system::Trait + timestamp::Trait {
     type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	/// The currency (or asset) that is pooled. This is usually the Balances module, but a multi-asset
+	/// runtime can instantiate a pool for any type that implements the Currency trait.
+	type Currency: Currency<Self::AccountId>;
+
+	/// Refunds of large_payout or more must be approved through this provider, see request_refund()
+	type Approval: ApprovalProvider<Self::AccountId, Self::Hash>;
+
+	/// Receives the protocol fee of donations. In the runtime this is the treasury module.
+	type Treasury: OnUnbalanced<NegativeImbalanceOf<Self>>;
+
+	/// Checks the origin of emergency_drain() and recover_pool(). In the runtime this is the Council of the
+	/// approve module.
+	type Council: GovernanceOrigin<Self::Origin>;
 }
 
-// #[derive(Encode, Decode, Default, Clone, PartialEq)]
-// #[cfg_attr(feature = "std", derive(Debug))]
-// pub struct Group<A, H> {
-// 	/// Hash unique random id
-//     id: H,
-// 	/// Arbitrary field that can be used for human-readable name or foreign key in other system.
-// 	/// The length of this field is limited by the max_name_size Config.
-// 	name: Vec<u8>,
-// 	/// Vec of AccountIds, where the owner is not automatically added and can just be an external actor
-// 	/// The size of this list is limited by the max_group_size Config.
-// 	members: Vec<A>,
-// 	/// Maximum number of members in group. Note that there is no min size of group since that is
-// 	/// likely a business rule that can be handled in the dapp or external systems.
-// 	/// Example: number of players required to start a game.
-// 	max_size: u32,
-// }
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Pool<H, B> {
+	/// Hash unique random id
+	id: H,
+	/// Sum of refundable contributions. Each contributor holds a share equal to the amount they put in,
+	/// which is recorded in the Contributions map.
+	contributed: B,
+	/// Sum of donations. Donations do not create a share and can never be refunded.
+	donated: B,
+	/// Set by emergency_drain(). A drained pool rejects all calls until it is recovered.
+	drained: bool,
+}
+
+/// The kind of a ledger entry. The ledger is append-only and is the source of truth used by
+/// recover_pool() to rebuild contributor shares.
+#[derive(Encode, Decode, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum LedgerKind {
+	Contribution,
+	Refund,
+	Donation,
+}
+
+impl Default for LedgerKind {
+	fn default() -> Self {
+		LedgerKind::Contribution
+	}
+}
+
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct LedgerEntry<A, B> {
+	who: A,
+	amount: B,
+	kind: LedgerKind,
+}
+
+/// The totals of a pool at the time a snapshot was taken. The contributor shares for the same snapshot
+/// are stored copy-on-write, see set_contribution().
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Snapshot<N, B> {
+	/// The block in which the snapshot was taken
+	block: N,
+	/// Sum of refundable contributions at that block
+	contributed: B,
+}
+
+/// The polkadot-js definitions of the types above, see the types module. Snapshot is renamed because
+/// polkadot-js type names are global.
+#[cfg(feature = "std")]
+pub fn type_definitions() -> Vec<crate::types::TypeDef> {
+	use crate::types::TypeDef;
+	vec![
+		TypeDef::Struct("Pool", vec![("id", "Hash"), ("contributed", "Balance"), ("donated", "Balance"), ("drained", "bool")]),
+		TypeDef::Enum("LedgerKind", vec!["Contribution", "Refund", "Donation"]),
+		TypeDef::Struct("LedgerEntry", vec![("who", "AccountId"), ("amount", "Balance"), ("kind", "LedgerKind")]),
+		TypeDef::Struct("PoolSnapshot", vec![("block", "BlockNumber"), ("contributed", "Balance")]),
+	]
+}
 
 decl_storage! {
 
@@ -45,10 +123,61 @@ decl_storage! {
 	// AccountId and lookup the Hash of a group based on the index values.
 	trait Store for Module<T: Trait> as Pool {
 
-        BalanceVal get(balance_val): Option<T::Balance>;
-		// SubPool get(subpool): map T::Hash => Group<T::AccountId, T::Hash>;
+		// The total amount held by the module across all pools
+        BalanceVal get(balance_val): Option<BalanceOf<T>>;
+
+		// These are the primary storage vars for storing the Pool struct and recording ownership of a Pool
+		Pools get(pool): map T::Hash => Pool<T::Hash, BalanceOf<T>>;
+		PoolOwner get(owner_of): map T::Hash => Option<T::AccountId>;
+
+		// Global index of all pools, similar to AllKittiesArray in the kitties example
+		AllPoolsArray get(pool_by_index): map u64 => T::Hash;
+		AllPoolsCount get(all_pools_count): u64;
+
+		// The refundable share of each contributor in a pool. Donations are not recorded here.
+		Contributions get(contribution_of): map (T::Hash, T::AccountId) => BalanceOf<T>;
+
+		// Config value that matches the value in the testnet_genesis in chain_spec.rs
+		// This is the number of snapshots kept per pool before the oldest is pruned.
+		MaxSnapshots get(max_snapshots) config(): Option<u32>;
+
+		// Snapshots use the copy-on-write approach: taking a snapshot only records the pool totals. The first
+		// time a share changes after that, the old value is saved under the latest snapshot id. The accounts
+		// written under each snapshot id are tracked so that pruning can remove them.
+		// Snapshot ids for a pool start at 1. SnapshotCount is the latest id and FirstSnapshot the oldest kept.
+		Snapshots get(snapshot): map (T::Hash, u32) => Option<Snapshot<T::BlockNumber, BalanceOf<T>>>;
+		SnapshotCount get(snapshot_count): map T::Hash => u32;
+		FirstSnapshot get(first_snapshot): map T::Hash => u32;
+		SnapshotContributions: map (T::Hash, T::AccountId, u32) => Option<BalanceOf<T>>;
+		SnapshotAccounts: map (T::Hash, u32) => Vec<T::AccountId>;
 
-		Nonce: u64;
+		// Config value that matches the value in the testnet_genesis in chain_spec.rs
+		// Refunds of this amount or more need an approval, see request_refund().
+		LargePayout get(large_payout) config(): Option<BalanceOf<T>>;
+		// Large refunds waiting for approval, by the proposal id from the approval provider
+		PendingRefunds get(pending_refund): map ProposalId<T::Hash> => Option<(PoolId<T::Hash>, T::AccountId, BalanceOf<T>)>;
+		// Queue of refund requests by the block they lapse in, processed in on_initialize(). Requests that were
+		// claimed before are skipped. Use refunds_lapsing_at() to read all pages of a block, see the deadline module.
+		RefundsLapsingAt: map T::BlockNumber => Vec<ProposalId<T::Hash>>;
+		RefundsLapsingAtPages: map (T::BlockNumber, u32) => Vec<ProposalId<T::Hash>>;
+		RefundsLapsingAtPageCount: map T::BlockNumber => u32;
+		RefundsLapsingAtCursor: Option<(T::BlockNumber, u32)>;
+
+		// Config values that match the values in the testnet_genesis in chain_spec.rs
+		// Large refunds are paid out without an approval while their sum over a period of spend_period blocks
+		// stays within spend_limit. Periods start at multiples of spend_period. A spend_limit of 0 turns this off.
+		SpendLimit get(spend_limit) config(): Option<BalanceOf<T>>;
+		SpendPeriod get(spend_period) config(): Option<u32>;
+		// The sum of the large refunds paid out without approval in the period that starts at SpendPeriodStart
+		SpendPeriodStart get(spend_period_start): T::BlockNumber;
+		PeriodSpent get(period_spent): BalanceOf<T>;
+
+		// The fraction of each donation that goes to the Treasury instead of the pool. Set through the admin module.
+		ProtocolFee get(protocol_fee): Permill;
+
+		// Append-only record of every contribution, refund and donation per pool
+		Ledger get(ledger_entry): map (T::Hash, u64) => LedgerEntry<T::AccountId, BalanceOf<T>>;
+		LedgerCount get(ledger_count): map T::Hash => u64;
 	}
 }
 
@@ -59,8 +188,49 @@ in an external datastore.
 */
 
 decl_event!(
-    pub enum Event<T> where B = <T as balances::Trait>::Balance {
+    pub enum Event<T> where
+		<T as system::Trait>::AccountId,
+		<T as system::Trait>::Hash,
+		B = BalanceOf<T>
+	{
+		/// The total amount held by the module changed
         NewBalance(B),
+
+		/// A pool was created with the given id and owner
+		PoolCreated(Hash, AccountId),
+
+		/// A refundable contribution was added to a pool
+		Contributed(Hash, AccountId, B),
+
+		/// A contributor took back part or all of their share
+		Refunded(Hash, AccountId, B),
+
+		/// A large refund was paid out without an approval, within the spend_limit of the current period
+		RefundAutoApproved(Hash, AccountId, B),
+
+		/// A contributor asked for a large refund. The last Hash is the proposal id that must be approved.
+		RefundRequested(Hash, AccountId, B, Hash),
+
+		/// A requested refund was not claimed within REFUND_CLAIM_BLOCKS and was dropped. The last Hash is its
+		/// proposal id.
+		RefundLapsed(Hash, AccountId, B, Hash),
+
+		/// A donation was made to a pool. The last Hash is the memo hash provided by the donor, which
+		/// lets an off-chain service match the donation to a receipt.
+		Donated(Hash, AccountId, B, Hash),
+
+		/// The contributor shares of a pool were frozen under the given snapshot id
+		PoolSnapshot(Hash, u32),
+
+		/// The oldest snapshot of a pool was removed to stay within the max_snapshots Config
+		SnapshotPruned(Hash, u32),
+
+		/// EMERGENCY: the council moved all funds of a pool to the given account and froze the pool
+		PoolDrained(Hash, AccountId, B),
+
+		/// EMERGENCY: the council rebuilt the shares of a drained pool from the ledger and refunded it from the
+		/// given account
+		PoolRecovered(Hash, AccountId, B),
     }
 );
 
@@ -70,31 +240,433 @@ decl_module! {
 
 		fn deposit_event<T>() = default;
 
-		pub fn add_funds(origin, increase_by: T::Balance) -> Result {
-			// This is a public call, so we ensure that the origin is some signed account.
-			let _sender = ensure_signed(origin)?;
+		fn on_initialize(n: T::BlockNumber) {
+			Self::lapse_refunds(n);
+		}
+
+		/// Create an empty pool owned by the current AccountId
+		pub fn create_pool(origin) -> Result {
+			let sender = ensure_signed(origin)?;
+
+			let pool_id = <ids::Module<T>>::next_id(IdKind::Pool, &sender)?;
+			if !<ids::Module<T>>::sequential() {
+				ensure!(!<Pools<T>>::exists(pool_id), "Pool Id already exists");
+			}
+
+			let all_pools_count = Self::all_pools_count();
+			let new_all_pools_count = all_pools_count.checked_add(1).ok_or("Overflow adding a new pool")?;
+
+			let pool = Pool {
+				id: pool_id,
+				contributed: Zero::zero(),
+				donated: Zero::zero(),
+				drained: false,
+			};
+			<Pools<T>>::insert(pool_id, pool);
+			<PoolOwner<T>>::insert(pool_id, &sender);
+			<AllPoolsArray<T>>::insert(all_pools_count, pool_id);
+			<AllPoolsCount<T>>::put(new_all_pools_count);
 
-			// use the `::get` on the storage item type itself
-			let balance_val = <BalanceVal<T>>::get();
+			Self::deposit_event(RawEvent::PoolCreated(pool_id, sender));
+			Ok(())
+		}
+
+		/// Add a refundable contribution to a pool. The sender's share grows by the same amount.
+		pub fn contribute(origin, pool_id: T::Hash, amount: BalanceOf<T>) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(<Pools<T>>::exists(pool_id), "This pool does not exist");
+			ensure!(!amount.is_zero(), "Amount must be greater than zero");
 
-			// Calculate the new value.
-			let new_balance = balance_val.map_or(increase_by, |val| val + increase_by);
+			let mut pool = Self::pool(pool_id);
+			ensure!(!pool.drained, "This pool has been drained");
+			let new_contributed = pool.contributed.checked_add(&amount).ok_or("Overflow adding to the pool")?;
+			let share = Self::contribution_of((pool_id, sender.clone()));
+			let new_share = share.checked_add(&amount).ok_or("Overflow adding to the contribution")?;
 
-			// Put the new value into storage.
-			<BalanceVal<T>>::put(new_balance);
+			Self::take_funds(&sender, amount, Zero::zero())?;
 
-			// Deposit an event to let the outside world know this happened.
-			Self::deposit_event(RawEvent::NewBalance(increase_by));
+			pool.contributed = new_contributed;
+			<Pools<T>>::insert(pool_id, pool);
+			Self::set_contribution(pool_id, &sender, new_share);
+			Self::append_ledger(pool_id, &sender, amount, LedgerKind::Contribution);
 
-			// All good.
+			Self::deposit_event(RawEvent::Contributed(pool_id, sender, amount));
+			Ok(())
+		}
+
+		/// Take back part or all of a contribution. Only the sender's own share can be refunded.
+		/// Rule: refunds of large_payout or more must go through request_refund() instead, unless they fit in
+		/// what is left of the spend_limit of the current period.
+		pub fn refund(origin, pool_id: T::Hash, amount: BalanceOf<T>) -> Result {
+			let sender = ensure_signed(origin)?;
+			let large_payout = Self::large_payout().ok_or("Config large_payout not set")?;
+			if amount < large_payout {
+				return Self::do_refund(pool_id, sender, amount);
+			}
+
+			let (period_start, spent) = Self::current_spend()?;
+			let new_spent = spent.checked_add(&amount).ok_or("Overflow adding to the period spend")?;
+			let limit = Self::spend_limit().unwrap_or_else(Zero::zero);
+			ensure!(new_spent <= limit, "Large refunds need an approval, use request_refund");
+
+			Self::do_refund(pool_id, sender.clone(), amount)?;
+			<SpendPeriodStart<T>>::put(period_start);
+			<PeriodSpent<T>>::put(new_spent);
+
+			Self::deposit_event(RawEvent::RefundAutoApproved(pool_id, sender, amount));
+			Ok(())
+		}
+
+		/// Ask for a large refund. The refund is checked now, and an approval is requested for it. Once the
+		/// approval is granted, the sender calls claim_refund() with the returned proposal id.
+		pub fn request_refund(origin, pool_id: T::Hash, amount: BalanceOf<T>) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(<Pools<T>>::exists(pool_id), "This pool does not exist");
+			ensure!(!amount.is_zero(), "Amount must be greater than zero");
+			ensure!(!Self::pool(pool_id).drained, "This pool has been drained");
+			ensure!(Self::contribution_of((pool_id, sender.clone())) >= amount, "Refund exceeds contribution");
+
+			let payload_hash = (pool_id, &sender, amount).using_encoded(<T as system::Trait>::Hashing::hash);
+			let proposal_id = T::Approval::request_approval(sender.clone(), payload_hash)?;
+			<PendingRefunds<T>>::insert(proposal_id, (PoolId(pool_id), sender.clone(), amount));
+			let lapse_at = <system::Module<T>>::block_number() + T::BlockNumber::sa(REFUND_CLAIM_BLOCKS);
+			<Lapses<T>>::schedule(lapse_at, proposal_id);
+
+			Self::deposit_event(RawEvent::RefundRequested(pool_id, sender, amount, proposal_id.0));
+			Ok(())
+		}
+
+		/// Pay out a refund that was requested with request_refund() and has been approved.
+		/// Rule: only the account that requested the refund can claim it.
+		pub fn claim_refund(origin, proposal_id: T::Hash) -> Result {
+			let sender = ensure_signed(origin)?;
+			let proposal_id = ProposalId(proposal_id);
+			let (PoolId(pool_id), who, amount) = Self::pending_refund(proposal_id).ok_or("No refund pending for this proposal")?;
+			ensure!(who == sender, "This refund was requested by another account");
+			ensure!(T::Approval::is_approved(proposal_id), "This refund has not been approved");
+
+			Self::do_refund(pool_id, sender, amount)?;
+			<PendingRefunds<T>>::remove(proposal_id);
+			Ok(())
+		}
+
+		/// Give funds to a pool without acquiring a share. The memo hash is not stored, it is only
+		/// passed through to the Donated event for off-chain receipts. The protocol fee is taken from the
+		/// amount, and the pool records what is left.
+		pub fn donate(origin, pool_id: T::Hash, amount: BalanceOf<T>, memo_hash: T::Hash) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(<Pools<T>>::exists(pool_id), "This pool does not exist");
+			ensure!(!amount.is_zero(), "Amount must be greater than zero");
+
+			let mut pool = Self::pool(pool_id);
+			ensure!(!pool.drained, "This pool has been drained");
+			let fee = Self::protocol_fee() * amount;
+			let kept = amount.checked_sub(&fee).ok_or("Protocol fee exceeds the donation")?;
+			let new_donated = pool.donated.checked_add(&kept).ok_or("Overflow adding to the pool")?;
+
+			Self::take_funds(&sender, amount, fee)?;
+
+			pool.donated = new_donated;
+			<Pools<T>>::insert(pool_id, pool);
+			Self::append_ledger(pool_id, &sender, kept, LedgerKind::Donation);
+
+			Self::deposit_event(RawEvent::Donated(pool_id, sender, kept, memo_hash));
+			Ok(())
+		}
+
+		/// Freeze the current contributor shares of a pool under a new snapshot id, so later distributions
+		/// or votes can use the holdings as of this block. Rule: only the owner of the pool can do this.
+		pub fn snapshot_pool(origin, pool_id: T::Hash) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(<Pools<T>>::exists(pool_id), "This pool does not exist");
+			let owner = Self::owner_of(pool_id).ok_or("No owner for this pool")?;
+			ensure!(owner == sender, "You do not own this pool");
+
+			ensure!(!Self::pool(pool_id).drained, "This pool has been drained");
+
+			let max_snapshots = Self::max_snapshots().ok_or("Config max_snapshots not set")?;
+			ensure!(max_snapshots > 0, "Snapshots are disabled");
+
+			let snapshot_id = Self::snapshot_count(pool_id).checked_add(1).ok_or("Overflow adding a new snapshot")?;
+			let snapshot = Snapshot {
+				block: <system::Module<T>>::block_number(),
+				contributed: Self::pool(pool_id).contributed,
+			};
+			<Snapshots<T>>::insert((pool_id, snapshot_id), snapshot);
+			<SnapshotCount<T>>::insert(pool_id, snapshot_id);
+			if Self::first_snapshot(pool_id) == 0 {
+				<FirstSnapshot<T>>::insert(pool_id, snapshot_id);
+			}
+
+			Self::deposit_event(RawEvent::PoolSnapshot(pool_id, snapshot_id));
+
+			if snapshot_id - Self::first_snapshot(pool_id) >= max_snapshots {
+				Self::prune_snapshot(pool_id);
+			}
+			Ok(())
+		}
+
+		/// Escape hatch for experimental chains: move everything held by a pool to the given account and
+		/// freeze the pool. Contributor shares and the ledger are left untouched so the pool can be
+		/// recovered later. Rule: a council motion.
+		pub fn emergency_drain(origin, pool_id: T::Hash, to: T::AccountId) -> Result {
+			T::Council::ensure_governance(origin)?;
+			ensure!(<Pools<T>>::exists(pool_id), "This pool does not exist");
+
+			let mut pool = Self::pool(pool_id);
+			ensure!(!pool.drained, "This pool has been drained");
+			let amount = pool.contributed.checked_add(&pool.donated).ok_or("Overflow adding the pool totals")?;
+
+			Self::pay_funds(&to, amount)?;
+
+			pool.contributed = Zero::zero();
+			pool.donated = Zero::zero();
+			pool.drained = true;
+			<Pools<T>>::insert(pool_id, pool);
+
+			Self::deposit_event(RawEvent::PoolDrained(pool_id, to, amount));
+			Ok(())
+		}
+
+		/// Rebuild the contributor shares and totals of a drained pool by replaying its ledger, then take
+		/// the replayed total from the given account (usually the one it was drained to) and reopen the
+		/// pool. Rule: a council motion.
+		pub fn recover_pool(origin, pool_id: T::Hash, from: T::AccountId) -> Result {
+			T::Council::ensure_governance(origin)?;
+			ensure!(<Pools<T>>::exists(pool_id), "This pool does not exist");
+
+			let mut pool = Self::pool(pool_id);
+			ensure!(pool.drained, "Pool must be drained before it can be recovered");
+
+			let mut shares: BTreeMap<T::AccountId, BalanceOf<T>> = BTreeMap::new();
+			let mut donated: BalanceOf<T> = Zero::zero();
+			for index in 0..Self::ledger_count(pool_id) {
+				let entry = Self::ledger_entry((pool_id, index));
+				let share = shares.entry(entry.who).or_insert_with(Zero::zero);
+				match entry.kind {
+					LedgerKind::Contribution => {
+						*share = share.checked_add(&entry.amount).ok_or("Overflow replaying the ledger")?;
+					}
+					LedgerKind::Refund => {
+						*share = share.checked_sub(&entry.amount).ok_or("Ledger refunds exceed contributions")?;
+					}
+					LedgerKind::Donation => {
+						donated = donated.checked_add(&entry.amount).ok_or("Overflow replaying the ledger")?;
+					}
+				}
+			}
+
+			let mut contributed: BalanceOf<T> = Zero::zero();
+			for share in shares.values() {
+				contributed = contributed.checked_add(share).ok_or("Overflow replaying the ledger")?;
+			}
+			let amount = contributed.checked_add(&donated).ok_or("Overflow adding the pool totals")?;
+
+			Self::take_funds(&from, amount, Zero::zero())?;
+
+			for (who, share) in shares.into_iter() {
+				Self::set_contribution(pool_id, &who, share);
+			}
+			pool.contributed = contributed;
+			pool.donated = donated;
+			pool.drained = false;
+			<Pools<T>>::insert(pool_id, pool);
+
+			Self::deposit_event(RawEvent::PoolRecovered(pool_id, from, amount));
 			Ok(())
 		}
 
 	}
 }
 
+/// The queue of refund requests by the block they lapse in, see the deadline module
+struct RefundLapseQueue<T>(PhantomData<T>);
+
+impl<T: Trait> DeadlineStorage for RefundLapseQueue<T> {
+	type BlockNumber = T::BlockNumber;
+	type Id = ProposalId<T::Hash>;
+	type Queue = RefundsLapsingAt<T>;
+	type Pages = RefundsLapsingAtPages<T>;
+	type PageCount = RefundsLapsingAtPageCount<T>;
+	type Cursor = RefundsLapsingAtCursor<T>;
+	const BUDGET: usize = MAX_LAPSES_PER_BLOCK;
+}
+
+type Lapses<T> = Deadlines<RefundLapseQueue<T>>;
+
 /// Custom methods – public and private
 impl<T: Trait> Module<T> {
+	// Private method called by: on_initialize(). Drops the refund requests that lapse in this block, at most
+	// MAX_LAPSES_PER_BLOCK of them.
+	fn lapse_refunds(n: T::BlockNumber) {
+		for proposal_id in <Lapses<T>>::take_due(n) {
+			if let Some((PoolId(pool_id), who, amount)) = <PendingRefunds<T>>::take(proposal_id) {
+				Self::deposit_event(RawEvent::RefundLapsed(pool_id, who, amount, proposal_id.0));
+			}
+		}
+	}
+
+	// Private method called by: refund() and claim_refund()
+	// The share is checked again here, because it can change while a large refund waits for approval.
+	fn do_refund(pool_id: T::Hash, who: T::AccountId, amount: BalanceOf<T>) -> Result {
+		ensure!(<Pools<T>>::exists(pool_id), "This pool does not exist");
+		ensure!(!amount.is_zero(), "Amount must be greater than zero");
+
+		let share = Self::contribution_of((pool_id, who.clone()));
+		ensure!(share >= amount, "Refund exceeds contribution");
+
+		let mut pool = Self::pool(pool_id);
+		ensure!(!pool.drained, "This pool has been drained");
+		pool.contributed = pool.contributed.checked_sub(&amount).ok_or("Underflow subtracting from the pool")?;
+
+		Self::pay_funds(&who, amount)?;
+
+		<Pools<T>>::insert(pool_id, pool);
+		Self::set_contribution(pool_id, &who, share - amount);
+		Self::append_ledger(pool_id, &who, amount, LedgerKind::Refund);
+
+		Self::deposit_event(RawEvent::Refunded(pool_id, who, amount));
+		Ok(())
+	}
+
+	// Private method called by: refund()
+	// The start of the current period and what was spent in it so far. The spend is reset once a new period
+	// has started, which only happens in storage when the next auto approved refund is written.
+	fn current_spend() -> result::Result<(T::BlockNumber, BalanceOf<T>), &'static str> {
+		let period = Self::spend_period().ok_or("Config spend_period not set")?;
+		ensure!(period > 0, "Config spend_period must be greater than zero");
+		let now = <system::Module<T>>::block_number();
+		let period_start = now - (now % T::BlockNumber::sa(period as u64));
+		if period_start == Self::spend_period_start() {
+			Ok((period_start, Self::period_spent()))
+		} else {
+			Ok((period_start, Zero::zero()))
+		}
+	}
+
+	/// Sets the fraction of each donation that goes to the treasury. Called by the admin module, which checks the
+	/// origin.
+	pub(crate) fn set_protocol_fee(protocol_fee: Permill) {
+		<ProtocolFee<T>>::put(protocol_fee);
+	}
+
+	/// Sets the config values. Called by the admin module, which checks the origin.
+	pub(crate) fn set_config(max_snapshots: u32, large_payout: BalanceOf<T>, spend_limit: BalanceOf<T>, spend_period: u32) {
+		<MaxSnapshots<T>>::put(max_snapshots);
+		<LargePayout<T>>::put(large_payout);
+		<SpendLimit<T>>::put(spend_limit);
+		<SpendPeriod<T>>::put(spend_period);
+	}
+
+	/// The refund requests queued to lapse in block n that were not taken yet
+	pub fn refunds_lapsing_at(n: T::BlockNumber) -> Vec<ProposalId<T::Hash>> {
+		<Lapses<T>>::due_at(n)
+	}
+
+	/// A page of at most `limit` pools as (pool id, owner, contributed, donated, drained), starting at index
+	/// `start` of AllPoolsArray. Used by the PoolApi runtime API.
+	pub fn pools(start: u64, limit: u64) -> Vec<(T::Hash, Option<T::AccountId>, BalanceOf<T>, BalanceOf<T>, bool)> {
+		let end = rstd::cmp::min(start.saturating_add(limit), Self::all_pools_count());
+		(start..end)
+			.map(|i| Self::pool(Self::pool_by_index(i)))
+			.map(|pool| (pool.id, Self::owner_of(pool.id), pool.contributed, pool.donated, pool.drained))
+			.collect()
+	}
+
+	/// What is left of the spend_limit in the current period, for UIs to tell whether a large refund
+	/// needs request_refund()
+	pub fn spend_remaining() -> BalanceOf<T> {
+		let limit = Self::spend_limit().unwrap_or_else(Zero::zero);
+		match Self::current_spend() {
+			Ok((_, spent)) => limit.checked_sub(&spent).unwrap_or_else(Zero::zero),
+			Err(_) => Zero::zero(),
+		}
+	}
+
+	// Private method called by: contribute(), donate() and recover_pool()
+	// Like the treasury pot, the withdrawn amount is held by the module and tracked in BalanceVal,
+	// so the imbalance is simply dropped here. The fee part of the amount is handed to the Treasury instead.
+	fn take_funds(who: &T::AccountId, amount: BalanceOf<T>, fee: BalanceOf<T>) -> Result {
+		let kept = amount.checked_sub(&fee).ok_or("Protocol fee exceeds the amount")?;
+		let balance_val = Self::balance_val().unwrap_or_else(Zero::zero);
+		let new_balance = balance_val.checked_add(&kept).ok_or("Overflow adding to the pool balance")?;
+
+		let imbalance = T::Currency::withdraw(who, amount, WithdrawReason::Transfer, ExistenceRequirement::KeepAlive)?;
+		let (fee, _) = imbalance.split(fee);
+		T::Treasury::on_unbalanced(fee);
+		<BalanceVal<T>>::put(new_balance);
+
+		Self::deposit_event(RawEvent::NewBalance(new_balance));
+		Ok(())
+	}
+
+	// Private method that every change of a contributor share must go through. If a snapshot has been taken
+	// since the share last changed, the old value is saved under the latest snapshot id first.
+	fn set_contribution(pool_id: T::Hash, who: &T::AccountId, value: BalanceOf<T>) {
+		let latest = Self::snapshot_count(pool_id);
+		let key = (pool_id, who.clone(), latest);
+		if latest > 0 && !<SnapshotContributions<T>>::exists(&key) {
+			<SnapshotContributions<T>>::insert(&key, Self::contribution_of((pool_id, who.clone())));
+			<SnapshotAccounts<T>>::mutate((pool_id, latest), |accounts| accounts.push(who.clone()));
+		}
+
+		if value.is_zero() {
+			<Contributions<T>>::remove((pool_id, who.clone()));
+		} else {
+			<Contributions<T>>::insert((pool_id, who.clone()), value);
+		}
+	}
+
+	// Private method that records every movement of funds in the pool ledger
+	fn append_ledger(pool_id: T::Hash, who: &T::AccountId, amount: BalanceOf<T>, kind: LedgerKind) {
+		let index = Self::ledger_count(pool_id);
+		<Ledger<T>>::insert((pool_id, index), LedgerEntry { who: who.clone(), amount, kind });
+		<LedgerCount<T>>::insert(pool_id, index + 1);
+	}
+
+	// Private method called by: snapshot_pool()
+	// Only the oldest snapshot is ever pruned. Lookups for a snapshot id never read the saved values of
+	// older ids, so removing the oldest one can not change the result for the snapshots that remain.
+	fn prune_snapshot(pool_id: T::Hash) {
+		let first = Self::first_snapshot(pool_id);
+		for who in <SnapshotAccounts<T>>::take((pool_id, first)) {
+			<SnapshotContributions<T>>::remove((pool_id, who, first));
+		}
+		<Snapshots<T>>::remove((pool_id, first));
+		<FirstSnapshot<T>>::insert(pool_id, first + 1);
+
+		Self::deposit_event(RawEvent::SnapshotPruned(pool_id, first));
+	}
+
+	/// The share of a contributor as of the given snapshot id, or None if the snapshot was pruned or
+	/// never taken.
+	pub fn contribution_at(pool_id: T::Hash, who: T::AccountId, snapshot_id: u32) -> Option<BalanceOf<T>> {
+		let latest = Self::snapshot_count(pool_id);
+		if snapshot_id == 0 || snapshot_id < Self::first_snapshot(pool_id) || snapshot_id > latest {
+			return None;
+		}
+		// The first value saved at or after the snapshot id is the value the share had when it was taken.
+		// If nothing was saved, the share has not changed since and the live value applies.
+		for id in snapshot_id..=latest {
+			if let Some(value) = <SnapshotContributions<T>>::get((pool_id, who.clone(), id)) {
+				return Some(value);
+			}
+		}
+		Some(Self::contribution_of((pool_id, who)))
+	}
+
+	// Private method called by: do_refund() and emergency_drain()
+	fn pay_funds(who: &T::AccountId, amount: BalanceOf<T>) -> Result {
+		let balance_val = Self::balance_val().unwrap_or_else(Zero::zero);
+		let new_balance = balance_val.checked_sub(&amount).ok_or("Pool balance is too low")?;
+
+		let _ = T::Currency::deposit_creating(who, amount);
+		<BalanceVal<T>>::put(new_balance);
+
+		Self::deposit_event(RawEvent::NewBalance(new_balance));
+		Ok(())
+	}
+
 
 	// Unused right now. Still considering timestamps for some record-keeping
 	pub fn get_time() -> T::Moment {
@@ -116,7 +688,7 @@ mod tests {
 	use support::{impl_outer_origin, assert_ok, assert_noop};
 	use runtime_primitives::{
 		BuildStorage,
-		traits::{BlakeTwo256, IdentityLookup},
+		traits::{BlakeTwo256, IdentityLookup, OnInitialize},
 		testing::{Digest, DigestItem, Header}
 	};
 
@@ -146,10 +718,53 @@ mod tests {
 		type Moment = u64;
 		type OnTimestampSet = ();
 	}
+	impl balances::Trait for PoolTest {
+		type Balance = u64;
+		type OnFreeBalanceZero = ();
+		type OnNewAccount = ();
+		type Event = ();
+		type TransactionPayment = ();
+		type TransferPayment = ();
+		type DustRemoval = ();
+	}
 	impl Trait for PoolTest {
 		type Event = ();
+		type Currency = balances::Module<PoolTest>;
+		type Approval = MockApproval;
+		type Treasury = ();
+		type Council = MockCouncil;
+	}
+
+	// Council motions are stood in for by the signed origin of this account
+	const COUNCIL: u64 = 100;
+
+	pub struct MockCouncil;
+	impl GovernanceOrigin<Origin> for MockCouncil {
+		fn ensure_governance(origin: Origin) -> std::result::Result<(), &'static str> {
+			match origin.into() {
+				Some(system::RawOrigin::Signed(COUNCIL)) => Ok(()),
+				_ => Err("bad origin: expected a council motion"),
+			}
+		}
+	}
+
+	// Approvals are granted by writing the proposal id to storage, see approve_mock()
+	pub struct MockApproval;
+	impl ApprovalProvider<u64, H256> for MockApproval {
+		fn request_approval(_who: u64, payload_hash: H256) -> std::result::Result<ProposalId<H256>, &'static str> {
+			Ok(ProposalId(payload_hash))
+		}
+
+		fn is_approved(proposal_id: ProposalId<H256>) -> bool {
+			runtime_io::storage(proposal_id.as_ref()).is_some()
+		}
+	}
+
+	fn approve_mock(proposal_id: H256) {
+		runtime_io::set_storage(proposal_id.as_ref(), &[1]);
 	}
 	type Pool = Module<PoolTest>;
+	type Balances = balances::Module<PoolTest>;
 
 	// This function basically just builds a genesis storage key/value store according to
 	// our desired mockup.
@@ -157,7 +772,24 @@ mod tests {
 	// Error: missing field `_genesis_phantom_data` in initializer of `groups::GenesisConfig<groups::tests::PoolTest>`
 	// See also: https://github.com/paritytech/substrate/pull/2913 and Issue #2219
 	fn build_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
-		let t = system::GenesisConfig::<PoolTest>::default().build_storage().unwrap().0;
+		let mut t = system::GenesisConfig::<PoolTest>::default().build_storage().unwrap().0;
+		t.extend(
+			GenesisConfig::<PoolTest> {
+				max_snapshots: 2,
+				large_payout: 50,
+				spend_limit: 0,
+				spend_period: 10,
+			}.build_storage().unwrap().0);
+		t.extend(
+			balances::GenesisConfig::<PoolTest> {
+				balances: vec![(1, 100), (2, 100), (3, 100)],
+				transaction_base_fee: 0,
+				transaction_byte_fee: 0,
+				existential_deposit: 0,
+				transfer_fee: 0,
+				creation_fee: 0,
+				vesting: vec![],
+			}.build_storage().unwrap().0);
 		// t.extend(
 		// 	GenesisConfig::<PoolTest> {
 		// 		max_group_size: 12,
@@ -167,4 +799,284 @@ mod tests {
 		// 	}.build_storage().unwrap().0);
 		t.into()
 	}
+	/// Type definitions test objectives:
+	/// * Each struct encodes like its fields in the order of type_definitions()
+	/// * LedgerKind variants encode as their index in type_definitions()
+	#[test]
+	fn type_definitions_should_match_encoding() {
+		let pool = Pool { id: H256::from([1; 32]), contributed: 2u64, donated: 3u64, drained: true };
+		assert_eq!(pool.encode(), (pool.id, pool.contributed, pool.donated, pool.drained).encode());
+		let entry = LedgerEntry { who: 4u64, amount: 5u64, kind: LedgerKind::Donation };
+		assert_eq!(entry.encode(), (entry.who, entry.amount, entry.kind.clone()).encode());
+		let snapshot = Snapshot { block: 6u64, contributed: 7u64 };
+		assert_eq!(snapshot.encode(), (snapshot.block, snapshot.contributed).encode());
+		assert_eq!((LedgerKind::Contribution, LedgerKind::Refund, LedgerKind::Donation).encode(), vec![0, 1, 2]);
+
+		let expected: Vec<Vec<&str>> = vec![
+			vec!["id", "contributed", "donated", "drained"],
+			vec!["Contribution", "Refund", "Donation"],
+			vec!["who", "amount", "kind"],
+			vec!["block", "contributed"],
+		];
+		let names: Vec<Vec<&str>> = type_definitions().iter().map(|def| match def {
+			crate::types::TypeDef::Struct(_, fields) => fields.iter().map(|(name, _)| *name).collect(),
+			crate::types::TypeDef::Enum(_, variants) => variants.clone(),
+			crate::types::TypeDef::Alias(_, ty) => vec![*ty],
+		}).collect();
+		assert_eq!(names, expected);
+	}
+
+	/// Contribute test objectives:
+	/// * Funds are taken from the sender through the configured Currency
+	/// * The pool and module balances track the sum of all contributions
+	/// * Contributing more than the free balance is an error and changes nothing
+	/// * A contributor can be refunded up to their share, and no more
+	#[test]
+	fn contribute_and_refund_should_work() {
+		with_externalities(&mut build_ext(), || {
+			assert_ok!(Pool::create_pool(Origin::signed(1)));
+			assert_eq!(Pool::all_pools_count(), 1);
+			let pool_id = Pool::pool_by_index(0);
+			assert_eq!(Pool::owner_of(pool_id), Some(1));
+			assert_eq!(Pool::pools(0, 10), vec![(pool_id, Some(1), 0, 0, false)]);
+			assert_eq!(Pool::pools(1, 10), vec![]);
+
+			assert_ok!(Pool::contribute(Origin::signed(1), pool_id, 40));
+			assert_ok!(Pool::contribute(Origin::signed(2), pool_id, 10));
+			assert_eq!(Balances::free_balance(&1), 60);
+			assert_eq!(Balances::free_balance(&2), 90);
+			assert_eq!(Pool::contribution_of((pool_id, 1)), 40);
+			assert_eq!(Pool::pool(pool_id).contributed, 50);
+			assert_eq!(Pool::balance_val(), Some(50));
+
+			assert!(Pool::contribute(Origin::signed(3), pool_id, 101).is_err());
+			assert_eq!(Balances::free_balance(&3), 100);
+			assert_eq!(Pool::balance_val(), Some(50));
+
+			assert_noop!(Pool::refund(Origin::signed(2), pool_id, 11), "Refund exceeds contribution");
+			assert_ok!(Pool::refund(Origin::signed(2), pool_id, 10));
+			assert_eq!(Balances::free_balance(&2), 100);
+			assert_eq!(Pool::contribution_of((pool_id, 2)), 0);
+			assert_eq!(Pool::pool(pool_id).contributed, 40);
+			assert_eq!(Pool::balance_val(), Some(40));
+		});
+	}
+
+	/// Donate test objectives:
+	/// * A donation moves funds into the pool but is accounted separately from contributions
+	/// * The donor does not receive a share and can not be refunded
+	#[test]
+	fn donate_should_not_create_share() {
+		with_externalities(&mut build_ext(), || {
+			assert_ok!(Pool::create_pool(Origin::signed(1)));
+			let pool_id = Pool::pool_by_index(0);
+
+			assert_ok!(Pool::donate(Origin::signed(3), pool_id, 25, H256::repeat_byte(7)));
+			assert_eq!(Balances::free_balance(&3), 75);
+			assert_eq!(Pool::pool(pool_id).donated, 25);
+			assert_eq!(Pool::pool(pool_id).contributed, 0);
+			assert_eq!(Pool::contribution_of((pool_id, 3)), 0);
+			assert_eq!(Pool::balance_val(), Some(25));
+
+			assert_noop!(Pool::refund(Origin::signed(3), pool_id, 1), "Refund exceeds contribution");
+			assert_noop!(Pool::donate(Origin::signed(3), H256::zero(), 5, H256::zero()), "This pool does not exist");
+		});
+	}
+
+	/// Protocol fee test objectives:
+	/// * The protocol fee of a donation goes to the treasury, the pool and its ledger record the rest
+	/// * Contributions pay no protocol fee
+	#[test]
+	fn donate_should_pay_protocol_fee() {
+		with_externalities(&mut build_ext(), || {
+			assert_ok!(Pool::create_pool(Origin::signed(1)));
+			let pool_id = Pool::pool_by_index(0);
+			Pool::set_protocol_fee(Permill::from_percent(10));
+
+			assert_ok!(Pool::donate(Origin::signed(3), pool_id, 30, H256::zero()));
+			assert_eq!(Balances::free_balance(&3), 70);
+			assert_eq!(Pool::pool(pool_id).donated, 27);
+			assert_eq!(Pool::ledger_entry((pool_id, 0)).amount, 27);
+			assert_eq!(Pool::balance_val(), Some(27));
+
+			assert_ok!(Pool::contribute(Origin::signed(2), pool_id, 10));
+			assert_eq!(Pool::contribution_of((pool_id, 2)), 10);
+			assert_eq!(Pool::balance_val(), Some(37));
+		});
+	}
+
+	/// Snapshot test objectives:
+	/// * Shares changed after a snapshot still read as the old value for that snapshot id
+	/// * Shares that never changed read as the live value
+	/// * Only the pool owner can take a snapshot
+	/// * The oldest snapshot is pruned once max_snapshots is exceeded
+	#[test]
+	fn snapshot_pool_should_work() {
+		with_externalities(&mut build_ext(), || {
+			assert_ok!(Pool::create_pool(Origin::signed(1)));
+			let pool_id = Pool::pool_by_index(0);
+
+			assert_ok!(Pool::contribute(Origin::signed(1), pool_id, 40));
+			assert_ok!(Pool::contribute(Origin::signed(2), pool_id, 10));
+			assert_noop!(Pool::snapshot_pool(Origin::signed(2), pool_id), "You do not own this pool");
+			assert_ok!(Pool::snapshot_pool(Origin::signed(1), pool_id));
+			assert_eq!(Pool::snapshot_count(pool_id), 1);
+			assert_eq!(Pool::snapshot((pool_id, 1)).map(|s| s.contributed), Some(50));
+
+			// Contributions continue after the snapshot
+			assert_ok!(Pool::contribute(Origin::signed(2), pool_id, 20));
+			assert_ok!(Pool::contribute(Origin::signed(3), pool_id, 5));
+			assert_eq!(Pool::contribution_of((pool_id, 2)), 30);
+			assert_eq!(Pool::contribution_at(pool_id, 1, 1), Some(40));
+			assert_eq!(Pool::contribution_at(pool_id, 2, 1), Some(10));
+			assert_eq!(Pool::contribution_at(pool_id, 3, 1), Some(0));
+
+			// A second snapshot sees the new values, the first one is unchanged
+			assert_ok!(Pool::snapshot_pool(Origin::signed(1), pool_id));
+			assert_ok!(Pool::refund(Origin::signed(2), pool_id, 30));
+			assert_eq!(Pool::contribution_at(pool_id, 2, 2), Some(30));
+			assert_eq!(Pool::contribution_at(pool_id, 2, 1), Some(10));
+
+			// The third snapshot prunes the first
+			assert_ok!(Pool::snapshot_pool(Origin::signed(1), pool_id));
+			assert_eq!(Pool::first_snapshot(pool_id), 2);
+			assert_eq!(Pool::snapshot((pool_id, 1)), None);
+			assert_eq!(Pool::contribution_at(pool_id, 2, 1), None);
+			assert_eq!(Pool::contribution_at(pool_id, 2, 2), Some(30));
+			assert_eq!(Pool::contribution_at(pool_id, 2, 3), Some(0));
+			assert_eq!(Pool::contribution_at(pool_id, 2, 4), None);
+		});
+	}
+
+	/// Emergency test objectives:
+	/// * Only a council motion can drain or recover a pool, root can not
+	/// * Draining moves the pool totals to the target account and freezes the pool
+	/// * Recovering rebuilds the shares from the ledger, even if they were corrupted in the meantime
+	#[test]
+	fn emergency_drain_and_recover_should_work() {
+		with_externalities(&mut build_ext(), || {
+			assert_ok!(Pool::create_pool(Origin::signed(1)));
+			let pool_id = Pool::pool_by_index(0);
+
+			assert_ok!(Pool::contribute(Origin::signed(1), pool_id, 40));
+			assert_ok!(Pool::contribute(Origin::signed(2), pool_id, 30));
+			assert_ok!(Pool::refund(Origin::signed(2), pool_id, 10));
+			assert_ok!(Pool::donate(Origin::signed(3), pool_id, 5, H256::zero()));
+			assert_eq!(Pool::ledger_count(pool_id), 4);
+
+			assert!(Pool::emergency_drain(Origin::signed(1), pool_id, 9).is_err());
+			assert_noop!(Pool::emergency_drain(Origin::ROOT, pool_id, 9), "bad origin: expected a council motion");
+			assert_ok!(Pool::emergency_drain(Origin::signed(COUNCIL), pool_id, 9));
+			assert_eq!(Balances::free_balance(&9), 65);
+			assert_eq!(Pool::balance_val(), Some(0));
+			assert!(Pool::pool(pool_id).drained);
+			assert_noop!(Pool::contribute(Origin::signed(1), pool_id, 1), "This pool has been drained");
+
+			// Simulate a corrupted share table after the incident
+			<Contributions<PoolTest>>::insert((pool_id, 1), 1);
+
+			assert!(Pool::recover_pool(Origin::ROOT, pool_id, 9).is_err());
+			assert_ok!(Pool::recover_pool(Origin::signed(COUNCIL), pool_id, 9));
+			assert_eq!(Balances::free_balance(&9), 0);
+			assert_eq!(Pool::balance_val(), Some(65));
+			assert_eq!(Pool::contribution_of((pool_id, 1)), 40);
+			assert_eq!(Pool::contribution_of((pool_id, 2)), 20);
+			assert_eq!(Pool::contribution_of((pool_id, 3)), 0);
+			assert_eq!(Pool::pool(pool_id).contributed, 60);
+			assert_eq!(Pool::pool(pool_id).donated, 5);
+			assert!(!Pool::pool(pool_id).drained);
+		});
+	}
+
+	/// Large refund test objectives:
+	/// * Refunds of large_payout or more can not be made directly
+	/// * A requested refund can only be claimed by the requester, and only once approved
+	/// * The share is checked again at claim time
+	#[test]
+	fn large_refund_should_need_approval() {
+		with_externalities(&mut build_ext(), || {
+			assert_ok!(Pool::create_pool(Origin::signed(1)));
+			let pool_id = Pool::pool_by_index(0);
+			assert_ok!(Pool::contribute(Origin::signed(1), pool_id, 80));
+
+			assert_noop!(Pool::refund(Origin::signed(1), pool_id, 50), "Large refunds need an approval, use request_refund");
+			assert_noop!(Pool::request_refund(Origin::signed(1), pool_id, 81), "Refund exceeds contribution");
+			assert_ok!(Pool::request_refund(Origin::signed(1), pool_id, 60));
+			let proposal_id = (pool_id, 1u64, 60u64).using_encoded(BlakeTwo256::hash);
+			assert_eq!(Pool::pending_refund(ProposalId(proposal_id)), Some((PoolId(pool_id), 1, 60)));
+
+			assert_noop!(Pool::claim_refund(Origin::signed(1), proposal_id), "This refund has not been approved");
+			approve_mock(proposal_id);
+			assert_noop!(Pool::claim_refund(Origin::signed(2), proposal_id), "This refund was requested by another account");
+
+			// The share dropped while the refund was waiting
+			assert_ok!(Pool::refund(Origin::signed(1), pool_id, 30));
+			assert_noop!(Pool::claim_refund(Origin::signed(1), proposal_id), "Refund exceeds contribution");
+
+			assert_ok!(Pool::contribute(Origin::signed(1), pool_id, 10));
+			assert_ok!(Pool::claim_refund(Origin::signed(1), proposal_id));
+			assert_eq!(Balances::free_balance(&1), 100);
+			assert_eq!(Pool::contribution_of((pool_id, 1)), 0);
+			assert_eq!(Pool::pending_refund(ProposalId(proposal_id)), None);
+		});
+	}
+
+	/// Refund lapse test objectives:
+	/// * A refund request that is not claimed within REFUND_CLAIM_BLOCKS is dropped
+	/// * A claimed request is skipped when its block comes
+	#[test]
+	fn refund_requests_should_lapse() {
+		with_externalities(&mut build_ext(), || {
+			assert_ok!(Pool::create_pool(Origin::signed(1)));
+			let pool_id = Pool::pool_by_index(0);
+			assert_ok!(Pool::contribute(Origin::signed(1), pool_id, 80));
+			assert_ok!(Pool::contribute(Origin::signed(2), pool_id, 80));
+			assert_ok!(Pool::request_refund(Origin::signed(1), pool_id, 60));
+			assert_ok!(Pool::request_refund(Origin::signed(2), pool_id, 60));
+			let lapsing = ProposalId((pool_id, 1u64, 60u64).using_encoded(BlakeTwo256::hash));
+			let claimed = ProposalId((pool_id, 2u64, 60u64).using_encoded(BlakeTwo256::hash));
+			assert_eq!(Pool::refunds_lapsing_at(REFUND_CLAIM_BLOCKS), vec![lapsing, claimed]);
+
+			approve_mock(claimed.0);
+			assert_ok!(Pool::claim_refund(Origin::signed(2), claimed.0));
+
+			Pool::on_initialize(REFUND_CLAIM_BLOCKS);
+			assert_eq!(Pool::pending_refund(lapsing), None);
+			assert!(Pool::refunds_lapsing_at(REFUND_CLAIM_BLOCKS).is_empty());
+			approve_mock(lapsing.0);
+			assert_noop!(Pool::claim_refund(Origin::signed(1), lapsing.0), "No refund pending for this proposal");
+			assert_eq!(Pool::contribution_of((pool_id, 1)), 80);
+		});
+	}
+
+	/// Spend limit test objectives:
+	/// * Large refunds within the spend_limit of the period are paid out without approval
+	/// * Refunds that would go over the limit still need an approval
+	/// * The spend is reset when a new period starts
+	#[test]
+	fn spend_limit_should_auto_approve() {
+		with_externalities(&mut build_ext(), || {
+			<SpendLimit<PoolTest>>::put(100);
+			<system::Module<PoolTest>>::set_block_number(3);
+			assert_ok!(Pool::create_pool(Origin::signed(1)));
+			let pool_id = Pool::pool_by_index(0);
+			assert_ok!(Pool::contribute(Origin::signed(1), pool_id, 90));
+			assert_ok!(Pool::contribute(Origin::signed(2), pool_id, 90));
+
+			assert_ok!(Pool::refund(Origin::signed(1), pool_id, 60));
+			assert_eq!(Pool::period_spent(), 60);
+			assert_eq!(Pool::spend_remaining(), 40);
+			assert_noop!(Pool::refund(Origin::signed(2), pool_id, 50), "Large refunds need an approval, use request_refund");
+			// Small refunds do not count towards the limit
+			assert_ok!(Pool::refund(Origin::signed(2), pool_id, 20));
+			assert_eq!(Pool::period_spent(), 60);
+
+			<system::Module<PoolTest>>::set_block_number(10);
+			assert_eq!(Pool::spend_remaining(), 100);
+			assert_ok!(Pool::refund(Origin::signed(2), pool_id, 70));
+			assert_eq!(Pool::spend_period_start(), 10);
+			assert_eq!(Pool::period_spent(), 70);
+			assert_eq!(Balances::free_balance(&2), 100);
+		});
+	}
 }
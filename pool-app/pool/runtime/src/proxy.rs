@@ -0,0 +1,460 @@
+/// Proxy lets an account, the real account, authorize a delegate to dispatch some of its calls for it, such as a
+/// game server that breeds and trades the kitties of its players. The proxy type limits which calls the delegate
+/// can make, see ProxyType. The runtime decides which calls belong to each type, see CallFilter.
+///
+/// Adding a proxy reserves the proxy_deposit from the real account, returned when the proxy is removed. A proxy
+/// can have a delay: the delegate then first announces the hash of a call, which reserves the
+/// announcement_deposit from the delegate, and can only dispatch it `delay` blocks later. In the meantime the
+/// real account can reject the announcement, so a compromised delegate key can be caught before it acts.
+
+use parity_codec::{Encode, Decode};
+use rstd::prelude::*;
+use rstd::result;
+use runtime_primitives::traits::{Dispatchable, Hash, Zero};
+use support::{decl_module, decl_storage, decl_event, ensure, dispatch::Result, Parameter, StorageMap};
+use support::traits::{Currency, ReservableCurrency};
+use system::ensure_signed;
+
+/// The most proxies of one real account
+pub const MAX_PROXIES: usize = 16;
+
+/// The most pending announcements of one delegate
+pub const MAX_PENDING_ANNOUNCEMENTS: usize = 16;
+
+/// The balance type of the currency that deposits are reserved in
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+
+pub trait Trait: system::Trait {
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	/// The currency that deposits are reserved in
+	type Currency: ReservableCurrency<Self::AccountId>;
+
+	/// A runtime Call that a delegate can dispatch. In the runtime this is the outer Call.
+	type Call: Parameter + Dispatchable<Origin = Self::Origin>;
+
+	/// Decides which calls each proxy type allows. In the runtime this is implemented for the outer Call.
+	type Filter: CallFilter<<Self as Trait>::Call>;
+}
+
+/// The calls a delegate can make for the real account
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum ProxyType {
+	/// Every call, including adding and removing proxies
+	Any,
+	/// The calls of the kitties module and the marketplace: creating, breeding, transferring and trading kitties
+	Kitties,
+	/// Joining and leaving groups, and adding and removing the members of owned groups
+	GroupMembership,
+}
+
+/// Decides whether a proxy type allows a call
+pub trait CallFilter<Call> {
+	fn allows(proxy_type: ProxyType, call: &Call) -> bool;
+}
+
+/// A delegate of a real account
+#[derive(Encode, Decode, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct ProxyDefinition<AccountId, BlockNumber, Balance> {
+	pub delegate: AccountId,
+	pub proxy_type: ProxyType,
+	/// Blocks between the announcement of a call and its dispatch, 0 for calls without announcement
+	pub delay: BlockNumber,
+	/// Reserved from the real account, the proxy_deposit when the proxy was added
+	pub deposit: Balance,
+}
+
+/// A call that a delegate announced for a real account, by the hash of the call
+#[derive(Encode, Decode, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Announcement<AccountId, Hash, BlockNumber, Balance> {
+	pub real: AccountId,
+	pub call_hash: Hash,
+	/// The block of the announcement
+	pub height: BlockNumber,
+	/// Reserved from the delegate, the announcement_deposit when the call was announced
+	pub deposit: Balance,
+}
+
+/// The polkadot-js definitions of the types above, see the types module
+#[cfg(feature = "std")]
+pub fn type_definitions() -> Vec<crate::types::TypeDef> {
+	use crate::types::TypeDef;
+	vec![
+		TypeDef::Enum("ProxyType", vec!["Any", "Kitties", "GroupMembership"]),
+		TypeDef::Struct("ProxyDefinition", vec![
+			("delegate", "AccountId"), ("proxy_type", "ProxyType"), ("delay", "BlockNumber"), ("deposit", "Balance"),
+		]),
+		TypeDef::Struct("Announcement", vec![
+			("real", "AccountId"), ("call_hash", "Hash"), ("height", "BlockNumber"), ("deposit", "Balance"),
+		]),
+	]
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Proxy {
+		// Config values that match the values in the testnet_genesis in chain_spec.rs
+		// Reserved for each proxy from the real account, and for each announcement from the delegate.
+		ProxyDeposit get(proxy_deposit) config(): Option<BalanceOf<T>>;
+		AnnouncementDeposit get(announcement_deposit) config(): Option<BalanceOf<T>>;
+
+		// The proxies of each real account, at most MAX_PROXIES
+		Proxies get(proxies_of): map T::AccountId => Vec<ProxyDefinition<T::AccountId, T::BlockNumber, BalanceOf<T>>>;
+		// The pending announcements of each delegate, at most MAX_PENDING_ANNOUNCEMENTS
+		Announcements get(announcements_of): map T::AccountId => Vec<Announcement<T::AccountId, T::Hash, T::BlockNumber, BalanceOf<T>>>;
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where
+		<T as system::Trait>::AccountId,
+		<T as system::Trait>::Hash,
+		<T as system::Trait>::BlockNumber
+	{
+		/// A proxy was added as (real, delegate, proxy type, delay)
+		ProxyAdded(AccountId, AccountId, ProxyType, BlockNumber),
+
+		/// A proxy was removed as (real, delegate, proxy type)
+		ProxyRemoved(AccountId, AccountId, ProxyType),
+
+		/// A delegate announced a call as (real, delegate, call hash)
+		Announced(AccountId, AccountId, Hash),
+
+		/// An announcement was removed by its delegate or rejected by the real account, as (real, delegate,
+		/// call hash)
+		AnnouncementRemoved(AccountId, AccountId, Hash),
+
+		/// A delegate dispatched a call for the real account as (real, delegate, success)
+		ProxyExecuted(AccountId, AccountId, bool),
+	}
+);
+
+decl_module! {
+	/// The module declaration.
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+
+		fn deposit_event<T>() = default;
+
+		/// Authorize a delegate to make the calls of the proxy type for the sender. With a delay greater than 0
+		/// the delegate has to announce each call that many blocks before it can dispatch it.
+		pub fn add_proxy(origin, delegate: T::AccountId, proxy_type: ProxyType, delay: T::BlockNumber) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(sender != delegate, "An account can not be its own proxy");
+			let mut proxies = Self::proxies_of(&sender);
+			ensure!(proxies.len() < MAX_PROXIES, "Too many proxies for this account");
+			ensure!(!proxies.iter().any(|p| p.delegate == delegate && p.proxy_type == proxy_type), "This proxy already exists");
+
+			let deposit = Self::proxy_deposit().ok_or("Config proxy_deposit not set")?;
+			T::Currency::reserve(&sender, deposit).map_err(|_| "Account can not pay the proxy deposit")?;
+			proxies.push(ProxyDefinition { delegate: delegate.clone(), proxy_type, delay, deposit });
+			<Proxies<T>>::insert(&sender, proxies);
+
+			Self::deposit_event(RawEvent::ProxyAdded(sender, delegate, proxy_type, delay));
+			Ok(())
+		}
+
+		/// Remove a proxy of the sender and return its deposit. Calls the delegate announced stay announced,
+		/// but can no longer be dispatched.
+		pub fn remove_proxy(origin, delegate: T::AccountId, proxy_type: ProxyType) -> Result {
+			let sender = ensure_signed(origin)?;
+			let mut proxies = Self::proxies_of(&sender);
+			let index = proxies.iter().position(|p| p.delegate == delegate && p.proxy_type == proxy_type)
+				.ok_or("No such proxy")?;
+
+			let proxy = proxies.remove(index);
+			T::Currency::unreserve(&sender, proxy.deposit);
+			if proxies.is_empty() {
+				<Proxies<T>>::remove(&sender);
+			} else {
+				<Proxies<T>>::insert(&sender, proxies);
+			}
+
+			Self::deposit_event(RawEvent::ProxyRemoved(sender, delegate, proxy_type));
+			Ok(())
+		}
+
+		/// Dispatch a call for the real account, signed by it. The sender needs a proxy of the real account that
+		/// allows the call and has no delay. A failing call is reported with ProxyExecuted.
+		pub fn proxy(origin, real: T::AccountId, call: Box<<T as Trait>::Call>) -> Result {
+			let sender = ensure_signed(origin)?;
+			let allowed = Self::proxies_of(&real).iter()
+				.any(|p| p.delegate == sender && p.delay.is_zero() && T::Filter::allows(p.proxy_type, &call));
+			ensure!(allowed, "No proxy without delay allows this call");
+
+			Self::dispatch_as(real, sender, *call);
+			Ok(())
+		}
+
+		/// Announce a call for the real account by its hash, to dispatch it with proxy_announced() once the delay
+		/// of the proxy has passed. Reserves the announcement_deposit from the sender.
+		pub fn announce(origin, real: T::AccountId, call_hash: T::Hash) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(Self::proxies_of(&real).iter().any(|p| p.delegate == sender), "Not a proxy of this account");
+			let mut announcements = Self::announcements_of(&sender);
+			ensure!(announcements.len() < MAX_PENDING_ANNOUNCEMENTS, "Too many pending announcements");
+
+			let deposit = Self::announcement_deposit().ok_or("Config announcement_deposit not set")?;
+			T::Currency::reserve(&sender, deposit).map_err(|_| "Account can not pay the announcement deposit")?;
+			let height = <system::Module<T>>::block_number();
+			announcements.push(Announcement { real: real.clone(), call_hash, height, deposit });
+			<Announcements<T>>::insert(&sender, announcements);
+
+			Self::deposit_event(RawEvent::Announced(real, sender, call_hash));
+			Ok(())
+		}
+
+		/// Withdraw an announcement of the sender and return its deposit
+		pub fn remove_announcement(origin, real: T::AccountId, call_hash: T::Hash) -> Result {
+			let sender = ensure_signed(origin)?;
+			Self::take_announcement(&sender, &real, call_hash)?;
+			Self::deposit_event(RawEvent::AnnouncementRemoved(real, sender, call_hash));
+			Ok(())
+		}
+
+		/// Reject a call that a delegate announced for the sender. The deposit goes back to the delegate.
+		pub fn reject_announcement(origin, delegate: T::AccountId, call_hash: T::Hash) -> Result {
+			let sender = ensure_signed(origin)?;
+			Self::take_announcement(&delegate, &sender, call_hash)?;
+			Self::deposit_event(RawEvent::AnnouncementRemoved(sender, delegate, call_hash));
+			Ok(())
+		}
+
+		/// Dispatch an announced call for the real account, signed by it. The sender needs a proxy of the real
+		/// account that allows the call and whose delay passed since the announcement.
+		pub fn proxy_announced(origin, real: T::AccountId, call: Box<<T as Trait>::Call>) -> Result {
+			let sender = ensure_signed(origin)?;
+			let call_hash = T::Hashing::hash_of(&call);
+			let announcement = Self::announcements_of(&sender).into_iter()
+				.find(|a| a.real == real && a.call_hash == call_hash)
+				.ok_or("This call was not announced")?;
+			let now = <system::Module<T>>::block_number();
+			let allowed = Self::proxies_of(&real).iter()
+				.any(|p| p.delegate == sender && T::Filter::allows(p.proxy_type, &call) && announcement.height + p.delay <= now);
+			ensure!(allowed, "No proxy allows this call yet");
+
+			Self::take_announcement(&sender, &real, call_hash)?;
+			Self::dispatch_as(real, sender, *call);
+			Ok(())
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	// Private method called by: proxy() and proxy_announced(). The proxy call succeeds whether the dispatched call
+	// does or not, like an approved call of the approve module.
+	fn dispatch_as(real: T::AccountId, delegate: T::AccountId, call: <T as Trait>::Call) {
+		let ok = match call.dispatch(system::RawOrigin::Signed(real.clone()).into()) {
+			Ok(_) => true,
+			Err(e) => {
+				runtime_io::print(e);
+				false
+			}
+		};
+		Self::deposit_event(RawEvent::ProxyExecuted(real, delegate, ok));
+	}
+
+	// Private method called by: remove_announcement(), reject_announcement() and proxy_announced(). Removes the
+	// announcement and returns its deposit to the delegate.
+	fn take_announcement(delegate: &T::AccountId, real: &T::AccountId, call_hash: T::Hash) -> result::Result<(), &'static str> {
+		let mut announcements = Self::announcements_of(delegate);
+		let index = announcements.iter().position(|a| &a.real == real && a.call_hash == call_hash)
+			.ok_or("No such announcement")?;
+
+		let announcement = announcements.remove(index);
+		T::Currency::unreserve(delegate, announcement.deposit);
+		if announcements.is_empty() {
+			<Announcements<T>>::remove(delegate);
+		} else {
+			<Announcements<T>>::insert(delegate, announcements);
+		}
+		Ok(())
+	}
+
+	/// Whether the delegate can dispatch calls of the proxy type for the real account now, without announcing
+	/// them. Used by UIs.
+	pub fn is_proxy(real: &T::AccountId, delegate: &T::AccountId, proxy_type: ProxyType) -> bool {
+		Self::proxies_of(real).iter()
+			.any(|p| &p.delegate == delegate && p.proxy_type == proxy_type && p.delay.is_zero())
+	}
+}
+
+// *****************************************************************************************************
+// Unit Tests!
+// *****************************************************************************************************
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use runtime_io::with_externalities;
+	use primitives::{H256, Blake2Hasher};
+	use support::{impl_outer_origin, assert_ok, assert_noop};
+	use runtime_primitives::{
+		BuildStorage,
+		traits::{BlakeTwo256, IdentityLookup},
+		testing::{Digest, DigestItem, Header}
+	};
+
+	impl_outer_origin! {
+		pub enum Origin for ProxyTest {}
+	}
+
+	// A stand-in for the runtime Call. Kitty and Group stand for a call of those modules, and write the account
+	// that signed them to storage, so tests can check who a call was dispatched as.
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+	pub enum MockCall {
+		Kitty,
+		Group,
+	}
+	impl Dispatchable for MockCall {
+		type Origin = Origin;
+		type Trait = ProxyTest;
+		fn dispatch(self, origin: Origin) -> Result {
+			let who = ensure_signed(origin)?;
+			runtime_io::set_storage(b"mock_call", &(self, who).encode());
+			Ok(())
+		}
+	}
+
+	fn dispatched() -> Option<(MockCall, u64)> {
+		runtime_io::storage(b"mock_call").and_then(|bytes| Decode::decode(&mut &bytes[..]))
+	}
+
+	pub struct MockFilter;
+	impl CallFilter<MockCall> for MockFilter {
+		fn allows(proxy_type: ProxyType, call: &MockCall) -> bool {
+			match proxy_type {
+				ProxyType::Any => true,
+				ProxyType::Kitties => *call == MockCall::Kitty,
+				ProxyType::GroupMembership => *call == MockCall::Group,
+			}
+		}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct ProxyTest;
+	impl system::Trait for ProxyTest {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type Digest = Digest;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type Log = DigestItem;
+	}
+	impl balances::Trait for ProxyTest {
+		type Balance = u64;
+		type OnFreeBalanceZero = ();
+		type OnNewAccount = ();
+		type Event = ();
+		type TransactionPayment = ();
+		type TransferPayment = ();
+		type DustRemoval = ();
+	}
+	impl Trait for ProxyTest {
+		type Event = ();
+		type Currency = balances::Module<ProxyTest>;
+		type Call = MockCall;
+		type Filter = MockFilter;
+	}
+	type Proxy = Module<ProxyTest>;
+	type Balances = balances::Module<ProxyTest>;
+
+	fn build_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+		let mut t = system::GenesisConfig::<ProxyTest>::default().build_storage().unwrap().0;
+		t.extend(GenesisConfig::<ProxyTest> {
+			proxy_deposit: 10,
+			announcement_deposit: 5,
+		}.build_storage().unwrap().0);
+		t.extend(balances::GenesisConfig::<ProxyTest> {
+			balances: vec![(1, 100), (2, 100), (3, 100)],
+			transaction_base_fee: 0,
+			transaction_byte_fee: 0,
+			existential_deposit: 0,
+			transfer_fee: 0,
+			creation_fee: 0,
+			vesting: vec![],
+		}.build_storage().unwrap().0);
+		t.into()
+	}
+
+	/// Type definitions test objectives:
+	/// * Each struct encodes like its fields in the order of type_definitions()
+	/// * ProxyType variants encode as their index in type_definitions()
+	#[test]
+	fn type_definitions_should_match_encoding() {
+		let proxy = ProxyDefinition { delegate: 1u64, proxy_type: ProxyType::Kitties, delay: 2u64, deposit: 3u64 };
+		assert_eq!(proxy.encode(), (proxy.delegate, proxy.proxy_type, proxy.delay, proxy.deposit).encode());
+		let announcement = Announcement { real: 1u64, call_hash: H256::repeat_byte(2), height: 3u64, deposit: 4u64 };
+		assert_eq!(announcement.encode(),
+			(announcement.real, announcement.call_hash, announcement.height, announcement.deposit).encode());
+
+		for (index, proxy_type) in [ProxyType::Any, ProxyType::Kitties, ProxyType::GroupMembership].iter().enumerate() {
+			assert_eq!(proxy_type.encode(), vec![index as u8]);
+		}
+	}
+
+	/// Proxy test objectives:
+	/// * Adding a proxy reserves the deposit, removing it returns the deposit
+	/// * A delegate can only dispatch the calls its proxy type allows, signed by the real account
+	/// * A proxy with a delay can not dispatch without an announcement
+	#[test]
+	fn proxy_should_filter_calls() {
+		with_externalities(&mut build_ext(), || {
+			assert_noop!(Proxy::add_proxy(Origin::signed(1), 1, ProxyType::Any, 0), "An account can not be its own proxy");
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 2, ProxyType::Kitties, 0));
+			assert_noop!(Proxy::add_proxy(Origin::signed(1), 2, ProxyType::Kitties, 0), "This proxy already exists");
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 3, ProxyType::GroupMembership, 2));
+			assert_eq!(Balances::reserved_balance(&1), 20);
+			assert!(Proxy::is_proxy(&1, &2, ProxyType::Kitties));
+			assert!(!Proxy::is_proxy(&1, &3, ProxyType::GroupMembership));
+
+			assert_noop!(Proxy::proxy(Origin::signed(2), 1, Box::new(MockCall::Group)), "No proxy without delay allows this call");
+			assert_ok!(Proxy::proxy(Origin::signed(2), 1, Box::new(MockCall::Kitty)));
+			assert_eq!(dispatched(), Some((MockCall::Kitty, 1)));
+			assert_noop!(Proxy::proxy(Origin::signed(3), 1, Box::new(MockCall::Group)), "No proxy without delay allows this call");
+
+			assert_ok!(Proxy::remove_proxy(Origin::signed(1), 2, ProxyType::Kitties));
+			assert_noop!(Proxy::remove_proxy(Origin::signed(1), 2, ProxyType::Kitties), "No such proxy");
+			assert_eq!(Balances::reserved_balance(&1), 10);
+			assert_noop!(Proxy::proxy(Origin::signed(2), 1, Box::new(MockCall::Kitty)), "No proxy without delay allows this call");
+		});
+	}
+
+	/// Announcement test objectives:
+	/// * An announced call can only be dispatched once the delay has passed, and only once
+	/// * The real account can reject an announcement, which returns the deposit to the delegate
+	#[test]
+	fn announced_calls_should_wait_for_the_delay() {
+		with_externalities(&mut build_ext(), || {
+			<system::Module<ProxyTest>>::set_block_number(1);
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 3, ProxyType::GroupMembership, 2));
+			let call_hash = BlakeTwo256::hash_of(&MockCall::Group);
+
+			assert_noop!(Proxy::announce(Origin::signed(2), 1, call_hash), "Not a proxy of this account");
+			assert_noop!(Proxy::proxy_announced(Origin::signed(3), 1, Box::new(MockCall::Group)), "This call was not announced");
+			assert_ok!(Proxy::announce(Origin::signed(3), 1, call_hash));
+			assert_eq!(Balances::reserved_balance(&3), 5);
+
+			<system::Module<ProxyTest>>::set_block_number(2);
+			assert_noop!(Proxy::proxy_announced(Origin::signed(3), 1, Box::new(MockCall::Group)), "No proxy allows this call yet");
+			<system::Module<ProxyTest>>::set_block_number(3);
+			assert_ok!(Proxy::proxy_announced(Origin::signed(3), 1, Box::new(MockCall::Group)));
+			assert_eq!(dispatched(), Some((MockCall::Group, 1)));
+			assert_eq!(Balances::reserved_balance(&3), 0);
+			assert_noop!(Proxy::proxy_announced(Origin::signed(3), 1, Box::new(MockCall::Group)), "This call was not announced");
+
+			assert_ok!(Proxy::announce(Origin::signed(3), 1, call_hash));
+			assert_noop!(Proxy::reject_announcement(Origin::signed(2), 3, call_hash), "No such announcement");
+			assert_ok!(Proxy::reject_announcement(Origin::signed(1), 3, call_hash));
+			assert!(Proxy::announcements_of(&3).is_empty());
+			assert_eq!(Balances::reserved_balance(&3), 0);
+		});
+	}
+}
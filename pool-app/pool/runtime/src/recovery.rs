@@ -0,0 +1,470 @@
+/// Recovery lets an account that lost its key regain control through its friends. The account designates one of
+/// its groups as its recovery circle, with a threshold and a delay. A rescuer, usually a new key of the same
+/// person, initiates a recovery. Once threshold members of the group vouched for it and the delay passed since
+/// it was initiated, the rescuer claims the lost account and can dispatch calls for it with as_recovered().
+///
+/// The circle is the live membership of the group: a vouch only counts if the voucher is still a member when the
+/// recovery is claimed, so the owner can remove a friend who went rogue. While the key is not lost the account
+/// can close any recovery it did not start, and keeps the deposit of the rescuer, so initiating is not free spam.
+///
+/// Setting up a recovery reserves the config_deposit from the account, initiating one reserves the
+/// recovery_deposit from the rescuer. The rescuer gets its deposit back when it claims the account.
+
+use parity_codec::{Encode, Decode};
+use rstd::prelude::*;
+use runtime_primitives::traits::Dispatchable;
+use support::{decl_module, decl_storage, decl_event, ensure, dispatch::Result, Parameter, StorageMap};
+use support::traits::{Currency, ReservableCurrency};
+use system::ensure_signed;
+
+use app_primitives::{GroupId, GroupInspector};
+
+/// The most recoveries of one account that are in progress at the same time
+pub const MAX_ACTIVE_RECOVERIES: usize = 8;
+
+/// The balance type of the currency that deposits are reserved in
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+
+pub trait Trait: system::Trait {
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	/// The currency that deposits are reserved in
+	type Currency: ReservableCurrency<Self::AccountId>;
+
+	/// The groups that hold the recovery circles. Implemented by the Groups module.
+	type Groups: GroupInspector<Self::AccountId, Self::Hash>;
+
+	/// A runtime Call that a rescuer can dispatch for a recovered account. In the runtime this is the outer Call.
+	type Call: Parameter + Dispatchable<Origin = Self::Origin>;
+}
+
+/// The recovery circle of an account
+#[derive(Encode, Decode, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct RecoveryConfig<Hash, BlockNumber, Balance> {
+	/// A group owned by the account when the config was created
+	pub group_id: Hash,
+	/// The vouches of current members needed to claim the account
+	pub threshold: u32,
+	/// Blocks between initiating a recovery and claiming the account
+	pub delay: BlockNumber,
+	/// Reserved from the account, the config_deposit when the config was created
+	pub deposit: Balance,
+}
+
+/// A recovery of a lost account that is in progress
+#[derive(Encode, Decode, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct ActiveRecovery<AccountId, BlockNumber, Balance> {
+	pub rescuer: AccountId,
+	/// The block the recovery was initiated in
+	pub created: BlockNumber,
+	/// The members that vouched, in the order they vouched
+	pub vouchers: Vec<AccountId>,
+	/// Reserved from the rescuer, the recovery_deposit when the recovery was initiated
+	pub deposit: Balance,
+}
+
+/// The polkadot-js definitions of the types above, see the types module
+#[cfg(feature = "std")]
+pub fn type_definitions() -> Vec<crate::types::TypeDef> {
+	use crate::types::TypeDef;
+	vec![
+		TypeDef::Struct("RecoveryConfig", vec![
+			("group_id", "Hash"), ("threshold", "u32"), ("delay", "BlockNumber"), ("deposit", "Balance"),
+		]),
+		TypeDef::Struct("ActiveRecovery", vec![
+			("rescuer", "AccountId"), ("created", "BlockNumber"), ("vouchers", "Vec<AccountId>"), ("deposit", "Balance"),
+		]),
+	]
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Recovery {
+		// Config values that match the values in the testnet_genesis in chain_spec.rs
+		// Reserved for each recovery config from the account, and for each recovery from the rescuer.
+		ConfigDeposit get(config_deposit) config(): Option<BalanceOf<T>>;
+		RecoveryDeposit get(recovery_deposit) config(): Option<BalanceOf<T>>;
+
+		// The recovery circle of each account that set one up
+		RecoveryConfigs get(recovery_config): map T::AccountId => Option<RecoveryConfig<T::Hash, T::BlockNumber, BalanceOf<T>>>;
+		// The recoveries in progress of each lost account, at most MAX_ACTIVE_RECOVERIES
+		ActiveRecoveries get(active_recoveries): map T::AccountId => Vec<ActiveRecovery<T::AccountId, T::BlockNumber, BalanceOf<T>>>;
+		// The rescuer that claimed each recovered account
+		Recovered get(rescuer_of): map T::AccountId => Option<T::AccountId>;
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where
+		<T as system::Trait>::AccountId,
+		<T as system::Trait>::Hash
+	{
+		/// An account set up its recovery circle as (account, group id, threshold)
+		RecoveryCreated(AccountId, Hash, u32),
+
+		/// An account removed its recovery circle
+		RecoveryRemoved(AccountId),
+
+		/// A rescuer initiated the recovery of a lost account as (lost, rescuer)
+		RecoveryInitiated(AccountId, AccountId),
+
+		/// A member vouched for a recovery as (lost, rescuer, member)
+		RecoveryVouched(AccountId, AccountId, AccountId),
+
+		/// The lost account closed a recovery and kept the deposit, as (lost, rescuer)
+		RecoveryClosed(AccountId, AccountId),
+
+		/// A rescuer claimed a lost account as (lost, rescuer)
+		AccountRecovered(AccountId, AccountId),
+
+		/// A rescuer gave up control of a recovered account as (lost, rescuer)
+		RecoveryCancelled(AccountId, AccountId),
+
+		/// A rescuer dispatched a call for a recovered account as (lost, rescuer, success)
+		RecoveredCallExecuted(AccountId, AccountId, bool),
+	}
+);
+
+decl_module! {
+	/// The module declaration.
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+
+		fn deposit_event<T>() = default;
+
+		/// Designate a group of the sender as its recovery circle. A recovery needs the vouches of threshold
+		/// members, and can be claimed delay blocks after it was initiated.
+		pub fn create_recovery(origin, group_id: T::Hash, threshold: u32, delay: T::BlockNumber) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(!<RecoveryConfigs<T>>::exists(&sender), "Recovery already set up for this account");
+			ensure!(T::Groups::owner(GroupId(group_id)).as_ref() == Some(&sender), "You do not own this group");
+			let members = T::Groups::members(GroupId(group_id)).ok_or("This group does not exist")?;
+			ensure!(threshold > 0, "Threshold must be greater than zero");
+			ensure!(threshold as usize <= members.len(), "Threshold exceeds the members of this group");
+
+			let deposit = Self::config_deposit().ok_or("Config config_deposit not set")?;
+			T::Currency::reserve(&sender, deposit).map_err(|_| "Account can not pay the recovery deposit")?;
+			<RecoveryConfigs<T>>::insert(&sender, RecoveryConfig { group_id, threshold, delay, deposit });
+
+			Self::deposit_event(RawEvent::RecoveryCreated(sender, group_id, threshold));
+			Ok(())
+		}
+
+		/// Remove the recovery circle of the sender and return its deposit. Close the recoveries in progress first.
+		pub fn remove_recovery(origin) -> Result {
+			let sender = ensure_signed(origin)?;
+			let config = Self::recovery_config(&sender).ok_or("No recovery set up for this account")?;
+			ensure!(Self::active_recoveries(&sender).is_empty(), "Close the recoveries in progress first");
+
+			T::Currency::unreserve(&sender, config.deposit);
+			<RecoveryConfigs<T>>::remove(&sender);
+			Self::deposit_event(RawEvent::RecoveryRemoved(sender));
+			Ok(())
+		}
+
+		/// Start the recovery of a lost account, with the sender as the rescuer
+		pub fn initiate_recovery(origin, lost: T::AccountId) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(sender != lost, "An account can not recover itself");
+			ensure!(<RecoveryConfigs<T>>::exists(&lost), "No recovery set up for this account");
+			ensure!(!<Recovered<T>>::exists(&lost), "This account is already recovered");
+			let mut recoveries = Self::active_recoveries(&lost);
+			ensure!(!recoveries.iter().any(|r| r.rescuer == sender), "Recovery already initiated");
+			ensure!(recoveries.len() < MAX_ACTIVE_RECOVERIES, "Too many recoveries in progress for this account");
+
+			let deposit = Self::recovery_deposit().ok_or("Config recovery_deposit not set")?;
+			T::Currency::reserve(&sender, deposit).map_err(|_| "Account can not pay the recovery deposit")?;
+			let created = <system::Module<T>>::block_number();
+			recoveries.push(ActiveRecovery { rescuer: sender.clone(), created, vouchers: Vec::new(), deposit });
+			<ActiveRecoveries<T>>::insert(&lost, recoveries);
+
+			Self::deposit_event(RawEvent::RecoveryInitiated(lost, sender));
+			Ok(())
+		}
+
+		/// Vouch for the rescuer of a lost account. Rule: only the members of the recovery circle.
+		pub fn vouch_recovery(origin, lost: T::AccountId, rescuer: T::AccountId) -> Result {
+			let sender = ensure_signed(origin)?;
+			let config = Self::recovery_config(&lost).ok_or("No recovery set up for this account")?;
+			ensure!(T::Groups::is_member(GroupId(config.group_id), &sender), "Not a member of the recovery circle");
+			let mut recoveries = Self::active_recoveries(&lost);
+			let recovery = recoveries.iter_mut().find(|r| r.rescuer == rescuer).ok_or("No such recovery")?;
+			ensure!(!recovery.vouchers.contains(&sender), "Already vouched for this recovery");
+
+			recovery.vouchers.push(sender.clone());
+			<ActiveRecoveries<T>>::insert(&lost, recoveries);
+			Self::deposit_event(RawEvent::RecoveryVouched(lost, rescuer, sender));
+			Ok(())
+		}
+
+		/// Claim a lost account once the delay passed and threshold members that are still in the circle vouched.
+		/// Returns the deposit of the sender.
+		pub fn claim_recovery(origin, lost: T::AccountId) -> Result {
+			let sender = ensure_signed(origin)?;
+			let config = Self::recovery_config(&lost).ok_or("No recovery set up for this account")?;
+			let mut recoveries = Self::active_recoveries(&lost);
+			let index = recoveries.iter().position(|r| r.rescuer == sender).ok_or("No such recovery")?;
+			let now = <system::Module<T>>::block_number();
+			ensure!(recoveries[index].created + config.delay <= now, "The recovery delay has not passed");
+			let vouches = recoveries[index].vouchers.iter()
+				.filter(|v| T::Groups::is_member(GroupId(config.group_id), v))
+				.count();
+			ensure!(vouches >= config.threshold as usize, "Not enough vouches for this recovery");
+
+			let recovery = recoveries.remove(index);
+			T::Currency::unreserve(&sender, recovery.deposit);
+			Self::store_recoveries(&lost, recoveries);
+			<Recovered<T>>::insert(&lost, &sender);
+
+			Self::deposit_event(RawEvent::AccountRecovered(lost, sender));
+			Ok(())
+		}
+
+		/// Close a recovery of the sender that someone else initiated. The sender keeps the deposit of the rescuer.
+		pub fn close_recovery(origin, rescuer: T::AccountId) -> Result {
+			let sender = ensure_signed(origin)?;
+			let mut recoveries = Self::active_recoveries(&sender);
+			let index = recoveries.iter().position(|r| r.rescuer == rescuer).ok_or("No such recovery")?;
+
+			let recovery = recoveries.remove(index);
+			T::Currency::repatriate_reserved(&rescuer, &sender, recovery.deposit)?;
+			Self::store_recoveries(&sender, recoveries);
+
+			Self::deposit_event(RawEvent::RecoveryClosed(sender, rescuer));
+			Ok(())
+		}
+
+		/// Dispatch a call for a lost account that the sender claimed, signed by the lost account. A failing call
+		/// is reported with RecoveredCallExecuted.
+		pub fn as_recovered(origin, lost: T::AccountId, call: Box<<T as Trait>::Call>) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(Self::rescuer_of(&lost) == Some(sender.clone()), "Not the rescuer of this account");
+
+			let ok = match call.dispatch(system::RawOrigin::Signed(lost.clone()).into()) {
+				Ok(_) => true,
+				Err(e) => {
+					runtime_io::print(e);
+					false
+				}
+			};
+			Self::deposit_event(RawEvent::RecoveredCallExecuted(lost, sender, ok));
+			Ok(())
+		}
+
+		/// Give up control of a recovered account, such as after moving its funds. The lost account can be
+		/// recovered again.
+		pub fn cancel_recovered(origin, lost: T::AccountId) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(Self::rescuer_of(&lost) == Some(sender.clone()), "Not the rescuer of this account");
+
+			<Recovered<T>>::remove(&lost);
+			Self::deposit_event(RawEvent::RecoveryCancelled(lost, sender));
+			Ok(())
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	// Private method called by: claim_recovery() and close_recovery()
+	fn store_recoveries(lost: &T::AccountId, recoveries: Vec<ActiveRecovery<T::AccountId, T::BlockNumber, BalanceOf<T>>>) {
+		if recoveries.is_empty() {
+			<ActiveRecoveries<T>>::remove(lost);
+		} else {
+			<ActiveRecoveries<T>>::insert(lost, recoveries);
+		}
+	}
+}
+
+// *****************************************************************************************************
+// Unit Tests!
+// *****************************************************************************************************
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use runtime_io::with_externalities;
+	use primitives::{H256, Blake2Hasher};
+	use support::{impl_outer_origin, assert_ok, assert_noop};
+	use runtime_primitives::{
+		BuildStorage,
+		traits::{BlakeTwo256, IdentityLookup},
+		testing::{Digest, DigestItem, Header}
+	};
+
+	impl_outer_origin! {
+		pub enum Origin for RecoveryTest {}
+	}
+
+	// A stand-in for the runtime Call that writes the account that signed it to storage
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+	pub struct MockCall;
+	impl Dispatchable for MockCall {
+		type Origin = Origin;
+		type Trait = RecoveryTest;
+		fn dispatch(self, origin: Origin) -> Result {
+			let who = ensure_signed(origin)?;
+			runtime_io::set_storage(b"mock_call", &who.encode());
+			Ok(())
+		}
+	}
+
+	fn dispatched_by() -> Option<u64> {
+		runtime_io::storage(b"mock_call").and_then(|bytes| Decode::decode(&mut &bytes[..]))
+	}
+
+	// The owner and members of a group are read from storage under the group id, see set_mock_group()
+	pub struct MockGroups;
+	impl GroupInspector<u64, H256> for MockGroups {
+		fn members(group_id: GroupId<H256>) -> Option<Vec<u64>> {
+			Self::group(group_id).map(|(_, members)| members)
+		}
+
+		fn is_member(group_id: GroupId<H256>, who: &u64) -> bool {
+			Self::members(group_id).map_or(false, |members| members.contains(who))
+		}
+
+		fn owner(group_id: GroupId<H256>) -> Option<u64> {
+			Self::group(group_id).map(|(owner, _)| owner)
+		}
+	}
+	impl MockGroups {
+		fn group(group_id: GroupId<H256>) -> Option<(u64, Vec<u64>)> {
+			runtime_io::storage(group_id.as_ref()).and_then(|v| Decode::decode(&mut &v[..]))
+		}
+	}
+
+	fn set_mock_group(group_id: H256, owner: u64, members: Vec<u64>) {
+		runtime_io::set_storage(group_id.as_ref(), &(owner, members).encode());
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct RecoveryTest;
+	impl system::Trait for RecoveryTest {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type Digest = Digest;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type Log = DigestItem;
+	}
+	impl balances::Trait for RecoveryTest {
+		type Balance = u64;
+		type OnFreeBalanceZero = ();
+		type OnNewAccount = ();
+		type Event = ();
+		type TransactionPayment = ();
+		type TransferPayment = ();
+		type DustRemoval = ();
+	}
+	impl Trait for RecoveryTest {
+		type Event = ();
+		type Currency = balances::Module<RecoveryTest>;
+		type Groups = MockGroups;
+		type Call = MockCall;
+	}
+	type Recovery = Module<RecoveryTest>;
+	type Balances = balances::Module<RecoveryTest>;
+
+	// Account 1 owns group 1 with the friends 2, 3 and 4. Account 5 is the new key of account 1.
+	fn build_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+		let mut t = system::GenesisConfig::<RecoveryTest>::default().build_storage().unwrap().0;
+		t.extend(GenesisConfig::<RecoveryTest> {
+			config_deposit: 10,
+			recovery_deposit: 20,
+		}.build_storage().unwrap().0);
+		t.extend(balances::GenesisConfig::<RecoveryTest> {
+			balances: vec![(1, 100), (5, 100), (6, 100)],
+			transaction_base_fee: 0,
+			transaction_byte_fee: 0,
+			existential_deposit: 0,
+			transfer_fee: 0,
+			creation_fee: 0,
+			vesting: vec![],
+		}.build_storage().unwrap().0);
+		t.into()
+	}
+
+	/// Type definitions test objectives:
+	/// * Each struct encodes like its fields in the order of type_definitions()
+	#[test]
+	fn type_definitions_should_match_encoding() {
+		let config = RecoveryConfig { group_id: H256::repeat_byte(1), threshold: 2u32, delay: 3u64, deposit: 4u64 };
+		assert_eq!(config.encode(), (config.group_id, config.threshold, config.delay, config.deposit).encode());
+		let recovery = ActiveRecovery { rescuer: 1u64, created: 2u64, vouchers: vec![3u64], deposit: 4u64 };
+		assert_eq!(recovery.encode(),
+			(recovery.rescuer, recovery.created, recovery.vouchers.clone(), recovery.deposit).encode());
+	}
+
+	/// Recovery test objectives:
+	/// * Only the owner of a group can make it its recovery circle
+	/// * A claim needs the delay to pass and threshold vouches of accounts that are still members
+	/// * The rescuer gets its deposit back and can dispatch calls signed by the lost account
+	#[test]
+	fn recovery_should_need_vouches_and_the_delay() {
+		with_externalities(&mut build_ext(), || {
+			let group_id = H256::repeat_byte(1);
+			set_mock_group(group_id, 1, vec![2, 3, 4]);
+			assert_noop!(Recovery::create_recovery(Origin::signed(5), group_id, 2, 10), "You do not own this group");
+			assert_noop!(Recovery::create_recovery(Origin::signed(1), group_id, 4, 10), "Threshold exceeds the members of this group");
+			assert_ok!(Recovery::create_recovery(Origin::signed(1), group_id, 2, 10));
+			assert_eq!(Balances::reserved_balance(&1), 10);
+
+			<system::Module<RecoveryTest>>::set_block_number(1);
+			assert_ok!(Recovery::initiate_recovery(Origin::signed(5), 1));
+			assert_eq!(Balances::reserved_balance(&5), 20);
+			assert_noop!(Recovery::vouch_recovery(Origin::signed(6), 1, 5), "Not a member of the recovery circle");
+			assert_ok!(Recovery::vouch_recovery(Origin::signed(2), 1, 5));
+			assert_noop!(Recovery::vouch_recovery(Origin::signed(2), 1, 5), "Already vouched for this recovery");
+			assert_ok!(Recovery::vouch_recovery(Origin::signed(3), 1, 5));
+
+			<system::Module<RecoveryTest>>::set_block_number(10);
+			assert_noop!(Recovery::claim_recovery(Origin::signed(5), 1), "The recovery delay has not passed");
+			<system::Module<RecoveryTest>>::set_block_number(11);
+			// member 3 left the circle, so its vouch no longer counts
+			set_mock_group(group_id, 1, vec![2, 4]);
+			assert_noop!(Recovery::claim_recovery(Origin::signed(5), 1), "Not enough vouches for this recovery");
+			assert_ok!(Recovery::vouch_recovery(Origin::signed(4), 1, 5));
+			assert_ok!(Recovery::claim_recovery(Origin::signed(5), 1));
+			assert_eq!(Recovery::rescuer_of(1), Some(5));
+			assert_eq!(Balances::reserved_balance(&5), 0);
+
+			assert_noop!(Recovery::as_recovered(Origin::signed(6), 1, Box::new(MockCall)), "Not the rescuer of this account");
+			assert_ok!(Recovery::as_recovered(Origin::signed(5), 1, Box::new(MockCall)));
+			assert_eq!(dispatched_by(), Some(1));
+
+			assert_ok!(Recovery::cancel_recovered(Origin::signed(5), 1));
+			assert_noop!(Recovery::as_recovered(Origin::signed(5), 1, Box::new(MockCall)), "Not the rescuer of this account");
+		});
+	}
+
+	/// Close recovery test objectives:
+	/// * The lost account can close a recovery it did not start and keeps the deposit of the rescuer
+	/// * A recovery config can only be removed without recoveries in progress
+	#[test]
+	fn close_recovery_should_keep_the_deposit() {
+		with_externalities(&mut build_ext(), || {
+			let group_id = H256::repeat_byte(1);
+			set_mock_group(group_id, 1, vec![2, 3, 4]);
+			assert_ok!(Recovery::create_recovery(Origin::signed(1), group_id, 2, 10));
+			assert_ok!(Recovery::initiate_recovery(Origin::signed(6), 1));
+			assert_noop!(Recovery::initiate_recovery(Origin::signed(6), 1), "Recovery already initiated");
+			assert_noop!(Recovery::remove_recovery(Origin::signed(1)), "Close the recoveries in progress first");
+
+			assert_noop!(Recovery::close_recovery(Origin::signed(1), 5), "No such recovery");
+			assert_ok!(Recovery::close_recovery(Origin::signed(1), 6));
+			assert_eq!(Balances::free_balance(&6), 80);
+			assert_eq!(Balances::reserved_balance(&6), 0);
+			assert_eq!(Balances::free_balance(&1), 110);
+
+			assert_ok!(Recovery::remove_recovery(Origin::signed(1)));
+			assert_eq!(Balances::reserved_balance(&1), 0);
+			assert!(Recovery::recovery_config(1).is_none());
+		});
+	}
+}
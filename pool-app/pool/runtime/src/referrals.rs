@@ -0,0 +1,341 @@
+/// Referrals rewards the members that bring new members into a group. A new member joins with
+/// join_with_referral() and names a member of the group as its referrer, which earns the referrer a point in the
+/// tally of that group. Each account can be referred into a group once, and never by itself, so leaving and
+/// joining again earns nothing.
+///
+/// The owner of a group can pay for its points: it sets a reward per point and funds the reward fund of the
+/// group, which is held like the pool and the treasury pot, in storage instead of an account. Referrers claim their
+/// points for the reward while the fund covers them. Points that are not claimed stay in the tally.
+
+use parity_codec::{Encode, Decode};
+use rstd::prelude::*;
+use runtime_primitives::traits::{As, CheckedAdd, Zero};
+use support::{decl_module, decl_storage, decl_event, ensure, dispatch::Result, StorageMap};
+use support::traits::{Currency, ExistenceRequirement, WithdrawReason};
+use system::ensure_signed;
+
+use app_primitives::{GroupId, GroupInspector, GroupJoiner};
+
+/// The balance type of the currency that rewards are paid in
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+
+pub trait Trait: system::Trait {
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	/// The currency that rewards are paid in
+	type Currency: Currency<Self::AccountId>;
+
+	/// The groups that members are referred into. Implemented by the Groups module.
+	type Groups: GroupInspector<Self::AccountId, Self::Hash> + GroupJoiner<Self::AccountId, Self::Hash>;
+}
+
+/// The referrals of one referrer in one group
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct ReferralTally {
+	/// All members the referrer brought into the group
+	pub referred: u32,
+	/// The points that were not claimed yet, one per referred member
+	pub unclaimed: u32,
+}
+
+/// The polkadot-js definitions of the types above, see the types module
+#[cfg(feature = "std")]
+pub fn type_definitions() -> Vec<crate::types::TypeDef> {
+	use crate::types::TypeDef;
+	vec![
+		TypeDef::Struct("ReferralTally", vec![("referred", "u32"), ("unclaimed", "u32")]),
+	]
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Referrals {
+		// The tally of each referrer in each group, and the number of members referred into each group
+		Tallies get(tally_of): map (T::Hash, T::AccountId) => ReferralTally;
+		GroupReferrals get(group_referrals): map T::Hash => u32;
+
+		// The referrer of each member that was referred into a group. Kept after the member leaves, so a member
+		// is only referred once.
+		ReferredBy get(referred_by): map (T::Hash, T::AccountId) => Option<T::AccountId>;
+
+		// The reward per point set by the owner of each group, and the funds held to pay it
+		RewardPerPoint get(reward_per_point): map T::Hash => BalanceOf<T>;
+		RewardFund get(reward_fund): map T::Hash => BalanceOf<T>;
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where
+		<T as system::Trait>::AccountId,
+		<T as system::Trait>::Hash,
+		Balance = BalanceOf<T>
+	{
+		/// A member joined a group with a referral as (group id, member, referrer)
+		Referred(Hash, AccountId, AccountId),
+
+		/// The owner of a group set the reward per point
+		RewardSet(Hash, Balance),
+
+		/// Funds were added to the reward fund of a group as (group id, amount, new fund)
+		RewardFunded(Hash, Balance, Balance),
+
+		/// A referrer claimed points for their reward as (group id, referrer, points, amount)
+		RewardClaimed(Hash, AccountId, u32, Balance),
+	}
+);
+
+decl_module! {
+	/// The module declaration.
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+
+		fn deposit_event<T>() = default;
+
+		/// Join a group like join_group() of the groups module, and credit the referrer with a point.
+		/// Rule: the referrer is a member or the owner of the group, and not the sender. An account is referred
+		/// into a group only once.
+		pub fn join_with_referral(origin, group_id: T::Hash, referrer: T::AccountId) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(sender != referrer, "Can not refer yourself");
+			let owner = T::Groups::owner(GroupId(group_id)).ok_or("This group does not exist")?;
+			ensure!(owner == referrer || T::Groups::is_member(GroupId(group_id), &referrer),
+				"The referrer is not in this group");
+			ensure!(!<ReferredBy<T>>::exists((group_id, sender.clone())), "Already referred into this group");
+
+			let mut tally = Self::tally_of((group_id, referrer.clone()));
+			tally.referred = tally.referred.checked_add(1).ok_or("Overflow adding a referral")?;
+			tally.unclaimed = tally.unclaimed.checked_add(1).ok_or("Overflow adding a referral")?;
+			let group_referrals = Self::group_referrals(group_id).checked_add(1).ok_or("Overflow adding a referral")?;
+
+			T::Groups::join(GroupId(group_id), sender.clone())?;
+			<Tallies<T>>::insert((group_id, referrer.clone()), tally);
+			<GroupReferrals<T>>::insert(group_id, group_referrals);
+			<ReferredBy<T>>::insert((group_id, sender.clone()), referrer.clone());
+
+			Self::deposit_event(RawEvent::Referred(group_id, sender, referrer));
+			Ok(())
+		}
+
+		/// Set the reward paid for each point of a group. Zero pays nothing, and points keep accruing.
+		/// Rule: only the owner of the group.
+		pub fn set_reward(origin, group_id: T::Hash, reward_per_point: BalanceOf<T>) -> Result {
+			let sender = ensure_signed(origin)?;
+			Self::ensure_owner(group_id, &sender)?;
+
+			<RewardPerPoint<T>>::insert(group_id, reward_per_point);
+			Self::deposit_event(RawEvent::RewardSet(group_id, reward_per_point));
+			Ok(())
+		}
+
+		/// Move funds of the sender into the reward fund of a group. The funds can only leave the fund as
+		/// rewards. Rule: only the owner of the group.
+		pub fn fund_rewards(origin, group_id: T::Hash, amount: BalanceOf<T>) -> Result {
+			let sender = ensure_signed(origin)?;
+			Self::ensure_owner(group_id, &sender)?;
+			ensure!(!amount.is_zero(), "Amount must be greater than zero");
+			let fund = Self::reward_fund(group_id).checked_add(&amount).ok_or("Overflow adding to the reward fund")?;
+
+			// Like the pool, the withdrawn amount is tracked in RewardFund and the imbalance is dropped
+			let _ = T::Currency::withdraw(&sender, amount, WithdrawReason::Transfer, ExistenceRequirement::KeepAlive)?;
+			<RewardFund<T>>::insert(group_id, fund);
+
+			Self::deposit_event(RawEvent::RewardFunded(group_id, amount, fund));
+			Ok(())
+		}
+
+		/// Claim the unclaimed points of the sender in a group for the reward per point. Pays as many points as
+		/// the reward fund covers, the rest stay unclaimed.
+		pub fn claim_rewards(origin, group_id: T::Hash) -> Result {
+			let sender = ensure_signed(origin)?;
+			let mut tally = Self::tally_of((group_id, sender.clone()));
+			ensure!(tally.unclaimed > 0, "No points to claim in this group");
+			let reward = Self::reward_per_point(group_id);
+			ensure!(!reward.is_zero(), "This group pays no referral rewards");
+
+			let fund = Self::reward_fund(group_id);
+			let covered: u64 = (fund / reward).as_();
+			let points = if covered < tally.unclaimed as u64 { covered as u32 } else { tally.unclaimed };
+			ensure!(points > 0, "The reward fund of this group is empty");
+			let amount = reward * <BalanceOf<T> as As<u64>>::sa(points as u64);
+
+			tally.unclaimed -= points;
+			<Tallies<T>>::insert((group_id, sender.clone()), tally);
+			<RewardFund<T>>::insert(group_id, fund - amount);
+			let _ = T::Currency::deposit_creating(&sender, amount);
+
+			Self::deposit_event(RawEvent::RewardClaimed(group_id, sender, points, amount));
+			Ok(())
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	// Private method called by: set_reward() and fund_rewards()
+	fn ensure_owner(group_id: T::Hash, who: &T::AccountId) -> Result {
+		let owner = T::Groups::owner(GroupId(group_id)).ok_or("This group does not exist")?;
+		ensure!(&owner == who, "You do not own this group");
+		Ok(())
+	}
+}
+
+// *****************************************************************************************************
+// Unit Tests!
+// *****************************************************************************************************
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use runtime_io::with_externalities;
+	use primitives::{H256, Blake2Hasher};
+	use support::{impl_outer_origin, assert_ok, assert_noop};
+	use runtime_primitives::{
+		BuildStorage,
+		traits::{BlakeTwo256, IdentityLookup},
+		testing::{Digest, DigestItem, Header}
+	};
+
+	impl_outer_origin! {
+		pub enum Origin for ReferralsTest {}
+	}
+
+	// The owner and members of a group are read from storage under the group id, see set_mock_group()
+	pub struct MockGroups;
+	impl GroupInspector<u64, H256> for MockGroups {
+		fn members(group_id: GroupId<H256>) -> Option<Vec<u64>> {
+			Self::group(group_id).map(|(_, members)| members)
+		}
+
+		fn is_member(group_id: GroupId<H256>, who: &u64) -> bool {
+			Self::members(group_id).map_or(false, |members| members.contains(who))
+		}
+
+		fn owner(group_id: GroupId<H256>) -> Option<u64> {
+			Self::group(group_id).map(|(owner, _)| owner)
+		}
+	}
+	impl GroupJoiner<u64, H256> for MockGroups {
+		fn join(group_id: GroupId<H256>, who: u64) -> std::result::Result<(), &'static str> {
+			let (owner, mut members) = Self::group(group_id).ok_or("This group does not exist")?;
+			ensure!(!members.contains(&who), "Account is already a member of this group");
+			members.push(who);
+			set_mock_group(group_id.0, owner, members);
+			Ok(())
+		}
+	}
+	impl MockGroups {
+		fn group(group_id: GroupId<H256>) -> Option<(u64, Vec<u64>)> {
+			runtime_io::storage(group_id.as_ref()).and_then(|v| Decode::decode(&mut &v[..]))
+		}
+	}
+
+	fn set_mock_group(group_id: H256, owner: u64, members: Vec<u64>) {
+		runtime_io::set_storage(group_id.as_ref(), &(owner, members).encode());
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct ReferralsTest;
+	impl system::Trait for ReferralsTest {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type Digest = Digest;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type Log = DigestItem;
+	}
+	impl balances::Trait for ReferralsTest {
+		type Balance = u64;
+		type OnFreeBalanceZero = ();
+		type OnNewAccount = ();
+		type Event = ();
+		type TransactionPayment = ();
+		type TransferPayment = ();
+		type DustRemoval = ();
+	}
+	impl Trait for ReferralsTest {
+		type Event = ();
+		type Currency = balances::Module<ReferralsTest>;
+		type Groups = MockGroups;
+	}
+	type Referrals = Module<ReferralsTest>;
+	type Balances = balances::Module<ReferralsTest>;
+
+	// Account 1 owns group 1 with the member 2
+	fn build_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+		let mut t = system::GenesisConfig::<ReferralsTest>::default().build_storage().unwrap().0;
+		t.extend(balances::GenesisConfig::<ReferralsTest> {
+			balances: vec![(1, 100)],
+			transaction_base_fee: 0,
+			transaction_byte_fee: 0,
+			existential_deposit: 0,
+			transfer_fee: 0,
+			creation_fee: 0,
+			vesting: vec![],
+		}.build_storage().unwrap().0);
+		let mut ext: runtime_io::TestExternalities<Blake2Hasher> = t.into();
+		with_externalities(&mut ext, || set_mock_group(H256::repeat_byte(1), 1, vec![2]));
+		ext
+	}
+
+	/// Type definitions test objectives:
+	/// * ReferralTally encodes like its fields in the order of type_definitions()
+	#[test]
+	fn type_definitions_should_match_encoding() {
+		let tally = ReferralTally { referred: 1, unclaimed: 2 };
+		assert_eq!(tally.encode(), (tally.referred, tally.unclaimed).encode());
+	}
+
+	/// Referral test objectives:
+	/// * A join with a referral adds the member and a point to the tally of the referrer
+	/// * Self referrals, referrers outside the group and a second referral of the same account are rejected
+	#[test]
+	fn referrals_should_be_tallied() {
+		with_externalities(&mut build_ext(), || {
+			let group_id = H256::repeat_byte(1);
+			assert_noop!(Referrals::join_with_referral(Origin::signed(3), group_id, 3), "Can not refer yourself");
+			assert_noop!(Referrals::join_with_referral(Origin::signed(3), group_id, 4), "The referrer is not in this group");
+			assert_noop!(Referrals::join_with_referral(Origin::signed(3), H256::repeat_byte(2), 2), "This group does not exist");
+
+			assert_ok!(Referrals::join_with_referral(Origin::signed(3), group_id, 2));
+			assert!(MockGroups::is_member(GroupId(group_id), &3));
+			assert_ok!(Referrals::join_with_referral(Origin::signed(4), group_id, 1));
+			assert_ok!(Referrals::join_with_referral(Origin::signed(5), group_id, 3));
+			assert_eq!(Referrals::tally_of((group_id, 2)), ReferralTally { referred: 1, unclaimed: 1 });
+			assert_eq!(Referrals::group_referrals(group_id), 3);
+			assert_eq!(Referrals::referred_by((group_id, 5)), Some(3));
+
+			// Leaving and joining again is not a new referral
+			set_mock_group(group_id, 1, vec![2, 4, 5]);
+			assert_noop!(Referrals::join_with_referral(Origin::signed(3), group_id, 2), "Already referred into this group");
+		});
+	}
+
+	/// Reward test objectives:
+	/// * Only the owner sets the reward and funds it
+	/// * Claims pay the points the fund covers and keep the rest
+	#[test]
+	fn rewards_should_be_paid_from_the_fund() {
+		with_externalities(&mut build_ext(), || {
+			let group_id = H256::repeat_byte(1);
+			for who in 3..6 {
+				assert_ok!(Referrals::join_with_referral(Origin::signed(who), group_id, 2));
+			}
+			assert_noop!(Referrals::claim_rewards(Origin::signed(2), group_id), "This group pays no referral rewards");
+			assert_noop!(Referrals::set_reward(Origin::signed(2), group_id, 10), "You do not own this group");
+			assert_ok!(Referrals::set_reward(Origin::signed(1), group_id, 10));
+			assert_noop!(Referrals::claim_rewards(Origin::signed(2), group_id), "The reward fund of this group is empty");
+
+			assert_ok!(Referrals::fund_rewards(Origin::signed(1), group_id, 25));
+			assert_eq!(Balances::free_balance(&1), 75);
+			assert_ok!(Referrals::claim_rewards(Origin::signed(2), group_id));
+			assert_eq!(Balances::free_balance(&2), 20);
+			assert_eq!(Referrals::reward_fund(group_id), 5);
+			assert_eq!(Referrals::tally_of((group_id, 2)), ReferralTally { referred: 3, unclaimed: 1 });
+			assert_noop!(Referrals::claim_rewards(Origin::signed(3), group_id), "No points to claim in this group");
+		});
+	}
+}
@@ -0,0 +1,341 @@
+/// Scheduler dispatches calls in a later block. An account schedules a call that is dispatched signed by it, and
+/// root, through sudo, one that is dispatched as root. Modules schedule their own calls through CallScheduler,
+/// dispatched as root, so a feature that acts after a delay, such as a timelock, a vesting release or a handoff of
+/// ownership, does not need a queue of its own.
+///
+/// The calls of a block are dispatched in its on_initialize(), at most MAX_DISPATCHES_PER_BLOCK of them, in the
+/// order they were scheduled. The rest wait for the next blocks, see the deadline module. A call that fails is
+/// dropped and reported with the Dispatched event.
+
+use parity_codec::{Encode, Decode};
+use rstd::marker::PhantomData;
+use rstd::prelude::*;
+use rstd::result;
+use runtime_primitives::traits::Dispatchable;
+use support::{decl_module, decl_storage, decl_event, ensure, dispatch::Result, Parameter, StorageMap, StorageValue};
+
+use app_primitives::CallScheduler;
+use crate::deadline::{DeadlineStorage, Deadlines};
+
+/// The most scheduled calls dispatched in one block
+pub const MAX_DISPATCHES_PER_BLOCK: usize = 10;
+
+/// The most calls an account can have scheduled at the same time
+pub const MAX_SCHEDULED_PER_ACCOUNT: u32 = 16;
+
+pub trait Trait: system::Trait {
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	/// A runtime Call that can be scheduled. In the runtime this is the outer Call.
+	type Call: Parameter + Dispatchable<Origin = Self::Origin>;
+}
+
+/// A call waiting for its block
+#[derive(Encode, Decode, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct ScheduledCall<AccountId, Call, BlockNumber> {
+	/// The account the call is dispatched as, or None for root
+	pub owner: Option<AccountId>,
+	pub call: Call,
+	/// The block the call is dispatched in, or later while that block is over budget
+	pub when: BlockNumber,
+}
+
+/// The polkadot-js definitions of the types above, see the types module
+#[cfg(feature = "std")]
+pub fn type_definitions() -> Vec<crate::types::TypeDef> {
+	use crate::types::TypeDef;
+	vec![
+		TypeDef::Struct("ScheduledCall", vec![("owner", "Option<AccountId>"), ("call", "Call"), ("when", "BlockNumber")]),
+	]
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Scheduler {
+		// The id of the next scheduled call
+		NextId get(next_id): u64;
+		// The scheduled calls by id. Removed when they are dispatched or cancelled.
+		Scheduled get(scheduled): map u64 => Option<ScheduledCall<T::AccountId, <T as Trait>::Call, T::BlockNumber>>;
+		// The ids of the calls due in each block, see the deadline module. Cancelled ids stay until their block.
+		Agenda: map T::BlockNumber => Vec<u64>;
+		AgendaPages: map (T::BlockNumber, u32) => Vec<u64>;
+		AgendaPageCount: map T::BlockNumber => u32;
+		AgendaCursor: Option<(T::BlockNumber, u32)>;
+		// The number of calls each account has scheduled
+		ScheduledCount get(scheduled_count): map T::AccountId => u32;
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where
+		<T as system::Trait>::BlockNumber
+	{
+		/// A call was scheduled as (id, block)
+		Scheduled(u64, BlockNumber),
+
+		/// A scheduled call was cancelled
+		Cancelled(u64),
+
+		/// A scheduled call was dispatched as (id, success)
+		Dispatched(u64, bool),
+	}
+);
+
+decl_module! {
+	/// The module declaration.
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+
+		fn deposit_event<T>() = default;
+
+		fn on_initialize(n: T::BlockNumber) {
+			Self::dispatch_due(n);
+		}
+
+		/// Dispatch a call in a later block, signed by the sender, or as root when scheduled through sudo
+		pub fn schedule(origin, call: Box<<T as Trait>::Call>, at_block: T::BlockNumber) -> Result {
+			let owner = Self::schedule_owner(origin)?;
+			if let Some(who) = &owner {
+				ensure!(Self::scheduled_count(who) < MAX_SCHEDULED_PER_ACCOUNT, "Too many scheduled calls for this account");
+			}
+			Self::insert(owner, *call, at_block)?;
+			Ok(())
+		}
+
+		/// Drop a scheduled call. Rule: the account that scheduled it, or root for any call.
+		pub fn cancel(origin, id: u64) -> Result {
+			let owner = Self::schedule_owner(origin)?;
+			let scheduled = Self::scheduled(id).ok_or("No such scheduled call")?;
+			ensure!(owner.is_none() || owner == scheduled.owner, "Only the account that scheduled this call can cancel it");
+
+			Self::remove(id, scheduled.owner);
+			Ok(())
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	// Private method called by: schedule() and cancel(). The account of a signed origin, or None for root.
+	fn schedule_owner(origin: T::Origin) -> result::Result<Option<T::AccountId>, &'static str> {
+		match origin.into() {
+			Some(system::RawOrigin::Signed(who)) => Ok(Some(who)),
+			Some(system::RawOrigin::Root) => Ok(None),
+			_ => Err("Only signed and root origins can schedule"),
+		}
+	}
+
+	// Private method called by: schedule() and CallScheduler::schedule()
+	fn insert(owner: Option<T::AccountId>, call: <T as Trait>::Call, when: T::BlockNumber) -> result::Result<u64, &'static str> {
+		ensure!(when > <system::Module<T>>::block_number(), "Calls can only be scheduled for a later block");
+		let id = Self::next_id();
+		let next_id = id.checked_add(1).ok_or("Overflow scheduling a call")?;
+
+		if let Some(who) = &owner {
+			<ScheduledCount<T>>::mutate(who, |count| *count += 1);
+		}
+		<Scheduled<T>>::insert(id, ScheduledCall { owner, call, when });
+		<NextId<T>>::put(next_id);
+		<DueCalls<T>>::schedule(when, id);
+
+		Self::deposit_event(RawEvent::Scheduled(id, when));
+		Ok(id)
+	}
+
+	// Private method called by: cancel() and CallScheduler::cancel()
+	fn remove(id: u64, owner: Option<T::AccountId>) {
+		<Scheduled<T>>::remove(id);
+		if let Some(who) = owner {
+			Self::release(&who);
+		}
+		Self::deposit_event(RawEvent::Cancelled(id));
+	}
+
+	// Private method called by: remove() and dispatch_due()
+	fn release(who: &T::AccountId) {
+		let count = Self::scheduled_count(who).saturating_sub(1);
+		if count == 0 {
+			<ScheduledCount<T>>::remove(who);
+		} else {
+			<ScheduledCount<T>>::insert(who, count);
+		}
+	}
+
+	// Private method called by: on_initialize(). Dispatches the calls due in this block, at most
+	// MAX_DISPATCHES_PER_BLOCK of them, and skips the ids of cancelled calls.
+	fn dispatch_due(n: T::BlockNumber) {
+		for id in <DueCalls<T>>::take_due(n) {
+			let scheduled = match <Scheduled<T>>::take(id) {
+				Some(scheduled) => scheduled,
+				None => continue,
+			};
+			let origin = match &scheduled.owner {
+				Some(who) => {
+					Self::release(who);
+					system::RawOrigin::Signed(who.clone())
+				},
+				None => system::RawOrigin::Root,
+			};
+			let ok = match scheduled.call.dispatch(origin.into()) {
+				Ok(_) => true,
+				Err(e) => {
+					runtime_io::print(e);
+					false
+				}
+			};
+			Self::deposit_event(RawEvent::Dispatched(id, ok));
+		}
+	}
+}
+
+impl<T: Trait, C: Into<<T as Trait>::Call>> CallScheduler<T::BlockNumber, C> for Module<T> {
+	fn schedule(when: T::BlockNumber, call: C) -> result::Result<u64, &'static str> {
+		Self::insert(None, call.into(), when)
+	}
+
+	fn cancel(id: u64) -> result::Result<(), &'static str> {
+		let scheduled = Self::scheduled(id).ok_or("No such scheduled call")?;
+		Self::remove(id, scheduled.owner);
+		Ok(())
+	}
+}
+
+/// The agenda of scheduled calls by block, see the deadline module
+struct ScheduleQueue<T>(PhantomData<T>);
+
+impl<T: Trait> DeadlineStorage for ScheduleQueue<T> {
+	type BlockNumber = T::BlockNumber;
+	type Id = u64;
+	type Queue = Agenda<T>;
+	type Pages = AgendaPages<T>;
+	type PageCount = AgendaPageCount<T>;
+	type Cursor = AgendaCursor<T>;
+	const BUDGET: usize = MAX_DISPATCHES_PER_BLOCK;
+}
+
+type DueCalls<T> = Deadlines<ScheduleQueue<T>>;
+
+// *****************************************************************************************************
+// Unit Tests!
+// *****************************************************************************************************
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use runtime_io::with_externalities;
+	use primitives::{H256, Blake2Hasher};
+	use support::{impl_outer_origin, assert_ok, assert_noop};
+	use runtime_primitives::{
+		BuildStorage,
+		traits::{BlakeTwo256, IdentityLookup, OnInitialize},
+		testing::{Digest, DigestItem, Header}
+	};
+
+	impl_outer_origin! {
+		pub enum Origin for SchedulerTest {}
+	}
+
+	// A stand-in for the runtime Call. Record appends its value and the account it was dispatched by, or 0 for
+	// root, to storage, so tests can check the order and the origin of the dispatched calls.
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+	pub enum MockCall {
+		Record(u32),
+		Fail,
+	}
+	impl Dispatchable for MockCall {
+		type Origin = Origin;
+		type Trait = SchedulerTest;
+		fn dispatch(self, origin: Origin) -> Result {
+			match self {
+				MockCall::Record(value) => {
+					let who = match origin.into() {
+						Some(system::RawOrigin::Signed(who)) => who,
+						Some(system::RawOrigin::Root) => 0,
+						_ => return Err("bad origin"),
+					};
+					let mut recorded = recorded();
+					recorded.push((value, who));
+					runtime_io::set_storage(b"mock_calls", &recorded.encode());
+					Ok(())
+				}
+				MockCall::Fail => Err("Mock call failed"),
+			}
+		}
+	}
+
+	fn recorded() -> Vec<(u32, u64)> {
+		runtime_io::storage(b"mock_calls")
+			.and_then(|bytes| Decode::decode(&mut &bytes[..]))
+			.unwrap_or_default()
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct SchedulerTest;
+	impl system::Trait for SchedulerTest {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type Digest = Digest;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type Log = DigestItem;
+	}
+	impl Trait for SchedulerTest {
+		type Event = ();
+		type Call = MockCall;
+	}
+	type Scheduler = Module<SchedulerTest>;
+
+	fn build_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+		system::GenesisConfig::<SchedulerTest>::default().build_storage().unwrap().0.into()
+	}
+
+	/// Schedule test objectives:
+	/// * Calls are dispatched in their block with the origin that scheduled them
+	/// * Only the account that scheduled a call, or root, can cancel it
+	/// * Modules schedule through CallScheduler, dispatched as root
+	#[test]
+	fn scheduled_calls_should_dispatch_in_their_block() {
+		with_externalities(&mut build_ext(), || {
+			<system::Module<SchedulerTest>>::set_block_number(1);
+			assert_noop!(Scheduler::schedule(Origin::signed(1), Box::new(MockCall::Record(1)), 1),
+				"Calls can only be scheduled for a later block");
+			assert_ok!(Scheduler::schedule(Origin::signed(1), Box::new(MockCall::Record(1)), 3));
+			assert_ok!(Scheduler::schedule(Origin::signed(2), Box::new(MockCall::Record(2)), 3));
+			assert_ok!(Scheduler::schedule(Origin::signed(2), Box::new(MockCall::Fail), 3));
+			assert_eq!(<Scheduler as CallScheduler<u64, MockCall>>::schedule(2, MockCall::Record(3)), Ok(3));
+			assert_eq!(Scheduler::scheduled_count(2), 2);
+
+			assert_noop!(Scheduler::cancel(Origin::signed(1), 1), "Only the account that scheduled this call can cancel it");
+			assert_ok!(Scheduler::cancel(Origin::signed(2), 1));
+			assert_eq!(Scheduler::scheduled_count(2), 1);
+
+			Scheduler::on_initialize(2);
+			assert_eq!(recorded(), vec![(3, 0)]);
+			Scheduler::on_initialize(3);
+			assert_eq!(recorded(), vec![(3, 0), (1, 1)]);
+			assert_eq!(Scheduler::scheduled(2), None);
+			assert_eq!(Scheduler::scheduled_count(2), 0);
+		});
+	}
+
+	/// Dispatch budget test objectives:
+	/// * At most MAX_DISPATCHES_PER_BLOCK calls are dispatched per block, the rest in the next blocks in order
+	#[test]
+	fn dispatches_should_keep_to_the_budget() {
+		with_externalities(&mut build_ext(), || {
+			for value in 0..MAX_DISPATCHES_PER_BLOCK as u32 + 2 {
+				assert_ok!(Scheduler::schedule(Origin::ROOT, Box::new(MockCall::Record(value)), 2));
+			}
+
+			Scheduler::on_initialize(2);
+			assert_eq!(recorded().len(), MAX_DISPATCHES_PER_BLOCK);
+			Scheduler::on_initialize(3);
+			let values: Vec<u32> = recorded().into_iter().map(|(value, _)| value).collect();
+			assert_eq!(values, (0..MAX_DISPATCHES_PER_BLOCK as u32 + 2).collect::<Vec<_>>());
+		});
+	}
+}
@@ -0,0 +1,734 @@
+/// The Substratekitties module is copied from the kitties runtime in this repo, so that kitties can live on
+/// the same chain as groups, pools and approvals.
+///
+/// Kitties are a collection of the nft module: KittyCollection names the storage below, the Kitty struct is its
+/// metadata schema and MaxKitties its supply rule. Breeding is the Breeding extension of the collection. Kitties
+/// are sold through the marketplace module, which trades them through NftProvider like any other item.
+
+use parity_codec::{Encode, Decode};
+use rstd::cmp;
+use rstd::marker::PhantomData;
+use rstd::prelude::*;
+use runtime_primitives::traits::{As, CheckedSub};
+use support::{decl_storage, decl_module, decl_event, ensure, StorageDoubleMap, StorageMap, StorageValue, dispatch::Result};
+use system::ensure_signed;
+use app_primitives::{FeeCall, FeeCharger, KittyId, NftProvider};
+use crate::ids::{self, IdKind};
+use crate::nft::{self, Breeding, NftCollection};
+use crate::ownership::{self, OwnershipStorage};
+
+#[cfg(feature = "std")]
+use runtime_io::{with_storage, StorageOverlay, ChildrenStorageOverlay};
+
+/// How many blocks KittiesChangedAt keeps, about 100 minutes with 10 second blocks
+pub const CHANGES_KEPT: u64 = 600;
+
+/// The storage layout version of this module. Chains started from genesis get this version, older chains are
+/// migrated to it in on_initialize(), one version step at a time:
+/// * 0: OwnedKittiesArray keyed by (owner, index)
+/// * 1: OwnedKittiesArray is a double map keyed by owner and index, the layout of the ownership registry
+/// * 2: Kitty has no price, kitties are listed and sold through the marketplace module
+pub const STORAGE_VERSION: u32 = 2;
+
+pub trait Trait: balances::Trait {
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+    /// Charges the fees of create_kitty() and breed_kitty(). In the runtime this is the Fees module.
+    type Fees: FeeCharger<Self::AccountId>;
+}
+
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Kitty<Hash> {
+    id: Hash,
+    dna: Hash,
+    gen: u64,
+}
+
+/// The polkadot-js definitions of the types above, see the types module
+#[cfg(feature = "std")]
+pub fn type_definitions() -> Vec<crate::types::TypeDef> {
+    use crate::types::TypeDef;
+    vec![
+        TypeDef::Struct("Kitty", vec![("id", "Hash"), ("dna", "Hash"), ("gen", "u64")]),
+    ]
+}
+
+// NOTE: We have added this `decl_event!` template for you
+decl_event!(
+    pub enum Event<T>
+    where
+        <T as system::Trait>::AccountId,
+        <T as system::Trait>::Hash
+    {
+        // ACTION: Add a `Created` event which includes an `AccountId` and a `Hash`
+        Created(AccountId, Hash),
+        Transferred(AccountId, AccountId, Hash),
+        /// The storage was migrated, with the new storage version and the number of kitties migrated
+        StorageMigrated(u32, u64),
+    }
+);
+
+decl_storage! {
+    trait Store for Module<T: Trait> as KittyStorage {
+        // Declare storage and getter functions here
+
+        //         - `Kitties` which maps a `T::Hash` to a `Kitty<T::Hash>`
+        //         - `KittyOwner` which maps a `T::Hash` to an `Option<T::AccountId>`
+        Kitties get(kitty): map T::Hash => Kitty<T::Hash>;
+        KittyOwner get(owner_of): map T::Hash => Option<T::AccountId>;
+
+        // ACTION: Create new storage items to globally track all kitties:
+        //         - `AllKittiesArray` which is a `map` from `u64` to `T::Hash`, add a getter function for this
+        //         - `AllKittiesCount` which is a `u64`, add a getter function for this
+        //         - `AllKittiesIndex` which is a `map` from `T::Hash` to `u64`
+        AllKittiesArray get(kitty_id): map u64 => T::Hash;
+        AllKittiesCount get(num_of_kitties): u64;
+        AllKittiesIndex get(index_of): map T::Hash => u64;
+
+        OwnedKittiesArray get(kitty_of_owner_by_index): double_map T::AccountId, blake2_256(u64) => T::Hash;
+
+        // ACTION: Add a new storage item `OwnedKittiesCount` which is a `map` from `T::AccountId` to `u64`
+        // ACTION: Add a new storage item `OwnedKittiesIndex` which is a `map` from `T::Hash` to `u64`
+        OwnedKittiesCount get(owned_kitty_count): map T::AccountId => u64;
+        OwnedKittiesIndex get(owned_kitties_index): map T::Hash => u64;
+
+        // The most kitties that can exist, or None for no cap. Set through the admin module.
+        MaxKitties get(max_kitties): Option<u64>;
+
+        // The kitties that were minted or changed owner in a block, in the order of the first change.
+        // Clients subscribe to the key of a block instead of diffing the maps above. Pruned after CHANGES_KEPT blocks.
+        KittiesChangedAt get(kitties_changed_at): map T::BlockNumber => Vec<T::Hash>;
+
+        // The layout version of the storage above, see STORAGE_VERSION. Unset (0) on chains older than the versioning.
+        StorageVersion get(storage_version): u32;
+    }
+
+    add_extra_genesis {
+        config(kitties): Vec<(T::AccountId, T::Hash)>;
+
+        build(|storage: &mut StorageOverlay, _: &mut ChildrenStorageOverlay, config: &GenesisConfig<T>| {
+            with_storage(storage, || {
+                <StorageVersion<T>>::put(STORAGE_VERSION);
+                for &(ref acct, hash) in &config.kitties {
+
+                    let k = Kitty {
+                                id: hash,
+                                dna: hash,
+                                gen: 0
+                            };
+
+                    let _ = <Module<T>>::mint(acct.clone(), hash, k);
+                }
+            });
+        });
+    }
+}
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        // Declare public functions here
+        fn deposit_event<T>() = default;
+
+        fn on_initialize(n: T::BlockNumber) {
+            if Self::storage_version() < STORAGE_VERSION {
+                Self::migrate();
+            }
+            if let Some(pruned) = n.checked_sub(&T::BlockNumber::sa(CHANGES_KEPT)) {
+                <KittiesChangedAt<T>>::remove(pruned);
+            }
+        }
+
+        fn create_kitty(origin) -> Result {
+            let sender = ensure_signed(origin)?;
+            // Checked before the fee, which a failed call does not undo, and before the id is taken, so a
+            // sequential id is only used by a kitty that is minted
+            <KittyItems<T>>::ensure_supply()?;
+            T::Fees::charge_fee(&sender, FeeCall::CreateKitty)?;
+
+            let kitty_id = <ids::Module<T>>::next_id(IdKind::Kitty, &sender)?;
+
+            let new_kitty = Kitty {
+                id: kitty_id,
+                dna: <ids::Module<T>>::random_hash(&sender),
+                gen: 0,
+            };
+            Self::mint(sender, kitty_id, new_kitty)?;
+
+            Ok(())
+        }
+        fn transfer(origin, to: T::AccountId, kitty_id: T::Hash) -> Result {
+            let sender = ensure_signed(origin)?;
+
+            let owner = Self::owner_of(kitty_id).ok_or("No owner for this kitty")?;
+            ensure!(owner == sender, "You do not own this kitty");
+
+            Self::transfer_from(sender, to, kitty_id)?;
+
+            Ok(())
+        }
+
+        /// Weight: constant, 9 storage writes, 8 of them in the mint of the collection, 3 more for a sequential id.
+        /// See the breed_kitty benchmark.
+        fn breed_kitty(origin, kitty_id_1: T::Hash, kitty_id_2: T::Hash) -> Result {
+            let sender = ensure_signed(origin)?;
+
+            // ACTION: Check both kitty 1 and kitty 2 "exists"
+            ensure!(<Kitties<T>>::exists(kitty_id_1), "Kitty 1 does not exist");
+            ensure!(<Kitties<T>>::exists(kitty_id_2), "Kitty 2 does not exist");
+            <KittyItems<T>>::ensure_supply()?;
+            T::Fees::charge_fee(&sender, FeeCall::BreedKitty)?;
+
+            // ACTION: Generate a `random_hash` with the ids module
+            let random_hash = <ids::Module<T>>::random_hash(&sender);
+            let kitty_id = <ids::Module<T>>::next_id(IdKind::Kitty, &sender)?;
+
+            // The child gets its dna from both parents, see the Breeding impl of KittyCollection
+            <KittyItems<T>>::breed(&sender, kitty_id, &kitty_id_1, &kitty_id_2, random_hash.as_ref())?;
+            Self::deposit_event(RawEvent::Created(sender, kitty_id));
+
+            Ok(())
+        }
+    }
+}
+
+
+impl<T: Trait> NftProvider<T::AccountId, T::Hash> for Module<T> {
+    fn owner_of(kitty_id: KittyId<T::Hash>) -> Option<T::AccountId> {
+        Self::owner_of(kitty_id.0)
+    }
+
+    fn transfer(from: T::AccountId, to: T::AccountId, kitty_id: KittyId<T::Hash>) -> Result {
+        Self::transfer_from(from, to, kitty_id.0)
+    }
+}
+
+/// The storage items that register the owner of each kitty, see the ownership module
+struct KittyOwnership<T>(PhantomData<T>);
+
+impl<T: Trait> OwnershipStorage for KittyOwnership<T> {
+    type Owner = T::AccountId;
+    type Id = T::Hash;
+    type OwnerOf = KittyOwner<T>;
+    type AllArray = AllKittiesArray<T>;
+    type AllCount = AllKittiesCount<T>;
+    type AllIndex = AllKittiesIndex<T>;
+    type OwnedArray = OwnedKittiesArray<T>;
+    type OwnedCount = OwnedKittiesCount<T>;
+    type OwnedIndex = OwnedKittiesIndex<T>;
+}
+
+type Registry<T> = ownership::Registry<KittyOwnership<T>>;
+
+/// Kitties as a collection of the nft module
+struct KittyCollection<T>(PhantomData<T>);
+
+impl<T: Trait> NftCollection for KittyCollection<T> {
+    type Ownership = KittyOwnership<T>;
+    type Metadata = Kitty<T::Hash>;
+    type Items = Kitties<T>;
+
+    fn max_supply() -> Option<u64> {
+        <Module<T>>::max_kitties()
+    }
+
+    fn on_change(kitty_id: &T::Hash) {
+        <Module<T>>::note_changed(*kitty_id);
+    }
+}
+
+impl<T: Trait> Breeding for KittyCollection<T> {
+    fn breed(child: &T::Hash, kitty_1: &Self::Metadata, kitty_2: &Self::Metadata, seed: &[u8]) -> Self::Metadata {
+        // NOTE: Our gene splicing algorithm, feel free to make it your own
+        let mut final_dna = kitty_1.dna;
+        for (i, (dna_2_element, r)) in kitty_2.dna.as_ref().iter().zip(seed.iter()).enumerate() {
+            if r % 2 == 0 {
+                final_dna.as_mut()[i] = *dna_2_element;
+            }
+        }
+
+        // The child is one generation after the younger parent
+        Kitty {
+            id: *child,
+            dna: final_dna,
+            gen: cmp::max(kitty_1.gen, kitty_2.gen) + 1,
+        }
+    }
+}
+
+type KittyItems<T> = nft::Collection<KittyCollection<T>>;
+
+impl<T: Trait> Module<T> {
+    /// The kitties of an account as (kitty id, dna, generation), in the order of OwnedKittiesArray.
+    /// Used by the KittiesApi runtime API, so inventory views can show the traits encoded in the dna.
+    pub fn kitties_of(owner: T::AccountId) -> Vec<(T::Hash, T::Hash, u64)> {
+        <Registry<T>>::owned(&owner)
+            .into_iter()
+            .map(|kitty_id| Self::kitty(kitty_id))
+            .map(|kitty| (kitty.id, kitty.dna, kitty.gen))
+            .collect()
+    }
+
+    /// Every kitty as (owner, id), the format of the kitties genesis config. Genesis kitties get their id
+    /// as dna and generation 0, so these are not kept. Used by the ExportApi runtime API.
+    pub fn export() -> Vec<(T::AccountId, T::Hash)> {
+        <Registry<T>>::all()
+            .into_iter()
+            .filter_map(|kitty_id| Self::owner_of(kitty_id).map(|owner| (owner, kitty_id)))
+            .collect()
+    }
+
+    /// Sets the supply cap, None removes it. Kitties above a lowered cap are kept, but no new ones are minted.
+    /// Called by the admin module, which checks the origin.
+    pub(crate) fn set_max_kitties(max_kitties: Option<u64>) {
+        match max_kitties {
+            Some(max_kitties) => <MaxKitties<T>>::put(max_kitties),
+            None => <MaxKitties<T>>::kill(),
+        }
+    }
+
+    fn mint(to: T::AccountId, kitty_id: T::Hash, new_kitty: Kitty<T::Hash>) -> Result {
+        // The collection checks the id for sequential ids too, the genesis kitties have ids of their own. Its
+        // registry adds the kitty to AllKittiesArray and to the OwnedKittiesArray of `to`.
+        <KittyItems<T>>::mint(&to, kitty_id, new_kitty)?;
+
+        Self::deposit_event(RawEvent::Created(to, kitty_id));
+
+        Ok(())
+    }
+
+    fn transfer_from(from: T::AccountId, to: T::AccountId, kitty_id: T::Hash) -> Result {
+        // ACTION: Check if owner exists for `kitty_id`
+        //         - If it does, sanity check that `from` is the `owner`
+        //         - If it doesn't, return an `Err()` that no `owner` exists
+
+        ensure!(Self::owner_of(kitty_id).is_some(), "No owner for this kitty");
+        ensure!(from != to, "Can not transfer a kitty to its owner");
+
+        // The collection checks that `from` is the owner, and its registry moves the kitty from the
+        // OwnedKittiesArray of `from` to the one of `to`, see the swap and pop in the ownership module
+        <KittyItems<T>>::transfer(&from, &to, kitty_id)?;
+        // ACTION: Deposit a `Transferred` event with the relevant data:
+        //         - from
+        //         - to
+        //         - kitty_id
+
+        Self::deposit_event(RawEvent::Transferred(from, to, kitty_id));
+        Ok(())
+    }
+
+    // Private method called by: the on_change() of KittyCollection, after a mint or a transfer
+    fn note_changed(kitty_id: T::Hash) {
+        <KittiesChangedAt<T>>::mutate(<system::Module<T>>::block_number(), |changed| {
+            if !changed.contains(&kitty_id) {
+                changed.push(kitty_id);
+            }
+        });
+    }
+
+    // Called by: on_initialize() while the storage is older than STORAGE_VERSION. Runs every missing step, so
+    // a chain can skip runtime versions. Steps are written against the layout of their version and never change.
+    fn migrate() {
+        let mut version = Self::storage_version();
+        let mut migrated = 0;
+        if version == 0 {
+            migrated = Self::migrate_to_v1();
+            version = 1;
+        }
+        if version == 1 {
+            migrated = Self::migrate_to_v2();
+            version = 2;
+        }
+        <StorageVersion<T>>::put(version);
+        Self::deposit_event(RawEvent::StorageMigrated(version, migrated));
+    }
+
+    // Version 1 moves OwnedKittiesArray from the (owner, index) map to the double map, returns the number of
+    // kitties.
+    fn migrate_to_v1() -> u64 {
+        let count = Self::num_of_kitties();
+        for i in 0..count {
+            let kitty_id = Self::kitty_id(i);
+            if let Some(owner) = Self::owner_of(kitty_id) {
+                let index = Self::owned_kitties_index(kitty_id);
+                <v0::OwnedKittiesArray<T>>::remove((owner.clone(), index));
+                <OwnedKittiesArray<T>>::insert(&owner, &index, kitty_id);
+            }
+        }
+        count
+    }
+
+    // Version 2 drops the price of every kitty, returns the number of kitties. Prices are not carried over, the
+    // owners list their kitties in the marketplace module.
+    fn migrate_to_v2() -> u64 {
+        let count = Self::num_of_kitties();
+        for i in 0..count {
+            let kitty_id = Self::kitty_id(i);
+            if let Some(kitty) = <v1::Kitties<T>>::get(kitty_id) {
+                <Kitties<T>>::insert(kitty_id, Kitty { id: kitty.id, dna: kitty.dna, gen: kitty.gen });
+            }
+        }
+        count
+    }
+}
+
+/// The storage of version 0, only used by the migration step to version 1. The items have the same names as in
+/// the module, so they read the same keys with the old types.
+mod v0 {
+    use super::*;
+
+    pub struct Module<T>(PhantomData<T>);
+
+    decl_storage! {
+        trait Store for Module<T: Trait> as KittyStorage {
+            pub OwnedKittiesArray: map (T::AccountId, u64) => T::Hash;
+        }
+    }
+}
+
+/// The storage of version 1, only used by the migration step to version 2
+mod v1 {
+    use super::*;
+
+    /// A kitty of version 1, with the price it was for sale at
+    #[derive(Encode, Decode)]
+    pub struct Kitty<Hash, Balance> {
+        pub id: Hash,
+        pub dna: Hash,
+        pub price: Balance,
+        pub gen: u64,
+    }
+
+    pub struct Module<T>(PhantomData<T>);
+
+    decl_storage! {
+        trait Store for Module<T: Trait> as KittyStorage {
+            pub Kitties: map T::Hash => Option<Kitty<T::Hash, T::Balance>>;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ACTION: Import test module dependencies here
+    use support::{impl_outer_origin, assert_ok, assert_noop};
+    use proptest::prelude::*;
+    use runtime_io::{with_externalities, TestExternalities};
+    use primitives::{H256, Blake2Hasher};
+    use runtime_primitives::{
+        BuildStorage,
+        traits::{BlakeTwo256, IdentityLookup, OnInitialize},
+        testing::{Digest, DigestItem, Header}
+    };
+
+    impl_outer_origin! {
+        pub enum Origin for KittiesTest {}
+    }
+
+    #[derive(Clone, Eq, PartialEq)]
+    pub struct KittiesTest;
+
+    impl system::Trait for KittiesTest {
+        type Origin = Origin;
+        type Index = u64;
+        type BlockNumber = u64;
+        type Hash = H256;
+        type Hashing = BlakeTwo256;
+        type Digest = Digest;
+        type AccountId = u64;
+        type Lookup = IdentityLookup<Self::AccountId>;
+        type Header = Header;
+        type Event = ();
+        type Log = DigestItem;
+    }
+
+    impl balances::Trait for KittiesTest {
+        // ACTION: Implement traits for balances module
+        type Balance = u64;
+        type OnFreeBalanceZero = ();
+        type OnNewAccount = ();
+        type Event = ();
+        type TransactionPayment = ();
+        type TransferPayment = ();
+        type DustRemoval = ();
+    }
+
+    impl super::Trait for KittiesTest {
+        // ACTION: Implement traits for your own module
+        type Event = ();
+        type Fees = ();
+    }
+
+    // ACTION: Build a genesis storage key/value store
+    type Kitties = super::Module<KittiesTest>;
+
+    fn build_ext() -> TestExternalities<Blake2Hasher> {
+        let mut t = system::GenesisConfig::<KittiesTest>::default().build_storage().unwrap().0;
+        t.extend(balances::GenesisConfig::<KittiesTest>::default().build_storage().unwrap().0);
+        t.extend(GenesisConfig::<KittiesTest> {
+            kitties: vec![  (0, H256::random()),
+                            (1, H256::zero())],
+        }.build_storage().unwrap().0);
+
+        t.into()
+    }
+
+    #[test]
+    fn create_kitty_should_work() {
+        // ACTION: test that create kitty works
+        with_externalities(&mut build_ext(), || {
+            // create a kitty with account #10.
+            assert_ok!(Kitties::create_kitty(Origin::signed(10)));
+
+            // check that there is now 1 kitty in storage besides the 2 genesis kitties
+            assert_eq!(Kitties::num_of_kitties(), 3);
+
+            // check that account #10 owns 1 kitty
+            assert_eq!(Kitties::owned_kitty_count(10), 1);
+
+            // check that some random account #5 does not own a kitty
+            assert_eq!(Kitties::owned_kitty_count(5), 0);
+
+            // check that this kitty is specifically owned by account #10
+            let hash = Kitties::kitty_id(2);
+            assert_eq!(Kitties::owner_of(hash), Some(10));
+
+            let other_hash = Kitties::kitty_of_owner_by_index(10, 0);
+            assert_eq!(hash, other_hash);
+        })
+    }
+
+    #[test]
+    fn transfer_kitty_should_work() {
+        // ACTION: test that transfer kitty works
+        with_externalities(&mut build_ext(), || {
+            // check that 10 own a kitty
+            assert_ok!(Kitties::create_kitty(Origin::signed(10)));
+
+            assert_eq!(Kitties::owned_kitty_count(10), 1);
+            let hash = Kitties::kitty_of_owner_by_index(10, 0);
+
+            // send kitty to 1.
+            assert_ok!(Kitties::transfer(Origin::signed(10), 1, hash));
+
+            // 10 now has nothing
+            assert_eq!(Kitties::owned_kitty_count(10), 0);
+            // but 1 does
+            assert_eq!(Kitties::owned_kitty_count(1), 1);
+            let new_hash = Kitties::kitty_of_owner_by_index(1, 0);
+            // and it has the same hash
+            assert_eq!(hash, new_hash);
+        })
+    }
+
+    #[test]
+    fn nft_provider_should_work() {
+        // other modules see and move kitties through NftProvider
+        with_externalities(&mut build_ext(), || {
+            assert_ok!(Kitties::create_kitty(Origin::signed(10)));
+            let kitty_id = KittyId(Kitties::kitty_of_owner_by_index(10, 0));
+            assert_eq!(<Kitties as NftProvider<u64, H256>>::owner_of(kitty_id), Some(10));
+            assert_eq!(<Kitties as NftProvider<u64, H256>>::owner_of(KittyId(H256::from([9; 32]))), None);
+
+            assert_eq!(<Kitties as NftProvider<u64, H256>>::transfer(9, 1, kitty_id), Err("From account is not the owner"));
+            assert_ok!(<Kitties as NftProvider<u64, H256>>::transfer(10, 1, kitty_id));
+            assert_eq!(Kitties::owner_of(kitty_id.0), Some(1));
+            assert_eq!(Kitties::kitty_of_owner_by_index(1, 0), kitty_id.0);
+        })
+    }
+
+    #[test]
+    fn changed_kitties_should_be_indexed_by_block() {
+        with_externalities(&mut build_ext(), || {
+            // the genesis kitties are minted at block 0
+            let genesis_1 = Kitties::kitty_id(0);
+            let genesis_2 = Kitties::kitty_id(1);
+            assert_eq!(Kitties::kitties_changed_at(0), vec![genesis_1, genesis_2]);
+
+            <system::Module<KittiesTest>>::set_block_number(5);
+            assert_ok!(Kitties::create_kitty(Origin::signed(10)));
+            let hash = Kitties::kitty_of_owner_by_index(10, 0);
+            assert_ok!(Kitties::transfer(Origin::signed(1), 10, genesis_2));
+            assert_ok!(Kitties::transfer(Origin::signed(10), 1, hash));
+            assert_eq!(Kitties::kitties_changed_at(5), vec![hash, genesis_2]);
+
+            Kitties::on_initialize(5 + CHANGES_KEPT - 1);
+            assert_eq!(Kitties::kitties_changed_at(5), vec![hash, genesis_2]);
+            Kitties::on_initialize(5 + CHANGES_KEPT);
+            assert_eq!(Kitties::kitties_changed_at(5), vec![]);
+        })
+    }
+
+    #[test]
+    fn kitties_of_should_list_owned_kitties() {
+        with_externalities(&mut build_ext(), || {
+            assert_eq!(Kitties::kitties_of(10), vec![]);
+            assert_ok!(Kitties::create_kitty(Origin::signed(10)));
+            let hash = Kitties::kitty_of_owner_by_index(10, 0);
+            let kitty = Kitties::kitty(hash);
+            assert_eq!(Kitties::kitties_of(10), vec![(hash, kitty.dna, 0)]);
+
+            // the genesis kitty of account 1 has its id as dna
+            let genesis_2 = Kitties::kitty_id(1);
+            assert_eq!(Kitties::kitties_of(1), vec![(genesis_2, genesis_2, 0)]);
+        })
+    }
+
+    #[test]
+    fn type_definitions_should_match_encoding() {
+        // a Kitty encodes like its fields in the order of type_definitions()
+        let kitty = Kitty { id: H256::from([1; 32]), dna: H256::from([2; 32]), gen: 4 };
+        assert_eq!(kitty.encode(), (kitty.id, kitty.dna, kitty.gen).encode());
+        match &type_definitions()[0] {
+            crate::types::TypeDef::Struct("Kitty", fields) => {
+                let names: Vec<_> = fields.iter().map(|(name, _)| *name).collect();
+                assert_eq!(names, vec!["id", "dna", "gen"]);
+            },
+            _ => panic!("Kitty is not the first type"),
+        }
+    }
+
+    #[test]
+    fn transfer_to_self_should_fail() {
+        with_externalities(&mut build_ext(), || {
+            assert_ok!(Kitties::create_kitty(Origin::signed(10)));
+            assert_ok!(Kitties::create_kitty(Origin::signed(10)));
+            let hash = Kitties::kitty_of_owner_by_index(10, 0);
+
+            assert_noop!(Kitties::transfer(Origin::signed(10), 10, hash), "Can not transfer a kitty to its owner");
+            assert_eq!(Kitties::owned_kitty_count(10), 2);
+        })
+    }
+
+    #[test]
+    fn storage_should_migrate_from_v0() {
+        with_externalities(&mut build_ext(), || {
+            assert_eq!(Kitties::storage_version(), STORAGE_VERSION);
+
+            // rewrite the genesis kitties in the layout of version 0, with the prices of version 0 and 1
+            for owner in 0..2 {
+                let kitty_id = Kitties::kitty_of_owner_by_index(owner, 0);
+                <OwnedKittiesArray<KittiesTest>>::remove(&owner, &0);
+                <v0::OwnedKittiesArray<KittiesTest>>::insert((owner, 0), kitty_id);
+                let kitty = v1::Kitty { id: kitty_id, dna: kitty_id, price: 50u64, gen: owner };
+                <v1::Kitties<KittiesTest>>::insert(kitty_id, kitty);
+            }
+            <StorageVersion<KittiesTest>>::put(0);
+
+            Kitties::on_initialize(1);
+            assert_eq!(Kitties::storage_version(), STORAGE_VERSION);
+            for owner in 0..2 {
+                let kitty_id = Kitties::kitty_of_owner_by_index(owner, 0);
+                assert_eq!(Kitties::owner_of(kitty_id), Some(owner));
+                assert!(!<v0::OwnedKittiesArray<KittiesTest>>::exists((owner, 0)));
+                assert_eq!(Kitties::kitty(kitty_id), Kitty { id: kitty_id, dna: kitty_id, gen: owner });
+            }
+        })
+    }
+
+    // Accounts used by the property test. Accounts 0 and 1 own the genesis kitties.
+    const ACCOUNTS: u64 = 4;
+
+    // A call of the property test. Kitties are picked by an index into AllKittiesArray, modulo the kitty count.
+    #[derive(Debug, Clone)]
+    enum Op {
+        Create(u64),
+        // (to, kitty), sent by the owner of the kitty
+        Transfer(u64, u64),
+        // (account, kitty, kitty)
+        Breed(u64, u64, u64),
+    }
+
+    fn op() -> impl Strategy<Value = Op> {
+        prop_oneof![
+            (0..ACCOUNTS).prop_map(Op::Create),
+            (0..ACCOUNTS, any::<u64>()).prop_map(|(to, kitty)| Op::Transfer(to, kitty)),
+            (0..ACCOUNTS, any::<u64>(), any::<u64>()).prop_map(|(who, kitty_1, kitty_2)| Op::Breed(who, kitty_1, kitty_2)),
+        ]
+    }
+
+    fn build_ext_with_balances() -> TestExternalities<Blake2Hasher> {
+        let mut t = system::GenesisConfig::<KittiesTest>::default().build_storage().unwrap().0;
+        t.extend(balances::GenesisConfig::<KittiesTest> {
+            balances: (0..ACCOUNTS).map(|account| (account, 10_000)).collect(),
+            transaction_base_fee: 0,
+            transaction_byte_fee: 0,
+            existential_deposit: 0,
+            transfer_fee: 0,
+            creation_fee: 0,
+            vesting: vec![],
+        }.build_storage().unwrap().0);
+        t.extend(GenesisConfig::<KittiesTest> {
+            kitties: vec![(0, H256::from([1; 32])), (1, H256::from([2; 32]))],
+        }.build_storage().unwrap().0);
+        t.into()
+    }
+
+    // Failing calls are part of the sequence too, they must not leave anything half written
+    fn apply(op: Op) {
+        let count = Kitties::num_of_kitties();
+        let pick = |kitty: u64| Kitties::kitty_id(kitty % count);
+        let _ = match op {
+            Op::Create(who) => Kitties::create_kitty(Origin::signed(who)),
+            Op::Transfer(to, kitty) => {
+                let kitty_id = pick(kitty);
+                let owner = Kitties::owner_of(kitty_id).unwrap_or_default();
+                Kitties::transfer(Origin::signed(owner), to, kitty_id)
+            },
+            Op::Breed(who, kitty_1, kitty_2) => Kitties::breed_kitty(Origin::signed(who), pick(kitty_1), pick(kitty_2)),
+        };
+    }
+
+    // The global and owned arrays hold every kitty exactly once, with no holes, and agree with their indexes
+    fn check_indexes() {
+        let count = Kitties::num_of_kitties();
+        for i in 0..count {
+            let kitty_id = Kitties::kitty_id(i);
+            assert_eq!(Kitties::index_of(kitty_id), i);
+            assert_eq!(Kitties::kitty(kitty_id).id, kitty_id);
+            assert!(Kitties::owner_of(kitty_id).is_some());
+        }
+        assert!(!<AllKittiesArray<KittiesTest>>::exists(count));
+
+        let mut owned_total = 0;
+        for account in 0..ACCOUNTS {
+            let owned = Kitties::owned_kitty_count(account);
+            for j in 0..owned {
+                let kitty_id = Kitties::kitty_of_owner_by_index(account, j);
+                assert_eq!(Kitties::owner_of(kitty_id), Some(account));
+                assert_eq!(Kitties::owned_kitties_index(kitty_id), j);
+            }
+            assert!(!<OwnedKittiesArray<KittiesTest>>::exists(&account, &owned));
+            owned_total += owned;
+        }
+        assert_eq!(owned_total, count);
+    }
+
+    proptest! {
+        #[test]
+        fn kitty_indexes_should_hold_after_every_call(ops in proptest::collection::vec(op(), 1..40)) {
+            with_externalities(&mut build_ext_with_balances(), || {
+                check_indexes();
+                for op in ops {
+                    apply(op);
+                    check_indexes();
+                }
+            });
+        }
+    }
+
+    #[test]
+    fn transfer_not_owned_kitty_should_fail() {
+        // ACTION: test that transfering owned kitty correctly fails
+        with_externalities(&mut build_ext(), || {
+            // check that 10 own a kitty
+            assert_ok!(Kitties::create_kitty(Origin::signed(10)));
+            let hash = Kitties::kitty_of_owner_by_index(10, 0);
+
+            // account 0 cannot transfer a kitty with this hash.
+            assert_noop!(Kitties::transfer(Origin::signed(9), 1, hash), "You do not own this kitty");
+        })
+    }
+}
\ No newline at end of file
@@ -0,0 +1,370 @@
+/// Tokens holds many fungible token classes next to the native currency, like an ERC1155 contract, so apps can
+/// issue their own in-game currencies such as gold or gems without a chain of their own. A class has an issuer, the
+/// only account that mints it, and metadata that wallets show: a name, a symbol and the decimals of its amounts.
+///
+/// Balances are kept per class and account. Anyone transfers and burns their own tokens, and batch_transfer() moves
+/// amounts of several classes in one call. Tokens are not the native currency: they pay no fees and are not reserved
+/// by the other modules.
+
+use parity_codec::{Encode, Decode};
+use rstd::prelude::*;
+use runtime_primitives::traits::{CheckedAdd, Member, SimpleArithmetic, Zero};
+use support::{decl_module, decl_storage, decl_event, ensure, dispatch::Result, Parameter, StorageDoubleMap, StorageMap};
+use system::ensure_signed;
+
+use crate::ids::{self, IdKind};
+
+/// The longest name of a class
+pub const MAX_NAME_LEN: usize = 32;
+
+/// The longest symbol of a class
+pub const MAX_SYMBOL_LEN: usize = 8;
+
+/// The most classes issued by one account
+pub const MAX_CLASSES_PER_ISSUER: usize = 16;
+
+/// The most transfers in one batch_transfer()
+pub const MAX_BATCH: usize = 16;
+
+pub trait Trait: system::Trait {
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	/// The type of token amounts, shared by all classes. In the runtime this is the native balance type.
+	type Balance: Parameter + Member + SimpleArithmetic + Default + Copy;
+}
+
+/// A token class, stored under its class id
+#[derive(Encode, Decode, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct TokenClass<AccountId, Balance> {
+	/// The account that mints the tokens and sets the metadata
+	pub issuer: AccountId,
+	pub name: Vec<u8>,
+	pub symbol: Vec<u8>,
+	/// The decimals that wallets show, amounts are always integers on chain
+	pub decimals: u8,
+	/// The minted tokens less the burned ones
+	pub supply: Balance,
+}
+
+/// The polkadot-js definitions of the types above, see the types module
+#[cfg(feature = "std")]
+pub fn type_definitions() -> Vec<crate::types::TypeDef> {
+	use crate::types::TypeDef;
+	vec![
+		TypeDef::Struct("TokenClass", vec![
+			("issuer", "AccountId"), ("name", "Vec<u8>"), ("symbol", "Vec<u8>"), ("decimals", "u8"), ("supply", "Balance"),
+		]),
+	]
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Tokens {
+		// The classes by id, and the ids of the classes of each issuer
+		Classes get(class_of): map T::Hash => Option<TokenClass<T::AccountId, T::Balance>>;
+		IssuedClasses get(classes_of): map T::AccountId => Vec<T::Hash>;
+
+		// The tokens of each class held by each account. Empty balances are removed.
+		TokenBalances get(balance_of): double_map T::Hash, blake2_256(T::AccountId) => T::Balance;
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where
+		<T as system::Trait>::AccountId,
+		<T as system::Trait>::Hash,
+		<T as Trait>::Balance
+	{
+		/// A class was created as (class id, issuer)
+		ClassCreated(Hash, AccountId),
+
+		/// The metadata of a class was changed by its issuer
+		MetadataSet(Hash),
+
+		/// A class was handed to a new issuer as (class id, new issuer)
+		IssuerChanged(Hash, AccountId),
+
+		/// Tokens were minted as (class id, receiver, amount)
+		Minted(Hash, AccountId, Balance),
+
+		/// Tokens were burned as (class id, holder, amount)
+		Burned(Hash, AccountId, Balance),
+
+		/// Tokens were transferred as (class id, from, to, amount), on their own or in a batch
+		Transferred(Hash, AccountId, AccountId, Balance),
+	}
+);
+
+decl_module! {
+	/// The module declaration.
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+
+		fn deposit_event<T>() = default;
+
+		/// Create a token class with the sender as its issuer and no supply
+		pub fn create_class(origin, name: Vec<u8>, symbol: Vec<u8>, decimals: u8) -> Result {
+			let sender = ensure_signed(origin)?;
+			Self::ensure_metadata(&name, &symbol)?;
+			let mut issued = Self::classes_of(&sender);
+			ensure!(issued.len() < MAX_CLASSES_PER_ISSUER, "Too many classes issued by this account");
+
+			// Taken after the checks above, so a sequential id is only used by a class that is created
+			let class_id = <ids::Module<T>>::next_id(IdKind::TokenClass, &sender)?;
+			if !<ids::Module<T>>::sequential() {
+				ensure!(!<Classes<T>>::exists(class_id), "Class Id already exists");
+			}
+
+			let class = TokenClass { issuer: sender.clone(), name, symbol, decimals, supply: Zero::zero() };
+			<Classes<T>>::insert(class_id, class);
+			issued.push(class_id);
+			<IssuedClasses<T>>::insert(&sender, issued);
+			Self::deposit_event(RawEvent::ClassCreated(class_id, sender));
+			Ok(())
+		}
+
+		/// Change the metadata of a class. Rule: the issuer of the class.
+		pub fn set_metadata(origin, class_id: T::Hash, name: Vec<u8>, symbol: Vec<u8>, decimals: u8) -> Result {
+			let sender = ensure_signed(origin)?;
+			let mut class = Self::issued_class(&sender, class_id)?;
+			Self::ensure_metadata(&name, &symbol)?;
+
+			class.name = name;
+			class.symbol = symbol;
+			class.decimals = decimals;
+			<Classes<T>>::insert(class_id, class);
+			Self::deposit_event(RawEvent::MetadataSet(class_id));
+			Ok(())
+		}
+
+		/// Hand the class to a new issuer, such as the account of a game server. Rule: the issuer of the class.
+		pub fn set_issuer(origin, class_id: T::Hash, new_issuer: T::AccountId) -> Result {
+			let sender = ensure_signed(origin)?;
+			let mut class = Self::issued_class(&sender, class_id)?;
+			ensure!(new_issuer != sender, "You already issue this class");
+			let mut issued = Self::classes_of(&new_issuer);
+			ensure!(issued.len() < MAX_CLASSES_PER_ISSUER, "Too many classes issued by this account");
+
+			<IssuedClasses<T>>::mutate(&sender, |classes| classes.retain(|id| *id != class_id));
+			issued.push(class_id);
+			<IssuedClasses<T>>::insert(&new_issuer, issued);
+			class.issuer = new_issuer.clone();
+			<Classes<T>>::insert(class_id, class);
+			Self::deposit_event(RawEvent::IssuerChanged(class_id, new_issuer));
+			Ok(())
+		}
+
+		/// Mint new tokens of a class to an account. Rule: the issuer of the class.
+		pub fn mint(origin, class_id: T::Hash, to: T::AccountId, amount: T::Balance) -> Result {
+			let sender = ensure_signed(origin)?;
+			let mut class = Self::issued_class(&sender, class_id)?;
+			ensure!(!amount.is_zero(), "Amount must be greater than zero");
+			// No balance can overflow, as each is at most the supply
+			class.supply = class.supply.checked_add(&amount).ok_or("Supply would overflow")?;
+
+			<Classes<T>>::insert(class_id, class);
+			<TokenBalances<T>>::mutate(&class_id, &to, |balance| *balance = *balance + amount);
+			Self::deposit_event(RawEvent::Minted(class_id, to, amount));
+			Ok(())
+		}
+
+		/// Burn tokens of the sender, such as an in-game currency spent on an item
+		pub fn burn(origin, class_id: T::Hash, amount: T::Balance) -> Result {
+			let sender = ensure_signed(origin)?;
+			let mut class = Self::class_of(class_id).ok_or("This class does not exist")?;
+			ensure!(!amount.is_zero(), "Amount must be greater than zero");
+			ensure!(Self::balance_of(&class_id, &sender) >= amount, "Not enough tokens");
+
+			class.supply = class.supply - amount;
+			<Classes<T>>::insert(class_id, class);
+			Self::take(class_id, &sender, amount);
+			Self::deposit_event(RawEvent::Burned(class_id, sender, amount));
+			Ok(())
+		}
+
+		/// Transfer tokens of one class from the sender to an account
+		pub fn transfer(origin, class_id: T::Hash, to: T::AccountId, amount: T::Balance) -> Result {
+			let sender = ensure_signed(origin)?;
+			Self::batch(sender, to, vec![(class_id, amount)])
+		}
+
+		/// Transfer tokens of several classes from the sender to an account, at most MAX_BATCH amounts. Either all of
+		/// them are transferred or none.
+		pub fn batch_transfer(origin, to: T::AccountId, amounts: Vec<(T::Hash, T::Balance)>) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(!amounts.is_empty(), "Nothing to transfer");
+			ensure!(amounts.len() <= MAX_BATCH, "Too many transfers in this batch");
+			Self::batch(sender, to, amounts)
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The balances of the account in the given classes. Used by the TokensApi runtime API, so a wallet asks for
+	/// the classes it shows in one call.
+	pub fn balances(who: T::AccountId, class_ids: Vec<T::Hash>) -> Vec<(T::Hash, T::Balance)> {
+		class_ids.into_iter().map(|class_id| (class_id, Self::balance_of(&class_id, &who))).collect()
+	}
+
+	// Private method called by: create_class() and set_metadata()
+	fn ensure_metadata(name: &[u8], symbol: &[u8]) -> Result {
+		ensure!(name.len() <= MAX_NAME_LEN, "Name is too long");
+		ensure!(!symbol.is_empty() && symbol.len() <= MAX_SYMBOL_LEN, "Symbol must be 1 to MAX_SYMBOL_LEN bytes");
+		Ok(())
+	}
+
+	// Private method called by the calls of the issuer. The class, if the sender issues it.
+	fn issued_class(sender: &T::AccountId, class_id: T::Hash)
+		-> rstd::result::Result<TokenClass<T::AccountId, T::Balance>, &'static str>
+	{
+		let class = Self::class_of(class_id).ok_or("This class does not exist")?;
+		ensure!(class.issuer == *sender, "Only the issuer of this class can do this");
+		Ok(class)
+	}
+
+	// Private method called by: transfer() and batch_transfer(). Checks every amount against the balance of the
+	// sender before any is moved, with the amounts of a class listed twice added up, so a batch never stops halfway.
+	fn batch(from: T::AccountId, to: T::AccountId, amounts: Vec<(T::Hash, T::Balance)>) -> Result {
+		let mut totals: Vec<(T::Hash, T::Balance)> = Vec::new();
+		for &(class_id, amount) in &amounts {
+			ensure!(!amount.is_zero(), "Amount must be greater than zero");
+			ensure!(<Classes<T>>::exists(class_id), "This class does not exist");
+			match totals.iter_mut().find(|(id, _)| *id == class_id) {
+				Some((_, total)) => *total = total.checked_add(&amount).ok_or("Not enough tokens")?,
+				None => totals.push((class_id, amount)),
+			}
+		}
+		for (class_id, total) in totals {
+			ensure!(Self::balance_of(&class_id, &from) >= total, "Not enough tokens");
+		}
+
+		for (class_id, amount) in amounts {
+			Self::take(class_id, &from, amount);
+			<TokenBalances<T>>::mutate(&class_id, &to, |balance| *balance = *balance + amount);
+			Self::deposit_event(RawEvent::Transferred(class_id, from.clone(), to.clone(), amount));
+		}
+		Ok(())
+	}
+
+	// Private method called by: burn() and batch(), after they checked the balance. Removes an empty balance.
+	fn take(class_id: T::Hash, who: &T::AccountId, amount: T::Balance) {
+		let balance = Self::balance_of(&class_id, who) - amount;
+		if balance.is_zero() {
+			<TokenBalances<T>>::remove(&class_id, who);
+		} else {
+			<TokenBalances<T>>::insert(&class_id, who, balance);
+		}
+	}
+}
+
+// *****************************************************************************************************
+// Unit Tests!
+// *****************************************************************************************************
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use runtime_io::with_externalities;
+	use primitives::{H256, Blake2Hasher};
+	use support::{impl_outer_origin, assert_ok, assert_noop};
+	use runtime_primitives::{
+		BuildStorage,
+		traits::{BlakeTwo256, IdentityLookup},
+		testing::{Digest, DigestItem, Header}
+	};
+
+	impl_outer_origin! {
+		pub enum Origin for TokensTest {}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct TokensTest;
+	impl system::Trait for TokensTest {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type Digest = Digest;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type Log = DigestItem;
+	}
+	impl Trait for TokensTest {
+		type Event = ();
+		type Balance = u64;
+	}
+	type Tokens = Module<TokensTest>;
+
+	fn build_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+		system::GenesisConfig::<TokensTest>::default().build_storage().unwrap().0.into()
+	}
+
+	// Account 1 creates a class and returns its id
+	fn create_gold() -> H256 {
+		assert_ok!(Tokens::create_class(Origin::signed(1), b"Gold".to_vec(), b"GLD".to_vec(), 2));
+		*Tokens::classes_of(1).last().unwrap()
+	}
+
+	/// Issuer test objectives:
+	/// * Only the issuer mints tokens and changes the metadata of a class
+	/// * The issuer hands the class over, and the new issuer mints from then on
+	/// * Holders burn their own tokens, and the supply follows
+	#[test]
+	fn only_the_issuer_should_mint() {
+		with_externalities(&mut build_ext(), || {
+			assert_noop!(
+				Tokens::create_class(Origin::signed(1), b"Gold".to_vec(), vec![], 2),
+				"Symbol must be 1 to MAX_SYMBOL_LEN bytes"
+			);
+			let gold = create_gold();
+			assert_noop!(Tokens::mint(Origin::signed(2), gold, 2, 100), "Only the issuer of this class can do this");
+			assert_ok!(Tokens::mint(Origin::signed(1), gold, 2, 100));
+			assert_eq!(Tokens::balance_of(&gold, &2), 100);
+
+			assert_noop!(
+				Tokens::set_metadata(Origin::signed(2), gold, b"Fools gold".to_vec(), b"FGD".to_vec(), 0),
+				"Only the issuer of this class can do this"
+			);
+			assert_ok!(Tokens::set_metadata(Origin::signed(1), gold, b"Gold coin".to_vec(), b"GLD".to_vec(), 0));
+			assert_eq!(Tokens::class_of(gold).unwrap().name, b"Gold coin".to_vec());
+
+			assert_ok!(Tokens::set_issuer(Origin::signed(1), gold, 3));
+			assert_eq!(Tokens::classes_of(1), vec![]);
+			assert_eq!(Tokens::classes_of(3), vec![gold]);
+			assert_noop!(Tokens::mint(Origin::signed(1), gold, 1, 10), "Only the issuer of this class can do this");
+			assert_ok!(Tokens::mint(Origin::signed(3), gold, 3, 10));
+
+			assert_noop!(Tokens::burn(Origin::signed(2), gold, 101), "Not enough tokens");
+			assert_ok!(Tokens::burn(Origin::signed(2), gold, 40));
+			assert_eq!(Tokens::balance_of(&gold, &2), 60);
+			assert_eq!(Tokens::class_of(gold).unwrap().supply, 70);
+		});
+	}
+
+	/// Transfer test objectives:
+	/// * Holders transfer tokens of one class, or of several classes in one batch
+	/// * A batch that one amount can not cover transfers nothing, also when a class is listed twice
+	#[test]
+	fn batch_transfers_should_move_all_or_nothing() {
+		with_externalities(&mut build_ext(), || {
+			let gold = create_gold();
+			assert_ok!(Tokens::create_class(Origin::signed(1), b"Gems".to_vec(), b"GEM".to_vec(), 0));
+			let gems = Tokens::classes_of(1)[1];
+			assert_ok!(Tokens::mint(Origin::signed(1), gold, 2, 100));
+			assert_ok!(Tokens::mint(Origin::signed(1), gems, 2, 5));
+
+			assert_ok!(Tokens::transfer(Origin::signed(2), gold, 3, 30));
+			assert_eq!(Tokens::balances(2, vec![gold, gems]), vec![(gold, 70), (gems, 5)]);
+
+			assert_noop!(Tokens::batch_transfer(Origin::signed(2), 3, vec![(gold, 10), (gems, 6)]), "Not enough tokens");
+			assert_noop!(Tokens::batch_transfer(Origin::signed(2), 3, vec![(gold, 50), (gold, 50)]), "Not enough tokens");
+			assert_ok!(Tokens::batch_transfer(Origin::signed(2), 3, vec![(gold, 20), (gems, 5), (gold, 50)]));
+			assert_eq!(Tokens::balances(3, vec![gold, gems]), vec![(gold, 100), (gems, 5)]);
+			assert_eq!(Tokens::balances(2, vec![gold, gems]), vec![(gold, 0), (gems, 0)]);
+			assert!(!<TokenBalances<TokensTest>>::exists(&gold, &2));
+		});
+	}
+}
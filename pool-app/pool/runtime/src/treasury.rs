@@ -0,0 +1,365 @@
+/// Treasury collects the revenue of the app: the fees of app calls, the cut of marketplace sales, the protocol fee of
+/// pool donations and the slashed bonds of rejected proposals. Each of those modules hands its funds over as an
+/// imbalance, see OnUnbalanced, so they share one destination and none of them needs to know this module.
+///
+/// Like the pool, the treasury holds its funds in the Pot instead of an account. Anyone can propose to spend from
+/// it, and each spend needs an approval through the approve module. Every spend_period blocks the approved spends
+/// are paid out while the pot covers them, and then the burn fraction of what is left is burned, so an unused
+/// treasury does not grow forever.
+
+use parity_codec::{Encode, Decode};
+use rstd::prelude::*;
+use runtime_primitives::Permill;
+use runtime_primitives::traits::{Hash, Saturating, Zero};
+use support::{decl_module, decl_storage, decl_event, ensure, dispatch::Result, StorageMap, StorageValue};
+use support::traits::{Currency, Imbalance, OnUnbalanced};
+use system::ensure_signed;
+
+use app_primitives::{ApprovalProvider, GovernanceOrigin, ProposalId};
+
+/// The most spends waiting for their approval or payout at the same time
+pub const MAX_PENDING_SPENDS: usize = 16;
+
+/// The balance type of the currency held by the treasury
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+type NegativeImbalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::NegativeImbalance;
+
+pub trait Trait: system::Trait {
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	/// The currency held by the treasury
+	type Currency: Currency<Self::AccountId>;
+
+	/// Spends are paid out once approved through this provider. In the runtime this is the approve module.
+	type Approval: ApprovalProvider<Self::AccountId, Self::Hash>;
+
+	/// Checks the origin of set_config(). In the runtime this is the Council of the approve module.
+	type Council: GovernanceOrigin<Self::Origin>;
+}
+
+/// A proposed spend, stored under the id of its approval proposal
+#[derive(Encode, Decode, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Spend<AccountId, Balance> {
+	pub proposer: AccountId,
+	pub beneficiary: AccountId,
+	pub amount: Balance,
+}
+
+/// The polkadot-js definitions of the types above, see the types module
+#[cfg(feature = "std")]
+pub fn type_definitions() -> Vec<crate::types::TypeDef> {
+	use crate::types::TypeDef;
+	vec![
+		TypeDef::Struct("Spend", vec![("proposer", "AccountId"), ("beneficiary", "AccountId"), ("amount", "Balance")]),
+	]
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Treasury {
+		// Config values that match the values in the testnet_genesis in chain_spec.rs
+		// Approved spends are paid out every spend_period blocks, 0 pays out nothing. Then the burn fraction of
+		// the pot is burned.
+		SpendPeriod get(spend_period) config(): T::BlockNumber;
+		Burn get(burn) config(): Permill;
+
+		// The funds held by the treasury
+		Pot get(pot): BalanceOf<T>;
+
+		// The proposed spends by the id of their approval proposal, and the ids in the order they were proposed
+		Spends get(spend_of): map ProposalId<T::Hash> => Option<Spend<T::AccountId, BalanceOf<T>>>;
+		PendingSpends get(pending_spends): Vec<ProposalId<T::Hash>>;
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where
+		<T as system::Trait>::AccountId,
+		<T as system::Trait>::Hash,
+		<T as system::Trait>::BlockNumber,
+		Balance = BalanceOf<T>
+	{
+		/// Funds went into the pot
+		Deposit(Balance),
+
+		/// A spend was proposed as (proposal id, beneficiary, amount)
+		SpendProposed(Hash, AccountId, Balance),
+
+		/// A spend was withdrawn by its proposer
+		SpendCancelled(Hash),
+
+		/// An approved spend was paid out as (proposal id, beneficiary, amount)
+		SpendPaid(Hash, AccountId, Balance),
+
+		/// Part of the pot was burned at the end of a spend period, as (burned, left in the pot)
+		Burned(Balance, Balance),
+
+		/// The config changed to (spend_period, burn)
+		ConfigChanged(BlockNumber, Permill),
+	}
+);
+
+decl_module! {
+	/// The module declaration.
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+
+		fn deposit_event<T>() = default;
+
+		fn on_initialize(n: T::BlockNumber) {
+			let period = Self::spend_period();
+			if !period.is_zero() && (n % period).is_zero() {
+				Self::spend_funds();
+			}
+		}
+
+		/// Propose to pay an amount from the pot to the beneficiary. This requests an approval, and the spend is
+		/// paid out at the end of the spend period in which it is approved, or later while the pot is too low.
+		pub fn propose_spend(origin, beneficiary: T::AccountId, amount: BalanceOf<T>) -> Result {
+			let sender = ensure_signed(origin)?;
+			ensure!(!amount.is_zero(), "Amount must be greater than zero");
+			let mut pending = Self::pending_spends();
+			ensure!(pending.len() < MAX_PENDING_SPENDS, "Too many pending spends");
+
+			let payload_hash = (&b"treasury_spend"[..], &beneficiary, amount)
+				.using_encoded(<T as system::Trait>::Hashing::hash);
+			let proposal_id = T::Approval::request_approval(sender.clone(), payload_hash)?;
+			ensure!(!<Spends<T>>::exists(proposal_id), "This spend was already proposed");
+
+			<Spends<T>>::insert(proposal_id, Spend { proposer: sender, beneficiary: beneficiary.clone(), amount });
+			pending.push(proposal_id);
+			<PendingSpends<T>>::put(pending);
+
+			Self::deposit_event(RawEvent::SpendProposed(proposal_id.0, beneficiary, amount));
+			Ok(())
+		}
+
+		/// Withdraw a spend, such as one whose approval was rejected. Rule: only the proposer of the spend.
+		pub fn cancel_spend(origin, proposal_id: T::Hash) -> Result {
+			let sender = ensure_signed(origin)?;
+			let spend = Self::spend_of(ProposalId(proposal_id)).ok_or("No such spend")?;
+			ensure!(spend.proposer == sender, "Only the proposer can cancel this spend");
+
+			Self::remove_spend(ProposalId(proposal_id));
+			Self::deposit_event(RawEvent::SpendCancelled(proposal_id));
+			Ok(())
+		}
+
+		/// Change the spend period and the fraction of the pot burned at its end.
+		/// Rule: a council motion.
+		pub fn set_config(origin, spend_period: T::BlockNumber, burn: Permill) -> Result {
+			T::Council::ensure_governance(origin)?;
+
+			<SpendPeriod<T>>::put(spend_period);
+			<Burn<T>>::put(burn);
+			Self::deposit_event(RawEvent::ConfigChanged(spend_period, burn));
+			Ok(())
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	// Private method called by: on_initialize(). Pays out the approved spends in the order they were proposed,
+	// skipping those the pot can not cover, then burns the burn fraction of the rest. At most MAX_PENDING_SPENDS.
+	fn spend_funds() {
+		let mut pot = Self::pot();
+		for proposal_id in Self::pending_spends() {
+			if !T::Approval::is_approved(proposal_id) {
+				continue;
+			}
+			let spend = match Self::spend_of(proposal_id) {
+				Some(spend) => spend,
+				None => continue,
+			};
+			if spend.amount > pot {
+				continue;
+			}
+			pot = pot.saturating_sub(spend.amount);
+			let _ = T::Currency::deposit_creating(&spend.beneficiary, spend.amount);
+			Self::remove_spend(proposal_id);
+			Self::deposit_event(RawEvent::SpendPaid(proposal_id.0, spend.beneficiary, spend.amount));
+		}
+
+		// The funds in the pot are already out of the total issuance, so burning them only shrinks the pot
+		let burned = Self::burn() * pot;
+		pot = pot.saturating_sub(burned);
+		<Pot<T>>::put(pot);
+		Self::deposit_event(RawEvent::Burned(burned, pot));
+	}
+
+	// Private method called by: cancel_spend() and spend_funds()
+	fn remove_spend(proposal_id: ProposalId<T::Hash>) {
+		<Spends<T>>::remove(proposal_id);
+		<PendingSpends<T>>::mutate(|pending| pending.retain(|id| *id != proposal_id));
+	}
+}
+
+/// Takes the funds of the fees, sale cuts, protocol fees and slashed bonds of the other modules. Like the pool, the
+/// imbalance is dropped and the amount is tracked in the Pot.
+impl<T: Trait> OnUnbalanced<NegativeImbalanceOf<T>> for Module<T> {
+	fn on_unbalanced(amount: NegativeImbalanceOf<T>) {
+		let value = amount.peek();
+		if value.is_zero() {
+			return;
+		}
+		<Pot<T>>::mutate(|pot| *pot = pot.saturating_add(value));
+		Self::deposit_event(RawEvent::Deposit(value));
+	}
+}
+
+// *****************************************************************************************************
+// Unit Tests!
+// *****************************************************************************************************
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use runtime_io::with_externalities;
+	use primitives::{H256, Blake2Hasher};
+	use support::{impl_outer_origin, assert_ok, assert_noop};
+	use support::traits::{ExistenceRequirement, WithdrawReason};
+	use runtime_primitives::{
+		BuildStorage,
+		traits::{BlakeTwo256, IdentityLookup, OnInitialize},
+		testing::{Digest, DigestItem, Header}
+	};
+	use crate::approve;
+
+	impl_outer_origin! {
+		pub enum Origin for TreasuryTest {
+			approve
+		}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct TreasuryTest;
+	impl system::Trait for TreasuryTest {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type Digest = Digest;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type Log = DigestItem;
+	}
+	impl balances::Trait for TreasuryTest {
+		type Balance = u64;
+		type OnFreeBalanceZero = ();
+		type OnNewAccount = ();
+		type Event = ();
+		type TransactionPayment = ();
+		type TransferPayment = ();
+		type DustRemoval = ();
+	}
+	impl Trait for TreasuryTest {
+		type Event = ();
+		type Currency = balances::Module<TreasuryTest>;
+		type Approval = MockApproval;
+		type Council = approve::Council;
+	}
+	type Treasury = Module<TreasuryTest>;
+	type Balances = balances::Module<TreasuryTest>;
+
+	// Approvals are granted by writing the proposal id to storage, see approve_mock()
+	pub struct MockApproval;
+	impl ApprovalProvider<u64, H256> for MockApproval {
+		fn request_approval(_who: u64, payload_hash: H256) -> std::result::Result<ProposalId<H256>, &'static str> {
+			Ok(ProposalId(payload_hash))
+		}
+
+		fn is_approved(proposal_id: ProposalId<H256>) -> bool {
+			runtime_io::storage(proposal_id.as_ref()).is_some()
+		}
+	}
+
+	fn approve_mock(proposal_id: ProposalId<H256>) {
+		runtime_io::set_storage(proposal_id.as_ref(), &[1]);
+	}
+
+	// Account 1 pays into the pot, the spend period is 10 blocks and a tenth of what is left is burned
+	fn build_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+		let mut t = system::GenesisConfig::<TreasuryTest>::default().build_storage().unwrap().0;
+		t.extend(GenesisConfig::<TreasuryTest> {
+			spend_period: 10,
+			burn: Permill::from_percent(10),
+		}.build_storage().unwrap().0);
+		t.extend(balances::GenesisConfig::<TreasuryTest> {
+			balances: vec![(1, 1000)],
+			transaction_base_fee: 0,
+			transaction_byte_fee: 0,
+			existential_deposit: 0,
+			transfer_fee: 0,
+			creation_fee: 0,
+			vesting: vec![],
+		}.build_storage().unwrap().0);
+		t.into()
+	}
+
+	// Hands funds of the account to the treasury, the way the fees module does
+	fn pay_in(who: u64, amount: u64) {
+		let imbalance = Balances::withdraw(&who, amount, WithdrawReason::Fee, ExistenceRequirement::KeepAlive).unwrap();
+		Treasury::on_unbalanced(imbalance);
+	}
+
+	/// Type definitions test objectives:
+	/// * Spend encodes like its fields in the order of type_definitions()
+	#[test]
+	fn type_definitions_should_match_encoding() {
+		let spend = Spend { proposer: 1u64, beneficiary: 2u64, amount: 3u64 };
+		assert_eq!(spend.encode(), (spend.proposer, spend.beneficiary, spend.amount).encode());
+	}
+
+	/// Spend test objectives:
+	/// * Funds handed to the treasury go into the pot
+	/// * Only approved spends are paid out, at the end of a spend period and while the pot covers them
+	/// * The burn fraction of what is left is burned
+	#[test]
+	fn approved_spends_should_be_paid_each_period() {
+		with_externalities(&mut build_ext(), || {
+			pay_in(1, 500);
+			assert_eq!(Treasury::pot(), 500);
+
+			assert_noop!(Treasury::propose_spend(Origin::signed(2), 3, 0), "Amount must be greater than zero");
+			assert_ok!(Treasury::propose_spend(Origin::signed(2), 3, 200));
+			assert_ok!(Treasury::propose_spend(Origin::signed(2), 4, 100));
+			assert_ok!(Treasury::propose_spend(Origin::signed(2), 5, 1000));
+			let ids = Treasury::pending_spends();
+			approve_mock(ids[0]);
+			approve_mock(ids[2]);
+
+			Treasury::on_initialize(9);
+			assert_eq!(Treasury::pot(), 500);
+			Treasury::on_initialize(10);
+			assert_eq!(Balances::free_balance(&3), 200);
+			assert_eq!(Balances::free_balance(&5), 0);
+			// 300 were left, 30 of them burned
+			assert_eq!(Treasury::pot(), 270);
+			assert_eq!(Treasury::pending_spends(), vec![ids[1], ids[2]]);
+
+			assert_noop!(Treasury::cancel_spend(Origin::signed(3), ids[1].0), "Only the proposer can cancel this spend");
+			assert_ok!(Treasury::cancel_spend(Origin::signed(2), ids[1].0));
+			assert_eq!(Treasury::pending_spends(), vec![ids[2]]);
+			assert_eq!(Treasury::spend_of(ids[1]), None);
+		});
+	}
+
+	/// Config test objectives:
+	/// * Only a council motion can change the spend period and the burn
+	/// * A spend period of 0 pays out and burns nothing
+	#[test]
+	fn set_config_should_work() {
+		with_externalities(&mut build_ext(), || {
+			assert!(Treasury::set_config(Origin::ROOT, 0, Permill::from_percent(0)).is_err());
+			assert_ok!(Treasury::set_config(Origin::from(approve::Origin::Approved(2)), 0, Permill::from_percent(50)));
+			assert_eq!(Treasury::burn(), Permill::from_percent(50));
+
+			pay_in(1, 100);
+			Treasury::on_initialize(10);
+			assert_eq!(Treasury::pot(), 100);
+		});
+	}
+}
@@ -0,0 +1,106 @@
+/// Type definitions of the custom runtime types for polkadot-js apps. Each module lists its own types in
+/// a type_definitions() function next to the structs, and tests there check that the listed fields match
+/// how the structs encode, so the definitions can not drift from the runtime.
+///
+/// The JSON is checked in as pool/types.json. After changing a type, regenerate it with
+/// `cargo test -p pool-runtime write_types_json -- --ignored` and load it in polkadot-js apps under
+/// Settings > Developer.
+
+use crate::{approve, groups, identity, ids, marketplace, messages, pool, proxy, recovery, referrals, scheduler, substratekitties, tokens,
+	treasury};
+
+/// A runtime type as polkadot-js sees it
+pub enum TypeDef {
+	/// A struct with its fields in encoding order, as (field name, polkadot-js type)
+	Struct(&'static str, Vec<(&'static str, &'static str)>),
+	/// An enum without data, with its variants in index order
+	Enum(&'static str, Vec<&'static str>),
+	/// A type that encodes like another type, as (name, polkadot-js type)
+	Alias(&'static str, &'static str),
+}
+
+impl TypeDef {
+	pub fn name(&self) -> &'static str {
+		match self {
+			TypeDef::Struct(name, _) => *name,
+			TypeDef::Enum(name, _) => *name,
+			TypeDef::Alias(name, _) => *name,
+		}
+	}
+}
+
+/// The types of app-primitives. The ids encode like the hash they wrap.
+fn app_primitives_type_definitions() -> Vec<TypeDef> {
+	vec![
+		TypeDef::Alias("GroupId", "Hash"),
+		TypeDef::Alias("KittyId", "Hash"),
+		TypeDef::Alias("PoolId", "Hash"),
+		TypeDef::Alias("ProposalId", "Hash"),
+		TypeDef::Enum("FeeCall", vec!["CreateKitty", "BreedKitty", "CreateGroup", "JoinGroup"]),
+	]
+}
+
+/// All custom types of this runtime, in the order they are written to the JSON
+pub fn type_definitions() -> Vec<TypeDef> {
+	let mut types = app_primitives_type_definitions();
+	types.extend(groups::type_definitions());
+	types.extend(substratekitties::type_definitions());
+	types.extend(pool::type_definitions());
+	types.extend(approve::type_definitions());
+	types.extend(ids::type_definitions());
+	types.extend(proxy::type_definitions());
+	types.extend(recovery::type_definitions());
+	types.extend(identity::type_definitions());
+	types.extend(messages::type_definitions());
+	types.extend(scheduler::type_definitions());
+	types.extend(treasury::type_definitions());
+	types.extend(referrals::type_definitions());
+	types.extend(marketplace::type_definitions());
+	types.extend(tokens::type_definitions());
+	types
+}
+
+/// The type definitions as a JSON object, one type per line
+pub fn types_json() -> String {
+	let types: Vec<String> = type_definitions().iter().map(|def| match def {
+		TypeDef::Struct(name, fields) => {
+			let fields: Vec<String> = fields.iter().map(|(field, ty)| format!("\"{}\": \"{}\"", field, ty)).collect();
+			format!("  \"{}\": {{ {} }}", name, fields.join(", "))
+		},
+		TypeDef::Enum(name, variants) => {
+			let variants: Vec<String> = variants.iter().map(|variant| format!("\"{}\"", variant)).collect();
+			format!("  \"{}\": {{ \"_enum\": [{}] }}", name, variants.join(", "))
+		},
+		TypeDef::Alias(name, ty) => format!("  \"{}\": \"{}\"", name, ty),
+	}).collect();
+	format!("{{\n{}\n}}\n", types.join(",\n"))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const TYPES_JSON_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../types.json");
+
+	#[test]
+	fn type_names_should_be_unique() {
+		let mut names: Vec<&str> = type_definitions().iter().map(|def| def.name()).collect();
+		let count = names.len();
+		names.sort();
+		names.dedup();
+		assert_eq!(names.len(), count);
+	}
+
+	#[test]
+	fn types_json_should_be_up_to_date() {
+		let checked_in = std::fs::read_to_string(TYPES_JSON_PATH).unwrap_or_default();
+		assert!(checked_in == types_json(),
+			"types.json is out of date, regenerate it with `cargo test -p pool-runtime write_types_json -- --ignored`");
+	}
+
+	#[test]
+	#[ignore]
+	fn write_types_json() {
+		std::fs::write(TYPES_JSON_PATH, types_json()).unwrap();
+	}
+}
@@ -0,0 +1,208 @@
+/// Utility lets an account send several calls of the runtime in one signed transaction, such as creating a group
+/// and adding its members. The calls are dispatched in order with the origin of the batch:
+/// * a signed batch dispatches each call signed by the same account
+/// * a root batch, sent through sudo, dispatches each call as root
+/// * any other origin, such as a council motion, can not batch. A motion carries a single call.
+///
+/// This Substrate version has no storage transactions, and a call that fails keeps what it wrote before it
+/// failed. So a batch stops at the first failing call, and the calls before it stay dispatched. batch() reports
+/// the failure with an event and succeeds, batch_all() fails the whole transaction with the error of the call.
+
+use rstd::prelude::*;
+use rstd::result;
+use runtime_primitives::traits::Dispatchable;
+use support::{decl_module, decl_event, ensure, dispatch::Result, Parameter};
+
+/// The most calls in one batch, so the weight of a batch stays close to that of a few transactions
+pub const MAX_BATCH_CALLS: usize = 20;
+
+pub trait Trait: system::Trait {
+	type Event: From<Event> + Into<<Self as system::Trait>::Event>;
+
+	/// A runtime Call that a batch can carry. In the runtime this is the outer Call.
+	type Call: Parameter + Dispatchable<Origin = Self::Origin>;
+}
+
+decl_event!(
+	pub enum Event {
+		/// All calls of a batch were dispatched, with the number of calls
+		BatchCompleted(u32),
+
+		/// A batch stopped at a failing call, with the index of the call and its error. The calls before it
+		/// were dispatched.
+		BatchInterrupted(u32, Vec<u8>),
+	}
+);
+
+decl_module! {
+	/// The module declaration.
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+
+		fn deposit_event() = default;
+
+		/// Dispatch the calls in order with the origin of the batch, up to the first call that fails. The batch
+		/// succeeds either way, a failing call emits BatchInterrupted.
+		pub fn batch(origin, calls: Vec<<T as Trait>::Call>) -> Result {
+			let origin = Self::batch_origin(origin)?;
+			ensure!(calls.len() <= MAX_BATCH_CALLS, "Too many calls in one batch");
+
+			let count = calls.len() as u32;
+			if let Err((index, e)) = Self::dispatch_all(origin, calls) {
+				Self::deposit_event(Event::BatchInterrupted(index, e.as_bytes().to_vec()));
+				return Ok(());
+			}
+			Self::deposit_event(Event::BatchCompleted(count));
+			Ok(())
+		}
+
+		/// Like batch(), but a failing call fails the batch with its error. The calls before it are not undone,
+		/// see the module docs.
+		pub fn batch_all(origin, calls: Vec<<T as Trait>::Call>) -> Result {
+			let origin = Self::batch_origin(origin)?;
+			ensure!(calls.len() <= MAX_BATCH_CALLS, "Too many calls in one batch");
+
+			let count = calls.len() as u32;
+			Self::dispatch_all(origin, calls).map_err(|(_, e)| e)?;
+			Self::deposit_event(Event::BatchCompleted(count));
+			Ok(())
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	// Private method called by: batch() and batch_all(). The system origin the calls are dispatched with.
+	fn batch_origin(origin: T::Origin) -> result::Result<system::RawOrigin<T::AccountId>, &'static str> {
+		match origin.into() {
+			Some(system::RawOrigin::Signed(who)) => Ok(system::RawOrigin::Signed(who)),
+			Some(system::RawOrigin::Root) => Ok(system::RawOrigin::Root),
+			_ => Err("Only signed and root origins can batch"),
+		}
+	}
+
+	// Private method called by: batch() and batch_all(). Stops at the first failing call, with its index and error.
+	fn dispatch_all(origin: system::RawOrigin<T::AccountId>, calls: Vec<<T as Trait>::Call>)
+		-> result::Result<(), (u32, &'static str)>
+	{
+		for (index, call) in calls.into_iter().enumerate() {
+			call.dispatch(origin.clone().into()).map_err(|e| (index as u32, e))?;
+		}
+		Ok(())
+	}
+}
+
+// *****************************************************************************************************
+// Unit Tests!
+// *****************************************************************************************************
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use parity_codec::{Decode, Encode};
+	use runtime_io::with_externalities;
+	use primitives::{H256, Blake2Hasher};
+	use support::{impl_outer_origin, assert_ok, assert_noop};
+	use runtime_primitives::{
+		BuildStorage,
+		traits::{BlakeTwo256, IdentityLookup},
+		testing::{Digest, DigestItem, Header}
+	};
+
+	impl_outer_origin! {
+		pub enum Origin for UtilityTest {}
+	}
+
+	// A stand-in for the runtime Call. Record appends its value and the account it was dispatched by, or 0 for
+	// root, to storage, so tests can check the order and the origin of the dispatched calls.
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+	pub enum MockCall {
+		Record(u32),
+		Fail,
+	}
+	impl Dispatchable for MockCall {
+		type Origin = Origin;
+		type Trait = UtilityTest;
+		fn dispatch(self, origin: Origin) -> Result {
+			match self {
+				MockCall::Record(value) => {
+					let who = match origin.into() {
+						Some(system::RawOrigin::Signed(who)) => who,
+						Some(system::RawOrigin::Root) => 0,
+						_ => return Err("bad origin"),
+					};
+					let mut recorded = recorded();
+					recorded.push((value, who));
+					runtime_io::set_storage(b"mock_calls", &recorded.encode());
+					Ok(())
+				}
+				MockCall::Fail => Err("Mock call failed"),
+			}
+		}
+	}
+
+	fn recorded() -> Vec<(u32, u64)> {
+		runtime_io::storage(b"mock_calls")
+			.and_then(|bytes| Decode::decode(&mut &bytes[..]))
+			.unwrap_or_default()
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct UtilityTest;
+	impl system::Trait for UtilityTest {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type Digest = Digest;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type Log = DigestItem;
+	}
+	impl Trait for UtilityTest {
+		type Event = ();
+		type Call = MockCall;
+	}
+	type Utility = Module<UtilityTest>;
+
+	fn build_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+		system::GenesisConfig::<UtilityTest>::default().build_storage().unwrap().0.into()
+	}
+
+	/// Batch test objectives:
+	/// * The calls are dispatched in order, signed by the sender
+	/// * A batch stops at the first failing call and still succeeds, the calls before it stay dispatched
+	/// * batch_all fails with the error of the failing call
+	#[test]
+	fn batch_should_stop_at_the_first_failure() {
+		with_externalities(&mut build_ext(), || {
+			assert_ok!(Utility::batch(Origin::signed(1), vec![MockCall::Record(1), MockCall::Record(2)]));
+			assert_eq!(recorded(), vec![(1, 1), (2, 1)]);
+
+			assert_ok!(Utility::batch(Origin::signed(2), vec![MockCall::Record(3), MockCall::Fail, MockCall::Record(4)]));
+			assert_eq!(recorded(), vec![(1, 1), (2, 1), (3, 2)]);
+
+			assert_eq!(Utility::batch_all(Origin::signed(2), vec![MockCall::Record(5), MockCall::Fail]),
+				Err("Mock call failed"));
+			assert_eq!(recorded(), vec![(1, 1), (2, 1), (3, 2), (5, 2)]);
+
+			let too_many = vec![MockCall::Record(6); MAX_BATCH_CALLS + 1];
+			assert_noop!(Utility::batch(Origin::signed(1), too_many), "Too many calls in one batch");
+		});
+	}
+
+	/// Batch origin test objectives:
+	/// * A root batch dispatches each call as root
+	/// * An unsigned batch is rejected
+	#[test]
+	fn batch_should_keep_the_origin() {
+		with_externalities(&mut build_ext(), || {
+			assert_ok!(Utility::batch_all(Origin::ROOT, vec![MockCall::Record(1)]));
+			assert_eq!(recorded(), vec![(1, 0)]);
+
+			assert_noop!(Utility::batch(Origin::NONE, vec![MockCall::Record(2)]), "Only signed and root origins can batch");
+		});
+	}
+}
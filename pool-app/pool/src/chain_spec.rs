@@ -1,7 +1,9 @@
 use primitives::{ed25519, sr25519, Pair};
 use pool_runtime::{
-	AccountId, GenesisConfig, ConsensusConfig, TimestampConfig, BalancesConfig,
-	SudoConfig, IndicesConfig, GroupsConfig,
+	AccountId, Hash, GenesisConfig, ConsensusConfig, TimestampConfig, BalancesConfig,
+	SudoConfig, IndicesConfig, GroupsConfig, PoolConfig, ApproveConfig, SubstratekittiesConfig, FeesConfig, FeeCall,
+	ProxyConfig, RecoveryConfig, IdentityConfig, MessagesConfig, TreasuryConfig, OracleConfig, MarketplaceConfig,
+	Permill,
 };
 use substrate_service;
 
@@ -22,6 +24,9 @@ pub enum Alternative {
 	Development,
 	/// Whatever the current runtime is, with simple Alice/Bob auths.
 	LocalTestnet,
+	/// The local testnet with kitties, groups and a second approver weight already in genesis, so there
+	/// is something to look at without sending any extrinsics.
+	Demo,
 }
 
 fn authority_key(s: &str) -> AuthorityId {
@@ -78,6 +83,19 @@ impl Alternative {
 				None,
 				None
 			),
+			Alternative::Demo => ChainSpec::from_genesis(
+				"Demo Testnet",
+				"demo_testnet",
+				|| demo_genesis(vec![
+					authority_key("Alice"),
+					authority_key("Bob"),
+				]),
+				vec![],
+				None,
+				None,
+				None,
+				None
+			),
 		})
 	}
 
@@ -85,6 +103,7 @@ impl Alternative {
 		match s {
 			"dev" => Some(Alternative::Development),
 			"" | "local" => Some(Alternative::LocalTestnet),
+			"demo" => Some(Alternative::Demo),
 			_ => None,
 		}
 	}
@@ -119,7 +138,112 @@ fn testnet_genesis(initial_authorities: Vec<AuthorityId>, endowed_accounts: Vec<
             max_group_size: 10,
             max_groups_per_owner: 5,
             max_name_size: 40,
-            _genesis_phantom_data: Default::default(),
+            initial_groups: vec![],
+        }),
+        pool: Some(PoolConfig {
+            max_snapshots: 5,
+            large_payout: 1 << 50,
+            spend_limit: 1 << 52,
+            spend_period: 8640, // about a day with 10 second blocks
+        }),
+        approve: Some(ApproveConfig {
+            threshold: 1,
+            threshold_percent: 0,
+            proposal_lifetime: 8640, // about a day with 10 second blocks
+            reminder_blocks: 360, // about an hour
+            proposal_bond: 1000,
+            cancel_grace: 30, // about five minutes
+            max_failure_percent: 75,
+            min_recorded_proposals: 4,
+            initial_approvers: endowed_accounts.iter().cloned().map(|k| (k, 1)).collect(),
+        }),
+        substratekitties: Some(SubstratekittiesConfig {
+            kitties: vec![],
+        }),
+        fees: Some(FeesConfig {
+            call_fees: vec![
+                (FeeCall::CreateKitty, 1000),
+                (FeeCall::BreedKitty, 1000),
+                (FeeCall::CreateGroup, 1000),
+                (FeeCall::JoinGroup, 1000),
+            ],
+            call_limits: vec![
+                (FeeCall::CreateKitty, 10),
+                (FeeCall::BreedKitty, 10),
+                (FeeCall::CreateGroup, 5),
+                (FeeCall::JoinGroup, 20),
+            ],
+            rate_window: 360, // about an hour
+        }),
+        proxy: Some(ProxyConfig {
+            proxy_deposit: 1000,
+            announcement_deposit: 100,
         }),
+        recovery: Some(RecoveryConfig {
+            config_deposit: 1000,
+            recovery_deposit: 5000,
+        }),
+        identity: Some(IdentityConfig {
+            registrars: vec![],
+        }),
+        messages: Some(MessagesConfig {
+            messages_per_window: 30,
+            rate_window: 60, // about ten minutes
+        }),
+        treasury: Some(TreasuryConfig {
+            spend_period: 8640, // about a day
+            burn: Permill::from_percent(1),
+        }),
+        oracle: Some(OracleConfig {
+            max_age: 360, // about an hour
+            min_feeds: 3, // MIN_FEEDS of the oracle
+            feeders: vec![],
+        }),
+        marketplace: Some(MarketplaceConfig {
+            listings: vec![],
+        }),
+	}
+}
+
+/// The local testnet accounts with deterministic demo data. Kitty ids are fixed bytes, so the same kitties
+/// exist on every demo chain.
+fn demo_genesis(initial_authorities: Vec<AuthorityId>) -> GenesisConfig {
+	let alice = account_key("Alice");
+	let bob = account_key("Bob");
+	let charlie = account_key("Charlie");
+	let dave = account_key("Dave");
+	let eve = account_key("Eve");
+	let ferdie = account_key("Ferdie");
+	let endowed_accounts = vec![alice.clone(), bob.clone(), charlie.clone(), dave.clone(), eve.clone(), ferdie.clone()];
+
+	let mut config = testnet_genesis(initial_authorities, endowed_accounts, alice.clone());
+	config.substratekitties = Some(SubstratekittiesConfig {
+		kitties: vec![
+			(alice.clone(), Hash::from([1; 32])),
+			(alice.clone(), Hash::from([2; 32])),
+			(bob.clone(), Hash::from([3; 32])),
+			(charlie.clone(), Hash::from([4; 32])),
+		],
+	});
+	config.marketplace = Some(MarketplaceConfig {
+		listings: vec![
+			(alice.clone(), Hash::from([2; 32]), 1 << 40),
+			(bob.clone(), Hash::from([3; 32]), 1 << 41),
+		],
+	});
+	if let Some(oracle) = config.oracle.as_mut() {
+		oracle.feeders = vec![alice.clone(), bob.clone(), charlie.clone()];
+	}
+	if let Some(groups) = config.groups.as_mut() {
+		groups.initial_groups = vec![
+			(alice.clone(), b"Alice's table".to_vec(), 4, vec![alice.clone(), bob.clone(), charlie.clone()]),
+			(bob.clone(), b"Bob's pool".to_vec(), 6, vec![bob.clone(), dave.clone()]),
+		];
+	}
+	if let Some(approve) = config.approve.as_mut() {
+		// Alice and Bob vote with weight 2, so the threshold needs more than one vote from the others
+		approve.threshold = 3;
+		approve.initial_approvers = vec![(alice, 2), (bob, 2), (charlie, 1), (dave, 1), (eve, 1), (ferdie, 1)];
 	}
+	config
 }
@@ -1,21 +1,58 @@
 use crate::service;
 use futures::{future, Future, sync::oneshot};
 use std::cell::RefCell;
+use std::path::PathBuf;
 use tokio::runtime::Runtime;
 pub use substrate_cli::{VersionInfo, IntoExit, error};
-use substrate_cli::{informant, parse_and_execute, NoCustom};
+use substrate_cli::{informant, parse_and_execute, NoCustom, GetLogFilter};
+use substrate_cli::params::SharedParams;
 use substrate_service::{ServiceFactory, Roles as ServiceRoles};
+use sr_primitives::{generic::BlockId, traits::ProvideRuntimeApi};
+use pool_runtime::ExportApi;
+use structopt::StructOpt;
 use crate::chain_spec;
 use std::ops::Deref;
 use log::info;
 
+/// Subcommands of this node, on top of the ones of substrate-cli
+#[derive(Debug, StructOpt, Clone)]
+pub enum CustomSubcommands {
+	/// Write the kitties and groups of a block as genesis config JSON
+	#[structopt(name = "export-app-state")]
+	ExportAppState(ExportAppStateCmd),
+}
+
+impl GetLogFilter for CustomSubcommands {
+	fn get_log_filter(&self) -> Option<String> {
+		match self {
+			CustomSubcommands::ExportAppState(cmd) => cmd.shared_params.log.clone(),
+		}
+	}
+}
+
+/// The `export-app-state` command
+#[derive(Debug, StructOpt, Clone)]
+pub struct ExportAppStateCmd {
+	/// The block to export the state of. Defaults to the best block.
+	#[structopt(long = "block", value_name = "NUMBER")]
+	pub block: Option<u64>,
+
+	/// The file to write the JSON to. Defaults to stdout.
+	#[structopt(long = "output", short = "o", parse(from_os_str))]
+	pub output: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+}
+
 /// Parse command line arguments into service configuration.
 pub fn run<I, T, E>(args: I, exit: E, version: VersionInfo) -> error::Result<()> where
 	I: IntoIterator<Item = T>,
 	T: Into<std::ffi::OsString> + Clone,
 	E: IntoExit,
 {
-	parse_and_execute::<service::Factory, NoCustom, NoCustom, _, _, _, _, _>(
+	let custom = parse_and_execute::<service::Factory, CustomSubcommands, NoCustom, _, _, _, _, _>(
 		load_spec, &version, "substrate-node", args, exit,
 	 	|exit, _custom_args, config| {
 			info!("{}", version.name);
@@ -39,7 +76,44 @@ pub fn run<I, T, E>(args: I, exit: E, version: VersionInfo) -> error::Result<()>
 				),
 			}.map_err(|e| format!("{:?}", e))
 		}
-	).map_err(Into::into).map(|_| ())
+	)?;
+
+	match custom {
+		Some(CustomSubcommands::ExportAppState(cmd)) => export_app_state(cmd, &version),
+		None => Ok(()),
+	}
+}
+
+/// Reads the kitties, listings and groups at the block through the ExportApi runtime API and writes them in the
+/// format of the genesis config. Pools are not exported: they have no genesis config and their funds are
+/// plain balances.
+fn export_app_state(cmd: ExportAppStateCmd, version: &VersionInfo) -> error::Result<()> {
+	let config = substrate_cli::create_config_with_db_path::<service::Factory, _>(
+		load_spec, &cmd.shared_params, version,
+	)?;
+	let client = substrate_service::new_client::<service::Factory>(&config).map_err(|e| format!("{:?}", e))?;
+	let at = match cmd.block {
+		Some(number) => BlockId::Number(number),
+		None => BlockId::Hash(client.info().map_err(|e| format!("{:?}", e))?.chain.best_hash),
+	};
+	info!("Exporting app state at {:?}", at);
+
+	let kitties = client.runtime_api().kitties(&at).map_err(|e| format!("{:?}", e))?;
+	let listings = client.runtime_api().listings(&at).map_err(|e| format!("{:?}", e))?;
+	let groups = client.runtime_api().groups(&at).map_err(|e| format!("{:?}", e))?;
+	info!("Exported {} kitties, {} listings and {} groups", kitties.len(), listings.len(), groups.len());
+
+	let state = serde_json::json!({
+		"substratekitties": { "kitties": kitties },
+		"marketplace": { "listings": listings },
+		"groups": { "initialGroups": groups },
+	});
+	let json = serde_json::to_string_pretty(&state).map_err(|e| format!("{:?}", e))?;
+	match cmd.output {
+		Some(path) => std::fs::write(path, json).map_err(|e| format!("{:?}", e))?,
+		None => println!("{}", json),
+	}
+	Ok(())
 }
 
 fn load_spec(id: &str) -> Result<Option<chain_spec::ChainSpec>, String> {